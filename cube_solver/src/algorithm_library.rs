@@ -0,0 +1,438 @@
+//! The user's algorithm library: algorithms for F2L and last-layer cases, each marked
+//! as known or not.
+//!
+//! The library starts from the built-in tables: the 41 F2L cases of the front-right
+//! slot, the OLL, COLL and PLL cases. Algorithms the user adds are kept next to them.
+//! Known marks and added algorithms are saved as lines of text in the user's config
+//! directory; the built-in algorithms themselves are not, so they follow the app.
+
+use crate::move_sequence::MoveSequence;
+use crate::solvers::last_layer::{COLL_CASES, OLL_CASES, PLL_CASES};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Step of a solve an algorithm belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlgorithmSet {
+    F2l,
+    Oll,
+    Coll,
+    Pll,
+    /// Anything else, such as a commutator
+    Other,
+}
+
+impl AlgorithmSet {
+    pub const ALL: [AlgorithmSet; 5] = [
+        AlgorithmSet::F2l,
+        AlgorithmSet::Oll,
+        AlgorithmSet::Coll,
+        AlgorithmSet::Pll,
+        AlgorithmSet::Other,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            AlgorithmSet::F2l => "F2L",
+            AlgorithmSet::Oll => "OLL",
+            AlgorithmSet::Coll => "COLL",
+            AlgorithmSet::Pll => "PLL",
+            AlgorithmSet::Other => "Other",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|set| set.name() == name)
+    }
+}
+
+impl fmt::Display for AlgorithmSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An algorithm of the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryEntry {
+    pub set: AlgorithmSet,
+    /// Case the algorithm solves, such as "OLL 27 (Sune)"
+    pub name: String,
+    /// The algorithm as learnt: F2L for the front-right slot with the cross on D, the
+    /// last layer on U
+    pub algorithm: String,
+    /// The user can do the algorithm from memory
+    pub known: bool,
+    /// From the built-in tables rather than added by the user
+    pub built_in: bool,
+}
+
+impl LibraryEntry {
+    /// Face turns of the algorithm, with slices, wide turns and rotations resolved
+    pub fn moves(&self) -> Result<MoveSequence, String> {
+        MoveSequence::parse(&self.algorithm)
+            .map(|moves| moves.to_face_turns())
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// Algorithms for the F2L cases of the front-right slot, named by where the corner
+/// and edge are and which way their white and front-colored stickers face. The
+/// shortest in `<R,U>` where that is at most a move longer than in `<R,U,F>`.
+const F2L_CASES: [(&str, &str); 41] = [
+    (
+        "Corner UFR (white front), edge UL (front color left)",
+        "F' U' F",
+    ),
+    (
+        "Corner UFR (white right), edge UB (front color up)",
+        "R U R'",
+    ),
+    (
+        "Corner UFR (white front), edge UR (front color up)",
+        "R' F R F'",
+    ),
+    (
+        "Corner UFR (white right), edge UF (front color front)",
+        "F R' F' R",
+    ),
+    (
+        "Corner DFR (white right), edge UF (front color front)",
+        "R U2 R' F' U2 F",
+    ),
+    (
+        "Corner UFR (white up), edge FR (front color right)",
+        "R U' R' F' U2 F",
+    ),
+    (
+        "Corner DFR (white down), edge UF (front color up)",
+        "F' U2 F U2 R U R'",
+    ),
+    (
+        "Corner DFR (white front), edge UF (front color front)",
+        "F' U' F U F' U' F",
+    ),
+    (
+        "Corner UFR (white front), edge UF (front color up)",
+        "F' U F U2 R U R'",
+    ),
+    (
+        "Corner UFR (white front), edge UL (front color up)",
+        "F2 U2 R' F2 R U2 F2",
+    ),
+    (
+        "Corner UFR (white front), edge UR (front color right)",
+        "F U2 F2 U' F2 U' F'",
+    ),
+    (
+        "Corner UFR (white right), edge UB (front color back)",
+        "R2 U2 F R2 F' U2 R2",
+    ),
+    (
+        "Corner UFR (white right), edge UF (front color up)",
+        "R' U2 R2 U R2 U R",
+    ),
+    (
+        "Corner UFR (white right), edge UR (front color right)",
+        "R U' R' U2 F' U' F",
+    ),
+    (
+        "Corner UFR (white up), edge FR (front color front)",
+        "R2 U R2 U R2 U2 R2",
+    ),
+    (
+        "Corner UFR (white up), edge UB (front color back)",
+        "F' U F U2 F' U' F",
+    ),
+    (
+        "Corner UFR (white up), edge UF (front color front)",
+        "F' U2 F U F' U' F",
+    ),
+    (
+        "Corner UFR (white up), edge UL (front color up)",
+        "R U' R' U2 R U R'",
+    ),
+    (
+        "Corner UFR (white up), edge UR (front color up)",
+        "R U2 R' U' R U R'",
+    ),
+    (
+        "Corner DFR (white down), edge UF (front color front)",
+        "R' U' R F' R' U R F",
+    ),
+    (
+        "Corner UFR (white front), edge UB (front color up)",
+        "F U F' R' F R U' F'",
+    ),
+    (
+        "Corner UFR (white front), edge UF (front color front)",
+        "R U R2 F R F2 U' F",
+    ),
+    (
+        "Corner UFR (white right), edge UL (front color left)",
+        "R' U' R F R' F' U R",
+    ),
+    (
+        "Corner UFR (white right), edge UR (front color up)",
+        "F' U' F2 R' F' R2 U R'",
+    ),
+    (
+        "Corner DFR (white down), edge FR (front color right)",
+        "R U' R U2 F R2 F' U2 R2",
+    ),
+    (
+        "Corner DFR (white front), edge FR (front color front)",
+        "R2 U2 R' U' R U' R' U2 R'",
+    ),
+    (
+        "Corner DFR (white front), edge FR (front color right)",
+        "R F U R U' R' F' U' R'",
+    ),
+    (
+        "Corner DFR (white front), edge UF (front color up)",
+        "R U2 R U2 R' U' R U' R2",
+    ),
+    (
+        "Corner DFR (white right), edge FR (front color front)",
+        "R U2 R U R' U R U2 R2",
+    ),
+    (
+        "Corner DFR (white right), edge FR (front color right)",
+        "R U F R U R' U' F' R'",
+    ),
+    (
+        "Corner DFR (white right), edge UF (front color up)",
+        "R U' R U R2 U R2 U2 R2",
+    ),
+    (
+        "Corner UFR (white front), edge FR (front color front)",
+        "R U' R U2 R' U' R U' R2",
+    ),
+    (
+        "Corner UFR (white front), edge UB (front color back)",
+        "R' U' R F' R' U R U2 F",
+    ),
+    (
+        "Corner UFR (white right), edge FR (front color front)",
+        "F R' F2 R U R U' R' F",
+    ),
+    (
+        "Corner UFR (white right), edge UL (front color up)",
+        "R U R2 F R F' R U R'",
+    ),
+    (
+        "Corner UFR (white up), edge UB (front color up)",
+        "R U' R U R' U R U2 R2",
+    ),
+    (
+        "Corner UFR (white up), edge UF (front color up)",
+        "R2 U R' U R U2 R' U' R'",
+    ),
+    (
+        "Corner UFR (white up), edge UL (front color left)",
+        "R' F2 R U' F U R' F2 R",
+    ),
+    (
+        "Corner UFR (white up), edge UR (front color right)",
+        "F U R U' R' F' R U' R'",
+    ),
+    (
+        "Corner UFR (white front), edge FR (front color right)",
+        "R U R' U2 R U' R' F' U' F",
+    ),
+    (
+        "Corner UFR (white right), edge FR (front color right)",
+        "R U R2 U' R F' R' U R F",
+    ),
+];
+
+/// All algorithms the user can pick from, in the order they are listed
+#[derive(Clone, PartialEq, Eq)]
+pub struct AlgorithmLibrary {
+    pub entries: Vec<LibraryEntry>,
+}
+
+// Counts only: the entries would swamp the logged solver settings
+impl fmt::Debug for AlgorithmLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let known = self.entries.iter().filter(|entry| entry.known).count();
+        write!(
+            f,
+            "AlgorithmLibrary({} algorithms, {} known)",
+            self.entries.len(),
+            known
+        )
+    }
+}
+
+impl Default for AlgorithmLibrary {
+    fn default() -> Self {
+        Self::built_in()
+    }
+}
+
+impl AlgorithmLibrary {
+    /// The built-in tables, none of them known
+    pub fn built_in() -> Self {
+        let entry = |set, name: String, algorithm: &str| LibraryEntry {
+            set,
+            name,
+            algorithm: algorithm.to_string(),
+            known: false,
+            built_in: true,
+        };
+        let f2l = F2L_CASES
+            .iter()
+            .map(|&(name, algorithm)| entry(AlgorithmSet::F2l, name.to_string(), algorithm));
+        let oll = OLL_CASES
+            .iter()
+            .map(|case| entry(AlgorithmSet::Oll, case.label(), case.algorithm));
+        let coll = COLL_CASES.iter().flat_map(|case| {
+            case.algorithms
+                .iter()
+                .map(|algorithm| entry(AlgorithmSet::Coll, case.label(), algorithm))
+        });
+        let pll = PLL_CASES
+            .iter()
+            .map(|case| entry(AlgorithmSet::Pll, case.label(), case.algorithm));
+        Self {
+            entries: f2l.chain(oll).chain(coll).chain(pll).collect(),
+        }
+    }
+
+    /// Known algorithms of `set`
+    pub fn known(&self, set: AlgorithmSet) -> impl Iterator<Item = &LibraryEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.set == set && entry.known)
+    }
+
+    /// Add an algorithm for the case `name` as known and return its index. An algorithm
+    /// already in `set` is marked known and renamed instead of added twice.
+    pub fn add(
+        &mut self,
+        set: AlgorithmSet,
+        name: impl Into<String>,
+        algorithm: &str,
+    ) -> Result<usize, String> {
+        let moves = MoveSequence::parse(algorithm).map_err(|error| error.to_string())?;
+        if moves.is_empty() {
+            return Err("An algorithm needs at least one move".to_string());
+        }
+        let name = name.into();
+        let existing = self.entries.iter().position(|entry| {
+            entry.set == set
+                && MoveSequence::parse(&entry.algorithm).is_ok_and(|other| other == moves)
+        });
+        match existing {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.known = true;
+                if !entry.built_in {
+                    entry.name = name;
+                }
+                Ok(index)
+            }
+            None => {
+                self.entries.push(LibraryEntry {
+                    set,
+                    name,
+                    algorithm: moves.to_string(),
+                    known: true,
+                    built_in: false,
+                });
+                Ok(self.entries.len() - 1)
+            }
+        }
+    }
+
+    /// The built-in tables with the user's marks and algorithms from `path` on top.
+    /// Without a file, the built-in tables are all there is.
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let mut library = Self::built_in();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(library),
+            Err(error) => return Err(error),
+        };
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let parsed = match fields[..] {
+                [set, known, name, algorithm] => {
+                    AlgorithmSet::from_name(set).map(|set| (set, known == "known", name, algorithm))
+                }
+                _ => None,
+            };
+            let Some((set, known, name, algorithm)) = parsed else {
+                log::warn!("Skipping line {} of {}", number + 1, path.display());
+                continue;
+            };
+            match library.add(set, name, algorithm) {
+                Ok(index) => library.entries[index].known = known,
+                Err(error) => log::warn!(
+                    "Skipping line {} of {}: {}",
+                    number + 1,
+                    path.display(),
+                    error
+                ),
+            }
+        }
+        Ok(library)
+    }
+
+    /// Save the known marks and the user's own algorithms to `path`
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let mut text = String::from("# set\tknown\tcase\talgorithm\n");
+        for entry in &self.entries {
+            if entry.built_in && !entry.known {
+                continue;
+            }
+            let known = if entry.known { "known" } else { "learning" };
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                entry.set, known, entry.name, entry.algorithm
+            ));
+        }
+        // Written next to the library first so a failed save leaves the old one intact
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, text)?;
+        fs::rename(temporary, path)
+    }
+
+    /// The library saved in the user's config directory
+    pub fn load() -> Self {
+        let path = library_path();
+        Self::load_from(&path).unwrap_or_else(|error| {
+            log::warn!("Could not read {}: {}", path.display(), error);
+            Self::built_in()
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&library_path())
+    }
+}
+
+/// Where the library is saved: `$CUBE_SOLVER_CONFIG_DIR`, the user's config
+/// directory, or the temporary directory as a last resort
+pub fn library_path() -> PathBuf {
+    let directory = std::env::var_os("CUBE_SOLVER_CONFIG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("XDG_CONFIG_HOME").map(|dir| PathBuf::from(dir).join("cube_solver"))
+        })
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|dir| PathBuf::from(dir).join(".config").join("cube_solver"))
+        })
+        .unwrap_or_else(|| std::env::temp_dir().join("cube_solver"));
+    directory.join("algorithms.txt")
+}
@@ -2,29 +2,43 @@ use bevy::color::palettes::css;
 use bevy::{asset::load_internal_binary_asset, prelude::*};
 
 use crate::camera::setup_camera_and_lighting;
-use crate::cube::create_cube;
+use crate::cube::{
+    CubeModelResource, HighlightedPieces, apply_layer_rotation_to_cube_model, create_cube,
+    dim_reduced_stickers, sync_faces_with_cube_model,
+};
 use crate::cube_moves::CubeMoveEvent;
 use crate::input::handle_touch;
 use crate::layer_rotation::LayerRotationPlugin;
 use crate::selection::{SelectionPlugin, detect_touch_selection};
+use crate::solve_task::{SolveFinishedEvent, SolveTasks, poll_solve_tasks};
+use crate::solver_backend::SolverRegistry;
 use crate::solver_integration::{
-    CubeSolverResource, lightweight_validation_on_recolor,
-    lightweight_validation_on_rotation_complete, update_solver_state,
+    CubeSolverResource, resolve_when_tables_ready, update_solver_state,
 };
+use crate::ui::challenge_panel::ChallengePanelPlugin;
 use crate::ui::color_panel::{
     create_ui_color_panel, handle_color_button_clicks, update_color_button_selection,
     update_color_count_labels, update_color_text_colors,
 };
+use crate::ui::fmc_panel::FmcPanelPlugin;
+use crate::ui::generator_panel::GeneratorPanelPlugin;
+use crate::ui::hint::HintPlugin;
+use crate::ui::inspection_panel::InspectionPanelPlugin;
+use crate::ui::last_layer_panel::LastLayerPanelPlugin;
+use crate::ui::library_panel::LibraryPanelPlugin;
 use crate::ui::move_test::MoveTestPlugin;
 use crate::ui::navigation::{
     handle_navigation_next_button_clicks, handle_navigation_prev_button_clicks,
     update_navigation_buttons,
 };
 use crate::ui::rotations_panel::RotationsPanelPlugin;
+use crate::ui::settings_panel::SettingsPanelPlugin;
 use crate::ui::solve::{
-    create_solve_button, handle_solution_move_completion, handle_solve_button_clicks,
-    update_solve_button,
+    MetricSelection, create_solve_button, handle_metric_button_clicks,
+    handle_solution_move_completion, handle_solve_button_clicks, handle_solve_finished,
+    update_metric_label, update_solve_button,
 };
+use crate::ui::state_panel::StatePanelPlugin;
 
 /// Create the Bevy app with common configuration
 pub fn create_app() -> App {
@@ -52,14 +66,29 @@ pub fn create_app() -> App {
     )
     .add_plugins(RotationsPanelPlugin)
     .add_plugins(MoveTestPlugin)
+    .add_plugins(SettingsPanelPlugin)
+    .add_plugins(InspectionPanelPlugin)
+    .add_plugins(FmcPanelPlugin)
+    .add_plugins(ChallengePanelPlugin)
+    .add_plugins(LastLayerPanelPlugin)
+    .add_plugins(LibraryPanelPlugin)
+    .add_plugins(StatePanelPlugin)
+    .add_plugins(GeneratorPanelPlugin)
+    .add_plugins(HintPlugin)
     .add_plugins(SelectionPlugin);
 
-    // Add color manager and solver resources
+    // Add color manager, cube model and solver resources
     app.init_resource::<crate::components::ColorManager>();
+    app.init_resource::<CubeModelResource>();
+    app.init_resource::<HighlightedPieces>();
     app.init_resource::<CubeSolverResource>();
+    app.init_resource::<SolverRegistry>();
+    app.init_resource::<MetricSelection>();
+    app.init_resource::<SolveTasks>();
 
     // Add cube move events
     app.add_event::<CubeMoveEvent>();
+    app.add_event::<SolveFinishedEvent>();
 
     // Dark background
     app.insert_resource(ClearColor(css::MIDNIGHT_BLUE.into()));
@@ -85,6 +114,7 @@ pub fn create_app() -> App {
             (
                 handle_color_button_clicks,
                 handle_solve_button_clicks,
+                handle_metric_button_clicks,
                 handle_navigation_next_button_clicks,
                 handle_navigation_prev_button_clicks,
                 update_color_button_selection,
@@ -92,12 +122,29 @@ pub fn create_app() -> App {
                 update_color_text_colors,
             ),
             // Button update systems run separately to avoid query conflicts
-            (update_solve_button, update_navigation_buttons),
+            (
+                update_solve_button,
+                update_navigation_buttons,
+                update_metric_label,
+            ),
             // 3D input systems and others
             handle_touch.before(detect_touch_selection),
-            update_solver_state,
-            lightweight_validation_on_recolor,
-            lightweight_validation_on_rotation_complete,
+            // The model is updated first, then rendered and handed to the solver
+            (
+                apply_layer_rotation_to_cube_model,
+                sync_faces_with_cube_model,
+                dim_reduced_stickers,
+                update_solver_state,
+            )
+                .chain(),
+            // Background solves are collected after the model update so stale results are dropped
+            (
+                poll_solve_tasks,
+                handle_solve_finished,
+                resolve_when_tables_ready,
+            )
+                .chain()
+                .after(update_solver_state),
             handle_solution_move_completion,
         ),
     )
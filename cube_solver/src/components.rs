@@ -278,6 +278,17 @@ impl Orientation {
         }
     }
 
+    pub fn to_model_face(&self) -> crate::cube_model::Face {
+        match self {
+            Orientation::Up => crate::cube_model::Face::U,
+            Orientation::Right => crate::cube_model::Face::R,
+            Orientation::Front => crate::cube_model::Face::F,
+            Orientation::Down => crate::cube_model::Face::D,
+            Orientation::Left => crate::cube_model::Face::L,
+            Orientation::Back => crate::cube_model::Face::B,
+        }
+    }
+
     pub fn from_cube_face(cube_face: crate::cube_moves::CubeFace) -> Self {
         match cube_face {
             crate::cube_moves::CubeFace::Up => Orientation::Up,
@@ -295,3 +306,11 @@ impl Orientation {
 pub struct Face {
     pub parent_cube: Entity,
 }
+
+/// Index of the model facelet a face entity currently shows.
+/// Updated whenever a layer rotation moves the face to another position.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub struct FaceletIndex {
+    pub index: usize,
+}
@@ -1,11 +1,33 @@
 use crate::colors::CubeColors;
-use crate::components::{Orientation, RotatingModel};
+use crate::components::{ColorManager, FaceletIndex, Orientation, RecoloredFace, RotatingModel};
+use crate::cube_model::{
+    CENTER_FACELETS, CORNER_FACELETS, CubeModel, EDGE_FACELETS, FACELET_COUNT, Face,
+    facelet_index_at,
+};
 use crate::cube_moves::CubeMoveTarget;
 use crate::layer_components::{CubeLayer, LayerFace, LayersCube, get_position_in_layer};
 use crate::selection::Selectable;
+use crate::solver_integration::FaceColor;
+use crate::solvers::thistlethwaite;
+use crate::ui::rotations_panel::{LayerRotationCompletedEvent, MoveQueue};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
 use std::collections::HashMap;
 
+/// Logical cube state shared by rendering, layer rotations and the solver
+#[derive(Resource, Debug, Clone, Default)]
+pub struct CubeModelResource {
+    pub model: CubeModel,
+}
+
+/// Pieces to pick out on the cube, such as the ones a hint is about; the stickers of
+/// every other piece are dimmed. Indexed by piece, so the highlight follows the pieces
+/// as they move.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct HighlightedPieces {
+    pub pieces: Option<([bool; 8], [bool; 12])>,
+}
+
 /// Creates a complete Rubik's cube with proper layer hierarchy
 /// Each layer contains 9 cubes organized as a cohesive group
 pub fn create_cube(
@@ -237,6 +259,9 @@ pub fn create_cube(
                                 crate::components::Face {
                                     parent_cube: small_cube,
                                 },
+                                FaceletIndex {
+                                    index: facelet_index_at(orientation.to_model_face(), [x, y, z]),
+                                },
                             ))
                             .id();
                         commands.entity(small_cube).add_child(face);
@@ -249,3 +274,172 @@ pub fn create_cube(
 
     info!("Rubik's cube creation completed with layer hierarchy");
 }
+
+/// System to apply completed layer rotations to the cube model.
+///
+/// Face entities physically moved with the layer, so their facelet indices are
+/// permuted the same way to keep pointing at the facelet they now show.
+pub fn apply_layer_rotation_to_cube_model(
+    mut rotation_completed_events: EventReader<LayerRotationCompletedEvent>,
+    mut cube_model: ResMut<CubeModelResource>,
+    mut facelet_query: Query<&mut FaceletIndex>,
+) {
+    for event in rotation_completed_events.read() {
+        let mv = event.layer_face.to_model_move(event.move_type);
+        cube_model.model.apply_move(mv);
+
+        let permutation = mv.facelet_permutation();
+        for mut facelet in &mut facelet_query {
+            facelet.index = permutation[facelet.index];
+        }
+
+        debug!("Cube model after {}: {}", mv, cube_model.model);
+    }
+}
+
+/// What a sticker can be drawn with: the cube colors or the uncolored placeholder
+#[derive(SystemParam)]
+pub struct FaceMaterials<'w> {
+    cube_colors: Res<'w, CubeColors>,
+    placeholder: Res<'w, crate::colors::PlaceholderMaterial>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+}
+
+/// System to render the cube model onto the face entities.
+///
+/// Runs whenever the model changes and updates materials, `RecoloredFace`
+/// markers and color usage counts to match the model.
+pub fn sync_faces_with_cube_model(
+    mut commands: Commands,
+    cube_model: Res<CubeModelResource>,
+    faces_query: Query<(Entity, &FaceletIndex, Option<&RecoloredFace>)>,
+    mut face_materials: FaceMaterials,
+    mut color_manager: ResMut<ColorManager>,
+    time: Res<Time>,
+) {
+    if !cube_model.is_changed() {
+        return;
+    }
+
+    let timestamp = time.elapsed_secs_f64();
+    let mut color_materials: [Option<Handle<StandardMaterial>>; 6] = Default::default();
+
+    for (entity, facelet, recolored) in &faces_query {
+        let model_color = cube_model
+            .model
+            .facelet(facelet.index)
+            .map(|face| FaceColor::from_model_face(face).index());
+        if model_color == recolored.map(|face| face.color_index) {
+            continue;
+        }
+
+        match model_color {
+            Some(color_index) => {
+                let material = color_materials[color_index]
+                    .get_or_insert_with(|| {
+                        crate::selection::create_face_material(
+                            face_materials.cube_colors.get(color_index),
+                            &mut face_materials.materials,
+                        )
+                    })
+                    .clone();
+                commands.entity(entity).insert((
+                    MeshMaterial3d(material),
+                    RecoloredFace::new(color_index, timestamp),
+                ));
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(MeshMaterial3d(face_materials.placeholder.0.clone()))
+                    .remove::<RecoloredFace>();
+            }
+        }
+    }
+
+    let mut usage_counts = [0; 6];
+    for face in Face::ALL {
+        usage_counts[FaceColor::from_model_face(face).index()] =
+            cube_model.model.count(face) as u32;
+    }
+    if color_manager.usage_counts != usage_counts {
+        color_manager.usage_counts = usage_counts;
+    }
+}
+
+/// System to dim the stickers of pieces that the current Thistlethwaite phase has
+/// nothing left to do with, so the pieces still to be reduced stand out. Outside the
+/// phase stages the pieces in `HighlightedPieces` stand out instead.
+///
+/// Runs after `sync_faces_with_cube_model` and restores the normal materials once
+/// neither applies.
+pub fn dim_reduced_stickers(
+    mut commands: Commands,
+    cube_model: Res<CubeModelResource>,
+    (move_queue, highlighted): (Res<MoveQueue>, Res<HighlightedPieces>),
+    faces_query: Query<(Entity, &FaceletIndex, &RecoloredFace)>,
+    cube_colors: Res<CubeColors>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut dimming: Local<bool>,
+) {
+    if !cube_model.is_changed() && !move_queue.is_changed() && !highlighted.is_changed() {
+        return;
+    }
+
+    let phase = move_queue
+        .highlight_index
+        .and_then(|index| move_queue.stage_at(index))
+        .and_then(|(_, stage)| thistlethwaite::phase_of_label(&stage.label));
+    let dimmed_pieces = cube_model
+        .model
+        .to_cubie()
+        .ok()
+        .and_then(|cube| match phase {
+            Some(phase) => Some(thistlethwaite::reduced_pieces(&cube, phase)),
+            None => highlighted.pieces.map(|(corners, edges)| {
+                (
+                    std::array::from_fn(|position| !corners[cube.cp[position] as usize]),
+                    std::array::from_fn(|position| !edges[cube.ep[position] as usize]),
+                )
+            }),
+        });
+    let dimmed = dimmed_pieces.map(|(corners, edges): ([bool; 8], [bool; 12])| {
+        let mut dimmed = [false; FACELET_COUNT];
+        for center in CENTER_FACELETS {
+            dimmed[center] = true;
+        }
+        for (facelets, reduced) in CORNER_FACELETS.iter().zip(corners) {
+            facelets
+                .iter()
+                .for_each(|&facelet| dimmed[facelet] = reduced);
+        }
+        for (facelets, reduced) in EDGE_FACELETS.iter().zip(edges) {
+            facelets
+                .iter()
+                .for_each(|&facelet| dimmed[facelet] = reduced);
+        }
+        dimmed
+    });
+    if dimmed.is_none() && !*dimming {
+        return;
+    }
+    *dimming = dimmed.is_some();
+
+    // Normal and dimmed material of each color, created on first use
+    let mut color_materials: [[Option<Handle<StandardMaterial>>; 2]; 6] = Default::default();
+    for (entity, facelet, recolored) in &faces_query {
+        let dim = dimmed.is_some_and(|dimmed| dimmed[facelet.index]);
+        let material = color_materials[recolored.color_index][dim as usize]
+            .get_or_insert_with(|| {
+                let color = cube_colors.get(recolored.color_index);
+                let color = if dim {
+                    color.mix(&CubeColors::base_color(), 0.75)
+                } else {
+                    color
+                };
+                crate::selection::create_face_material(color, &mut materials)
+            })
+            .clone();
+        commands.entity(entity).insert(MeshMaterial3d(material));
+    }
+}
@@ -0,0 +1,1018 @@
+//! Bevy-independent logical model of the 3x3x3 cube.
+//!
+//! The model is the single source of truth for the cube state: the 3D scene
+//! renders from it, completed layer rotations are applied to it and the solver
+//! reads its facelet string. Nothing in here depends on Bevy, so tests and tools
+//! can reason about cube states without an ECS world.
+//!
+//! Facelets use the min2phase layout:
+//! ```text
+//!          +--------+
+//!          |U1 U2 U3|
+//!          |U4 U5 U6|
+//!          |U7 U8 U9|
+//! +--------+--------+--------+--------+
+//! |L1 L2 L3|F1 F2 F3|R1 R2 R3|B1 B2 B3|
+//! |L4 L5 L6|F4 F5 F6|R4 R5 R6|B4 B5 B6|
+//! |L7 L8 L9|F7 F8 F9|R7 R8 R9|B7 B8 B9|
+//! +--------+--------+--------+--------+
+//!          |D1 D2 D3|
+//!          |D4 D5 D6|
+//!          |D7 D8 D9|
+//!          +--------+
+//! ```
+//! stored as U1..U9 R1..R9 F1..F9 D1..D9 L1..L9 B1..B9.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Total number of facelets (stickers) on the cube
+pub const FACELET_COUNT: usize = 54;
+
+/// Facelet string of the solved cube
+pub const SOLVED_FACELETS: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+
+/// Center facelet indices in the facelet string (position 5 of each face)
+pub const CENTER_FACELETS: [usize; 6] = [4, 13, 22, 31, 40, 49];
+
+/// The six faces of the cube, in facelet string order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Face {
+    U,
+    R,
+    F,
+    D,
+    L,
+    B,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::U, Face::R, Face::F, Face::D, Face::L, Face::B];
+
+    /// Position of the face in the facelet string (0-5)
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Face::U => 'U',
+            Face::R => 'R',
+            Face::F => 'F',
+            Face::D => 'D',
+            Face::L => 'L',
+            Face::B => 'B',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'U' => Some(Face::U),
+            'R' => Some(Face::R),
+            'F' => Some(Face::F),
+            'D' => Some(Face::D),
+            'L' => Some(Face::L),
+            'B' => Some(Face::B),
+            _ => None,
+        }
+    }
+
+    /// Index of the first facelet of this face in the facelet string
+    pub fn facelet_offset(self) -> usize {
+        self.index() * 9
+    }
+
+    pub fn axis(self) -> Axis {
+        match self {
+            Face::R | Face::L => Axis::X,
+            Face::U | Face::D => Axis::Y,
+            Face::F | Face::B => Axis::Z,
+        }
+    }
+
+    pub fn opposite(self) -> Face {
+        match self {
+            Face::U => Face::D,
+            Face::R => Face::L,
+            Face::F => Face::B,
+            Face::D => Face::U,
+            Face::L => Face::R,
+            Face::B => Face::F,
+        }
+    }
+
+    /// Whether the face lies on the positive side of its axis (R, U, F)
+    pub fn is_positive(self) -> bool {
+        matches!(self, Face::R | Face::U | Face::F)
+    }
+
+    /// The face this face moves to when the cube is turned by quarter turns around `axis` (right-hand rule)
+    pub fn rotated(self, axis: Axis, quarter_turns: i32) -> Face {
+        Face::from_normal(axis.rotate(self.normal(), quarter_turns))
+    }
+
+    fn normal(self) -> [i32; 3] {
+        let sign = if self.is_positive() { 1 } else { -1 };
+        let mut normal = [0; 3];
+        normal[self.axis().index()] = sign;
+        normal
+    }
+
+    fn from_normal(normal: [i32; 3]) -> Self {
+        match normal {
+            [1, 0, 0] => Face::R,
+            [-1, 0, 0] => Face::L,
+            [0, 1, 0] => Face::U,
+            [0, -1, 0] => Face::D,
+            [0, 0, 1] => Face::F,
+            _ => Face::B,
+        }
+    }
+}
+
+impl fmt::Display for Face {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// Cube axes; X points to R, Y to U and Z to F
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+
+    /// Rotate an integer vector by quarter turns around this axis (right-hand rule)
+    fn rotate(self, v: [i32; 3], quarter_turns: i32) -> [i32; 3] {
+        let mut v = v;
+        for _ in 0..quarter_turns.rem_euclid(4) {
+            v = match self {
+                Axis::X => [v[0], -v[2], v[1]],
+                Axis::Y => [v[2], v[1], -v[0]],
+                Axis::Z => [-v[1], v[0], v[2]],
+            };
+        }
+        v
+    }
+}
+
+/// Middle slices: M follows L, E follows D and S follows F
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Slice {
+    M,
+    E,
+    S,
+}
+
+impl Slice {
+    pub fn from_axis(axis: Axis) -> Slice {
+        match axis {
+            Axis::X => Slice::M,
+            Axis::Y => Slice::E,
+            Axis::Z => Slice::S,
+        }
+    }
+
+    pub fn axis(self) -> Axis {
+        match self {
+            Slice::M => Axis::X,
+            Slice::E => Axis::Y,
+            Slice::S => Axis::Z,
+        }
+    }
+
+    /// The outer face whose turning direction the slice follows
+    pub fn reference_face(self) -> Face {
+        match self {
+            Slice::M => Face::L,
+            Slice::E => Face::D,
+            Slice::S => Face::F,
+        }
+    }
+}
+
+/// Which layers a move turns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MoveKind {
+    /// Single outer layer (R, U', F2, ...)
+    Face(Face),
+    /// Outer layer together with the adjacent middle slice (Rw, r, ...)
+    Wide(Face),
+    /// Middle slice (M, E, S)
+    Slice(Slice),
+    /// Whole-cube rotation (x follows R, y follows U, z follows F)
+    Rotation(Axis),
+}
+
+/// A single move: a set of layers turned clockwise by `turns` quarter turns.
+///
+/// `turns` is normalised to 0..=3, so a prime move has three turns and a move
+/// with zero turns is the identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub kind: MoveKind,
+    pub turns: u8,
+}
+
+impl Move {
+    pub fn new(kind: MoveKind, turns: i32) -> Self {
+        Self {
+            kind,
+            turns: turns.rem_euclid(4) as u8,
+        }
+    }
+
+    pub fn face(face: Face, turns: i32) -> Self {
+        Self::new(MoveKind::Face(face), turns)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.turns == 0
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self::new(self.kind, 4 - self.turns as i32)
+    }
+
+    pub fn axis(&self) -> Axis {
+        match self.kind {
+            MoveKind::Face(face) | MoveKind::Wide(face) => face.axis(),
+            MoveKind::Slice(slice) => slice.axis(),
+            MoveKind::Rotation(axis) => axis,
+        }
+    }
+
+    /// Split the move into single-layer moves (outer faces and slices) with the same effect.
+    /// Wide moves become face + slice, rotations become face + slice + opposite face.
+    pub fn layer_moves(&self) -> Vec<Move> {
+        let turns = self.turns as i32;
+        let slice_turns = |face: Face| {
+            let slice = Slice::from_axis(face.axis());
+            let direction = if slice.reference_face() == face {
+                1
+            } else {
+                -1
+            };
+            Move::new(MoveKind::Slice(slice), direction * turns)
+        };
+        match self.kind {
+            MoveKind::Face(_) | MoveKind::Slice(_) => vec![*self],
+            MoveKind::Wide(face) => vec![Move::face(face, turns), slice_turns(face)],
+            MoveKind::Rotation(axis) => {
+                let face = match axis {
+                    Axis::X => Face::R,
+                    Axis::Y => Face::U,
+                    Axis::Z => Face::F,
+                };
+                vec![
+                    Move::face(face, turns),
+                    slice_turns(face),
+                    Move::face(face.opposite(), -turns),
+                ]
+            }
+        }
+    }
+
+    /// Layer coordinates (-1, 0, 1) along the move axis that this move turns
+    fn layers(&self) -> &'static [i32] {
+        match self.kind {
+            MoveKind::Face(face) => {
+                if face.is_positive() {
+                    &[1]
+                } else {
+                    &[-1]
+                }
+            }
+            MoveKind::Wide(face) => {
+                if face.is_positive() {
+                    &[0, 1]
+                } else {
+                    &[-1, 0]
+                }
+            }
+            MoveKind::Slice(_) => &[0],
+            MoveKind::Rotation(_) => &[-1, 0, 1],
+        }
+    }
+
+    /// Quarter turns around the positive axis (right-hand rule) for one clockwise turn
+    fn axis_direction(&self) -> i32 {
+        let reference = match self.kind {
+            MoveKind::Face(face) | MoveKind::Wide(face) => face,
+            MoveKind::Slice(slice) => slice.reference_face(),
+            MoveKind::Rotation(Axis::X) => Face::R,
+            MoveKind::Rotation(Axis::Y) => Face::U,
+            MoveKind::Rotation(Axis::Z) => Face::F,
+        };
+        // Clockwise as seen from outside the face is a negative turn around its outward normal
+        if reference.is_positive() { -1 } else { 1 }
+    }
+
+    /// Quarter turns of the move around its positive axis (right-hand rule)
+    pub fn axis_quarter_turns(&self) -> i32 {
+        self.axis_direction() * self.turns as i32
+    }
+
+    /// Where every facelet ends up after this move: `permutation[from] = to`
+    pub fn facelet_permutation(&self) -> [usize; FACELET_COUNT] {
+        let axis = self.axis();
+        let quarter_turns = self.axis_quarter_turns();
+        let layers = self.layers();
+
+        let mut permutation = [0; FACELET_COUNT];
+        for (index, target) in permutation.iter_mut().enumerate() {
+            let (position, normal) = facelet_geometry(index);
+            *target = if layers.contains(&position[axis.index()]) {
+                facelet_index_from_geometry(
+                    axis.rotate(position, quarter_turns),
+                    axis.rotate(normal, quarter_turns),
+                )
+            } else {
+                index
+            };
+        }
+        permutation
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            MoveKind::Face(face) => write!(f, "{}", face)?,
+            MoveKind::Wide(face) => write!(f, "{}w", face)?,
+            MoveKind::Slice(slice) => write!(f, "{:?}", slice)?,
+            MoveKind::Rotation(Axis::X) => write!(f, "x")?,
+            MoveKind::Rotation(Axis::Y) => write!(f, "y")?,
+            MoveKind::Rotation(Axis::Z) => write!(f, "z")?,
+        }
+        match self.turns {
+            2 => write!(f, "2"),
+            3 => write!(f, "'"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Position of the cubie carrying a facelet (-1, 0, 1 per axis) and the outward normal of the facelet
+fn facelet_geometry(index: usize) -> ([i32; 3], [i32; 3]) {
+    let face = Face::from_index(index / 9).expect("facelet index out of range");
+    let row = (index % 9 / 3) as i32;
+    let col = (index % 3) as i32;
+    let position = match face {
+        Face::U => [col - 1, 1, row - 1],
+        Face::D => [col - 1, -1, 1 - row],
+        Face::F => [col - 1, 1 - row, 1],
+        Face::B => [1 - col, 1 - row, -1],
+        Face::R => [1, 1 - row, 1 - col],
+        Face::L => [-1, 1 - row, col - 1],
+    };
+    (position, face.normal())
+}
+
+fn facelet_index_from_geometry(position: [i32; 3], normal: [i32; 3]) -> usize {
+    facelet_index_at(Face::from_normal(normal), position)
+}
+
+/// Facelet index of the sticker on `face` of the cubie at grid `position` (-1, 0, 1 per axis)
+pub fn facelet_index_at(face: Face, position: [i32; 3]) -> usize {
+    let [x, y, z] = position;
+    let (col, row) = match face {
+        Face::U => (x + 1, z + 1),
+        Face::D => (x + 1, 1 - z),
+        Face::F => (x + 1, 1 - y),
+        Face::B => (1 - x, 1 - y),
+        Face::R => (1 - z, 1 - y),
+        Face::L => (z + 1, 1 - y),
+    };
+    face.facelet_offset() + (row * 3 + col) as usize
+}
+
+/// Errors produced when a facelet state cannot be turned into a cubie state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeModelError {
+    InvalidFaceletLength,
+    InvalidFaceletCharacter,
+    Incomplete,
+    IncorrectColorCount,
+    MissingEdges,
+    EdgeFlipError,
+    MissingCorners,
+    CornerTwistError,
+    ParityError,
+}
+
+impl CubeModelError {
+    pub fn description(&self) -> &'static str {
+        match self {
+            CubeModelError::InvalidFaceletLength => "Invalid facelet string: incorrect length",
+            CubeModelError::InvalidFaceletCharacter => {
+                "Invalid facelet string: contains invalid characters"
+            }
+            CubeModelError::Incomplete => "Incomplete cube: not all faces are colored",
+            CubeModelError::IncorrectColorCount => {
+                "Invalid cube: there are not exactly nine facelets of each color"
+            }
+            CubeModelError::MissingEdges => "Invalid cube: not all 12 edges exist exactly once",
+            CubeModelError::EdgeFlipError => "Invalid cube: one edge has to be flipped",
+            CubeModelError::MissingCorners => "Invalid cube: not all 8 corners exist exactly once",
+            CubeModelError::CornerTwistError => "Invalid cube: one corner has to be twisted",
+            CubeModelError::ParityError => {
+                "Invalid cube: two corners or two edges have to be exchanged"
+            }
+        }
+    }
+}
+
+impl fmt::Display for CubeModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl std::error::Error for CubeModelError {}
+
+/// Facelet representation of a (possibly partially colored) cube
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CubeModel {
+    facelets: [Option<Face>; FACELET_COUNT],
+}
+
+impl CubeModel {
+    /// A cube with no colored facelets
+    pub fn empty() -> Self {
+        Self {
+            facelets: [None; FACELET_COUNT],
+        }
+    }
+
+    pub fn solved() -> Self {
+        let mut model = Self::empty();
+        for (index, facelet) in model.facelets.iter_mut().enumerate() {
+            *facelet = Face::from_index(index / 9);
+        }
+        model
+    }
+
+    /// Parse a 54 character facelet string; ' ' and '.' mark uncolored facelets
+    pub fn from_facelets(facelets: &str) -> Result<Self, CubeModelError> {
+        if facelets.chars().count() != FACELET_COUNT {
+            return Err(CubeModelError::InvalidFaceletLength);
+        }
+        let mut model = Self::empty();
+        for (index, c) in facelets.chars().enumerate() {
+            model.facelets[index] = match c {
+                ' ' | '.' => None,
+                _ => Some(Face::from_char(c).ok_or(CubeModelError::InvalidFaceletCharacter)?),
+            };
+        }
+        Ok(model)
+    }
+
+    pub fn from_cubie(cubie: &CubieCube) -> Self {
+        let mut model = Self::solved();
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let piece = cubie.cp[position] as usize;
+            let twist = cubie.co[position] as usize;
+            for n in 0..3 {
+                model.facelets[facelets[(n + twist) % 3]] = Some(CORNER_COLORS[piece][n]);
+            }
+        }
+        for (position, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let piece = cubie.ep[position] as usize;
+            let flip = cubie.eo[position] as usize;
+            for n in 0..2 {
+                model.facelets[facelets[(n + flip) % 2]] = Some(EDGE_COLORS[piece][n]);
+            }
+        }
+        model
+    }
+
+    pub fn facelet(&self, index: usize) -> Option<Face> {
+        self.facelets.get(index).copied().flatten()
+    }
+
+    pub fn set_facelet(&mut self, index: usize, face: Option<Face>) {
+        if index < FACELET_COUNT {
+            self.facelets[index] = face;
+        }
+    }
+
+    pub fn facelets(&self) -> &[Option<Face>; FACELET_COUNT] {
+        &self.facelets
+    }
+
+    /// Remove all colors
+    pub fn clear(&mut self) {
+        self.facelets = [None; FACELET_COUNT];
+    }
+
+    /// Number of facelets colored with the color of `face`
+    pub fn count(&self, face: Face) -> usize {
+        self.facelets.iter().filter(|&&f| f == Some(face)).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.facelets.iter().all(Option::is_some)
+    }
+
+    /// Every face shows a single color (in any whole-cube orientation)
+    pub fn is_solved(&self) -> bool {
+        self.is_complete()
+            && self
+                .facelets
+                .chunks(9)
+                .all(|face| face.iter().all(|&f| f == face[4]))
+    }
+
+    pub fn apply_move(&mut self, mv: Move) {
+        if mv.is_identity() {
+            return;
+        }
+        let permutation = mv.facelet_permutation();
+        let mut moved = [None; FACELET_COUNT];
+        for (from, &to) in permutation.iter().enumerate() {
+            moved[to] = self.facelets[from];
+        }
+        self.facelets = moved;
+    }
+
+    pub fn apply_moves<'a>(&mut self, moves: impl IntoIterator<Item = &'a Move>) {
+        for mv in moves {
+            self.apply_move(*mv);
+        }
+    }
+
+    /// Facelet string with ' ' for uncolored facelets
+    pub fn to_facelet_string(&self) -> String {
+        self.facelets
+            .iter()
+            .map(|f| f.map(Face::to_char).unwrap_or(' '))
+            .collect()
+    }
+
+    /// Facelet string relabelled so that every center carries the letter of the face it sits on.
+    ///
+    /// Slice moves and whole-cube rotations move the centers, while min2phase
+    /// expects U to be the color of the up center and so on. Colors whose
+    /// center is not painted yet are kept as they are.
+    pub fn normalized_facelet_string(&self) -> String {
+        let mut mapping: [Option<Face>; 6] = [None; 6];
+        for (face, &center) in Face::ALL.iter().zip(CENTER_FACELETS.iter()) {
+            if let Some(color) = self.facelets[center] {
+                mapping[color.index()] = Some(*face);
+            }
+        }
+
+        self.facelets
+            .iter()
+            .map(|f| match f {
+                Some(color) => mapping[color.index()].unwrap_or(*color).to_char(),
+                None => ' ',
+            })
+            .collect()
+    }
+
+    /// Cubie representation of the state relative to the centers
+    pub fn to_cubie(&self) -> Result<CubieCube, CubeModelError> {
+        if !self.is_complete() {
+            return Err(CubeModelError::Incomplete);
+        }
+        CubieCube::from_facelets(&self.normalized_facelet_string())
+    }
+}
+
+impl Default for CubeModel {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl fmt::Display for CubeModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_facelet_string().replace(' ', "."))
+    }
+}
+
+/// Corner names in cubie order
+pub const CORNER_NAMES: [&str; 8] = ["URF", "UFL", "ULB", "UBR", "DFR", "DLF", "DBL", "DRB"];
+
+/// Edge names in cubie order
+pub const EDGE_NAMES: [&str; 12] = [
+    "UR", "UF", "UL", "UB", "DR", "DF", "DL", "DB", "FR", "FL", "BL", "BR",
+];
+
+/// Facelet indices of each corner position, starting with the U/D sticker, clockwise
+pub const CORNER_FACELETS: [[usize; 3]; 8] = [
+    [8, 9, 20],
+    [6, 18, 38],
+    [0, 36, 47],
+    [2, 45, 11],
+    [29, 26, 15],
+    [27, 44, 24],
+    [33, 53, 42],
+    [35, 17, 51],
+];
+
+/// Facelet indices of each edge position, starting with the U/D (or F/B) sticker
+pub const EDGE_FACELETS: [[usize; 2]; 12] = [
+    [5, 10],
+    [7, 19],
+    [3, 37],
+    [1, 46],
+    [32, 16],
+    [28, 25],
+    [30, 43],
+    [34, 52],
+    [23, 12],
+    [21, 41],
+    [50, 39],
+    [48, 14],
+];
+
+const CORNER_COLORS: [[Face; 3]; 8] = [
+    [Face::U, Face::R, Face::F],
+    [Face::U, Face::F, Face::L],
+    [Face::U, Face::L, Face::B],
+    [Face::U, Face::B, Face::R],
+    [Face::D, Face::F, Face::R],
+    [Face::D, Face::L, Face::F],
+    [Face::D, Face::B, Face::L],
+    [Face::D, Face::R, Face::B],
+];
+
+const EDGE_COLORS: [[Face; 2]; 12] = [
+    [Face::U, Face::R],
+    [Face::U, Face::F],
+    [Face::U, Face::L],
+    [Face::U, Face::B],
+    [Face::D, Face::R],
+    [Face::D, Face::F],
+    [Face::D, Face::L],
+    [Face::D, Face::B],
+    [Face::F, Face::R],
+    [Face::F, Face::L],
+    [Face::B, Face::L],
+    [Face::B, Face::R],
+];
+
+/// Cubie representation: which piece sits in every position and how it is oriented.
+///
+/// `cp[i]`/`ep[i]` is the piece in corner/edge position `i`, `co`/`eo` its
+/// twist (0-2) and flip (0-1). Centers are fixed, so the state is always
+/// relative to the centers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CubieCube {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+    pub ep: [u8; 12],
+    pub eo: [u8; 12],
+}
+
+impl CubieCube {
+    pub const SOLVED: CubieCube = CubieCube {
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        co: [0; 8],
+        ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        eo: [0; 12],
+    };
+
+    /// Parse a complete facelet string whose centers are in standard position
+    pub fn from_facelets(facelets: &str) -> Result<Self, CubeModelError> {
+        let chars: Vec<char> = facelets.chars().collect();
+        if chars.len() != FACELET_COUNT {
+            return Err(CubeModelError::InvalidFaceletLength);
+        }
+        let mut faces = [Face::U; FACELET_COUNT];
+        let mut counts = [0; 6];
+        for (index, &c) in chars.iter().enumerate() {
+            let face = match c {
+                ' ' | '.' => return Err(CubeModelError::Incomplete),
+                _ => Face::from_char(c).ok_or(CubeModelError::InvalidFaceletCharacter)?,
+            };
+            faces[index] = face;
+            counts[face.index()] += 1;
+        }
+        if counts.iter().any(|&count| count != 9) {
+            return Err(CubeModelError::IncorrectColorCount);
+        }
+
+        let mut cube = CubieCube {
+            cp: [u8::MAX; 8],
+            co: [0; 8],
+            ep: [u8::MAX; 12],
+            eo: [0; 12],
+        };
+
+        for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+            let Some(twist) = (0..3).find(|&t| matches!(faces[facelets[t]], Face::U | Face::D))
+            else {
+                return Err(CubeModelError::MissingCorners);
+            };
+            let first = faces[facelets[(twist + 1) % 3]];
+            let second = faces[facelets[(twist + 2) % 3]];
+            let Some(piece) = CORNER_COLORS
+                .iter()
+                .position(|colors| colors[1] == first && colors[2] == second)
+            else {
+                return Err(CubeModelError::MissingCorners);
+            };
+            cube.cp[position] = piece as u8;
+            cube.co[position] = twist as u8;
+        }
+
+        for (position, facelets) in EDGE_FACELETS.iter().enumerate() {
+            let stickers = [faces[facelets[0]], faces[facelets[1]]];
+            let found = EDGE_COLORS.iter().enumerate().find_map(|(piece, colors)| {
+                if stickers == *colors {
+                    Some((piece, 0))
+                } else if stickers == [colors[1], colors[0]] {
+                    Some((piece, 1))
+                } else {
+                    None
+                }
+            });
+            let Some((piece, flip)) = found else {
+                return Err(CubeModelError::MissingEdges);
+            };
+            cube.ep[position] = piece as u8;
+            cube.eo[position] = flip;
+        }
+
+        cube.verify()?;
+        Ok(cube)
+    }
+
+    /// Check that the cube is reachable with legal moves
+    pub fn verify(&self) -> Result<(), CubeModelError> {
+        let mut seen_edges = [false; 12];
+        for &piece in &self.ep {
+            match seen_edges.get_mut(piece as usize) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(CubeModelError::MissingEdges),
+            }
+        }
+        if self.eo.iter().map(|&o| o as u32).sum::<u32>() % 2 != 0 {
+            return Err(CubeModelError::EdgeFlipError);
+        }
+
+        let mut seen_corners = [false; 8];
+        for &piece in &self.cp {
+            match seen_corners.get_mut(piece as usize) {
+                Some(seen) if !*seen => *seen = true,
+                _ => return Err(CubeModelError::MissingCorners),
+            }
+        }
+        if self.co.iter().map(|&o| o as u32).sum::<u32>() % 3 != 0 {
+            return Err(CubeModelError::CornerTwistError);
+        }
+
+        if self.corner_parity() != self.edge_parity() {
+            return Err(CubeModelError::ParityError);
+        }
+        Ok(())
+    }
+
+    pub fn to_facelets(&self) -> String {
+        CubeModel::from_cubie(self).to_facelet_string()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Self::SOLVED
+    }
+
+    /// State after applying `other` to this state
+    pub fn multiply(&self, other: &CubieCube) -> CubieCube {
+        let mut result = CubieCube::SOLVED;
+        for i in 0..8 {
+            let from = other.cp[i] as usize;
+            result.cp[i] = self.cp[from];
+            result.co[i] = (self.co[from] + other.co[i]) % 3;
+        }
+        for i in 0..12 {
+            let from = other.ep[i] as usize;
+            result.ep[i] = self.ep[from];
+            result.eo[i] = (self.eo[from] + other.eo[i]) % 2;
+        }
+        result
+    }
+
+    pub fn inverse(&self) -> CubieCube {
+        let mut result = CubieCube::SOLVED;
+        for i in 0..8 {
+            let piece = self.cp[i] as usize;
+            result.cp[piece] = i as u8;
+        }
+        for i in 0..8 {
+            let piece = self.cp[i] as usize;
+            result.co[piece] = (3 - self.co[i]) % 3;
+        }
+        for i in 0..12 {
+            let piece = self.ep[i] as usize;
+            result.ep[piece] = i as u8;
+            result.eo[piece] = self.eo[i];
+        }
+        result
+    }
+
+    /// Apply a move; slice moves and rotations are taken relative to the centers
+    pub fn apply_move(&mut self, mv: Move) {
+        if mv.is_identity() {
+            return;
+        }
+        if let MoveKind::Face(face) = mv.kind {
+            *self = self.multiply(&face_move_cube(face, mv.turns));
+        } else {
+            let mut model = CubeModel::from_cubie(self);
+            model.apply_move(mv);
+            *self = model.to_cubie().expect("moves keep a valid cube valid");
+        }
+    }
+
+    pub fn apply_moves<'a>(&mut self, moves: impl IntoIterator<Item = &'a Move>) {
+        for mv in moves {
+            self.apply_move(*mv);
+        }
+    }
+
+    /// true for an odd corner permutation
+    pub fn corner_parity(&self) -> bool {
+        permutation_parity(&self.cp)
+    }
+
+    /// true for an odd edge permutation
+    pub fn edge_parity(&self) -> bool {
+        permutation_parity(&self.ep)
+    }
+}
+
+impl Default for CubieCube {
+    fn default() -> Self {
+        Self::SOLVED
+    }
+}
+
+fn permutation_parity(permutation: &[u8]) -> bool {
+    let mut odd = false;
+    for i in 0..permutation.len() {
+        for j in i + 1..permutation.len() {
+            if permutation[i] > permutation[j] {
+                odd = !odd;
+            }
+        }
+    }
+    odd
+}
+
+/// Cubie state of the solved cube after a single face turn
+pub fn face_move_cube(face: Face, turns: u8) -> CubieCube {
+    static TABLE: OnceLock<[CubieCube; 18]> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = [CubieCube::SOLVED; 18];
+        for face in Face::ALL {
+            for turns in 1..=3 {
+                let mut model = CubeModel::solved();
+                model.apply_move(Move::face(face, turns));
+                table[face.index() * 3 + turns as usize - 1] = model
+                    .to_cubie()
+                    .expect("face turns keep the solved cube valid");
+            }
+        }
+        table
+    });
+    match turns % 4 {
+        0 => CubieCube::SOLVED,
+        t => table[face.index() * 3 + t as usize - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// R U R' U' F2 D' L B' S M' y: every kind of move, scrambling every piece
+    fn scramble() -> Vec<Move> {
+        vec![
+            Move::face(Face::R, 1),
+            Move::face(Face::U, 1),
+            Move::face(Face::R, 3),
+            Move::face(Face::U, 3),
+            Move::face(Face::F, 2),
+            Move::face(Face::D, 3),
+            Move::face(Face::L, 1),
+            Move::face(Face::B, 3),
+            Move::new(MoveKind::Slice(Slice::S), 1),
+            Move::new(MoveKind::Slice(Slice::M), 3),
+            Move::new(MoveKind::Wide(Face::R), 1),
+            Move::new(MoveKind::Rotation(Axis::Y), 1),
+        ]
+    }
+
+    #[test]
+    fn four_quarter_turns_are_the_identity() {
+        for face in Face::ALL {
+            let mut model = CubeModel::solved();
+            let mut cube = CubieCube::SOLVED;
+            for _ in 0..4 {
+                model.apply_move(Move::face(face, 1));
+                cube.apply_move(Move::face(face, 1));
+            }
+            assert_eq!(model, CubeModel::solved(), "{face}4");
+            assert!(cube.is_solved(), "{face}4");
+        }
+    }
+
+    #[test]
+    fn move_then_inverse_is_the_identity() {
+        let mut moves = scramble();
+        moves.push(Move::new(MoveKind::Rotation(Axis::X), 1));
+        for mv in moves {
+            let mut model = CubeModel::solved();
+            model.apply_move(mv);
+            assert_ne!(model, CubeModel::solved(), "{mv}");
+            model.apply_move(mv.inverse());
+            assert_eq!(model, CubeModel::solved(), "{mv} {}", mv.inverse());
+        }
+    }
+
+    #[test]
+    fn facelets_and_cubies_round_trip() {
+        let mut model = CubeModel::solved();
+        model.apply_moves(&scramble());
+        let cube = model.to_cubie().unwrap();
+        assert!(!cube.is_solved());
+        assert_eq!(cube.verify(), Ok(()));
+
+        let facelets = cube.to_facelets();
+        assert_eq!(facelets, model.normalized_facelet_string());
+        assert_eq!(CubieCube::from_facelets(&facelets), Ok(cube));
+        assert_eq!(
+            CubeModel::from_facelets(&facelets).unwrap().to_cubie(),
+            Ok(cube)
+        );
+    }
+
+    #[test]
+    fn unreachable_states_are_rejected() {
+        let mut swapped = CubieCube::SOLVED;
+        swapped.cp.swap(0, 1);
+        let mut twisted = CubieCube::SOLVED;
+        twisted.co[0] = 1;
+        let mut flipped = CubieCube::SOLVED;
+        flipped.eo[0] = 1;
+
+        for (cube, error) in [
+            (swapped, CubeModelError::ParityError),
+            (twisted, CubeModelError::CornerTwistError),
+            (flipped, CubeModelError::EdgeFlipError),
+        ] {
+            assert_eq!(cube.verify(), Err(error));
+            assert_eq!(CubieCube::from_facelets(&cube.to_facelets()), Err(error));
+        }
+    }
+
+    #[test]
+    fn duplicate_stickers_are_rejected() {
+        let with = |changes: &[(usize, char)]| {
+            let mut facelets: Vec<char> = SOLVED_FACELETS.chars().collect();
+            for &(index, c) in changes {
+                facelets[index] = c;
+            }
+            CubieCube::from_facelets(&facelets.into_iter().collect::<String>())
+        };
+
+        assert_eq!(with(&[(0, 'R')]), Err(CubeModelError::IncorrectColorCount));
+        // UF shows two F stickers and FR two U stickers; the color counts still match
+        assert_eq!(
+            with(&[(7, 'F'), (23, 'U')]),
+            Err(CubeModelError::MissingEdges)
+        );
+        // URF shows F twice, UFL shows U twice
+        assert_eq!(
+            with(&[(8, 'F'), (18, 'U')]),
+            Err(CubeModelError::MissingCorners)
+        );
+        assert_eq!(
+            with(&[(0, 'X')]),
+            Err(CubeModelError::InvalidFaceletCharacter)
+        );
+        assert_eq!(
+            CubieCube::from_facelets("UUU"),
+            Err(CubeModelError::InvalidFaceletLength)
+        );
+    }
+}
@@ -54,30 +54,29 @@ pub enum MoveType {
     Double,           // 2
 }
 
-/// Parse move notation and return face and move type
+/// Parse move notation and return face and move type (outer face turns only)
 pub fn parse_move_notation(notation: &str) -> Option<(CubeFace, MoveType)> {
-    if notation.is_empty() {
+    use crate::cube_model::{Face, MoveKind};
+
+    let mv = crate::notation::parse_move(notation).ok()?;
+    let MoveKind::Face(face) = mv.kind else {
         return None;
-    }
+    };
 
-    let face = match notation.chars().next()? {
-        'F' => CubeFace::Front,
-        'B' => CubeFace::Back,
-        'R' => CubeFace::Right,
-        'L' => CubeFace::Left,
-        'U' => CubeFace::Up,
-        'D' => CubeFace::Down,
-        _ => return None,
+    let face = match face {
+        Face::F => CubeFace::Front,
+        Face::B => CubeFace::Back,
+        Face::R => CubeFace::Right,
+        Face::L => CubeFace::Left,
+        Face::U => CubeFace::Up,
+        Face::D => CubeFace::Down,
     };
 
-    let move_type = if notation.len() > 1 {
-        match &notation[1..] {
-            "'" => MoveType::CounterClockwise,
-            "2" => MoveType::Double,
-            _ => return None,
-        }
-    } else {
-        MoveType::Clockwise
+    let move_type = match mv.turns {
+        1 => MoveType::Clockwise,
+        2 => MoveType::Double,
+        3 => MoveType::CounterClockwise,
+        _ => return None,
     };
 
     Some((face, move_type))
@@ -0,0 +1,355 @@
+//! Rough ergonomics scoring of move sequences for two-handed execution, and a rewrite
+//! of solutions into a form that is easier on the hands.
+//!
+//! The model assumes the usual grip with the thumbs on F: R, U, L and F are
+//! comfortable, B and D turns are awkward, and switching to or from front/back
+//! turns needs a regrip.
+
+use crate::cube_model::{Axis, Face, Move, MoveKind, Slice};
+use crate::move_sequence::{Metric, MoveSequence, face_turns_and_rotation};
+use crate::solvers::Solution;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Extra cost of an awkward move (B, D and the slices next to them)
+const AWKWARD_MOVE_COST: f32 = 1.5;
+
+/// Extra cost of a regrip
+const REGRIP_COST: f32 = 2.0;
+
+/// Extra cost of a whole-cube rotation
+const ROTATION_COST: f32 = 2.0;
+
+/// Extra cost of a wide turn, which is slower than a face turn and leaves the cube
+/// held differently
+const WIDE_MOVE_COST: f32 = 0.5;
+
+/// Weights of the ergonomics score. On top of these every move costs its length in
+/// the half turn metric.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostModel {
+    /// Extra cost of a B, D, E or S turn
+    pub awkward_move: f32,
+    /// Extra cost of a change between the home grip and the front grip
+    pub regrip: f32,
+    /// Extra cost of a whole-cube rotation
+    pub rotation: f32,
+    /// Extra cost of a wide turn
+    pub wide_move: f32,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self {
+            awkward_move: AWKWARD_MOVE_COST,
+            regrip: REGRIP_COST,
+            rotation: ROTATION_COST,
+            wide_move: WIDE_MOVE_COST,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ErgonomicsReport {
+    /// Length in the half turn metric
+    pub length: usize,
+    /// B, D, E and S turns (and their wide versions)
+    pub awkward_moves: usize,
+    /// Estimated number of times the hands have to change grip
+    pub regrips: usize,
+    /// Whole-cube rotations
+    pub rotations: usize,
+    /// Wide turns
+    pub wide_moves: usize,
+    /// Combined cost; lower is easier to execute
+    pub score: f32,
+}
+
+impl fmt::Display for ErgonomicsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} moves, {} awkward, {} regrips, {} rotations, score {:.1}",
+            self.length, self.awkward_moves, self.regrips, self.rotations, self.score
+        )
+    }
+}
+
+fn is_awkward(mv: &Move) -> bool {
+    match mv.kind {
+        MoveKind::Face(face) | MoveKind::Wide(face) => matches!(face, Face::B | Face::D),
+        MoveKind::Slice(slice) => slice.axis() != Axis::X,
+        MoveKind::Rotation(_) => false,
+    }
+}
+
+/// Front/back turns are done with the thumbs or after turning the grip
+fn needs_front_grip(mv: &Move) -> bool {
+    mv.axis() == Axis::Z && !matches!(mv.kind, MoveKind::Rotation(_))
+}
+
+/// Running report of a sequence, with the grip the hands are left in
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    report: ErgonomicsReport,
+    front_grip: bool,
+}
+
+impl Tally {
+    fn add(&mut self, mv: &Move, costs: &CostModel) {
+        let report = &mut self.report;
+        let length = Metric::Htm.move_cost(mv);
+        report.length += length;
+        report.score += length as f32;
+
+        if matches!(mv.kind, MoveKind::Rotation(_)) {
+            report.rotations += 1;
+            report.score += costs.rotation;
+            return;
+        }
+        if matches!(mv.kind, MoveKind::Wide(_)) {
+            report.wide_moves += 1;
+            report.score += costs.wide_move;
+        }
+        if is_awkward(mv) {
+            report.awkward_moves += 1;
+            report.score += costs.awkward_move;
+        }
+        let wants_front_grip = needs_front_grip(mv);
+        if wants_front_grip != self.front_grip {
+            report.regrips += 1;
+            report.score += costs.regrip;
+            self.front_grip = wants_front_grip;
+        }
+    }
+}
+
+pub fn evaluate(sequence: &MoveSequence) -> ErgonomicsReport {
+    evaluate_with(sequence, &CostModel::default())
+}
+
+/// Score `sequence` with the weights in `costs`
+pub fn evaluate_with(sequence: &MoveSequence, costs: &CostModel) -> ErgonomicsReport {
+    let mut tally = Tally::default();
+    for mv in &sequence.moves {
+        tally.add(mv, costs);
+    }
+    tally.report
+}
+
+/// Rewrite `sequence` with rotations, wide moves and slices so it scores lowest under
+/// `costs`. The result leaves every piece in the same place relative to the centers,
+/// but the cube may end up held differently.
+pub fn optimize(sequence: &MoveSequence, costs: &CostModel) -> MoveSequence {
+    optimize_spans(sequence, &[sequence.len()], costs)
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Rewrite the moves of `solution` like [`optimize`], keeping its stages
+pub fn optimize_solution(solution: &Solution, costs: &CostModel) -> Solution {
+    if solution.stages.is_empty() {
+        return Solution::from(optimize(&solution.moves, costs));
+    }
+    let spans: Vec<usize> = solution
+        .stages
+        .iter()
+        .map(|stage| stage.move_count)
+        .collect();
+    let rewritten = optimize_spans(&solution.moves, &spans, costs);
+    let mut result = Solution::default();
+    for (stage, moves) in solution.stages.iter().zip(rewritten) {
+        result.push_stage(
+            stage.label.clone(),
+            stage.explanation.clone(),
+            &MoveSequence::new(moves),
+        );
+    }
+    result
+}
+
+/// Where the face each move of the original sequence names is while the cube is held
+/// turned, indexed by `Face::index`
+type Orientation = [Face; 6];
+
+/// One way of executing the first face turns of the sequence
+struct Node {
+    tally: Tally,
+    orientation: Orientation,
+    /// The node this one was reached from, in the layer `consumed` face turns back
+    previous: usize,
+    consumed: usize,
+    /// Moves executed since the previous node
+    moves: Vec<Move>,
+}
+
+/// Rewrite the consecutive spans of `sequence` whose lengths are in `spans`, returning
+/// the moves of each span. A slice never combines face turns of two spans.
+fn optimize_spans(sequence: &MoveSequence, spans: &[usize], costs: &CostModel) -> Vec<Vec<Move>> {
+    // Face turns of the original, with the span each belongs to. Rotations are folded
+    // into later moves, and a prefix of the moves gives a prefix of the face turns.
+    let mut turns: Vec<Move> = Vec::new();
+    let mut span_of: Vec<usize> = Vec::new();
+    let mut end = 0;
+    for (span, &length) in spans.iter().enumerate() {
+        end += length;
+        let prefix = MoveSequence::new(sequence.moves[..end.min(sequence.len())].to_vec());
+        let prefix = prefix.to_face_turns().moves;
+        span_of.resize(prefix.len(), span);
+        turns = prefix;
+    }
+
+    let rotations: Vec<Option<Move>> = std::iter::once(None)
+        .chain([Axis::X, Axis::Y, Axis::Z].into_iter().flat_map(|axis| {
+            (1..=3).map(move |turns| Some(Move::new(MoveKind::Rotation(axis), turns)))
+        }))
+        .collect();
+    let candidates: Vec<(Move, Vec<Move>, Option<Move>)> = Face::ALL
+        .iter()
+        .flat_map(|&face| [MoveKind::Face(face), MoveKind::Wide(face)])
+        .chain([Slice::M, Slice::E, Slice::S].map(MoveKind::Slice))
+        .flat_map(|kind| (1..=3).map(move |turns| Move::new(kind, turns)))
+        .map(|mv| {
+            let mut parts = face_turns_and_rotation(&mv);
+            let rotation = parts
+                .last()
+                .filter(|part| matches!(part.kind, MoveKind::Rotation(_)))
+                .copied();
+            if rotation.is_some() {
+                parts.pop();
+            }
+            (mv, parts, rotation)
+        })
+        .collect();
+
+    // layers[n] holds the cheapest ways of executing the first n face turns, one per
+    // orientation and grip
+    let mut layers: Vec<Vec<Node>> = (0..=turns.len()).map(|_| Vec::new()).collect();
+    let mut index: Vec<HashMap<(Orientation, bool), usize>> =
+        (0..=turns.len()).map(|_| HashMap::new()).collect();
+    layers[0].push(Node {
+        tally: Tally::default(),
+        orientation: Face::ALL,
+        previous: 0,
+        consumed: 0,
+        moves: Vec::new(),
+    });
+
+    for done in 0..turns.len() {
+        for from in 0..layers[done].len() {
+            let (tally, orientation) = (layers[done][from].tally, layers[done][from].orientation);
+            for rotation in &rotations {
+                let held = rotation.map_or(orientation, |r| rotate(orientation, &r));
+                for (mv, parts, turn) in &candidates {
+                    let consumed = parts.len();
+                    let next = done + consumed;
+                    if next > turns.len() || span_of[next - 1] != span_of[done] {
+                        continue;
+                    }
+                    let named: Vec<Move> = parts.iter().map(|part| named(held, part)).collect();
+                    let matches = match named.as_slice() {
+                        [only] => *only == turns[done],
+                        // The two turns of a slice are on one axis, so either order works
+                        [first, second] => {
+                            (*first, *second) == (turns[done], turns[done + 1])
+                                || (*second, *first) == (turns[done], turns[done + 1])
+                        }
+                        _ => false,
+                    };
+                    if !matches {
+                        continue;
+                    }
+
+                    let mut moves = Vec::with_capacity(2);
+                    moves.extend(*rotation);
+                    moves.push(*mv);
+                    let mut next_tally = tally;
+                    for executed in &moves {
+                        next_tally.add(executed, costs);
+                    }
+                    let node = Node {
+                        tally: next_tally,
+                        orientation: turn.map_or(held, |r| rotate(held, &r)),
+                        previous: from,
+                        consumed,
+                        moves,
+                    };
+                    let key = (node.orientation, next_tally.front_grip);
+                    match index[next].get(&key) {
+                        Some(&existing) => {
+                            if next_tally.report.score < layers[next][existing].tally.report.score {
+                                layers[next][existing] = node;
+                            }
+                        }
+                        None => {
+                            index[next].insert(key, layers[next].len());
+                            layers[next].push(node);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut result: Vec<Vec<Move>> = vec![Vec::new(); spans.len()];
+    let Some(mut at) = layers[turns.len()]
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.tally.report.score.total_cmp(&b.tally.report.score))
+        .map(|(at, _)| at)
+    else {
+        return result;
+    };
+    let mut done = turns.len();
+    while done > 0 {
+        let node = &layers[done][at];
+        let span = span_of[done - 1];
+        result[span].splice(0..0, node.moves.iter().copied());
+        at = node.previous;
+        done -= node.consumed;
+    }
+    result
+}
+
+/// The orientation after the whole cube is turned by `rotation`
+fn rotate(orientation: Orientation, rotation: &Move) -> Orientation {
+    orientation.map(|face| face.rotated(rotation.axis(), rotation.axis_quarter_turns()))
+}
+
+/// A face turn of the layer now at `part`, named as in the original sequence
+fn named(orientation: Orientation, part: &Move) -> Move {
+    let MoveKind::Face(position) = part.kind else {
+        return *part;
+    };
+    let face = Face::ALL
+        .into_iter()
+        .find(|face| orientation[face.index()] == position)
+        .unwrap_or(position);
+    Move::face(face, part.turns as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube_model::{CubeModel, CubieCube};
+    use crate::solvers::beginner;
+
+    #[test]
+    fn optimized_staged_solution_still_solves() {
+        let scramble = MoveSequence::parse("R U2 F' L D B2 R' U F2 D' L2 B").unwrap();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&scramble.moves);
+        let solution = beginner::solve(&cube).unwrap();
+        let optimized = optimize_solution(&solution, &CostModel::default());
+
+        // The cube may end up held differently; `to_cubie` reads it by its centers
+        let mut model = CubeModel::from_cubie(&cube);
+        model.apply_moves(&optimized.moves.moves);
+        assert!(model.to_cubie().unwrap().is_solved(), "{}", optimized.moves);
+        let staged: usize = optimized.stages.iter().map(|stage| stage.move_count).sum();
+        assert_eq!(staged, optimized.move_count());
+        assert_eq!(optimized.stages.len(), solution.stages.len());
+    }
+}
@@ -0,0 +1,247 @@
+//! Solver backend that runs a command-line solver as a child process.
+//!
+//! The facelet string is written to the solver's stdin as one line. Every line it
+//! prints to stdout that reads as a move sequence and solves the cube is taken as a
+//! solution; text in parentheses, such as a move count, is ignored. The command comes
+//! from `CUBE_SOLVER_EXTERNAL`, split on whitespace, for example
+//! `CUBE_SOLVER_EXTERNAL="my-solver --facelets"`, and its time limit in seconds from
+//! `CUBE_SOLVER_EXTERNAL_TIMEOUT`.
+//!
+//! On Unix the solver runs in a process group of its own, so stopping it also stops
+//! anything it started.
+
+use crate::cube_model::CubieCube;
+use crate::move_sequence::MoveSequence;
+use crate::solver_backend::{SolveOutput, SolveStats, SolverBackend, SolverError};
+use crate::solver_integration::SolverConfig;
+use crate::solvers::Solution;
+use std::io::{Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How often the child process is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Time a solver gets unless `CUBE_SOLVER_EXTERNAL_TIMEOUT` says otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An executable that reads a facelet string and prints solutions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalBackend {
+    program: String,
+    args: Vec<String>,
+    /// How long the solver may run before it is killed
+    pub timeout: Duration,
+}
+
+impl ExternalBackend {
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// The backend configured by `CUBE_SOLVER_EXTERNAL` and
+    /// `CUBE_SOLVER_EXTERNAL_TIMEOUT`, if the command is set
+    pub fn from_env() -> Option<Self> {
+        let command = std::env::var("CUBE_SOLVER_EXTERNAL").ok()?;
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words.next()?;
+        let mut backend = Self::new(program, words.collect());
+        if let Ok(seconds) = std::env::var("CUBE_SOLVER_EXTERNAL_TIMEOUT") {
+            match parse_timeout(&seconds) {
+                Some(timeout) => backend.timeout = timeout,
+                None => log::warn!(
+                    "Ignoring CUBE_SOLVER_EXTERNAL_TIMEOUT={}, expected seconds",
+                    seconds
+                ),
+            }
+        }
+        Some(backend)
+    }
+
+    /// Run the solver on `facelets` and return what it printed, killing it once
+    /// `timeout` passes or `should_stop` returns true
+    fn run(
+        &self,
+        facelets: &str,
+        timeout: Duration,
+        should_stop: &dyn Fn() -> bool,
+    ) -> Result<String, SolverError> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command.spawn().map_err(|error| {
+            SolverError::Failed(format!("Could not start {}: {}", self.program, error))
+        })?;
+
+        // A solver that exits without reading its input closes the pipe early; its
+        // exit status tells what went wrong
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = writeln!(stdin, "{}", facelets);
+        }
+        let stdout = read_in_background(child.stdout.take());
+        let stderr = read_in_background(child.stderr.take());
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => {}
+                Err(error) => {
+                    kill(&mut child);
+                    return Err(SolverError::Failed(format!(
+                        "Lost track of {}: {}",
+                        self.program, error
+                    )));
+                }
+            }
+            let stopped = should_stop();
+            if stopped || started.elapsed() >= timeout {
+                kill(&mut child);
+                return Err(if stopped {
+                    SolverError::Cancelled
+                } else {
+                    SolverError::NoSolution(format!(
+                        "{} did not answer within {:.1}s",
+                        self.program,
+                        timeout.as_secs_f32()
+                    ))
+                });
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        let stdout = stdout.join().unwrap_or_default();
+        if !status.success() {
+            let stderr = stderr.join().unwrap_or_default();
+            let reason = stderr.lines().find(|line| !line.trim().is_empty());
+            return Err(SolverError::Failed(format!(
+                "{} failed ({}){}",
+                self.program,
+                status,
+                reason
+                    .map(|line| format!(": {}", line.trim()))
+                    .unwrap_or_default()
+            )));
+        }
+        Ok(stdout)
+    }
+}
+
+impl SolverBackend for ExternalBackend {
+    fn name(&self) -> &'static str {
+        "External"
+    }
+
+    fn solve(
+        &self,
+        cube: &CubieCube,
+        config: &SolverConfig,
+        should_stop: &dyn Fn() -> bool,
+        on_improved: &mut dyn FnMut(usize),
+    ) -> Result<SolveOutput, SolverError> {
+        let output = self.run(&cube.to_facelets(), self.timeout, should_stop)?;
+
+        let mut solutions: Vec<Solution> = Vec::new();
+        let mut rejected = None;
+        for line in output.lines() {
+            let text = line.split('(').next().unwrap_or_default().trim();
+            // A blank line is only an answer for a cube that is already solved
+            if text.is_empty() && !cube.is_solved() {
+                continue;
+            }
+            let Ok(moves) = MoveSequence::parse(text) else {
+                continue;
+            };
+            let mut solved = *cube;
+            solved.apply_moves(&moves.moves);
+            if !solved.is_solved() {
+                rejected.get_or_insert(text.to_string());
+                continue;
+            }
+            if solutions.iter().any(|solution| solution.moves == moves) {
+                continue;
+            }
+            if solutions
+                .iter()
+                .all(|solution| moves.len() < solution.move_count())
+            {
+                on_improved(moves.len());
+            }
+            solutions.push(Solution::from(moves));
+        }
+
+        if solutions.is_empty() {
+            return Err(SolverError::Failed(match rejected {
+                Some(moves) => format!(
+                    "{} answered {}, which does not solve the cube",
+                    self.program, moves
+                ),
+                None => format!("{} printed no solution", self.program),
+            }));
+        }
+        solutions.sort_by_key(Solution::move_count);
+        solutions.truncate(config.solution_count);
+        Ok(SolveOutput {
+            solutions,
+            stats: SolveStats {
+                searches: 1,
+                ..Default::default()
+            },
+        })
+    }
+}
+
+/// A positive number of seconds that fits a `Duration`
+fn parse_timeout(seconds: &str) -> Option<Duration> {
+    let seconds = seconds.trim().parse::<f32>().ok()?;
+    if seconds <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f32(seconds).ok()
+}
+
+/// Kill the solver and everything in its process group, which would otherwise keep
+/// its output pipes open
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: kill() only sends a signal; the group is the one the child leads
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Read a pipe to the end on a separate thread, so a full pipe never blocks the child
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut text);
+        }
+        text
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_out_of_range_are_ignored() {
+        assert_eq!(parse_timeout(" 2.5 "), Some(Duration::from_millis(2500)));
+        for seconds in ["0", "-1", "inf", "NaN", "1e30", "soon"] {
+            assert_eq!(parse_timeout(seconds), None, "{seconds}");
+        }
+    }
+}
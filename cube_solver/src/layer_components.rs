@@ -38,16 +38,15 @@ impl LayerFace {
     }
 
     /// Get the rotation direction multiplier (1.0 for counter-clockwise, -1.0 for clockwise)
-    /// Middle layers use the same direction as their positive counterparts
+    /// Middle layers follow standard notation: M turns like L, E like D and S like F
     pub fn rotation_direction(&self) -> f32 {
         match self {
-            LayerFace::Back | LayerFace::Down | LayerFace::Left => 1.0,
-            LayerFace::Front
-            | LayerFace::Right
-            | LayerFace::MiddleZ
+            LayerFace::Back
+            | LayerFace::Down
+            | LayerFace::Left
             | LayerFace::MiddleX
-            | LayerFace::Up
-            | LayerFace::MiddleY => -1.0,
+            | LayerFace::MiddleY => 1.0,
+            LayerFace::Front | LayerFace::Right | LayerFace::MiddleZ | LayerFace::Up => -1.0,
         }
     }
 
@@ -60,6 +59,54 @@ impl LayerFace {
         }
     }
 
+    /// Convert a layer turn into the equivalent move of the logical cube model
+    pub fn to_model_move(&self, move_type: LayerMoveType) -> crate::cube_model::Move {
+        use crate::cube_model::{Face, Move, MoveKind, Slice};
+
+        let kind = match self {
+            LayerFace::Right => MoveKind::Face(Face::R),
+            LayerFace::MiddleX => MoveKind::Slice(Slice::M),
+            LayerFace::Left => MoveKind::Face(Face::L),
+            LayerFace::Up => MoveKind::Face(Face::U),
+            LayerFace::MiddleY => MoveKind::Slice(Slice::E),
+            LayerFace::Down => MoveKind::Face(Face::D),
+            LayerFace::Front => MoveKind::Face(Face::F),
+            LayerFace::MiddleZ => MoveKind::Slice(Slice::S),
+            LayerFace::Back => MoveKind::Face(Face::B),
+        };
+        let turns = match move_type {
+            LayerMoveType::Clockwise => 1,
+            LayerMoveType::Double => 2,
+            LayerMoveType::CounterClockwise => 3,
+        };
+        Move::new(kind, turns)
+    }
+
+    /// Convert a single-layer model move back into a layer turn (None for wide moves, rotations and identities)
+    pub fn from_model_move(mv: crate::cube_model::Move) -> Option<(Self, LayerMoveType)> {
+        use crate::cube_model::{Face, MoveKind, Slice};
+
+        let layer_face = match mv.kind {
+            MoveKind::Face(Face::R) => LayerFace::Right,
+            MoveKind::Slice(Slice::M) => LayerFace::MiddleX,
+            MoveKind::Face(Face::L) => LayerFace::Left,
+            MoveKind::Face(Face::U) => LayerFace::Up,
+            MoveKind::Slice(Slice::E) => LayerFace::MiddleY,
+            MoveKind::Face(Face::D) => LayerFace::Down,
+            MoveKind::Face(Face::F) => LayerFace::Front,
+            MoveKind::Slice(Slice::S) => LayerFace::MiddleZ,
+            MoveKind::Face(Face::B) => LayerFace::Back,
+            MoveKind::Wide(_) | MoveKind::Rotation(_) => return None,
+        };
+        let move_type = match mv.turns {
+            1 => LayerMoveType::Clockwise,
+            2 => LayerMoveType::Double,
+            3 => LayerMoveType::CounterClockwise,
+            _ => return None,
+        };
+        Some((layer_face, move_type))
+    }
+
     /// Convert from the old CubeFace enum (only handles outer layers)
     pub fn from_cube_face(face: crate::cube_moves::CubeFace) -> Self {
         match face {
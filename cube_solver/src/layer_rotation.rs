@@ -1,4 +1,4 @@
-use crate::cube_moves::{CubeMoveEvent, CubeMoveTarget, parse_move_notation};
+use crate::cube_moves::{CubeMoveEvent, CubeMoveTarget};
 use crate::layer_components::{
     CubeLayer, LayerFace, LayerMoveType, LayerRotationAnimation, cube_belongs_to_layer,
 };
@@ -271,39 +271,12 @@ pub fn get_layer_entities(
         .map(|(entity, transform, _)| (entity, *transform))
 }
 
-/// Extended move notation parser that supports middle layer moves
+/// Extended move notation parser that supports middle layer moves.
+/// Only single-layer moves can be animated; wide moves and rotations return None
+/// and must be split with `Move::layer_moves` first.
 pub fn parse_extended_move_notation(notation: &str) -> Option<(LayerFace, LayerMoveType)> {
-    if notation.is_empty() {
-        return None;
-    }
-
-    // Handle standard face moves first
-    if let Some((face, move_type)) = parse_move_notation(notation) {
-        let layer_face = LayerFace::from_cube_face(face);
-        let layer_move_type = LayerMoveType::from_move_type(move_type);
-        return Some((layer_face, layer_move_type));
-    }
-
-    // Handle middle layer moves (M, E, S)
-    let base_char = notation.chars().next()?;
-    let layer_face = match base_char {
-        'M' => LayerFace::MiddleX, // Middle slice (between L and R)
-        'E' => LayerFace::MiddleY, // Equatorial slice (between U and D)
-        'S' => LayerFace::MiddleZ, // Standing slice (between F and B)
-        _ => return None,
-    };
-
-    let move_type = if notation.len() > 1 {
-        match &notation[1..] {
-            "'" => LayerMoveType::CounterClockwise,
-            "2" => LayerMoveType::Double,
-            _ => return None,
-        }
-    } else {
-        LayerMoveType::Clockwise
-    };
-
-    Some((layer_face, move_type))
+    let mv = crate::notation::parse_move(notation).ok()?;
+    LayerFace::from_model_move(mv)
 }
 
 /// System to handle extended move commands (including middle layers)
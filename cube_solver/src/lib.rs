@@ -1,13 +1,23 @@
+pub mod algorithm_library;
 pub mod app;
 pub mod camera;
 pub mod colors;
 pub mod components;
 pub mod cube;
+pub mod cube_model;
 pub mod cube_moves;
+pub mod ergonomics;
+pub mod external_solver;
 pub mod input;
 pub mod layer_components;
 pub mod layer_rotation;
+pub mod move_sequence;
+pub mod notation;
+pub mod permutation;
 pub mod ray_caster;
 pub mod selection;
+pub mod solve_task;
+pub mod solver_backend;
 pub mod solver_integration;
+pub mod solvers;
 pub mod ui;
@@ -0,0 +1,300 @@
+//! Move-sequence algebra: inversion, mirroring, rotation, cancellation and turn metrics.
+
+use crate::cube_model::{Axis, Face, Move, MoveKind, Slice};
+use crate::notation::{self, NotationError};
+use std::fmt;
+
+/// Ways of counting the length of a sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Metric {
+    /// Half turn metric: any turn of an outer layer or wide pair counts 1, slices count 2
+    #[default]
+    Htm,
+    /// Quarter turn metric: half turns count 2, slices count double
+    Qtm,
+    /// Slice turn metric: any turn of any layer set counts 1
+    Stm,
+    /// Execution turn metric: every move, including rotations, counts 1
+    Etm,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [Metric::Htm, Metric::Qtm, Metric::Stm, Metric::Etm];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Htm => "HTM",
+            Metric::Qtm => "QTM",
+            Metric::Stm => "STM",
+            Metric::Etm => "ETM",
+        }
+    }
+
+    /// The next metric in `ALL`, wrapping around
+    pub fn next(self) -> Metric {
+        let index = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Cost of a single move in this metric
+    pub fn move_cost(self, mv: &Move) -> usize {
+        if mv.is_identity() {
+            return 0;
+        }
+        let quarter_turns = if mv.turns == 2 { 2 } else { 1 };
+        match (self, mv.kind) {
+            (Metric::Etm, _) => 1,
+            (_, MoveKind::Rotation(_)) => 0,
+            (Metric::Htm, MoveKind::Slice(_)) => 2,
+            (Metric::Htm, _) => 1,
+            (Metric::Qtm, MoveKind::Slice(_)) => 2 * quarter_turns,
+            (Metric::Qtm, _) => quarter_turns,
+            (Metric::Stm, _) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An ordered list of moves
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MoveSequence {
+    pub moves: Vec<Move>,
+}
+
+impl MoveSequence {
+    pub fn new(moves: Vec<Move>) -> Self {
+        Self { moves }
+    }
+
+    pub fn parse(algorithm: &str) -> Result<Self, NotationError> {
+        notation::parse_algorithm(algorithm).map(Self::new)
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Length of the sequence in the given metric
+    pub fn length(&self, metric: Metric) -> usize {
+        self.moves.iter().map(|mv| metric.move_cost(mv)).sum()
+    }
+
+    /// The sequence that undoes this one
+    pub fn inverse(&self) -> Self {
+        Self::new(self.moves.iter().rev().map(Move::inverse).collect())
+    }
+
+    /// Mirror the sequence across the plane of a middle slice.
+    /// Mirroring across M swaps R and L, across E swaps U and D, across S swaps F and B.
+    pub fn mirror(&self, plane: Slice) -> Self {
+        Self::new(self.moves.iter().map(|mv| mirror_move(mv, plane)).collect())
+    }
+
+    /// Express the sequence for a cube held turned by `rotation`:
+    /// `rotation` + result + inverse rotation has the same effect as the original.
+    /// Non-rotation moves passed as `rotation` are treated as the rotation around their axis.
+    pub fn rotate(&self, rotation: Move) -> Self {
+        let quarter_turns = rotation.axis_quarter_turns();
+        Self::new(
+            self.moves
+                .iter()
+                .map(|mv| rotate_move(mv, rotation.axis(), quarter_turns))
+                .collect(),
+        )
+    }
+
+    /// Outer face turns only, with the same effect up to a whole-cube rotation at the end.
+    /// Wide turns and slices become face turns plus a rotation, and rotations are folded
+    /// into the moves after them.
+    pub fn to_face_turns(&self) -> Self {
+        let mut rotations: Vec<Move> = Vec::new();
+        let mut moves = Vec::with_capacity(self.moves.len());
+        for part in self.moves.iter().flat_map(face_turns_and_rotation) {
+            if let MoveKind::Rotation(_) = part.kind {
+                rotations.push(part);
+                continue;
+            }
+            let fixed = rotations.iter().rev().fold(part, |mv, rotation| {
+                rotate_move(&mv, rotation.axis(), -rotation.axis_quarter_turns())
+            });
+            moves.push(fixed);
+        }
+        Self::new(moves)
+    }
+
+    /// Cancel and merge moves: `R R` -> `R2`, `R R'` -> nothing, `R L R` -> `R2 L`.
+    /// Moves on the same axis commute, so merging looks past them.
+    pub fn simplify(&self) -> Self {
+        let mut result: Vec<Move> = Vec::with_capacity(self.moves.len());
+        for mv in &self.moves {
+            if mv.is_identity() {
+                continue;
+            }
+            let mut merged = false;
+            for index in (0..result.len()).rev() {
+                if result[index].axis() != mv.axis() {
+                    break;
+                }
+                if result[index].kind == mv.kind {
+                    let combined = Move::new(mv.kind, (result[index].turns + mv.turns) as i32);
+                    if combined.is_identity() {
+                        result.remove(index);
+                    } else {
+                        result[index] = combined;
+                    }
+                    merged = true;
+                    break;
+                }
+            }
+            if !merged {
+                result.push(*mv);
+            }
+        }
+        Self::new(result)
+    }
+
+    /// Append another sequence
+    pub fn then(&self, other: &MoveSequence) -> Self {
+        let mut moves = self.moves.clone();
+        moves.extend_from_slice(&other.moves);
+        Self::new(moves)
+    }
+}
+
+impl fmt::Display for MoveSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, mv) in self.moves.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", mv)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<Move>> for MoveSequence {
+    fn from(moves: Vec<Move>) -> Self {
+        Self::new(moves)
+    }
+}
+
+fn mirror_move(mv: &Move, plane: Slice) -> Move {
+    let inverse_turns = -(mv.turns as i32);
+    if mv.axis() != plane.axis() {
+        // Moves parallel to the mirror plane keep their layers and change direction
+        return Move::new(mv.kind, inverse_turns);
+    }
+    match mv.kind {
+        MoveKind::Face(face) => Move::face(face.opposite(), inverse_turns),
+        MoveKind::Wide(face) => Move::new(MoveKind::Wide(face.opposite()), inverse_turns),
+        // Slices and rotations around the mirror normal are unchanged
+        MoveKind::Slice(_) | MoveKind::Rotation(_) => *mv,
+    }
+}
+
+/// Face whose clockwise turn defines the direction of the move
+fn reference_face(kind: MoveKind) -> Face {
+    match kind {
+        MoveKind::Face(face) | MoveKind::Wide(face) => face,
+        MoveKind::Slice(slice) => slice.reference_face(),
+        MoveKind::Rotation(Axis::X) => Face::R,
+        MoveKind::Rotation(Axis::Y) => Face::U,
+        MoveKind::Rotation(Axis::Z) => Face::F,
+    }
+}
+
+/// A move as face turns followed by at most one whole-cube rotation:
+/// `r` = `L x`, `M` = `L' R x'`
+pub fn face_turns_and_rotation(mv: &Move) -> Vec<Move> {
+    let turns = mv.turns as i32;
+    // Rotation in the direction of a clockwise turn of `face`
+    let rotation_like = |face: Face| {
+        let direction = if face.is_positive() { 1 } else { -1 };
+        Move::new(MoveKind::Rotation(face.axis()), direction * turns)
+    };
+    match mv.kind {
+        MoveKind::Face(_) | MoveKind::Rotation(_) => vec![*mv],
+        MoveKind::Wide(face) => vec![Move::face(face.opposite(), turns), rotation_like(face)],
+        MoveKind::Slice(slice) => {
+            let face = slice.reference_face();
+            vec![
+                Move::face(face, -turns),
+                Move::face(face.opposite(), turns),
+                rotation_like(face),
+            ]
+        }
+    }
+}
+
+fn rotate_move(mv: &Move, axis: Axis, quarter_turns: i32) -> Move {
+    let face = reference_face(mv.kind).rotated(axis, quarter_turns);
+    let turns = mv.turns as i32;
+    match mv.kind {
+        MoveKind::Face(_) => Move::face(face, turns),
+        MoveKind::Wide(_) => Move::new(MoveKind::Wide(face), turns),
+        MoveKind::Slice(_) => {
+            let slice = Slice::from_axis(face.axis());
+            let direction = if slice.reference_face() == face {
+                1
+            } else {
+                -1
+            };
+            Move::new(MoveKind::Slice(slice), direction * turns)
+        }
+        MoveKind::Rotation(_) => {
+            let direction = if face.is_positive() { 1 } else { -1 };
+            Move::new(MoveKind::Rotation(face.axis()), direction * turns)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(algorithm: &str) -> MoveSequence {
+        MoveSequence::parse(algorithm).unwrap()
+    }
+
+    #[test]
+    fn simplify_cancels_and_merges() {
+        assert!(sequence("R R'").simplify().is_empty());
+        assert_eq!(sequence("R L R").simplify(), sequence("R2 L"));
+        assert_eq!(sequence("R U U' R'").simplify(), MoveSequence::default());
+        assert_eq!(sequence("R U2 U2 L").simplify(), sequence("R L"));
+        // U and R don't commute, so the Rs stay apart
+        assert_eq!(sequence("R U R").simplify(), sequence("R U R"));
+    }
+
+    #[test]
+    fn metrics() {
+        let slices = sequence("M2 E S'");
+        assert_eq!(slices.length(Metric::Htm), 6);
+        assert_eq!(slices.length(Metric::Qtm), 8);
+        assert_eq!(slices.length(Metric::Stm), 3);
+        assert_eq!(slices.length(Metric::Etm), 3);
+
+        let with_rotation = sequence("R U2 x Rw'");
+        assert_eq!(with_rotation.length(Metric::Htm), 3);
+        assert_eq!(with_rotation.length(Metric::Qtm), 4);
+        assert_eq!(with_rotation.length(Metric::Stm), 3);
+        assert_eq!(with_rotation.length(Metric::Etm), 4);
+    }
+
+    #[test]
+    fn inverse_undoes_the_sequence() {
+        let alg = sequence("R U R' F' (M E)2 x");
+        assert!(alg.then(&alg.inverse()).simplify().is_empty());
+        assert_eq!(alg.inverse().inverse(), alg);
+    }
+}
@@ -0,0 +1,678 @@
+//! WCA/SiGN move notation parser.
+//!
+//! Parses complete algorithm strings into a syntax tree that keeps the source
+//! span of every node, and expands that tree into a flat list of moves.
+//!
+//! Supported syntax:
+//! - face turns `R U' F2`, arbitrary amounts `R3 U2'`, concatenation `RUR'U'`
+//! - wide moves `Rw r 2Rw`, inner slices `2R`, `3Rw` (whole cube)
+//! - slices `M E S` and rotations `x y z`
+//! - groups with repetitions `(R U R' U')3`, inverted groups `(R U)'`
+//! - commutators `[R, U]` and conjugates `[R: U]`
+//! - comments `// to the end of the line` and `/* blocks */`
+
+use crate::cube_model::{Axis, Face, Move, MoveKind, Slice};
+use std::fmt;
+use std::ops::Range;
+
+/// Byte range of a node or error in the source string
+pub type Span = Range<usize>;
+
+/// Largest repetition count or move amount accepted
+const MAX_AMOUNT: u32 = 9999;
+
+/// Longest expansion accepted, protects against `((R U)99)99`-style inputs
+const MAX_EXPANDED_MOVES: usize = 10_000;
+
+/// Node of a parsed algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlgNode {
+    Move {
+        mv: Move,
+        span: Span,
+    },
+    /// `(A)n`; a negative amount repeats the inverse
+    Group {
+        nodes: Vec<AlgNode>,
+        amount: i32,
+        span: Span,
+    },
+    /// `[A, B]n` = (A B A' B')n
+    Commutator {
+        a: Vec<AlgNode>,
+        b: Vec<AlgNode>,
+        amount: i32,
+        span: Span,
+    },
+    /// `[A: B]n` = (A B A')n
+    Conjugate {
+        a: Vec<AlgNode>,
+        b: Vec<AlgNode>,
+        amount: i32,
+        span: Span,
+    },
+}
+
+impl AlgNode {
+    pub fn span(&self) -> Span {
+        match self {
+            AlgNode::Move { span, .. }
+            | AlgNode::Group { span, .. }
+            | AlgNode::Commutator { span, .. }
+            | AlgNode::Conjugate { span, .. } => span.clone(),
+        }
+    }
+}
+
+/// A parsed algorithm
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Alg {
+    pub nodes: Vec<AlgNode>,
+}
+
+impl Alg {
+    pub fn parse(source: &str) -> Result<Self, NotationError> {
+        let mut parser = Parser { source, pos: 0 };
+        let nodes = parser.parse_sequence(Context::TopLevel)?;
+        Ok(Self { nodes })
+    }
+
+    /// Flatten groups, commutators and conjugates into a list of moves.
+    /// Moves that turn by a multiple of four quarter turns are dropped.
+    pub fn expand(&self) -> Result<Vec<Move>, NotationError> {
+        let mut moves = Vec::new();
+        expand_nodes(&self.nodes, &mut moves)?;
+        Ok(moves)
+    }
+}
+
+impl fmt::Display for Alg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_nodes(f, &self.nodes)
+    }
+}
+
+/// Parse and expand an algorithm string
+pub fn parse_algorithm(source: &str) -> Result<Vec<Move>, NotationError> {
+    Alg::parse(source)?.expand()
+}
+
+/// Parse a single move token such as `R'`, `U2`, `Rw` or `x`
+pub fn parse_move(token: &str) -> Result<Move, NotationError> {
+    let alg = Alg::parse(token)?;
+    match alg.nodes.as_slice() {
+        [AlgNode::Move { mv, .. }] => Ok(*mv),
+        [] => Err(NotationError::new(NotationErrorKind::Empty, 0..token.len())),
+        [_, second, ..] => Err(NotationError::new(
+            NotationErrorKind::ExpectedSingleMove,
+            second.span().start..token.len(),
+        )),
+        [other] => Err(NotationError::new(
+            NotationErrorKind::ExpectedSingleMove,
+            other.span(),
+        )),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationErrorKind {
+    Empty,
+    ExpectedSingleMove,
+    UnexpectedCharacter(char),
+    InvalidMove(String),
+    LayerOutOfRange(String),
+    UnclosedGroup,
+    UnclosedBracket,
+    UnmatchedClosing(char),
+    MissingSeparator,
+    UnexpectedSeparator(char),
+    AmountTooLarge,
+    UnterminatedComment,
+    TooLong,
+}
+
+/// Parse error with the byte span of the offending token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationError {
+    pub kind: NotationErrorKind,
+    pub span: Span,
+}
+
+impl NotationError {
+    fn new(kind: NotationErrorKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    /// Render the source line with the offending token underlined
+    pub fn display_with_source(&self, source: &str) -> String {
+        let line_start = source[..self.span.start.min(source.len())]
+            .rfind('\n')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let line_end = source[line_start..]
+            .find('\n')
+            .map(|i| line_start + i)
+            .unwrap_or(source.len());
+        let line = &source[line_start..line_end];
+
+        let padding = source[line_start..self.span.start.min(line_end)]
+            .chars()
+            .count();
+        let width = source[self.span.start.min(line_end)..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        format!(
+            "{}\n{}{} {}",
+            line,
+            " ".repeat(padding),
+            "^".repeat(width),
+            self
+        )
+    }
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            NotationErrorKind::Empty => write!(f, "empty algorithm"),
+            NotationErrorKind::ExpectedSingleMove => write!(f, "expected a single move"),
+            NotationErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character '{}'", c),
+            NotationErrorKind::InvalidMove(token) => write!(f, "invalid move '{}'", token),
+            NotationErrorKind::LayerOutOfRange(token) => {
+                write!(f, "layer out of range for a 3x3x3 cube in '{}'", token)
+            }
+            NotationErrorKind::UnclosedGroup => write!(f, "'(' is never closed"),
+            NotationErrorKind::UnclosedBracket => write!(f, "'[' is never closed"),
+            NotationErrorKind::UnmatchedClosing(c) => write!(f, "unmatched '{}'", c),
+            NotationErrorKind::MissingSeparator => {
+                write!(
+                    f,
+                    "expected ',' (commutator) or ':' (conjugate) inside brackets"
+                )
+            }
+            NotationErrorKind::UnexpectedSeparator(c) => write!(f, "unexpected '{}'", c),
+            NotationErrorKind::AmountTooLarge => {
+                write!(f, "amount is larger than {}", MAX_AMOUNT)
+            }
+            NotationErrorKind::UnterminatedComment => write!(f, "comment is never closed"),
+            NotationErrorKind::TooLong => write!(
+                f,
+                "algorithm expands to more than {} moves",
+                MAX_EXPANDED_MOVES
+            ),
+        }?;
+        write!(f, " at {}..{}", self.span.start, self.span.end)
+    }
+}
+
+impl std::error::Error for NotationError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Context {
+    TopLevel,
+    Group,
+    BracketFirst,
+    BracketSecond,
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), NotationError> {
+        loop {
+            let rest = &self.source[self.pos..];
+            if let Some(c) = self.peek()
+                && c.is_whitespace()
+            {
+                self.bump();
+            } else if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(end) => self.pos += end + 4,
+                    None => {
+                        return Err(NotationError::new(
+                            NotationErrorKind::UnterminatedComment,
+                            self.pos..self.source.len(),
+                        ));
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_sequence(&mut self, context: Context) -> Result<Vec<AlgNode>, NotationError> {
+        let mut nodes = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            let start = self.pos;
+            let Some(c) = self.peek() else {
+                return Ok(nodes);
+            };
+            match c {
+                '(' => nodes.push(self.parse_group()?),
+                '[' => nodes.push(self.parse_bracket()?),
+                ')' if context == Context::Group => return Ok(nodes),
+                ']' if context == Context::BracketSecond => return Ok(nodes),
+                ',' | ':' if context == Context::BracketFirst => return Ok(nodes),
+                ']' if context == Context::BracketFirst => return Ok(nodes),
+                ')' | ']' => {
+                    return Err(NotationError::new(
+                        NotationErrorKind::UnmatchedClosing(c),
+                        start..start + 1,
+                    ));
+                }
+                ',' | ':' => {
+                    return Err(NotationError::new(
+                        NotationErrorKind::UnexpectedSeparator(c),
+                        start..start + 1,
+                    ));
+                }
+                _ => nodes.push(self.parse_move_token()?),
+            }
+        }
+    }
+
+    fn parse_group(&mut self) -> Result<AlgNode, NotationError> {
+        let start = self.pos;
+        self.bump();
+        let nodes = self.parse_sequence(Context::Group)?;
+        if self.bump() != Some(')') {
+            return Err(NotationError::new(
+                NotationErrorKind::UnclosedGroup,
+                start..start + 1,
+            ));
+        }
+        let amount = self.parse_amount()?;
+        Ok(AlgNode::Group {
+            nodes,
+            amount,
+            span: start..self.pos,
+        })
+    }
+
+    fn parse_bracket(&mut self) -> Result<AlgNode, NotationError> {
+        let start = self.pos;
+        self.bump();
+        let a = self.parse_sequence(Context::BracketFirst)?;
+        let separator = match self.bump() {
+            Some(c @ (',' | ':')) => c,
+            Some(_) => {
+                return Err(NotationError::new(
+                    NotationErrorKind::MissingSeparator,
+                    start..self.pos,
+                ));
+            }
+            None => {
+                return Err(NotationError::new(
+                    NotationErrorKind::UnclosedBracket,
+                    start..start + 1,
+                ));
+            }
+        };
+        let b = self.parse_sequence(Context::BracketSecond)?;
+        if self.bump() != Some(']') {
+            return Err(NotationError::new(
+                NotationErrorKind::UnclosedBracket,
+                start..start + 1,
+            ));
+        }
+        let amount = self.parse_amount()?;
+        let span = start..self.pos;
+        Ok(if separator == ',' {
+            AlgNode::Commutator { a, b, amount, span }
+        } else {
+            AlgNode::Conjugate { a, b, amount, span }
+        })
+    }
+
+    fn parse_number(&mut self) -> Result<Option<u32>, NotationError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+        if start == self.pos {
+            return Ok(None);
+        }
+        match self.source[start..self.pos].parse::<u32>() {
+            Ok(n) if n <= MAX_AMOUNT => Ok(Some(n)),
+            _ => Err(NotationError::new(
+                NotationErrorKind::AmountTooLarge,
+                start..self.pos,
+            )),
+        }
+    }
+
+    fn parse_prime(&mut self) -> bool {
+        if matches!(self.peek(), Some('\'' | '’' | '′')) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Amount suffix: `2`, `'`, `2'` or `'2`
+    fn parse_amount(&mut self) -> Result<i32, NotationError> {
+        let prime_first = self.parse_prime();
+        let amount = self.parse_number()?.unwrap_or(1) as i32;
+        let prime = prime_first || self.parse_prime();
+        Ok(if prime { -amount } else { amount })
+    }
+
+    fn parse_move_token(&mut self) -> Result<AlgNode, NotationError> {
+        let start = self.pos;
+        let layer_count = self.parse_number()?;
+        let letter_start = self.pos;
+        let Some(letter) = self.bump() else {
+            return Err(NotationError::new(
+                NotationErrorKind::InvalidMove(self.source[start..].to_string()),
+                start..self.pos,
+            ));
+        };
+        if !letter.is_ascii_alphabetic() {
+            return Err(NotationError::new(
+                NotationErrorKind::UnexpectedCharacter(letter),
+                letter_start..self.pos,
+            ));
+        }
+        let wide_suffix = letter.is_ascii_uppercase() && self.peek() == Some('w');
+        if wide_suffix {
+            self.bump();
+        }
+        let amount = self.parse_amount()?;
+        let span = start..self.pos;
+        let token = &self.source[span.clone()];
+
+        let invalid = || {
+            NotationError::new(
+                NotationErrorKind::InvalidMove(token.to_string()),
+                span.clone(),
+            )
+        };
+        let out_of_range = || {
+            NotationError::new(
+                NotationErrorKind::LayerOutOfRange(token.to_string()),
+                span.clone(),
+            )
+        };
+
+        let (kind, direction) = match letter {
+            'x' | 'y' | 'z' | 'M' | 'E' | 'S' => {
+                if layer_count.is_some() {
+                    return Err(invalid());
+                }
+                let kind = match letter {
+                    'x' => MoveKind::Rotation(Axis::X),
+                    'y' => MoveKind::Rotation(Axis::Y),
+                    'z' => MoveKind::Rotation(Axis::Z),
+                    'M' => MoveKind::Slice(Slice::M),
+                    'E' => MoveKind::Slice(Slice::E),
+                    _ => MoveKind::Slice(Slice::S),
+                };
+                (kind, 1)
+            }
+            _ => {
+                let Some(face) = Face::from_char(letter.to_ascii_uppercase()) else {
+                    return Err(NotationError::new(
+                        NotationErrorKind::UnexpectedCharacter(letter),
+                        letter_start..letter_start + 1,
+                    ));
+                };
+                let wide = wide_suffix || letter.is_ascii_lowercase();
+                match (wide, layer_count) {
+                    (false, None | Some(1)) => (MoveKind::Face(face), 1),
+                    // Second layer from a face is the middle slice
+                    (false, Some(2)) => slice_for_face(face),
+                    // Third layer from a face is the opposite face turned the other way
+                    (false, Some(3)) => (MoveKind::Face(face.opposite()), -1),
+                    (true, Some(1)) => (MoveKind::Face(face), 1),
+                    (true, None | Some(2)) => (MoveKind::Wide(face), 1),
+                    (true, Some(3)) => rotation_for_face(face),
+                    _ => return Err(out_of_range()),
+                }
+            }
+        };
+
+        Ok(AlgNode::Move {
+            mv: Move::new(kind, direction * amount),
+            span,
+        })
+    }
+}
+
+/// The middle slice next to `face`, with the direction that turns it like `face`
+fn slice_for_face(face: Face) -> (MoveKind, i32) {
+    let slice = Slice::from_axis(face.axis());
+    let direction = if slice.reference_face() == face {
+        1
+    } else {
+        -1
+    };
+    (MoveKind::Slice(slice), direction)
+}
+
+/// The whole-cube rotation that turns like `face`
+fn rotation_for_face(face: Face) -> (MoveKind, i32) {
+    let direction = if face.is_positive() { 1 } else { -1 };
+    (MoveKind::Rotation(face.axis()), direction)
+}
+
+fn invert(moves: &[Move]) -> Vec<Move> {
+    moves.iter().rev().map(Move::inverse).collect()
+}
+
+fn repeat_into(
+    sequence: &[Move],
+    amount: i32,
+    span: &Span,
+    out: &mut Vec<Move>,
+) -> Result<(), NotationError> {
+    let repeated = if amount < 0 {
+        invert(sequence)
+    } else {
+        sequence.to_vec()
+    };
+    let count = amount.unsigned_abs() as usize;
+    if out.len() + repeated.len() * count > MAX_EXPANDED_MOVES {
+        return Err(NotationError::new(NotationErrorKind::TooLong, span.clone()));
+    }
+    for _ in 0..count {
+        out.extend_from_slice(&repeated);
+    }
+    Ok(())
+}
+
+fn expand_nodes(nodes: &[AlgNode], out: &mut Vec<Move>) -> Result<(), NotationError> {
+    for node in nodes {
+        match node {
+            AlgNode::Move { mv, span } => {
+                if out.len() >= MAX_EXPANDED_MOVES {
+                    return Err(NotationError::new(NotationErrorKind::TooLong, span.clone()));
+                }
+                if !mv.is_identity() {
+                    out.push(*mv);
+                }
+            }
+            AlgNode::Group {
+                nodes,
+                amount,
+                span,
+            } => {
+                let mut inner = Vec::new();
+                expand_nodes(nodes, &mut inner)?;
+                repeat_into(&inner, *amount, span, out)?;
+            }
+            AlgNode::Commutator { a, b, amount, span } => {
+                let (mut a_moves, mut b_moves) = (Vec::new(), Vec::new());
+                expand_nodes(a, &mut a_moves)?;
+                expand_nodes(b, &mut b_moves)?;
+                let mut inner = a_moves.clone();
+                inner.extend_from_slice(&b_moves);
+                inner.extend(invert(&a_moves));
+                inner.extend(invert(&b_moves));
+                repeat_into(&inner, *amount, span, out)?;
+            }
+            AlgNode::Conjugate { a, b, amount, span } => {
+                let (mut a_moves, mut b_moves) = (Vec::new(), Vec::new());
+                expand_nodes(a, &mut a_moves)?;
+                expand_nodes(b, &mut b_moves)?;
+                let mut inner = a_moves.clone();
+                inner.extend_from_slice(&b_moves);
+                inner.extend(invert(&a_moves));
+                repeat_into(&inner, *amount, span, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_amount(f: &mut fmt::Formatter<'_>, amount: i32) -> fmt::Result {
+    let count = amount.unsigned_abs();
+    if count != 1 {
+        write!(f, "{}", count)?;
+    }
+    if amount < 0 {
+        write!(f, "'")?;
+    }
+    Ok(())
+}
+
+fn write_nodes(f: &mut fmt::Formatter<'_>, nodes: &[AlgNode]) -> fmt::Result {
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        match node {
+            AlgNode::Move { mv, .. } => write!(f, "{}", mv)?,
+            AlgNode::Group { nodes, amount, .. } => {
+                write!(f, "(")?;
+                write_nodes(f, nodes)?;
+                write!(f, ")")?;
+                write_amount(f, *amount)?;
+            }
+            AlgNode::Commutator { a, b, amount, .. } | AlgNode::Conjugate { a, b, amount, .. } => {
+                let separator = if matches!(node, AlgNode::Commutator { .. }) {
+                    ", "
+                } else {
+                    ": "
+                };
+                write!(f, "[")?;
+                write_nodes(f, a)?;
+                write!(f, "{}", separator)?;
+                write_nodes(f, b)?;
+                write!(f, "]")?;
+                write_amount(f, *amount)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube_model::CubeModel;
+
+    fn moves(source: &str) -> Vec<Move> {
+        parse_algorithm(source).unwrap()
+    }
+
+    fn error(source: &str) -> (NotationErrorKind, Span) {
+        let error = parse_algorithm(source).unwrap_err();
+        (error.kind, error.span)
+    }
+
+    /// The cube after `source`, from solved
+    fn state(source: &str) -> CubeModel {
+        let mut model = CubeModel::solved();
+        model.apply_moves(&moves(source));
+        model
+    }
+
+    #[test]
+    fn nodes_keep_their_source_spans() {
+        let alg = Alg::parse("R U' (F2 D)2 [L, B]").unwrap();
+        let spans: Vec<Span> = alg.nodes.iter().map(AlgNode::span).collect();
+        assert_eq!(spans, vec![0..1, 2..4, 5..12, 13..19]);
+    }
+
+    #[test]
+    fn inverted_repeated_group() {
+        assert_eq!(moves("(R U)3'"), moves("U' R' U' R' U' R'"));
+        assert_eq!(moves("(R U)3'"), moves("((R U)')3"));
+        assert_eq!(moves("[R, U]"), moves("R U R' U'"));
+        assert_eq!(moves("[R: U]"), moves("R U R'"));
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(moves("R /* setup */ U // insert\nF"), moves("R U F"));
+    }
+
+    #[test]
+    fn wide_and_slice_moves_match_their_face_turns() {
+        for (wide, equivalent) in [
+            ("r", "Rw"),
+            ("Rw", "L x"),
+            ("M", "R L' x'"),
+            ("E", "U D' y'"),
+            ("S", "F' B z"),
+            ("3Rw", "x"),
+        ] {
+            assert_eq!(state(wide), state(equivalent), "{wide} = {equivalent}");
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        assert_eq!(
+            error("R U Q"),
+            (NotationErrorKind::UnexpectedCharacter('Q'), 4..5)
+        );
+        assert_eq!(error("R (U F"), (NotationErrorKind::UnclosedGroup, 2..3));
+        assert_eq!(
+            error("R U)"),
+            (NotationErrorKind::UnmatchedClosing(')'), 3..4)
+        );
+        assert_eq!(error("[R U]").0, NotationErrorKind::MissingSeparator);
+        assert_eq!(error("R /* U").0, NotationErrorKind::UnterminatedComment);
+        assert_eq!(error("((R U)99)99").0, NotationErrorKind::TooLong);
+
+        let message = parse_algorithm("R U Q").unwrap_err();
+        assert_eq!(
+            message.display_with_source("R U Q"),
+            "R U Q\n    ^ unexpected character 'Q' at 4..5"
+        );
+    }
+
+    #[test]
+    fn single_moves() {
+        assert_eq!(parse_move("U2'"), Ok(Move::face(Face::U, 2)));
+        assert_eq!(
+            parse_move("x'"),
+            Ok(Move::new(MoveKind::Rotation(Axis::X), 3))
+        );
+        assert_eq!(
+            parse_move("R U").unwrap_err().kind,
+            NotationErrorKind::ExpectedSingleMove
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! A move sequence as a permutation of the pieces: its order, the cycles it moves
+//! corners and edges in, and whether it keeps every piece oriented.
+//!
+//! Everything is relative to the centers, so a sequence counts as returning to solved
+//! however the cube ends up held, and slices or rotations only matter for how they
+//! move the pieces around the centers.
+
+use crate::cube_model::{CENTER_FACELETS, CORNER_NAMES, CubeModel, CubieCube, EDGE_NAMES};
+use crate::move_sequence::MoveSequence;
+use std::fmt;
+
+/// Pieces one position moves to the next, the last back to the first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceCycle {
+    /// Positions in the order the pieces travel, by name
+    pub positions: Vec<&'static str>,
+    /// Twist (corners, 0-2) or flip (edges, 0-1) a piece picks up going once round
+    pub orientation: u8,
+}
+
+impl PieceCycle {
+    /// Times the sequence is repeated before the pieces of this cycle are back,
+    /// the right way round
+    pub fn order(&self, orientations: u8) -> usize {
+        if self.orientation == 0 {
+            self.positions.len()
+        } else {
+            self.positions.len() * orientations as usize
+        }
+    }
+}
+
+impl fmt::Display for PieceCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let turned = if self.positions[0].len() == 3 {
+            "twisted"
+        } else {
+            "flipped"
+        };
+        if self.positions.len() == 1 {
+            return write!(f, "{} {}", self.positions[0], turned);
+        }
+        // "->" rather than an arrow, which the UI font has no glyph for
+        write!(
+            f,
+            "{}-cycle {}",
+            self.positions.len(),
+            self.positions.join("->")
+        )?;
+        if self.orientation != 0 {
+            write!(f, ", {}", turned)?;
+        }
+        Ok(())
+    }
+}
+
+/// What a move sequence does to the pieces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceStructure {
+    /// Repetitions that return the cube to solved
+    pub order: usize,
+    /// Corner cycles, including single corners twisted in place
+    pub corner_cycles: Vec<PieceCycle>,
+    /// Edge cycles, including single edges flipped in place
+    pub edge_cycles: Vec<PieceCycle>,
+    /// Every piece ends up oriented as it was, in the U/D sense used by OLL
+    pub preserves_orientation: bool,
+}
+
+pub fn analyze(sequence: &MoveSequence) -> SequenceStructure {
+    let mut model = CubeModel::solved();
+    model.apply_moves(&sequence.moves);
+    let cube = model.to_cubie().expect("moves keep the solved cube valid");
+    let mut structure = of_cube(&cube);
+
+    // A sequence that turns the whole cube may look solved before the pieces are back
+    // where they started relative to the cube held still, so count repetitions
+    if !CENTER_FACELETS
+        .iter()
+        .all(|&center| model.facelet(center) == CubeModel::solved().facelet(center))
+    {
+        structure.order = 1;
+        while !model.is_solved() {
+            model.apply_moves(&sequence.moves);
+            structure.order += 1;
+        }
+    }
+    structure
+}
+
+/// The structure of the permutation taking the solved cube to `cube`
+pub fn of_cube(cube: &CubieCube) -> SequenceStructure {
+    let corner_cycles = cycles(&cube.cp, &cube.co, 3, &CORNER_NAMES);
+    let edge_cycles = cycles(&cube.ep, &cube.eo, 2, &EDGE_NAMES);
+    let order = corner_cycles
+        .iter()
+        .map(|cycle| cycle.order(3))
+        .chain(edge_cycles.iter().map(|cycle| cycle.order(2)))
+        .fold(1, lcm);
+    SequenceStructure {
+        order,
+        corner_cycles,
+        edge_cycles,
+        preserves_orientation: cube.co.iter().chain(&cube.eo).all(|&o| o == 0),
+    }
+}
+
+/// Cycles of the pieces that move or turn; `permutation[i]` is the piece ending up in
+/// position `i`, with orientation `orientation[i]` out of `orientations`
+fn cycles(
+    permutation: &[u8],
+    orientation: &[u8],
+    orientations: u8,
+    names: &[&'static str],
+) -> Vec<PieceCycle> {
+    // Where the piece in each position goes
+    let mut next = vec![0; permutation.len()];
+    for (position, &piece) in permutation.iter().enumerate() {
+        next[piece as usize] = position;
+    }
+    let mut seen = vec![false; permutation.len()];
+    let mut result = Vec::new();
+    for start in 0..permutation.len() {
+        if seen[start] {
+            continue;
+        }
+        let mut positions = Vec::new();
+        let mut turned = 0;
+        let mut position = start;
+        while !seen[position] {
+            seen[position] = true;
+            positions.push(names[position]);
+            turned += orientation[position];
+            position = next[position];
+        }
+        let cycle = PieceCycle {
+            positions,
+            orientation: turned % orientations,
+        };
+        if cycle.positions.len() > 1 || cycle.orientation != 0 {
+            result.push(cycle);
+        }
+    }
+    result
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+/// Cycles of one kind of piece, or "<kind> unaffected"
+fn describe(cycles: &[PieceCycle], kind: &str) -> String {
+    if cycles.is_empty() {
+        return format!("{} unaffected", kind);
+    }
+    cycles
+        .iter()
+        .map(PieceCycle::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl SequenceStructure {
+    /// The cycles without the order: "3-cycle UF->UL->UR; corners unaffected"
+    pub fn cycles(&self) -> String {
+        format!(
+            "{}; {}",
+            describe(&self.edge_cycles, "edges"),
+            describe(&self.corner_cycles, "corners")
+        )
+    }
+}
+
+/// "Order 3: 3-cycle UF->UL->UR; corners unaffected. Orientation preserved"
+impl fmt::Display for SequenceStructure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Order {}: {}. Orientation {}",
+            self.order,
+            self.cycles(),
+            if self.preserves_orientation {
+                "preserved"
+            } else {
+                "changed"
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure(algorithm: &str) -> SequenceStructure {
+        analyze(&MoveSequence::parse(algorithm).unwrap())
+    }
+
+    #[test]
+    fn face_turn() {
+        assert_eq!(
+            structure("U").to_string(),
+            "Order 4: 4-cycle UR->UF->UL->UB; 4-cycle URF->UFL->ULB->UBR. Orientation preserved"
+        );
+    }
+
+    #[test]
+    fn orders() {
+        assert_eq!(structure("R U R' U'").order, 6);
+        assert_eq!(structure("R U").order, 105);
+        assert_eq!(structure("M2 U M2 U2 M2 U M2").order, 2);
+        // However the cube ends up held, it counts as solved
+        assert_eq!(structure("x").order, 1);
+        assert_eq!(structure("r U R'").order, structure("L x U R'").order);
+        assert_eq!(structure("").order, 1);
+    }
+}
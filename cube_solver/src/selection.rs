@@ -1,5 +1,6 @@
 use crate::colors::CubeColors;
 use crate::ray_caster::RayCaster;
+use crate::solver_integration::FaceColor;
 use bevy::prelude::*;
 
 #[derive(Component, Debug, Clone, PartialEq, Reflect)]
@@ -362,19 +363,21 @@ pub fn update_selection_borders(
     }
 }
 
+/// A selected cube face and the facelet it shows
+type SelectedFacelet = (Entity, &'static crate::components::FaceletIndex);
+/// Selected faces of the cube, leaving out the color panel squares
+type SelectedCubeFace = (With<Selected>, Without<crate::components::ColorSquare>);
+
 /// System to apply colors to selected cube faces when a color is chosen.
 ///
-/// This system listens for color application events and updates the
-/// material and components of cube faces accordingly.
+/// This system listens for color application events and writes the new
+/// color into the cube model; the faces are re-rendered from the model.
 pub fn apply_color_to_selected_faces(
     mut commands: Commands,
-    selected_cube_faces: Query<Entity, (With<Selected>, Without<crate::components::ColorSquare>)>,
-    recolored_faces_query: Query<&crate::components::RecoloredFace>,
+    selected_cube_faces: Query<SelectedFacelet, SelectedCubeFace>,
     cube_colors: Res<CubeColors>,
-    placeholder_material: Res<crate::colors::PlaceholderMaterial>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut cube_model: ResMut<crate::cube::CubeModelResource>,
     mut color_manager: ResMut<crate::components::ColorManager>,
-    time: Res<Time>,
     mut color_events: EventWriter<SelectionEvent>,
 ) {
     let Some(selected_color_index) = color_manager.get_selected_color() else {
@@ -389,26 +392,22 @@ pub fn apply_color_to_selected_faces(
         return;
     }
 
-    let selected_color = cube_colors.get(selected_color_index);
-    let timestamp = time.elapsed_secs_f64();
+    let selected_face = FaceColor::from_index(selected_color_index).to_model_face();
 
-    for entity in selected_cube_faces.iter() {
+    for (entity, facelet) in selected_cube_faces.iter() {
         // Get previous color if any
-        let previous_color = recolored_faces_query
-            .get(entity)
-            .map(|face| face.color_index)
-            .ok();
+        let previous_color = cube_model
+            .model
+            .facelet(facelet.index)
+            .map(|face| FaceColor::from_model_face(face).index());
 
         // Check if we're decoloring (same color as selected)
         if let Some(prev_color) = previous_color
             && prev_color == selected_color_index
         {
-            // Decolor the face - return to placeholder color
-            commands
-                .entity(entity)
-                .insert(MeshMaterial3d(placeholder_material.0.clone()))
-                .remove::<crate::components::RecoloredFace>()
-                .remove::<Selected>();
+            // Decolor the face - it returns to the placeholder color when the model is rendered
+            cube_model.model.set_facelet(facelet.index, None);
+            commands.entity(entity).remove::<Selected>();
 
             // Decrement the color count
             color_manager.decrement_color(selected_color_index);
@@ -431,18 +430,11 @@ pub fn apply_color_to_selected_faces(
         // Try to apply the color using the centralized manager
         match color_manager.apply_color_to_face(selected_color_index, previous_color) {
             Ok(reached_limit) => {
-                // Create new material with selected color
-                let material = create_face_material(selected_color, &mut materials);
-
-                // Update the entity with new material and components
-                commands
-                    .entity(entity)
-                    .insert(MeshMaterial3d(material))
-                    .insert(crate::components::RecoloredFace::new(
-                        selected_color_index,
-                        timestamp,
-                    ))
-                    .remove::<Selected>(); // Remove selection after applying color
+                // Update the model; materials follow in sync_faces_with_cube_model
+                cube_model
+                    .model
+                    .set_facelet(facelet.index, Some(selected_face));
+                commands.entity(entity).remove::<Selected>(); // Remove selection after applying color
 
                 if reached_limit {
                     info!("Color {} has reached its limit!", selected_color_index);
@@ -473,7 +465,7 @@ pub fn apply_color_to_selected_faces(
 ///
 /// This function creates a PBR material with appropriate properties
 /// for Rubik's cube faces, including emissive lighting for better visibility.
-fn create_face_material(
+pub fn create_face_material(
     base_color: Color,
     materials: &mut ResMut<Assets<StandardMaterial>>,
 ) -> Handle<StandardMaterial> {
@@ -0,0 +1,169 @@
+//! Background solving on Bevy's `AsyncComputeTaskPool`.
+//!
+//! min2phase can take a noticeable time (especially its first call, which builds
+//! the pruning tables), so searches never run inside a frame. Each solve gets a
+//! generation number; starting a new one or cancelling supersedes the old one and
+//! its result is dropped.
+
+use crate::cube_model::CubieCube;
+use crate::ergonomics;
+use crate::solver_backend::{SolveOutput, SolverError, SolverRegistry, solve_to_target};
+use crate::solver_integration::SolverConfig;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Sent when a background solve finishes
+#[derive(Event, Debug, Clone)]
+pub struct SolveFinishedEvent {
+    pub generation: u64,
+    pub facelets: String,
+    pub result: Result<SolveOutput, SolverError>,
+}
+
+struct RunningSolve {
+    generation: u64,
+    task: Task<Result<SolveOutput, SolverError>>,
+    facelets: String,
+    cancelled: Arc<AtomicBool>,
+    /// Length of the best solution found so far, `usize::MAX` until there is one
+    best_length: Arc<AtomicUsize>,
+    started_at: f32,
+}
+
+/// The background solve currently in flight, if any
+#[derive(Resource, Default)]
+pub struct SolveTasks {
+    generation: u64,
+    running: Option<RunningSolve>,
+    /// Load the solution into the move queue as soon as it arrives
+    pub load_when_ready: bool,
+}
+
+impl SolveTasks {
+    /// Start solving `facelets` with the configured backend, superseding any solve still running
+    pub fn spawn(
+        &mut self,
+        facelets: String,
+        config: &SolverConfig,
+        registry: &SolverRegistry,
+        now: f32,
+    ) -> u64 {
+        self.cancel_running();
+        self.generation += 1;
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let best_length = Arc::new(AtomicUsize::new(usize::MAX));
+        let task_cancelled = cancelled.clone();
+        let task_best_length = best_length.clone();
+        let task_facelets = facelets.clone();
+        let task_config = config.clone();
+        let backend = registry.backend(config.backend);
+        let task = AsyncComputeTaskPool::get().spawn(async move {
+            if task_cancelled.load(Ordering::Relaxed) {
+                return Err(SolverError::Cancelled);
+            }
+            let cube =
+                CubieCube::from_facelets(&task_facelets).map_err(SolverError::InvalidCube)?;
+            let started = Instant::now();
+            let mut output = solve_to_target(
+                backend.as_ref(),
+                &cube,
+                &task_config,
+                &|| task_cancelled.load(Ordering::Relaxed),
+                &mut |length| task_best_length.store(length, Ordering::Relaxed),
+            )?;
+            // A rewrite would leave a restricted move set
+            if let Some(costs) = task_config.finger_tricks
+                && task_config.move_set.has_all_faces()
+            {
+                for solution in &mut output.solutions {
+                    *solution = ergonomics::optimize_solution(solution, &costs);
+                }
+            }
+            output.stats.elapsed = started.elapsed();
+            Ok(output)
+        });
+
+        log::debug!(
+            "Started background solve #{} with {}",
+            self.generation,
+            config.backend
+        );
+        self.running = Some(RunningSolve {
+            generation: self.generation,
+            task,
+            facelets,
+            cancelled,
+            best_length,
+            started_at: now,
+        });
+        self.generation
+    }
+
+    /// Abandon the running solve and any pending request to load its result
+    pub fn cancel(&mut self) {
+        if self.running.is_some() {
+            self.generation += 1;
+        }
+        self.cancel_running();
+        self.load_when_ready = false;
+    }
+
+    fn cancel_running(&mut self) {
+        if let Some(running) = self.running.take() {
+            running.cancelled.store(true, Ordering::Relaxed);
+            log::debug!("Cancelled background solve #{}", running.generation);
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Generation of the most recent solve; results from older generations are stale
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Length of the best solution the running solve has found so far
+    pub fn best_length(&self) -> Option<usize> {
+        self.running
+            .as_ref()
+            .map(|running| running.best_length.load(Ordering::Relaxed))
+            .filter(|&length| length != usize::MAX)
+    }
+
+    /// Seconds since the running solve was started
+    pub fn elapsed(&self, now: f32) -> Option<f32> {
+        self.running
+            .as_ref()
+            .map(|running| now - running.started_at)
+    }
+}
+
+/// System to collect finished background solves and report them as events
+pub fn poll_solve_tasks(
+    mut solve_tasks: ResMut<SolveTasks>,
+    mut finished_events: EventWriter<SolveFinishedEvent>,
+) {
+    // Polling alone must not mark the resource as changed
+    let Some(running) = solve_tasks.bypass_change_detection().running.as_mut() else {
+        return;
+    };
+    let Some(result) = block_on(poll_once(&mut running.task)) else {
+        return;
+    };
+
+    let running = solve_tasks
+        .running
+        .take()
+        .expect("running solve was just polled");
+    finished_events.send(SolveFinishedEvent {
+        generation: running.generation,
+        facelets: running.facelets,
+        result,
+    });
+}
@@ -0,0 +1,311 @@
+//! Pluggable solver backends and the registry the app picks one from.
+//!
+//! The app only talks to solvers through [`SolverBackend`]: a backend takes a cubie
+//! state and returns move sequences with optional stages. A new method is added by
+//! implementing the trait and registering it in [`SolverRegistry::default`].
+
+use crate::cube_model::{CubeModelError, CubieCube};
+use crate::external_solver::ExternalBackend;
+use crate::solver_integration::{LibraryBackend, OptimalBackend, SolverConfig, TwoPhaseBackend};
+use crate::solvers::{Solution, beginner, cfop, restricted, roux, target, thistlethwaite};
+use bevy::prelude::*;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Why a backend returned no solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolverError {
+    /// The state is not one a real cube can be turned into
+    InvalidCube(CubeModelError),
+    /// The search ended without a solution within its limits
+    NoSolution(String),
+    /// The cube cannot be solved with the allowed moves
+    Unreachable(String),
+    /// The solve was superseded before it found anything
+    Cancelled,
+    /// Any other failure, as described by the backend
+    Failed(String),
+}
+
+impl fmt::Display for SolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverError::InvalidCube(error) => write!(f, "{}", error),
+            SolverError::NoSolution(reason)
+            | SolverError::Unreachable(reason)
+            | SolverError::Failed(reason) => write!(f, "{}", reason),
+            SolverError::Cancelled => write!(f, "Solve cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for SolverError {}
+
+/// How a solve went, for logs and the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SolveStats {
+    /// Searches the backend ran; one for solvers that search once
+    pub searches: usize,
+    /// Wall-clock time of the whole solve
+    pub elapsed: Duration,
+    /// A search was skipped because its tables are still being built; solving again
+    /// once they are ready may find better solutions
+    pub tables_pending: bool,
+}
+
+/// Solutions of a backend, shortest or preferred first
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SolveOutput {
+    pub solutions: Vec<Solution>,
+    pub stats: SolveStats,
+}
+
+/// A method of solving the cube
+pub trait SolverBackend: Send + Sync {
+    /// Name shown in the solver settings; unique within a registry
+    fn name(&self) -> &'static str;
+
+    /// Solve `cube` within the limits in `config`. Blocks, so it runs off the main thread.
+    /// Long searches should give up once `should_stop` returns true, and report the
+    /// length of every new best solution to `on_improved`.
+    fn solve(
+        &self,
+        cube: &CubieCube,
+        config: &SolverConfig,
+        should_stop: &dyn Fn() -> bool,
+        on_improved: &mut dyn FnMut(usize),
+    ) -> Result<SolveOutput, SolverError>;
+}
+
+/// Solve `cube` towards `config.target`. A complete target is solved by `backend`
+/// relative to the target, without the backend's stages, which would describe the
+/// relative cube; a target with blank stickers is placed piece group by piece group
+/// within `config.timeout`. A move set without every face turn is searched on its own
+/// within `config.move_set_budget` instead of with `backend`.
+pub fn solve_to_target(
+    backend: &dyn SolverBackend,
+    cube: &CubieCube,
+    config: &SolverConfig,
+    should_stop: &dyn Fn() -> bool,
+    on_improved: &mut dyn FnMut(usize),
+) -> Result<SolveOutput, SolverError> {
+    let goal = &config.target;
+    if goal.is_solved() {
+        return solve_within_move_set(backend, cube, config, should_stop, on_improved);
+    }
+
+    if goal.model.is_complete() {
+        let goal_cube = goal.model.to_cubie().map_err(|error| {
+            SolverError::Failed(format!("Target is not a valid cube: {}", error))
+        })?;
+        let relative = target::relative_to(cube, &goal_cube);
+        let mut output =
+            solve_within_move_set(backend, &relative, config, should_stop, on_improved)?;
+        for solution in &mut output.solutions {
+            *solution = Solution::from(solution.moves.clone());
+        }
+        return Ok(output);
+    }
+
+    if !config.move_set.has_all_faces() {
+        return Err(SolverError::Failed(format!(
+            "A target with blank stickers cannot be solved with {}",
+            config.move_set
+        )));
+    }
+    let deadline = Instant::now() + config.timeout;
+    let out_of_time = || Instant::now() >= deadline;
+    let solution = target::solve_partial(cube, goal, &|| should_stop() || out_of_time()).map_err(
+        |reason| {
+            if should_stop() {
+                SolverError::Cancelled
+            } else if out_of_time() {
+                SolverError::NoSolution(format!(
+                    "No way to {} found within {:.1}s",
+                    goal.name,
+                    config.timeout.as_secs_f32()
+                ))
+            } else {
+                SolverError::NoSolution(reason)
+            }
+        },
+    )?;
+    on_improved(solution.move_count());
+    Ok(SolveOutput {
+        solutions: vec![solution],
+        stats: SolveStats {
+            searches: 1,
+            ..default()
+        },
+    })
+}
+
+/// Solve `cube` with `backend`, or with a search of its own when `config.move_set`
+/// leaves out face turns
+fn solve_within_move_set(
+    backend: &dyn SolverBackend,
+    cube: &CubieCube,
+    config: &SolverConfig,
+    should_stop: &dyn Fn() -> bool,
+    on_improved: &mut dyn FnMut(usize),
+) -> Result<SolveOutput, SolverError> {
+    let set = &config.move_set;
+    if set.has_all_faces() {
+        return backend.solve(cube, config, should_stop, on_improved);
+    }
+    if !restricted::is_reachable(cube, set) {
+        return Err(SolverError::Unreachable(format!(
+            "This state cannot be solved with {}",
+            set
+        )));
+    }
+
+    let deadline = Instant::now() + config.move_set_budget;
+    let out_of_time = || Instant::now() >= deadline;
+    let moves = restricted::solve(cube, set, &|| should_stop() || out_of_time(), on_improved)
+        .map_err(|reason| {
+            if should_stop() {
+                SolverError::Cancelled
+            } else if out_of_time() {
+                SolverError::NoSolution(format!(
+                    "No solution with {} found within {}s",
+                    set,
+                    config.move_set_budget.as_secs()
+                ))
+            } else {
+                SolverError::NoSolution(reason)
+            }
+        })?;
+    Ok(SolveOutput {
+        solutions: vec![Solution::from(moves)],
+        stats: SolveStats {
+            searches: 1,
+            ..default()
+        },
+    })
+}
+
+/// Backend for the solvers in `solvers` that find one staged solution
+pub struct StagedBackend {
+    pub name: &'static str,
+    pub solve: fn(&CubieCube) -> Result<Solution, String>,
+}
+
+impl SolverBackend for StagedBackend {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn solve(
+        &self,
+        cube: &CubieCube,
+        _config: &SolverConfig,
+        _should_stop: &dyn Fn() -> bool,
+        on_improved: &mut dyn FnMut(usize),
+    ) -> Result<SolveOutput, SolverError> {
+        let solution = (self.solve)(cube).map_err(SolverError::Failed)?;
+        on_improved(solution.move_count());
+        Ok(SolveOutput {
+            solutions: vec![solution],
+            stats: SolveStats {
+                searches: 1,
+                ..default()
+            },
+        })
+    }
+}
+
+/// The backends the user can choose from, in the order the settings cycle through them
+#[derive(Resource, Clone)]
+pub struct SolverRegistry {
+    backends: Vec<Arc<dyn SolverBackend>>,
+}
+
+impl SolverRegistry {
+    pub fn empty() -> Self {
+        Self {
+            backends: Vec::new(),
+        }
+    }
+
+    /// Add a backend, replacing any registered under the same name
+    pub fn register(&mut self, backend: impl SolverBackend + 'static) {
+        let backend: Arc<dyn SolverBackend> = Arc::new(backend);
+        match self.position(backend.name()) {
+            Some(index) => self.backends[index] = backend,
+            None => self.backends.push(backend),
+        }
+    }
+
+    /// The backend called `name`, or the first one if there is none by that name
+    pub fn backend(&self, name: &str) -> Arc<dyn SolverBackend> {
+        let index = self.position(name).unwrap_or(0);
+        self.backends
+            .get(index)
+            .cloned()
+            .expect("no solver backends registered")
+    }
+
+    /// Name of the backend `step` places after `name` (before it for a negative step),
+    /// wrapping around
+    pub fn step_name(&self, name: &str, step: i32) -> &'static str {
+        let index = self.position(name).unwrap_or(0) as i32 + step;
+        self.backends[index.rem_euclid(self.backends.len() as i32) as usize].name()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.backends
+            .iter()
+            .position(|backend| backend.name() == name)
+    }
+}
+
+impl Default for SolverRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(TwoPhaseBackend);
+        registry.register(OptimalBackend);
+        registry.register(StagedBackend {
+            name: "Beginner",
+            solve: beginner::solve,
+        });
+        registry.register(StagedBackend {
+            name: "CFOP",
+            solve: cfop::solve,
+        });
+        registry.register(LibraryBackend);
+        registry.register(StagedBackend {
+            name: "Roux",
+            solve: roux::solve,
+        });
+        registry.register(StagedBackend {
+            name: "Thistlethwaite",
+            solve: thistlethwaite::solve,
+        });
+        if let Some(external) = ExternalBackend::from_env() {
+            registry.register(external);
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_steps_both_ways() {
+        let registry = SolverRegistry::default();
+        let first = registry.backend("").name();
+        let second = registry.step_name(first, 1);
+        assert_ne!(first, second);
+        assert_eq!(registry.step_name(second, -1), first);
+        let last = registry.step_name(first, -1);
+        assert_eq!(registry.step_name(last, 1), first);
+        assert_eq!(
+            registry.step_name(first, registry.backends.len() as i32),
+            first
+        );
+    }
+}
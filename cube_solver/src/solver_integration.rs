@@ -1,15 +1,19 @@
-use crate::components::{Face, Orientation, RecoloredFace};
-use crate::ui::rotations_panel::LayerRotationCompletedEvent;
+use crate::algorithm_library::AlgorithmLibrary;
+use crate::cube::CubeModelResource;
+use crate::cube_model::{
+    Axis, CubeModel, CubeModelError, CubieCube, FACELET_COUNT, Face, Move, MoveKind,
+};
+use crate::ergonomics::CostModel;
+use crate::move_sequence::{Metric, MoveSequence};
+use crate::solve_task::SolveTasks;
+use crate::solver_backend::{SolveOutput, SolveStats, SolverBackend, SolverError, SolverRegistry};
+use crate::solvers::move_set::MoveSet;
+use crate::solvers::{Solution, SolutionStage, optimal, personal, target::Target};
 use bevy::prelude::*;
 use min2phase::solve;
 use std::collections::HashMap;
 use std::fmt;
-
-// Default center face orientations in solved state: U, R, F, D, L, B
-const DEFAULT_CENTER_FACES: [char; 6] = ['U', 'R', 'F', 'D', 'L', 'B'];
-
-// Center facelet indices in the facelet string (position 4 of each face)
-const CENTER_FACELET_INDICES: [usize; 6] = [4, 13, 22, 31, 40, 49];
+use std::time::{Duration, Instant};
 
 /// Face colors for the cube
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -47,6 +51,41 @@ impl FaceColor {
             _ => unreachable!(),
         }
     }
+
+    /// Convert to color index (0-5), the inverse of `from_index`
+    pub fn index(self) -> usize {
+        match self {
+            FaceColor::White => 0,
+            FaceColor::Yellow => 1,
+            FaceColor::Red => 2,
+            FaceColor::Orange => 3,
+            FaceColor::Blue => 4,
+            FaceColor::Green => 5,
+        }
+    }
+
+    /// Face of the cube model whose color this is in the solved state
+    pub fn to_model_face(self) -> Face {
+        match self {
+            FaceColor::White => Face::U,
+            FaceColor::Red => Face::R,
+            FaceColor::Green => Face::F,
+            FaceColor::Yellow => Face::D,
+            FaceColor::Orange => Face::L,
+            FaceColor::Blue => Face::B,
+        }
+    }
+
+    pub fn from_model_face(face: Face) -> Self {
+        match face {
+            Face::U => FaceColor::White,
+            Face::R => FaceColor::Red,
+            Face::F => FaceColor::Green,
+            Face::D => FaceColor::Yellow,
+            Face::L => FaceColor::Orange,
+            Face::B => FaceColor::Blue,
+        }
+    }
 }
 
 /// min2phase error codes and their descriptions
@@ -98,6 +137,23 @@ impl Min2PhaseError {
         }
     }
 
+    /// The same problem found while reading the state into cubies
+    pub fn from_cube_error(error: CubeModelError) -> Option<Self> {
+        match error {
+            CubeModelError::InvalidFaceletLength => Some(Min2PhaseError::InvalidFaceletLength),
+            CubeModelError::InvalidFaceletCharacter => {
+                Some(Min2PhaseError::InvalidFaceletCharacter)
+            }
+            CubeModelError::Incomplete => None,
+            CubeModelError::IncorrectColorCount => Some(Min2PhaseError::IncorrectColorCount),
+            CubeModelError::MissingEdges => Some(Min2PhaseError::MissingEdges),
+            CubeModelError::EdgeFlipError => Some(Min2PhaseError::EdgeFlipError),
+            CubeModelError::MissingCorners => Some(Min2PhaseError::MissingCorners),
+            CubeModelError::CornerTwistError => Some(Min2PhaseError::CornerTwistError),
+            CubeModelError::ParityError => Some(Min2PhaseError::ParityError),
+        }
+    }
+
     /// Get human-readable description of the error
     pub fn description(&self) -> &'static str {
         match self {
@@ -192,7 +248,7 @@ impl Min2PhaseError {
                 "This cube cannot be solved with standard moves. You may need to disassemble and reassemble it."
             }
             Min2PhaseError::NoSolutionExists | Min2PhaseError::ProbeLimitExceeded => {
-                "Try increasing the solver's move limit or probe limit. This cube may require many moves to solve."
+                "Try increasing the max depth, searches or timeout in the solver settings. This cube may require many moves to solve."
             }
         }
     }
@@ -238,26 +294,31 @@ impl Default for CubeValidation {
 pub struct CubeState {
     facelets: String,
     validation: CubeValidation,
-    solution: Option<String>,
+    /// Distinct solutions, shortest first
+    solutions: Vec<Solution>,
+    /// Index of the solution the user picked
+    selected: usize,
 }
 
 impl CubeState {
     const FACE_SIZE: usize = 9;
-    const TOTAL_FACELETS: usize = 54;
+    const TOTAL_FACELETS: usize = FACELET_COUNT;
     const VALID_CHARS: [char; 6] = ['U', 'R', 'F', 'D', 'L', 'B'];
 
     pub fn new() -> Self {
         Self {
             facelets: String::new(),
             validation: CubeValidation::NotValidated,
-            solution: None,
+            solutions: Vec::new(),
+            selected: 0,
         }
     }
 
+    /// Build a state and run the lightweight validation; solving happens in the background
     pub fn from_facelets(facelets: String) -> Self {
         let mut state = Self::new();
         state.facelets = facelets;
-        state.validate();
+        state.validate_lightweight_only();
         state
     }
 
@@ -269,14 +330,41 @@ impl CubeState {
         &self.validation
     }
 
-    pub fn solution(&self) -> Option<&str> {
-        self.solution.as_deref()
+    /// The selected solution
+    pub fn solution(&self) -> Option<&MoveSequence> {
+        self.solutions
+            .get(self.selected)
+            .map(|solution| &solution.moves)
+    }
+
+    /// Stages of the selected solution
+    pub fn solution_stages(&self) -> &[SolutionStage] {
+        self.solutions
+            .get(self.selected)
+            .map(|solution| solution.stages.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn solutions(&self) -> &[Solution] {
+        &self.solutions
+    }
+
+    pub fn selected_solution(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select_solution(&mut self, index: usize) -> bool {
+        if index < self.solutions.len() {
+            self.selected = index;
+            true
+        } else {
+            false
+        }
     }
 
     pub fn solution_moves(&self) -> Vec<String> {
-        self.solution
-            .as_ref()
-            .map(|s| s.split_whitespace().map(|s| s.to_string()).collect())
+        self.solution()
+            .map(|moves| moves.moves.iter().map(|mv| mv.to_string()).collect())
             .unwrap_or_default()
     }
 
@@ -324,32 +412,16 @@ impl CubeState {
         self.validation = CubeValidation::Valid;
 
         // Clear any previous solution since we're only doing lightweight validation
-        self.solution = None;
-    }
-
-    /// Perform full validation including solving attempt
-    fn validate(&mut self) {
-        // Reset validation state and clear any previous solution
-        self.validation = CubeValidation::NotValidated;
-        self.solution = None;
-
-        // First do lightweight validation
-        self.perform_lightweight_validation_only();
-
-        // If lightweight validation passed, attempt to solve
-        if matches!(self.validation, CubeValidation::Valid) {
-            log::debug!("Lightweight validation passed, attempting to solve...");
-            self.attempt_solve();
-        } else {
-            log::debug!("Lightweight validation failed, skipping solve attempt");
-        }
+        self.solutions.clear();
+        self.selected = 0;
     }
 
     /// Perform lightweight validation only (no solving)
     fn validate_lightweight_only(&mut self) {
         // Reset validation state and clear any previous solution
         self.validation = CubeValidation::NotValidated;
-        self.solution = None;
+        self.solutions.clear();
+        self.selected = 0;
 
         // Do lightweight validation only
         self.perform_lightweight_validation_only();
@@ -510,30 +582,445 @@ impl CubeState {
         Ok(())
     }
 
-    fn attempt_solve(&mut self) {
-        // Try to solve with min2phase
-        let solution = solve(&self.facelets, 21);
+    /// Store the outcome of a solve of this state
+    fn apply_solve_result(&mut self, result: Result<Vec<Solution>, String>) {
+        self.selected = 0;
+        match result {
+            Ok(solutions) => {
+                self.validation = CubeValidation::Valid;
+                self.solutions = solutions;
+            }
+            Err(message) => {
+                self.validation = CubeValidation::SolvingFailed(message);
+                self.solutions.clear();
+            }
+        }
+    }
+}
+
+/// Solver selection and search limits
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct SolverConfig {
+    /// Name of the backend in the `SolverRegistry` that solves the cube
+    pub backend: &'static str,
+    /// Longest solution accepted; the first, loose search runs at this depth
+    pub max_depth: usize,
+    /// Stop tightening once a solution of at most this many moves is found
+    pub target_depth: usize,
+    /// Maximum number of min2phase searches per solve. min2phase fixes the probe budget
+    /// of a single search itself, so this is not a probe limit: a search that runs out
+    /// of probes is retried with the cube held in another orientation, which sends it
+    /// down a different path.
+    pub max_searches: u32,
+    /// Wall-clock budget for a solve. min2phase cannot be interrupted, so the 2-phase
+    /// solver only checks it between searches and a single search can overrun it; the
+    /// other searches stop on time.
+    pub timeout: Duration,
+    /// How many distinct solutions to collect for the user to choose from
+    pub solution_count: usize,
+    /// Metric the optimal solver minimizes (HTM or QTM)
+    pub optimal_metric: Metric,
+    /// Time the optimal solver may take before the min2phase solution is used instead
+    pub optimal_budget: Duration,
+    /// State the solution should end in; the solved cube unless the user picks another
+    pub target: Target,
+    /// Moves the solution may use. Any set without every face turn is solved by its own
+    /// search, whichever backend is picked.
+    pub move_set: MoveSet,
+    /// Time that search may take; it keeps shortening its solution until then
+    pub move_set_budget: Duration,
+    /// Weights to rewrite solutions with for easier execution, or `None` to keep them as
+    /// the backend found them
+    pub finger_tricks: Option<CostModel>,
+    /// Algorithms the user knows, for the solver that builds solutions from them
+    pub library: AlgorithmLibrary,
+}
+
+impl SolverConfig {
+    /// min2phase never searches deeper than 25 moves, whatever it is asked for
+    pub const DEPTH_RANGE: std::ops::RangeInclusive<usize> = 12..=25;
+    pub const SEARCH_LIMIT_RANGE: std::ops::RangeInclusive<u32> = 1..=64;
+    pub const TIMEOUT_RANGE: std::ops::RangeInclusive<Duration> =
+        Duration::from_millis(500)..=Duration::from_secs(60);
+    pub const SOLUTION_COUNT_RANGE: std::ops::RangeInclusive<usize> = 1..=8;
+    pub const OPTIMAL_BUDGET_RANGE: std::ops::RangeInclusive<Duration> =
+        Duration::from_secs(5)..=Duration::from_secs(600);
+    pub const MOVE_SET_BUDGET_RANGE: std::ops::RangeInclusive<Duration> =
+        Duration::from_secs(5)..=Duration::from_secs(600);
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 24,
+            target_depth: 20,
+            max_searches: 12,
+            timeout: Duration::from_secs(3),
+            solution_count: 4,
+            backend: TwoPhaseBackend.name(),
+            optimal_metric: Metric::Htm,
+            optimal_budget: Duration::from_secs(30),
+            target: Target::solved(),
+            move_set: MoveSet::all_faces(),
+            move_set_budget: Duration::from_secs(15),
+            finger_tricks: None,
+            library: AlgorithmLibrary::default(),
+        }
+    }
+}
+
+/// A symmetric variant of the cube state that min2phase is asked to solve.
+/// Different variants send the search down different paths and yield different solutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SearchVariant {
+    /// Hold the cube turned by this rotation
+    rotation: Option<Move>,
+    /// Solve the inverse state and invert the solution
+    inverse: bool,
+}
+
+/// Variants tried in turn: the cube as it is and its inverse, then both turned around each axis
+fn search_variants() -> Vec<SearchVariant> {
+    let rotation = |axis, turns| Some(Move::new(MoveKind::Rotation(axis), turns));
+    let rotations = [
+        None,
+        rotation(Axis::Y, 1),
+        rotation(Axis::Y, 2),
+        rotation(Axis::Y, 3),
+        rotation(Axis::X, 1),
+        rotation(Axis::X, 3),
+        rotation(Axis::Z, 1),
+        rotation(Axis::Z, 3),
+    ];
+    rotations
+        .into_iter()
+        .flat_map(|rotation| [false, true].map(|inverse| SearchVariant { rotation, inverse }))
+        .collect()
+}
+
+/// Human-readable message for a min2phase error string
+fn describe_min2phase_error(code: &str) -> String {
+    match Min2PhaseError::from_error_code(code) {
+        Some(error) => format!(
+            "{} (Error: {})\n\nExplanation: {}\n\nSuggestion: {}",
+            error.description(),
+            code,
+            error.detailed_explanation(),
+            error.suggestions()
+        ),
+        // Fallback for unknown error codes
+        None => format!("Unknown min2phase error: {}", code),
+    }
+}
+
+/// Typed error for a min2phase error string
+fn min2phase_error(code: &str) -> SolverError {
+    match Min2PhaseError::from_error_code(code) {
+        Some(Min2PhaseError::NoSolutionExists | Min2PhaseError::ProbeLimitExceeded) => {
+            SolverError::NoSolution(describe_min2phase_error(code))
+        }
+        _ => SolverError::Failed(describe_min2phase_error(code)),
+    }
+}
 
-        if solution.starts_with("Error") {
-            // Parse the error code and provide human-readable description
-            if let Some(error) = Min2PhaseError::from_error_code(&solution) {
-                let detailed_message = format!(
-                    "{} (Error: {})\n\nExplanation: {}\n\nSuggestion: {}",
+/// Message shown to the user for a failed solve, with an explanation where one is known
+pub fn describe_solver_error(error: &SolverError) -> String {
+    match error {
+        SolverError::InvalidCube(cube_error) => {
+            match Min2PhaseError::from_cube_error(*cube_error) {
+                Some(error) => format!(
+                    "{}\n\nExplanation: {}\n\nSuggestion: {}",
                     error.description(),
-                    solution,
                     error.detailed_explanation(),
                     error.suggestions()
+                ),
+                None => cube_error.to_string(),
+            }
+        }
+        _ => error.to_string(),
+    }
+}
+
+/// One min2phase search with the cube held turned by `rotation`.
+/// The solution is translated back so it applies to the cube as given.
+fn search_in_orientation(
+    facelets: &str,
+    max_depth: usize,
+    rotation: Option<Move>,
+) -> Result<String, SolverError> {
+    let Some(rotation) = rotation else {
+        // Tightening may ask for less than the settings allow, never for more
+        let depth = max_depth.min(*SolverConfig::DEPTH_RANGE.end());
+        let depth = u8::try_from(depth).expect("the depth range fits min2phase's u8 depth");
+        let solution = solve(&facelets.to_string(), depth);
+        return if solution.starts_with("Error") {
+            Err(min2phase_error(&solution))
+        } else {
+            Ok(solution.trim().to_string())
+        };
+    };
+
+    let mut model = CubeModel::from_facelets(facelets).map_err(SolverError::InvalidCube)?;
+    model.apply_move(rotation);
+    let solution = search_in_orientation(&model.normalized_facelet_string(), max_depth, None)?;
+    Ok(parse_solution(&solution)?
+        .rotate(rotation.inverse())
+        .to_string())
+}
+
+/// One min2phase search of a variant of the state; the solution applies to the state as given
+fn search_variant(
+    facelets: &str,
+    max_depth: usize,
+    variant: SearchVariant,
+) -> Result<String, SolverError> {
+    if !variant.inverse {
+        return search_in_orientation(facelets, max_depth, variant.rotation);
+    }
+
+    let cubie = CubieCube::from_facelets(facelets).map_err(SolverError::InvalidCube)?;
+    let solution =
+        search_in_orientation(&cubie.inverse().to_facelets(), max_depth, variant.rotation)?;
+    Ok(parse_solution(&solution)?.inverse().to_string())
+}
+
+/// Read back a solution min2phase printed
+fn parse_solution(solution: &str) -> Result<MoveSequence, SolverError> {
+    MoveSequence::parse(solution)
+        .map_err(|error| SolverError::Failed(format!("Unreadable min2phase solution: {}", error)))
+}
+
+/// Solve a facelet string within the configured limits.
+///
+/// Starts with a loose search at `max_depth`, then keeps asking for shorter
+/// solutions until the target length, the search limit or the timeout is hit, or
+/// `should_stop` returns true. Searches that exceed min2phase's probe limit are
+/// retried on another variant of the state. `on_improved` is called with the
+/// length of every new best solution.
+///
+/// With the remaining budget, further variants are solved at a slightly looser
+/// depth to collect up to `solution_count` distinct solutions, shortest first.
+///
+/// This blocks for as long as the searches take, so call it off the main thread.
+pub fn solve_with_config(
+    facelets: &str,
+    config: &SolverConfig,
+    should_stop: impl Fn() -> bool,
+    mut on_improved: impl FnMut(usize),
+) -> Result<SolveOutput, SolverError> {
+    /// Extra moves allowed for alternative solutions over the best one
+    const ALTERNATIVE_SLACK: usize = 2;
+
+    let started = Instant::now();
+    let variants = search_variants();
+    let mut searches = 0;
+    let mut solutions: Vec<String> = Vec::new();
+    let mut best_length: Option<usize> = None;
+    let mut last_error = None;
+    let mut depth = config.max_depth;
+    let mut tightening = true;
+
+    for attempt in 0..config.max_searches as usize {
+        // The first search always runs, unless the solve is already superseded
+        if should_stop() || (attempt > 0 && started.elapsed() >= config.timeout) {
+            break;
+        }
+        if let Some(length) = best_length
+            && length <= config.target_depth
+            && tightening
+        {
+            // Target reached: spend what is left on alternatives
+            tightening = false;
+            depth = (length + ALTERNATIVE_SLACK).min(config.max_depth);
+        }
+        if !tightening && solutions.len() >= config.solution_count {
+            break;
+        }
+
+        let variant = variants[attempt % variants.len()];
+        searches += 1;
+        match search_variant(facelets, depth, variant) {
+            Ok(solution) => {
+                let length = solution.split_whitespace().count();
+                log::debug!(
+                    "Search {} ({:?}) found {} moves at depth {}",
+                    attempt + 1,
+                    variant,
+                    length,
+                    depth
+                );
+                if !solutions.contains(&solution) {
+                    solutions.push(solution);
+                }
+                if best_length.is_none_or(|best| length < best) {
+                    best_length = Some(length);
+                    on_improved(length);
+                }
+                if length == 0 {
+                    break;
+                }
+                if tightening {
+                    depth = length - 1;
+                }
+            }
+            Err(SolverError::NoSolution(reason)) => {
+                log::debug!(
+                    "Search {} at depth {} gave up, retrying on another variant",
+                    attempt + 1,
+                    depth
                 );
-                self.validation = CubeValidation::SolvingFailed(detailed_message);
+                last_error = Some(SolverError::NoSolution(reason));
+            }
+            // The state itself is broken; retrying cannot help
+            Err(error) => return Err(error),
+        }
+    }
+
+    if solutions.is_empty() {
+        return Err(last_error.unwrap_or(SolverError::Cancelled));
+    }
+
+    solutions.sort_by_key(|solution| solution.split_whitespace().count());
+    solutions.truncate(config.solution_count);
+    let solutions = solutions
+        .iter()
+        .map(|solution| parse_solution(solution).map(Solution::from))
+        .collect::<Result<_, _>>()?;
+    Ok(SolveOutput {
+        solutions,
+        stats: SolveStats {
+            searches,
+            elapsed: started.elapsed(),
+            ..default()
+        },
+    })
+}
+
+/// min2phase: near-optimal solutions within milliseconds
+pub struct TwoPhaseBackend;
+
+impl SolverBackend for TwoPhaseBackend {
+    fn name(&self) -> &'static str {
+        "2-phase"
+    }
+
+    fn solve(
+        &self,
+        cube: &CubieCube,
+        config: &SolverConfig,
+        should_stop: &dyn Fn() -> bool,
+        on_improved: &mut dyn FnMut(usize),
+    ) -> Result<SolveOutput, SolverError> {
+        solve_with_config(&cube.to_facelets(), config, should_stop, on_improved)
+    }
+}
+
+/// CFOP from the algorithms the user marked as known, with min2phase for a last layer
+/// none of them fits
+pub struct LibraryBackend;
+
+impl SolverBackend for LibraryBackend {
+    fn name(&self) -> &'static str {
+        "My algorithms"
+    }
+
+    fn solve(
+        &self,
+        cube: &CubieCube,
+        config: &SolverConfig,
+        should_stop: &dyn Fn() -> bool,
+        on_improved: &mut dyn FnMut(usize),
+    ) -> Result<SolveOutput, SolverError> {
+        let started = Instant::now();
+        let mut searches = 1;
+        let mut search = |cube: &CubieCube| {
+            let output = solve_with_config(&cube.to_facelets(), config, should_stop, |_| {})
+                .map_err(|error| error.to_string())?;
+            searches += output.stats.searches;
+            output
+                .solutions
+                .into_iter()
+                .next()
+                .map(|solution| solution.moves)
+                .ok_or_else(|| "Search found no solution".to_string())
+        };
+        let solution = personal::solve(cube, &config.library, &mut search).map_err(|reason| {
+            if should_stop() {
+                SolverError::Cancelled
             } else {
-                // Fallback for unknown error codes
-                self.validation =
-                    CubeValidation::SolvingFailed(format!("Unknown min2phase error: {}", solution));
+                SolverError::Failed(reason)
             }
-        } else {
-            self.validation = CubeValidation::Valid;
-            self.solution = Some(solution);
+        })?;
+        on_improved(solution.move_count());
+        Ok(SolveOutput {
+            solutions: vec![solution],
+            stats: SolveStats {
+                searches,
+                elapsed: started.elapsed(),
+                ..default()
+            },
+        })
+    }
+}
+
+/// IDA* with pattern databases, falling back to min2phase when the budget runs out.
+///
+/// min2phase solutions are found first as a fallback, then an optimal one is searched
+/// for within `optimal_budget`. If the search finishes, its solution is put first;
+/// otherwise the min2phase solutions are returned unchanged. Until the pattern
+/// databases are built the optimal search is skipped and the output says so in
+/// `tables_pending`, so `resolve_when_tables_ready` can solve again later.
+pub struct OptimalBackend;
+
+impl SolverBackend for OptimalBackend {
+    fn name(&self) -> &'static str {
+        "Optimal"
+    }
+
+    fn solve(
+        &self,
+        cube: &CubieCube,
+        config: &SolverConfig,
+        should_stop: &dyn Fn() -> bool,
+        on_improved: &mut dyn FnMut(usize),
+    ) -> Result<SolveOutput, SolverError> {
+        optimal::prepare_tables();
+        let facelets = cube.to_facelets();
+        let mut output = solve_with_config(&facelets, config, should_stop, &mut *on_improved)?;
+        let metric = config.optimal_metric;
+        let best_length = output
+            .solutions
+            .first()
+            .map(|solution| solution.moves.length(metric));
+
+        if !optimal::tables_ready() {
+            log::info!("Pattern databases are still being built, using the two-phase solution");
+            output.stats.tables_pending = true;
+            return Ok(output);
         }
+        let deadline = Instant::now() + config.optimal_budget;
+        output.stats.searches += 1;
+        match optimal::solve(&facelets, metric, || {
+            should_stop() || Instant::now() >= deadline
+        })
+        .map_err(|error| error.to_string())
+        .and_then(|solution| MoveSequence::parse(&solution).map_err(|error| error.to_string()))
+        {
+            Ok(moves) => {
+                if best_length.is_none_or(|best| moves.length(metric) < best) {
+                    on_improved(moves.length(metric));
+                }
+                let solutions = &mut output.solutions;
+                solutions.retain(|other| other.moves != moves);
+                solutions.insert(0, Solution::from(moves));
+                solutions.truncate(config.solution_count);
+            }
+            Err(error) => log::info!(
+                "Optimal search gave up ({}), using the two-phase solution",
+                error
+            ),
+        }
+        Ok(output)
     }
 }
 
@@ -548,84 +1035,59 @@ impl Default for CubeState {
 pub struct CubeSolverResource {
     current_state: Option<CubeState>,
     is_solving: bool,
+    /// The last solve skipped a search whose tables were not built yet
+    tables_pending: bool,
 }
 
 impl CubeSolverResource {
-    pub fn update_from_entities(
-        &mut self,
-        all_faces_query: &Query<(Entity, &Face)>,
-        colored_faces_query: &Query<(Entity, &RecoloredFace)>,
-        small_cube_transforms: &Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-        main_cube_transforms: &Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-        face_transforms: &Query<&GlobalTransform, With<Face>>,
-    ) {
-        let facelets = self.map_entities_to_facelets(
-            all_faces_query,
-            colored_faces_query,
-            small_cube_transforms,
-            main_cube_transforms,
-            face_transforms,
-        );
+    pub fn update_from_model(&mut self, model: &CubeModel) {
+        // Relabel colors by the centers so slice moves and rotations don't confuse the solver
+        let facelets = model.normalized_facelet_string();
+        log::debug!("Current facelet state: {}", model);
+        log::debug!("Remapped facelet state: {}", facelets.replace(' ', "."));
 
-        if facelets.len() == CubeState::TOTAL_FACELETS {
-            let new_state = CubeState::from_facelets(facelets);
-            self.current_state = Some(new_state);
-        } else {
-            self.current_state = None;
-        }
+        self.current_state = Some(CubeState::from_facelets(facelets));
+        self.tables_pending = false;
 
         // Reset solving state when cube state changes
         self.set_solving(false);
     }
 
-    pub fn perform_lightweight_validation(&mut self) {
-        // Perform lightweight validation without calling the heavy solve process
-        // This is called on every recolor and rotation event for immediate feedback
-
-        if let Some(state) = &mut self.current_state {
-            // Use the lightweight-only validation method
-            state.validate_lightweight_only();
-        }
-    }
-
-    pub fn perform_full_solve(&mut self) -> bool {
-        if let Some(state) = &mut self.current_state {
-            log::info!("Performing full solve - redoing all validation from scratch");
-
-            // Perform full validation (including solving attempt) from scratch
-            state.validate();
-
-            match state.validation() {
-                CubeValidation::Valid => {
-                    // Check if we have a solution
-                    if state.solution().is_some() {
-                        log::info!(
-                            "Full solve successful - solution found with {} moves",
-                            state.solution_moves().len()
-                        );
-                        self.set_solving(true);
-                        true
-                    } else {
-                        log::warn!("Cube is valid but no solution was found");
-                        false
-                    }
-                }
-                CubeValidation::Invalid(msg) => {
-                    log::warn!("Cannot solve invalid cube: {}", msg);
-                    false
-                }
-                CubeValidation::SolvingFailed(msg) => {
-                    log::warn!("Solving failed: {}", msg);
-                    false
-                }
-                CubeValidation::NotValidated => {
-                    log::warn!("Cube not yet validated");
-                    false
+    /// Apply a finished background solve. Results for a state other than the current one are ignored.
+    pub fn apply_solve_result(
+        &mut self,
+        facelets: &str,
+        result: Result<SolveOutput, SolverError>,
+    ) -> bool {
+        match &mut self.current_state {
+            Some(state) if state.facelets() == facelets => {
+                let stats = result
+                    .as_ref()
+                    .map(|output| output.stats)
+                    .unwrap_or_default();
+                self.tables_pending = stats.tables_pending;
+                state.apply_solve_result(
+                    result
+                        .map(|output| output.solutions)
+                        .map_err(|error| describe_solver_error(&error)),
+                );
+                match state.validation() {
+                    CubeValidation::Valid => log::info!(
+                        "Background solve finished - {} solutions, shortest {} moves, {} searches in {:.2?}",
+                        state.solutions().len(),
+                        state.solution_moves().len(),
+                        stats.searches,
+                        stats.elapsed
+                    ),
+                    CubeValidation::SolvingFailed(msg) => log::warn!("Solving failed: {}", msg),
+                    _ => {}
                 }
+                true
+            }
+            _ => {
+                log::debug!("Discarding solve result for an outdated cube state");
+                false
             }
-        } else {
-            log::warn!("No cube state available");
-            false
         }
     }
 
@@ -634,9 +1096,15 @@ impl CubeSolverResource {
             Some(state) => match state.validation() {
                 CubeValidation::NotValidated => "Cube not yet validated".to_string(),
                 CubeValidation::Valid => {
-                    if let Some(solution) = state.solution() {
-                        let move_count = solution.split_whitespace().count();
-                        format!("Valid cube, solvable in {} moves", move_count)
+                    if let Some(solution) = state.solution()
+                        && self.tables_pending
+                    {
+                        format!(
+                            "Valid cube, solvable in {} moves (optimal search once its tables are built)",
+                            solution.len()
+                        )
+                    } else if let Some(solution) = state.solution() {
+                        format!("Valid cube, solvable in {} moves", solution.len())
                     } else {
                         "Valid cube (press Solve to find solution)".to_string()
                     }
@@ -678,377 +1146,148 @@ impl CubeSolverResource {
         self.is_solving = false;
     }
 
-    pub fn solve_moves(&self) -> Vec<String> {
+    pub fn solutions(&self) -> &[Solution] {
         self.current_state
             .as_ref()
-            .map(|s| s.solution_moves())
+            .map(|s| s.solutions())
             .unwrap_or_default()
     }
 
-    pub fn facelets(&self) -> Option<&str> {
-        self.current_state.as_ref().map(|s| s.facelets())
-    }
-
-    /// Solve a Rubik's cube represented in facelet
-    /// Facelet for the rubik's cube:
-    /// ```text
-    ///          +--------+
-    ///          |U1 U2 U3|
-    ///          |U4 U5 U6|
-    ///          |U7 U8 U9|
-    /// +--------+--------+--------+--------+
-    /// |L1 L2 L3|F1 F2 F3|R1 R2 R3|B1 B2 B3|
-    /// |L4 L5 L6|F4 F5 F6|R4 R5 R6|B4 B5 B6|
-    /// |L7 L8 L9|F7 F8 F9|R7 R8 R9|B7 B8 B9|
-    /// +--------+--------+--------+--------+
-    ///          |D1 D2 D3|
-    ///          |D4 D5 D6|
-    ///          |D7 D8 D9|
-    ///          +--------+
-    /// ```
-    /// should be: U1U2...U9R1R2...R9F1..F9D1..D9L1..L9B1..B9
-    /// Example, facelet of solved cube is UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB
-    /// Map cube face entities to min2phase facelet string
-    fn map_entities_to_facelets(
-        &self,
-        all_faces_query: &Query<(Entity, &Face)>,
-        colored_faces_query: &Query<(Entity, &RecoloredFace)>,
-        small_cube_transforms: &Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-        main_cube_transforms: &Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-        face_transforms: &Query<&GlobalTransform, With<Face>>,
-    ) -> String {
-        // Initialize facelets array with spaces
-        let mut facelets = vec![' '; CubeState::TOTAL_FACELETS];
-
-        // Create a map of entity IDs to their color indices for quick lookup
-        let entity_colors: HashMap<Entity, usize> = colored_faces_query
-            .iter()
-            .map(|(entity, recolored_face)| (entity, recolored_face.color_index))
-            .collect();
-
-        // Map each entity to its position in the cube state
-        for (entity, _face) in all_faces_query.iter() {
-            // Calculate facelet letter based on entity color
-            if let Some(&color_index) = entity_colors.get(&entity) {
-                let face_color = FaceColor::from_index(color_index);
-                let facelet_char = face_color.to_facelet_char();
-
-                // Calculate facelet index based on parent small cube position
-                if let Some(facelet_index) = calculate_facelet_index(
-                    entity,
-                    all_faces_query,
-                    small_cube_transforms,
-                    main_cube_transforms,
-                    face_transforms,
-                ) && facelet_index < facelets.len()
-                {
-                    facelets[facelet_index] = facelet_char;
-                }
-            }
-        }
-
-        let facelet_string = facelets.iter().collect::<String>();
-        log::debug!(
-            "Current facelet state: {}",
-            facelet_string.replace(" ", ".")
-        );
-
-        // Remap facelets based on center face orientations
-        let remapped_facelet_string = remap_facelets_by_centers(&facelet_string);
-        log::debug!(
-            "Remapped facelet state: {}",
-            remapped_facelet_string.replace(" ", ".")
-        );
-        remapped_facelet_string
-    }
-}
-
-/// Remap facelets based on center face orientations
-/// Maps current center faces to default center faces and applies the mapping to all facelets
-fn remap_facelets_by_centers(facelet_string: &str) -> String {
-    if facelet_string.len() != 54 {
-        log::warn!("Facelet string length is not 54, skipping remapping");
-        return facelet_string.to_string();
-    }
-
-    // Extract current center facelets
-    let mut current_centers = [' '; 6];
-    for (i, &index) in CENTER_FACELET_INDICES.iter().enumerate() {
-        if index < facelet_string.len() {
-            current_centers[i] = facelet_string.chars().nth(index).unwrap_or(' ');
-        }
+    /// Stages of the selected solution; empty if the solver doesn't work in stages
+    pub fn solution_stages(&self) -> &[SolutionStage] {
+        self.current_state
+            .as_ref()
+            .map(|s| s.solution_stages())
+            .unwrap_or_default()
     }
 
-    // Create mapping from current centers to default centers
-    let mut color_mapping = HashMap::new();
-    for (i, &current_center) in current_centers.iter().enumerate() {
-        if current_center != ' ' {
-            color_mapping.insert(current_center, DEFAULT_CENTER_FACES[i]);
-        }
+    pub fn selected_solution(&self) -> usize {
+        self.current_state
+            .as_ref()
+            .map(|s| s.selected_solution())
+            .unwrap_or(0)
     }
 
-    // Apply mapping to all facelets
-    let mut remapped_facelets = String::new();
-    for c in facelet_string.chars() {
-        if c == ' ' {
-            remapped_facelets.push(c);
-        } else {
-            let mapped_char = color_mapping.get(&c).unwrap_or(&c);
-            remapped_facelets.push(*mapped_char);
-        }
+    /// Pick which of the alternative solutions `solve_moves` returns
+    pub fn select_solution(&mut self, index: usize) -> bool {
+        self.current_state
+            .as_mut()
+            .is_some_and(|s| s.select_solution(index))
     }
 
-    log::debug!(
-        "Facelet remapping: current centers {:?} -> default centers {:?}",
-        current_centers.iter().collect::<String>(),
-        DEFAULT_CENTER_FACES.iter().collect::<String>()
-    );
-
-    remapped_facelets
-}
-
-/// Calculate facelet index based on face's parent small cube position
-fn calculate_facelet_index(
-    face_entity: Entity,
-    face_query: &Query<(Entity, &Face)>,
-    small_cube_transforms: &Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-    main_cube_transforms: &Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-    face_transforms: &Query<&GlobalTransform, With<Face>>,
-) -> Option<usize> {
-    // Get the parent small cube entity from the Face component
-    if let Ok((_, face)) = face_query.get(face_entity) {
-        let small_cube_entity = face.parent_cube;
-
-        // Get the small cube's transform
-        if let Ok(small_cube_transform) = small_cube_transforms.get(small_cube_entity) {
-            // Get the main cube's transform
-            if let Ok(main_cube_transform) = main_cube_transforms.get_single() {
-                // Get the face's transform
-                if let Ok(face_transform) = face_transforms.get(face_entity) {
-                    // Calculate the face's position relative to the main cube
-                    // This gives us the face's orientation in the main cube's coordinate system
-                    let face_relative_to_main =
-                        main_cube_transform.affine().inverse() * face_transform.affine();
-                    let (_, _, face_main_pos) =
-                        face_relative_to_main.to_scale_rotation_translation();
-
-                    // Determine face orientation from face's position in main cube coordinates
-                    let face_orientation =
-                        determine_face_orientation_from_main_position(&face_main_pos);
-
-                    // Calculate the small cube's position relative to the main cube in LOCAL SPACE
-                    // This gives us the original grid position regardless of rotation
-                    let relative_transform =
-                        main_cube_transform.affine().inverse() * small_cube_transform.affine();
-                    let (_, _, relative_position) =
-                        relative_transform.to_scale_rotation_translation();
-
-                    // Convert the relative position to local indices (-1, 0, 1)
-                    let local_indices = world_to_local_indices(&relative_position);
-
-                    // Calculate position within the face using local indices
-                    let position_in_face =
-                        calculate_position_in_face_from_indices(&local_indices, face_orientation);
-
-                    // Calculate facelet index: group_offset + position_within_face
-                    let group_offset = face_orientation.facelet_offset();
-                    let facelet_index = group_offset + position_in_face;
-
-                    log::debug!(
-                        "Facelet mapping: face={:?} -> indices=({:.0},{:.0},{:.0}) -> {}{} -> index={}",
-                        face_entity,
-                        local_indices.x,
-                        local_indices.y,
-                        local_indices.z,
-                        face_orientation.as_str(),
-                        position_in_face + 1,
-                        facelet_index
-                    );
-
-                    return Some(facelet_index);
-                }
-            }
-        }
+    pub fn solve_moves(&self) -> Vec<String> {
+        self.current_state
+            .as_ref()
+            .map(|s| s.solution_moves())
+            .unwrap_or_default()
     }
 
-    // If we can't calculate the index, return None (face will be treated as not colored)
-    None
-}
-
-/// Convert world position to local indices (-1, 0, 1)
-fn world_to_local_indices(position: &Vec3) -> Vec3 {
-    // Grid step is 2.0/3.0 (from cube creation)
-    const GRID_STEP: f32 = 2.0 / 3.0;
-
-    // Convert to local indices by dividing by grid step and rounding
-    let x = (position.x / GRID_STEP).round() as i32;
-    let y = (position.y / GRID_STEP).round() as i32;
-    let z = (position.z / GRID_STEP).round() as i32;
-
-    // Clamp to valid range (-1, 0, 1)
-    let x = x.clamp(-1, 1);
-    let y = y.clamp(-1, 1);
-    let z = z.clamp(-1, 1);
-
-    Vec3::new(x as f32, y as f32, z as f32)
-}
-
-/// Determine face orientation from face's position in main cube coordinates
-fn determine_face_orientation_from_main_position(face_main_pos: &Vec3) -> Orientation {
-    // Determine which axis the face is on and in which direction
-    // This matches the face spawning logic in cube.rs
-    if face_main_pos.x.abs() > face_main_pos.y.abs()
-        && face_main_pos.x.abs() > face_main_pos.z.abs()
-    {
-        if face_main_pos.x > 0.0 {
-            Orientation::Right
-        } else {
-            Orientation::Left
-        }
-    } else if face_main_pos.y.abs() > face_main_pos.z.abs() {
-        if face_main_pos.y > 0.0 {
-            Orientation::Up
-        } else {
-            Orientation::Down
-        }
-    } else if face_main_pos.z > 0.0 {
-        Orientation::Front
-    } else {
-        Orientation::Back
+    pub fn facelets(&self) -> Option<&str> {
+        self.current_state.as_ref().map(|s| s.facelets())
     }
 }
 
-/// Calculate position within a face using local indices
-fn calculate_position_in_face_from_indices(indices: &Vec3, face_orientation: Orientation) -> usize {
-    // For each face, we need to map the other two coordinates to a 3x3 grid
-    // The grid layout is:
-    // 0 1 2
-    // 3 4 5
-    // 6 7 8
-
-    let (grid_x, grid_y) = match face_orientation {
-        // Front face: use X and Y coordinates
-        Orientation::Front => {
-            let x = (indices.x + 1.0) as usize;
-            let y = (-indices.y + 1.0) as usize; // Elegant Y inversion
-            (x, y)
-        }
-        // Back face: use X and Y coordinates, but invert X
-        Orientation::Back => {
-            let x = (-indices.x + 1.0) as usize; // Invert X for Back face
-            let y = (-indices.y + 1.0) as usize; // Elegant Y inversion
-            (x, y)
-        }
-        // Left face: use Z and Y coordinates
-        Orientation::Left => {
-            let z = (indices.z + 1.0) as usize;
-            let y = (-indices.y + 1.0) as usize; // Elegant Y inversion
-            (z, y)
-        }
-        // Right face: use Z and Y coordinates, but invert Z
-        Orientation::Right => {
-            let z = (-indices.z + 1.0) as usize; // Invert Z for Right face
-            let y = (-indices.y + 1.0) as usize; // Elegant Y inversion
-            (z, y)
-        }
-        // Up face: use X and Z coordinates
-        Orientation::Up => {
-            let x = (indices.x + 1.0) as usize;
-            let z = (indices.z + 1.0) as usize;
-            (x, z)
-        }
-        // Down face: use X and Z coordinates, but invert Z
-        Orientation::Down => {
-            let x = (indices.x + 1.0) as usize;
-            let z = (-indices.z + 1.0) as usize; // Invert Z to flip rows
-            (x, z)
-        }
-    };
-
-    // Convert to linear index (0-8)
-
-    grid_y * 3 + grid_x
-}
-
-/// System to update solver state when cube faces change
+/// System to update solver state when the cube model or the solver limits change.
+/// Any solve still running for the previous state is superseded by a new background solve.
 pub fn update_solver_state(
     mut solver: ResMut<CubeSolverResource>,
-    face_query: Query<(&RecoloredFace, &Face), Changed<RecoloredFace>>,
-    all_faces_query: Query<(Entity, &Face)>,
-    colored_faces_query: Query<(Entity, &RecoloredFace)>,
-    small_cube_transforms: Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-    main_cube_transforms: Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-    face_transforms: Query<&GlobalTransform, With<Face>>,
+    mut solve_tasks: ResMut<SolveTasks>,
+    cube_model: Res<CubeModelResource>,
+    solver_config: Res<SolverConfig>,
+    registry: Res<SolverRegistry>,
+    time: Res<Time>,
 ) {
-    // Only update if there are changes
-    if !face_query.is_empty() {
-        solver.update_from_entities(
-            &all_faces_query,
-            &colored_faces_query,
-            &small_cube_transforms,
-            &main_cube_transforms,
-            &face_transforms,
-        );
+    // Only update if there are changes (recolors and completed rotations alike)
+    if cube_model.is_changed() || solver_config.is_changed() {
+        solver.update_from_model(&cube_model.model);
+        solve_tasks.cancel();
 
         log::info!("Cube solver updated: {}", solver.get_validation_message());
 
-        if solver.is_solvable() {
-            log::info!("Generated {} solving moves", solver.solve_moves().len());
+        if solver.is_valid()
+            && let Some(facelets) = solver.facelets()
+        {
+            solve_tasks.spawn(
+                facelets.to_string(),
+                &solver_config,
+                &registry,
+                time.elapsed_secs(),
+            );
         }
     }
 }
 
-/// System to perform lightweight validation on recolor events
-pub fn lightweight_validation_on_recolor(
+/// System to solve the cube again once the tables a solve had to skip are built
+pub fn resolve_when_tables_ready(
     mut solver: ResMut<CubeSolverResource>,
-    face_query: Query<(&RecoloredFace, &Face), Changed<RecoloredFace>>,
+    mut solve_tasks: ResMut<SolveTasks>,
+    solver_config: Res<SolverConfig>,
+    registry: Res<SolverRegistry>,
+    time: Res<Time>,
 ) {
-    // Only perform validation if there are recolor events
-    if !face_query.is_empty() {
-        solver.perform_lightweight_validation();
-
-        log::debug!(
-            "Lightweight validation on recolor: valid={}, message={}",
-            solver.is_solvable(),
-            solver.get_validation_message()
+    if !solver.tables_pending || solve_tasks.is_running() || !optimal::tables_ready() {
+        return;
+    }
+    solver.tables_pending = false;
+    if let Some(facelets) = solver.facelets() {
+        log::info!("Pattern databases are ready, solving again");
+        solve_tasks.spawn(
+            facelets.to_string(),
+            &solver_config,
+            &registry,
+            time.elapsed_secs(),
         );
     }
 }
 
-/// System to perform lightweight validation on rotation completion events
-pub fn lightweight_validation_on_rotation_complete(
-    mut solver: ResMut<CubeSolverResource>,
-    mut rotation_completed_events: EventReader<LayerRotationCompletedEvent>,
-    all_faces_query: Query<(Entity, &Face)>,
-    colored_faces_query: Query<(Entity, &RecoloredFace)>,
-    small_cube_transforms: Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-    main_cube_transforms: Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-    face_transforms: Query<&GlobalTransform, With<Face>>,
-) {
-    // Only perform validation if there are rotation completion events
-    if !rotation_completed_events.is_empty() {
-        // Clear the events to avoid processing them multiple times
-        rotation_completed_events.clear();
-
-        // Update solver state with current entity mappings (this calls map_entities_to_facelets)
-        solver.update_from_entities(
-            &all_faces_query,
-            &colored_faces_query,
-            &small_cube_transforms,
-            &main_cube_transforms,
-            &face_transforms,
-        );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_phase_solutions_solve_the_cube() {
+        let scramble = MoveSequence::parse("R U2 F' L D B2 R' U F2 D' L2 B").unwrap();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&scramble.moves);
+        let config = SolverConfig {
+            solution_count: 2,
+            ..SolverConfig::default()
+        };
+
+        let output = solve_with_config(&cube.to_facelets(), &config, || false, |_| {}).unwrap();
+        assert!(!output.solutions.is_empty());
+        for solution in &output.solutions {
+            let mut solved = cube;
+            solved.apply_moves(&solution.moves.moves);
+            assert!(solved.is_solved(), "{}", solution.moves);
+            assert!(solution.moves.len() <= config.max_depth);
+        }
+    }
 
-        log::info!(
-            "Cube solver updated after rotation: {}",
-            solver.get_validation_message()
+    #[test]
+    fn superseded_solves_do_not_search() {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse("R U F").unwrap().moves);
+        let result = solve_with_config(
+            &cube.to_facelets(),
+            &SolverConfig::default(),
+            || true,
+            |_| {},
         );
+        assert_eq!(result, Err(SolverError::Cancelled));
+    }
 
-        if solver.is_solvable() {
-            log::info!(
-                "Generated {} solving moves after rotation",
-                solver.solve_moves().len()
-            );
-        }
+    #[test]
+    fn broken_cubes_are_reported_without_retrying() {
+        let mut twisted = CubieCube::SOLVED;
+        twisted.co[0] = 1;
+        let error = solve_with_config(
+            &twisted.to_facelets(),
+            &SolverConfig::default(),
+            || false,
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(matches!(error, SolverError::Failed(_)), "{error:?}");
     }
 }
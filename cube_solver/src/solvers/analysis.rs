@@ -0,0 +1,264 @@
+//! What a cube state already has right: solved pieces and F2L pairs, orientation on
+//! every axis, permutation parity and a lower bound on its distance from solved.
+//!
+//! The bound is the largest of small exact tables, the cross and each F2L pair on
+//! every face, and of the optimal solver's pattern databases once they are loaded.
+
+use super::cfop::pair_distance;
+use super::cross::cross_distance;
+use super::fmc::{AXES, axis_name, bad_edges, twisted_corners};
+use super::optimal;
+use crate::cube_model::{
+    Axis, CENTER_FACELETS, CORNER_NAMES, CubeModel, CubieCube, EDGE_NAMES, Face, Move, MoveKind,
+};
+use std::fmt;
+
+/// Corner and edge of each F2L slot with the first layer on U, as the app builds it:
+/// URF + FR, UFL + FL, ULB + BL, UBR + BR
+const SLOTS: [(usize, usize); 4] = [(0, 8), (1, 9), (2, 10), (3, 11)];
+
+/// Where a lower bound on the distance comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundSource {
+    /// The cross and F2L pair tables
+    PieceTables,
+    /// The optimal solver's pattern databases
+    PatternDatabases,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateAnalysis {
+    /// Corner positions holding their own piece the right way round
+    pub solved_corners: Vec<usize>,
+    /// Edge positions holding their own piece the right way round
+    pub solved_edges: Vec<usize>,
+    /// F2L slots with their corner and edge solved, by the name of the edge, for the
+    /// first layer on U
+    pub f2l_pairs: Vec<&'static str>,
+    /// Bad edges for orientation on each axis, in [`AXES`] order
+    pub misoriented_edges: [(Axis, usize); 3],
+    /// Corners twisted on each axis, in [`AXES`] order
+    pub twisted_corners: [(Axis, usize); 3],
+    /// The corner permutation is odd
+    pub odd_corners: bool,
+    /// The edge permutation is odd
+    pub odd_edges: bool,
+    /// Face turns the cube needs at least
+    pub lower_bound: usize,
+    pub bound_source: BoundSource,
+}
+
+/// The cube held with `face` down
+fn with_face_down(cube: &CubieCube, face: Face) -> CubieCube {
+    let rotations = [
+        None,
+        Some((Axis::X, 1)),
+        Some((Axis::X, 2)),
+        Some((Axis::X, 3)),
+        Some((Axis::Z, 1)),
+        Some((Axis::Z, 3)),
+    ];
+    let down = CENTER_FACELETS[Face::D.index()];
+    rotations
+        .into_iter()
+        .map(|rotation| {
+            let mut model = CubeModel::from_cubie(cube);
+            if let Some((axis, turns)) = rotation {
+                model.apply_move(Move::new(MoveKind::Rotation(axis), turns));
+            }
+            model
+        })
+        .find(|model| model.facelet(down) == Some(face))
+        .and_then(|model| model.to_cubie().ok())
+        .expect("a whole-cube turn brings every face down")
+}
+
+/// Cross and F2L pair distances with every face down; the largest is a lower bound
+fn piece_table_bound(cube: &CubieCube) -> usize {
+    Face::ALL
+        .into_iter()
+        .map(|face| {
+            let held = with_face_down(cube, face);
+            (0..4)
+                .map(|slot| pair_distance(&held, slot))
+                .fold(cross_distance(&held), usize::max)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+pub fn analyze(cube: &CubieCube) -> StateAnalysis {
+    let solved_corners: Vec<usize> = (0..8)
+        .filter(|&i| cube.cp[i] as usize == i && cube.co[i] == 0)
+        .collect();
+    let solved_edges: Vec<usize> = (0..12)
+        .filter(|&i| cube.ep[i] as usize == i && cube.eo[i] == 0)
+        .collect();
+
+    let f2l_pairs = SLOTS
+        .iter()
+        .filter(|(corner, edge)| solved_corners.contains(corner) && solved_edges.contains(edge))
+        .map(|&(_, edge)| EDGE_NAMES[edge])
+        .collect();
+
+    let (lower_bound, bound_source) = match optimal::distance_bound(cube) {
+        Some(bound) => (
+            bound.max(piece_table_bound(cube)),
+            BoundSource::PatternDatabases,
+        ),
+        None => (piece_table_bound(cube), BoundSource::PieceTables),
+    };
+
+    StateAnalysis {
+        solved_corners,
+        solved_edges,
+        f2l_pairs,
+        misoriented_edges: AXES.map(|axis| (axis, bad_edges(cube, axis))),
+        twisted_corners: AXES.map(|axis| (axis, twisted_corners(cube, axis))),
+        odd_corners: cube.corner_parity(),
+        odd_edges: cube.edge_parity(),
+        lower_bound,
+        bound_source,
+    }
+}
+
+/// Names of the given positions, or "none"
+fn names(positions: &[usize], all: &[&str]) -> String {
+    if positions.is_empty() {
+        return "none".to_string();
+    }
+    positions
+        .iter()
+        .map(|&position| all[position])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn per_axis(counts: &[(Axis, usize); 3]) -> String {
+    counts
+        .iter()
+        .map(|&(axis, count)| format!("{} {}", axis_name(axis), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn parity(odd: bool) -> &'static str {
+    if odd { "odd" } else { "even" }
+}
+
+/// One line per fact, as the state panel shows them
+impl fmt::Display for StateAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Solved corners ({}/8): {}",
+            self.solved_corners.len(),
+            names(&self.solved_corners, &CORNER_NAMES)
+        )?;
+        writeln!(
+            f,
+            "Solved edges ({}/12): {}",
+            self.solved_edges.len(),
+            names(&self.solved_edges, &EDGE_NAMES)
+        )?;
+        let pairs = if self.f2l_pairs.is_empty() {
+            "none".to_string()
+        } else {
+            self.f2l_pairs.join(" ")
+        };
+        writeln!(
+            f,
+            "F2L pairs below U ({}/4): {}",
+            self.f2l_pairs.len(),
+            pairs
+        )?;
+        writeln!(
+            f,
+            "Misoriented edges: {}",
+            per_axis(&self.misoriented_edges)
+        )?;
+        writeln!(f, "Twisted corners: {}", per_axis(&self.twisted_corners))?;
+        writeln!(
+            f,
+            "Permutation parity: corners {}, edges {}",
+            parity(self.odd_corners),
+            parity(self.odd_edges)
+        )?;
+        let source = match self.bound_source {
+            BoundSource::PieceTables => "cross and F2L pair tables",
+            BoundSource::PatternDatabases => "pattern databases",
+        };
+        write!(
+            f,
+            "At least {} move{} (HTM, from the {})",
+            self.lower_bound,
+            if self.lower_bound == 1 { "" } else { "s" },
+            source
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_sequence::MoveSequence;
+
+    fn scrambled(algorithm: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(algorithm).unwrap().moves);
+        cube
+    }
+
+    #[test]
+    fn solved_cube() {
+        let analysis = analyze(&CubieCube::SOLVED);
+        assert_eq!(analysis.solved_corners.len(), 8);
+        assert_eq!(analysis.solved_edges.len(), 12);
+        assert_eq!(analysis.f2l_pairs, vec!["FR", "FL", "BL", "BR"]);
+        assert!(analysis.misoriented_edges.iter().all(|&(_, n)| n == 0));
+        assert!(analysis.twisted_corners.iter().all(|&(_, n)| n == 0));
+        assert!(!analysis.odd_corners && !analysis.odd_edges);
+        assert_eq!(analysis.lower_bound, 0);
+    }
+
+    #[test]
+    fn single_face_turn() {
+        let analysis = analyze(&scrambled("R"));
+        assert_eq!(
+            names(&analysis.solved_corners, &CORNER_NAMES),
+            "UFL ULB DLF DBL"
+        );
+        assert_eq!(analysis.solved_edges.len(), 8);
+        assert_eq!(analysis.f2l_pairs, vec!["FL", "BL"]);
+        assert!(analysis.odd_corners && analysis.odd_edges);
+        assert_eq!(analysis.lower_bound, 1);
+
+        let text = analysis.to_string();
+        assert!(text.contains("F2L pairs below U (2/4): FL BL"), "{text}");
+        assert!(text.contains("Permutation parity: corners odd, edges odd"));
+        if analysis.bound_source == BoundSource::PieceTables {
+            assert!(text.ends_with("At least 1 move (HTM, from the cross and F2L pair tables)"));
+        }
+    }
+
+    #[test]
+    fn orientation_on_each_axis() {
+        let analysis = analyze(&scrambled("F"));
+        let on = |counts: &[(Axis, usize); 3], axis: Axis| {
+            counts.iter().find(|&&(a, _)| a == axis).unwrap().1
+        };
+        // F flips its four edges for F/B orientation and twists its corners off U/D
+        assert_eq!(on(&analysis.misoriented_edges, Face::F.axis()), 4);
+        assert_eq!(on(&analysis.misoriented_edges, Face::U.axis()), 0);
+        assert_eq!(on(&analysis.twisted_corners, Face::U.axis()), 4);
+        assert_eq!(on(&analysis.twisted_corners, Face::F.axis()), 0);
+    }
+
+    #[test]
+    fn bound_grows_with_the_scramble() {
+        let analysis = analyze(&scrambled("R U F' L2 D B' R2 U'"));
+        assert!(analysis.lower_bound >= 2);
+        assert!(analysis.lower_bound <= 8);
+        assert!(analysis.f2l_pairs.len() < 4);
+    }
+}
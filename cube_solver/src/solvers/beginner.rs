@@ -0,0 +1,360 @@
+//! Beginner layer-by-layer solver with labelled, explained stages.
+//!
+//! The first layer is built on U (white in the default orientation) and the last
+//! layer on D. Internally the cube is turned upside down so the stages can use
+//! the usual algorithms with the first layer on D; the moves are turned back
+//! before they are returned.
+//!
+//! Most stages are made of steps: turns of the top layer and the classic beginner
+//! algorithms at every slot. The shortest combination of steps that reaches the
+//! stage goal without breaking finished pieces is searched for, so the output
+//! follows the method a beginner would use.
+
+use super::cross::solve_cross;
+use super::{Solution, from_upside_down, upside_down_cube};
+use crate::cube_model::{Axis, CubieCube, Face, Move, MoveKind};
+use crate::move_sequence::MoveSequence;
+
+struct Stage {
+    label: &'static str,
+    explanation: &'static str,
+    solve: fn(&CubieCube) -> Option<MoveSequence>,
+}
+
+const STAGES: [Stage; 7] = [
+    Stage {
+        label: "White cross",
+        explanation: "Bring the four white edges around the white center, each matching its side center",
+        solve: white_cross,
+    },
+    Stage {
+        label: "First-layer corners",
+        explanation: "Bring each white corner above its slot and repeat the trigger until it drops in",
+        solve: first_layer_corners,
+    },
+    Stage {
+        label: "Middle edges",
+        explanation: "Insert each middle-layer edge from the yellow layer to the left or to the right",
+        solve: middle_edges,
+    },
+    Stage {
+        label: "Yellow cross",
+        explanation: "Flip the yellow edges until they form a cross around the yellow center",
+        solve: yellow_cross,
+    },
+    Stage {
+        label: "Yellow edges",
+        explanation: "Cycle the yellow edges until each one matches its side center",
+        solve: yellow_edges,
+    },
+    Stage {
+        label: "Corner placement",
+        explanation: "Cycle the yellow corners until each one sits between its three centers",
+        solve: corner_placement,
+    },
+    Stage {
+        label: "Corner orientation",
+        explanation: "Twist the yellow corners one by one, turning only the yellow layer in between",
+        solve: corner_orientation,
+    },
+];
+
+/// Solve `cube` layer by layer, one solution stage per step of the method
+pub fn solve(cube: &CubieCube) -> Result<Solution, String> {
+    let mut cube = upside_down_cube(cube);
+
+    let mut solution = Solution::default();
+    for stage in &STAGES {
+        let moves =
+            (stage.solve)(&cube).ok_or_else(|| format!("{} could not be solved", stage.label))?;
+        cube.apply_moves(&moves.moves);
+        solution.push_stage(stage.label, stage.explanation, &from_upside_down(&moves));
+    }
+
+    if !cube.is_solved() {
+        return Err("Layer-by-layer solve did not finish the cube".to_string());
+    }
+    Ok(solution)
+}
+
+/// A building block of a stage
+struct Step {
+    moves: MoveSequence,
+    /// Effect of the moves on the solved cube
+    cube: CubieCube,
+    /// Turn of the top layer only; two in a row are never useful
+    top_turn: bool,
+}
+
+impl Step {
+    fn new(moves: MoveSequence, top_turn: bool) -> Self {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&moves.moves);
+        Self {
+            moves,
+            cube,
+            top_turn,
+        }
+    }
+}
+
+fn top_turns() -> Vec<Step> {
+    (1..=3)
+        .map(|turns| Step::new(MoveSequence::new(vec![Move::face(Face::U, turns)]), true))
+        .collect()
+}
+
+/// The algorithm performed at each of the four slots around the top layer
+fn at_every_slot(algorithm: &str) -> impl Iterator<Item = Step> {
+    let sequence = MoveSequence::parse(algorithm).expect("built-in algorithms are valid");
+    (0..4).map(move |turns| {
+        Step::new(
+            sequence.rotate(Move::new(MoveKind::Rotation(Axis::Y), turns)),
+            false,
+        )
+    })
+}
+
+/// Shortest combination of at most `max_steps` steps after which `goal` holds.
+/// Fewer steps win, then fewer moves.
+fn search_steps(
+    cube: &CubieCube,
+    steps: &[Step],
+    max_steps: usize,
+    goal: &dyn Fn(&CubieCube) -> bool,
+) -> Option<MoveSequence> {
+    for depth in 0..=max_steps {
+        let mut best = None;
+        search_depth(cube, steps, depth, goal, &mut Vec::new(), &mut best);
+        if let Some((_, path)) = best {
+            let moves = path
+                .iter()
+                .fold(MoveSequence::default(), |moves: MoveSequence, &index| {
+                    moves.then(&steps[index].moves)
+                });
+            return Some(moves.simplify());
+        }
+    }
+    None
+}
+
+fn search_depth(
+    cube: &CubieCube,
+    steps: &[Step],
+    remaining: usize,
+    goal: &dyn Fn(&CubieCube) -> bool,
+    path: &mut Vec<usize>,
+    best: &mut Option<(usize, Vec<usize>)>,
+) {
+    if remaining == 0 {
+        if goal(cube) {
+            let length = path.iter().map(|&index| steps[index].moves.len()).sum();
+            if best
+                .as_ref()
+                .is_none_or(|(best_length, _)| length < *best_length)
+            {
+                *best = Some((length, path.clone()));
+            }
+        }
+        return;
+    }
+    for (index, step) in steps.iter().enumerate() {
+        if step.top_turn && path.last().is_some_and(|&last| steps[last].top_turn) {
+            continue;
+        }
+        path.push(index);
+        search_depth(
+            &cube.multiply(&step.cube),
+            steps,
+            remaining - 1,
+            goal,
+            path,
+            best,
+        );
+        path.pop();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Piece {
+    Corner(usize),
+    Edge(usize),
+}
+
+impl Piece {
+    fn is_solved(self, cube: &CubieCube) -> bool {
+        match self {
+            Piece::Corner(position) => {
+                cube.cp[position] as usize == position && cube.co[position] == 0
+            }
+            Piece::Edge(position) => {
+                cube.ep[position] as usize == position && cube.eo[position] == 0
+            }
+        }
+    }
+}
+
+/// Place `pieces` one at a time, always taking the one that is cheapest to place next,
+/// while keeping `kept` and the pieces already placed
+fn place_one_by_one(
+    cube: &CubieCube,
+    steps: &[Step],
+    max_steps: usize,
+    kept: fn(&CubieCube) -> bool,
+    pieces: &[Piece],
+) -> Option<MoveSequence> {
+    let mut cube = *cube;
+    let mut moves = MoveSequence::default();
+    let mut placed: Vec<Piece> = Vec::new();
+    let mut remaining = pieces.to_vec();
+
+    while !remaining.is_empty() {
+        let (index, next_moves) = remaining
+            .iter()
+            .enumerate()
+            .filter_map(|(index, &piece)| {
+                let goal = |cube: &CubieCube| {
+                    kept(cube)
+                        && piece.is_solved(cube)
+                        && placed.iter().all(|placed| placed.is_solved(cube))
+                };
+                search_steps(&cube, steps, max_steps, &goal).map(|moves| (index, moves))
+            })
+            .min_by_key(|(_, moves)| moves.len())?;
+        cube.apply_moves(&next_moves.moves);
+        moves = moves.then(&next_moves);
+        placed.push(remaining.remove(index));
+    }
+    Some(moves.simplify())
+}
+
+fn cross_solved(cube: &CubieCube) -> bool {
+    (4..8).all(|position| Piece::Edge(position).is_solved(cube))
+}
+
+fn first_layer_solved(cube: &CubieCube) -> bool {
+    cross_solved(cube) && (4..8).all(|position| Piece::Corner(position).is_solved(cube))
+}
+
+fn first_two_layers_solved(cube: &CubieCube) -> bool {
+    first_layer_solved(cube) && (8..12).all(|position| Piece::Edge(position).is_solved(cube))
+}
+
+fn top_edges_solved(cube: &CubieCube) -> bool {
+    (0..4).all(|position| Piece::Edge(position).is_solved(cube))
+}
+
+fn white_cross(cube: &CubieCube) -> Option<MoveSequence> {
+    Some(solve_cross(cube))
+}
+
+fn first_layer_corners(cube: &CubieCube) -> Option<MoveSequence> {
+    let mut steps = top_turns();
+    for repetitions in 1..=5 {
+        steps.extend(at_every_slot(&"R U R' U' ".repeat(repetitions)));
+    }
+    let corners = [4, 5, 6, 7].map(Piece::Corner);
+    place_one_by_one(cube, &steps, 3, cross_solved, &corners)
+}
+
+fn middle_edges(cube: &CubieCube) -> Option<MoveSequence> {
+    let mut steps = top_turns();
+    steps.extend(at_every_slot("U R U' R' U' F' U F"));
+    steps.extend(at_every_slot("U' L' U L U F U' F'"));
+    let edges = [8, 9, 10, 11].map(Piece::Edge);
+    place_one_by_one(cube, &steps, 3, first_layer_solved, &edges)
+}
+
+fn yellow_cross(cube: &CubieCube) -> Option<MoveSequence> {
+    let mut steps = top_turns();
+    steps.push(Step::new(
+        MoveSequence::parse("F R U R' U' F'").expect("built-in algorithms are valid"),
+        false,
+    ));
+    let goal = |cube: &CubieCube| {
+        first_two_layers_solved(cube) && cube.eo[..4].iter().all(|&flip| flip == 0)
+    };
+    search_steps(cube, &steps, 5, &goal)
+}
+
+fn yellow_edges(cube: &CubieCube) -> Option<MoveSequence> {
+    let mut steps = top_turns();
+    steps.push(Step::new(
+        MoveSequence::parse("R U R' U R U2 R'").expect("built-in algorithms are valid"),
+        false,
+    ));
+    let goal = |cube: &CubieCube| first_two_layers_solved(cube) && top_edges_solved(cube);
+    search_steps(cube, &steps, 6, &goal)
+}
+
+fn corner_placement(cube: &CubieCube) -> Option<MoveSequence> {
+    let steps: Vec<Step> = at_every_slot("U R U' L' U R' U' L")
+        .chain(at_every_slot("L' U R U' L U R' U'"))
+        .collect();
+    let goal = |cube: &CubieCube| {
+        first_two_layers_solved(cube)
+            && top_edges_solved(cube)
+            && (0..4).all(|position| cube.cp[position] as usize == position)
+    };
+    search_steps(cube, &steps, 2, &goal)
+}
+
+/// Twist the corner at URF with the trigger pair until it is oriented, then bring the
+/// next corner there with U. After four U turns the top layer is back in place.
+fn corner_orientation(cube: &CubieCube) -> Option<MoveSequence> {
+    let twist = MoveSequence::parse("R' D' R D R' D' R D").expect("built-in algorithms are valid");
+    let top_turn = Move::face(Face::U, 1);
+    let mut cube = *cube;
+    let mut moves = MoveSequence::default();
+    for _ in 0..4 {
+        for _ in 0..2 {
+            if cube.co[0] == 0 {
+                break;
+            }
+            cube.apply_moves(&twist.moves);
+            moves = moves.then(&twist);
+        }
+        if cube.co[0] != 0 {
+            return None;
+        }
+        cube.apply_move(top_turn);
+        moves.moves.push(top_turn);
+    }
+    Some(moves.simplify())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrambled(scramble: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(scramble).unwrap().moves);
+        cube
+    }
+
+    #[test]
+    fn scrambles_solve_stage_by_stage() {
+        for scramble in [
+            "R U2 F' L D B2 R' U F2 D' L2 B",
+            "D2 F' U R2 B L' U2 F D' R B2 L U' F2 R'",
+        ] {
+            let cube = scrambled(scramble);
+            let solution = solve(&cube).unwrap();
+            let mut solved = cube;
+            solved.apply_moves(&solution.moves.moves);
+            assert!(solved.is_solved(), "{}: {}", scramble, solution.moves);
+
+            let staged: usize = solution.stages.iter().map(|stage| stage.move_count).sum();
+            assert_eq!(staged, solution.move_count(), "{}", scramble);
+            assert_eq!(solution.stages[0].label, "White cross");
+        }
+    }
+
+    #[test]
+    fn solved_cube_needs_no_stages() {
+        let solution = solve(&CubieCube::SOLVED).unwrap();
+        assert!(solution.moves.is_empty());
+        assert!(solution.stages.is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! CFOP solver: an optimal cross, the four F2L pairs one at a time, then OLL and PLL.
+//!
+//! Like the beginner solver it works on the cube turned upside down, with the cross
+//! on D. Each F2L pair is the shortest sequence that solves one more pair while
+//! keeping the cross and the pairs already inserted, found by IDA* over small exact
+//! distance tables. The last layer is recognised against the standard case tables.
+
+use super::coordinates::{
+    MOVE_COUNT, corner_states, corner_transitions, edge_states, edge_transitions, face_turn,
+};
+use super::cross::{cross_distance_of_edges, solve_cross};
+use super::last_layer::{
+    adjust_turns, algorithm_moves, last_layer_oriented, recognize_oll, recognize_pll,
+};
+use super::{Solution, from_upside_down, upside_down_cube};
+use crate::cube_model::{Axis, CubieCube, Face, Move};
+use crate::move_sequence::MoveSequence;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+/// Corner and edge piece of each F2L slot: DFR + FR, DLF + FL, DBL + BL, DRB + BR
+const SLOTS: [(usize, usize); 4] = [(4, 8), (5, 9), (6, 10), (7, 11)];
+/// Faces around each slot, for its name
+pub const SLOT_FACES: [(Face, Face); 4] = [
+    (Face::F, Face::R),
+    (Face::F, Face::L),
+    (Face::B, Face::L),
+    (Face::B, Face::R),
+];
+/// Corner states times edge states
+const PAIR_STATES: usize = 24 * 24;
+
+/// Solve `cube` with CFOP, one solution stage per cross, pair, OLL and PLL
+pub fn solve(cube: &CubieCube) -> Result<Solution, String> {
+    let mut cube = upside_down_cube(cube);
+    let mut solution = Solution::default();
+    let mut push_stage =
+        |cube: &mut CubieCube, label: &str, explanation: &str, moves: MoveSequence| {
+            cube.apply_moves(&moves.moves);
+            solution.push_stage(label, explanation, &from_upside_down(&moves));
+        };
+
+    let cross = solve_cross(&cube);
+    push_stage(
+        &mut cube,
+        "Cross",
+        "Solve the four edges around the white center in the fewest moves",
+        cross,
+    );
+
+    let mut placed = Vec::new();
+    let mut remaining = vec![0, 1, 2, 3];
+    for number in 1..=4 {
+        let (slot, moves) = solve_next_pair(&cube, &placed, &remaining);
+        remaining.retain(|&other| other != slot);
+        placed.push(slot);
+        let name = slot_name(slot);
+        push_stage(
+            &mut cube,
+            &format!("F2L {} ({})", number, name),
+            &format!(
+                "Pair up the {} corner and edge and insert them together",
+                name
+            ),
+            moves,
+        );
+    }
+
+    if !last_layer_oriented(&cube) {
+        let (turns, case) =
+            recognize_oll(&cube).ok_or_else(|| "OLL case not recognised".to_string())?;
+        let moves = top_turns(turns).then(&algorithm_moves(case.algorithm));
+        push_stage(
+            &mut cube,
+            &case.label(),
+            "Orient the last layer so its whole face shows one color",
+            moves.simplify(),
+        );
+    }
+
+    if let Some(turns) = adjust_turns(&cube) {
+        push_stage(
+            &mut cube,
+            "AUF",
+            "Turn the last layer to line it up with the centers",
+            top_turns(turns),
+        );
+    } else {
+        let (before, case, after) =
+            recognize_pll(&cube).ok_or_else(|| "PLL case not recognised".to_string())?;
+        let moves = top_turns(before)
+            .then(&algorithm_moves(case.algorithm))
+            .then(&top_turns(after));
+        push_stage(
+            &mut cube,
+            &case.label(),
+            "Move the last-layer pieces to their places without twisting them",
+            moves.simplify(),
+        );
+    }
+
+    if !cube.is_solved() {
+        return Err("CFOP solve did not finish the cube".to_string());
+    }
+    Ok(solution)
+}
+
+/// Name of a slot as the app holds the cube, such as "FR"
+pub fn slot_name(slot: usize) -> String {
+    let (front_or_back, side) = SLOT_FACES[slot];
+    // The slots are found on the upside-down cube
+    [front_or_back, side]
+        .map(|face| face.rotated(Axis::X, 2).to_char())
+        .iter()
+        .collect()
+}
+
+/// `turns` quarter turns of U, nothing for a whole turn
+pub fn top_turns(turns: i32) -> MoveSequence {
+    MoveSequence::new(vec![Move::face(Face::U, turns)]).simplify()
+}
+
+fn pair_index(corners: &[u8; 8], edges: &[u8; 12], slot: usize) -> usize {
+    let (corner, edge) = SLOTS[slot];
+    corners[corner] as usize * 24 + edges[edge] as usize
+}
+
+/// Face turns that solve the pair of each slot on its own, found by breadth-first search
+fn pair_distances() -> &'static [[u8; PAIR_STATES]; 4] {
+    static DISTANCES: OnceLock<[[u8; PAIR_STATES]; 4]> = OnceLock::new();
+    DISTANCES.get_or_init(|| {
+        let corner_transitions = corner_transitions();
+        let edge_transitions = edge_transitions();
+        std::array::from_fn(|slot| {
+            let mut distances = [u8::MAX; PAIR_STATES];
+            let (corner, edge) = SLOTS[slot];
+            let solved = corner * 3 * 24 + edge * 2;
+            distances[solved] = 0;
+            let mut queue = VecDeque::from([solved]);
+            while let Some(index) = queue.pop_front() {
+                for m in 0..MOVE_COUNT {
+                    let next = corner_transitions[m][index / 24] as usize * 24
+                        + edge_transitions[m][index % 24] as usize;
+                    if distances[next] == u8::MAX {
+                        distances[next] = distances[index] + 1;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            distances
+        })
+    })
+}
+
+/// Number of face turns needed to solve the corner and edge of F2L slot `slot`,
+/// ignoring every other piece
+pub fn pair_distance(cube: &CubieCube, slot: usize) -> usize {
+    pair_distance_of_states(&corner_states(cube), &edge_states(cube), slot)
+}
+
+/// [`pair_distance`] from the states of all corners and edges, as given by
+/// [`corner_states`] and [`edge_states`]
+pub fn pair_distance_of_states(corners: &[u8; 8], edges: &[u8; 12], slot: usize) -> usize {
+    pair_distances()[slot][pair_index(corners, edges, slot)] as usize
+}
+
+/// Pieces tracked while searching for the next pair
+struct PairSearch<'a> {
+    placed: &'a [usize],
+    remaining: &'a [usize],
+    moves: Vec<usize>,
+}
+
+impl PairSearch<'_> {
+    /// Lower bound on the turns to the goal, which is zero exactly at the goal:
+    /// the cross and placed pairs are kept and one more pair is solved
+    fn lower_bound(&self, corners: &[u8; 8], edges: &[u8; 12]) -> usize {
+        let distances = pair_distances();
+        let kept = self
+            .placed
+            .iter()
+            .map(|&slot| distances[slot][pair_index(corners, edges, slot)] as usize)
+            .fold(cross_distance_of_edges(edges), usize::max);
+        let next = self
+            .remaining
+            .iter()
+            .map(|&slot| distances[slot][pair_index(corners, edges, slot)] as usize)
+            .min()
+            .unwrap_or(0);
+        kept.max(next)
+    }
+
+    /// Depth-first search within `budget` turns; returns the slot that got solved
+    fn search(&mut self, corners: &[u8; 8], edges: &[u8; 12], budget: usize) -> Option<usize> {
+        let bound = self.lower_bound(corners, edges);
+        if bound == 0 {
+            let distances = pair_distances();
+            return self
+                .remaining
+                .iter()
+                .copied()
+                .find(|&slot| distances[slot][pair_index(corners, edges, slot)] == 0);
+        }
+        if bound > budget {
+            return None;
+        }
+        let corner_transitions = corner_transitions();
+        let edge_transitions = edge_transitions();
+        for m in 0..MOVE_COUNT {
+            if let Some(&last) = self.moves.last() {
+                let (face, last_face) = (m / 3, last / 3);
+                // Same face twice, or opposite faces in both orders, repeat other paths
+                if face == last_face || (face % 3 == last_face % 3 && face < last_face) {
+                    continue;
+                }
+            }
+            let next_corners = corners.map(|state| corner_transitions[m][state as usize]);
+            let next_edges = edges.map(|state| edge_transitions[m][state as usize]);
+            self.moves.push(m);
+            if let Some(slot) = self.search(&next_corners, &next_edges, budget - 1) {
+                return Some(slot);
+            }
+            self.moves.pop();
+        }
+        None
+    }
+}
+
+/// The shortest sequence that solves one of the `remaining` pairs while keeping the
+/// cross and the `placed` pairs, with the slot it solves
+pub fn solve_next_pair(
+    cube: &CubieCube,
+    placed: &[usize],
+    remaining: &[usize],
+) -> (usize, MoveSequence) {
+    let corners = corner_states(cube);
+    let edges = edge_states(cube);
+    let mut search = PairSearch {
+        placed,
+        remaining,
+        moves: Vec::new(),
+    };
+    let mut budget = search.lower_bound(&corners, &edges);
+    loop {
+        if let Some(slot) = search.search(&corners, &edges, budget) {
+            let moves = search.moves.iter().map(|&m| face_turn(m)).collect();
+            return (slot, MoveSequence::new(moves));
+        }
+        budget += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrambled(scramble: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(scramble).unwrap().moves);
+        cube
+    }
+
+    #[test]
+    fn scrambles_solve_stage_by_stage() {
+        for scramble in [
+            "R U2 F' L D B2 R' U F2 D' L2 B",
+            "D2 F' U R2 B L' U2 F D' R B2 L U' F2 R'",
+        ] {
+            let cube = scrambled(scramble);
+            let solution = solve(&cube).unwrap();
+            let mut solved = cube;
+            solved.apply_moves(&solution.moves.moves);
+            assert!(solved.is_solved(), "{}: {}", scramble, solution.moves);
+
+            let staged: usize = solution.stages.iter().map(|stage| stage.move_count).sum();
+            assert_eq!(staged, solution.move_count(), "{}", scramble);
+            let labels: Vec<&str> = solution.stages.iter().map(|s| s.label.as_str()).collect();
+            assert_eq!(labels[0], "Cross", "{:?}", labels);
+            let pairs = labels
+                .iter()
+                .filter(|label| label.starts_with("F2L"))
+                .count();
+            assert_eq!(pairs, 4, "{:?}", labels);
+        }
+    }
+
+    #[test]
+    fn last_layer_case_is_named() {
+        // A Sune on the last layer, which the app holds on D
+        let cube = scrambled("R D2 R' D' R D' R'");
+        let solution = solve(&cube).unwrap();
+        let mut solved = cube;
+        solved.apply_moves(&solution.moves.moves);
+        assert!(solved.is_solved(), "{}", solution.moves);
+        let labels: Vec<&str> = solution.stages.iter().map(|s| s.label.as_str()).collect();
+        assert!(labels[0].starts_with("OLL"), "{:?}", labels);
+    }
+}
@@ -0,0 +1,233 @@
+//! Piece coordinates shared by the table-driven solvers.
+//!
+//! Face turns are numbered 0..18 as U U2 U' R R2 R' ... in `Face::ALL` order.
+
+use crate::cube_model::{CORNER_NAMES, CubieCube, EDGE_NAMES, Face, Move, face_move_cube};
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+/// Number of face turns
+pub const MOVE_COUNT: usize = 18;
+/// Most pieces in one [`PieceDistances`] table, which has 24^n entries for n pieces
+pub const GROUP_SIZE: usize = 4;
+/// Nodes a search visits between checks of its stop condition
+pub const STOP_CHECK_INTERVAL: usize = 1 << 12;
+
+/// The face turn with number `m`
+pub fn face_turn(m: usize) -> Move {
+    Move::face(Face::ALL[m / 3], m as i32 % 3 + 1)
+}
+
+/// Cubie state of the solved cube after face turn `m`
+pub fn face_turn_cube(m: usize) -> CubieCube {
+    face_move_cube(Face::ALL[m / 3], (m % 3 + 1) as u8)
+}
+
+/// Rank of a sequence of distinct values below `n` among all sequences of its length
+pub fn rank_placement(values: &[u8], n: usize) -> usize {
+    let mut used = 0u32;
+    let mut rank = 0;
+    for (i, &value) in values.iter().enumerate() {
+        let smaller_unused = value as u32 - (used & ((1 << value) - 1)).count_ones();
+        rank = rank * (n - i) + smaller_unused as usize;
+        used |= 1 << value;
+    }
+    rank
+}
+
+/// Inverse of [`rank_placement`]
+pub fn unrank_placement(mut rank: usize, n: usize, values: &mut [u8]) {
+    let mut digits = [0; 12];
+    for i in (0..values.len()).rev() {
+        digits[i] = rank % (n - i);
+        rank /= n - i;
+    }
+    let mut used = 0u32;
+    for (value, &digit) in values.iter_mut().zip(&digits) {
+        let candidate = (0..n as u8)
+            .filter(|&v| used & (1 << v) == 0)
+            .nth(digit)
+            .expect("placement rank out of range");
+        *value = candidate;
+        used |= 1 << candidate;
+    }
+}
+
+/// `edge_transitions()[m][position * 2 + flip]`: where a single edge goes under face turn `m`
+pub fn edge_transitions() -> &'static [[u8; 24]; MOVE_COUNT] {
+    static TRANSITIONS: OnceLock<[[u8; 24]; MOVE_COUNT]> = OnceLock::new();
+    TRANSITIONS.get_or_init(|| std::array::from_fn(|m| edge_transition(&face_turn_cube(m))))
+}
+
+/// Where a single edge (`position * 2 + flip`) goes under the move with cubie state `cube`
+pub fn edge_transition(cube: &CubieCube) -> [u8; 24] {
+    // The piece at position ep[i] of a move cube goes to position i, flipped by eo[i]
+    std::array::from_fn(|state| {
+        let to = cube
+            .ep
+            .iter()
+            .position(|&from| from as usize == state / 2)
+            .expect("a move cube holds every edge");
+        (to * 2 + (state % 2 + cube.eo[to] as usize) % 2) as u8
+    })
+}
+
+/// Position and flip (`position * 2 + flip`) of every edge piece
+pub fn edge_states(cube: &CubieCube) -> [u8; 12] {
+    let mut edges = [0; 12];
+    for (position, (&piece, &flip)) in cube.ep.iter().zip(&cube.eo).enumerate() {
+        edges[piece as usize] = position as u8 * 2 + flip;
+    }
+    edges
+}
+
+/// Index of a group of edges (`position * 2 + flip` each) among all placements and flips
+pub fn edge_group_index(group: &[u8]) -> usize {
+    let mut positions = [0; 12];
+    let mut flips = 0;
+    for (position, &state) in positions.iter_mut().zip(group) {
+        *position = state / 2;
+        flips = flips << 1 | (state % 2) as usize;
+    }
+    rank_placement(&positions[..group.len()], 12) << group.len() | flips
+}
+
+/// Inverse of [`edge_group_index`]
+pub fn edge_group_from_index<const N: usize>(index: usize) -> [u8; N] {
+    let mut positions = [0; N];
+    unrank_placement(index >> N, 12, &mut positions);
+    std::array::from_fn(|i| {
+        let flip = (index >> (N - 1 - i)) & 1;
+        positions[i] * 2 + flip as u8
+    })
+}
+
+/// `corner_transitions()[m][position * 3 + twist]`: where a single corner goes under face turn `m`
+pub fn corner_transitions() -> &'static [[u8; 24]; MOVE_COUNT] {
+    static TRANSITIONS: OnceLock<[[u8; 24]; MOVE_COUNT]> = OnceLock::new();
+    TRANSITIONS.get_or_init(|| std::array::from_fn(|m| corner_transition(&face_turn_cube(m))))
+}
+
+/// Where a single corner (`position * 3 + twist`) goes under the move with cubie state `cube`
+pub fn corner_transition(cube: &CubieCube) -> [u8; 24] {
+    std::array::from_fn(|state| {
+        let to = cube
+            .cp
+            .iter()
+            .position(|&from| from as usize == state / 3)
+            .expect("a move cube holds every corner");
+        (to * 3 + (state % 3 + cube.co[to] as usize) % 3) as u8
+    })
+}
+
+/// Position and twist (`position * 3 + twist`) of every corner piece
+pub fn corner_states(cube: &CubieCube) -> [u8; 8] {
+    let mut corners = [0; 8];
+    for (position, (&piece, &twist)) in cube.cp.iter().zip(&cube.co).enumerate() {
+        corners[piece as usize] = position as u8 * 3 + twist;
+    }
+    corners
+}
+
+/// A corner or edge, tracked by its state `position * 3 + twist` or `position * 2 + flip`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Piece {
+    Corner(usize),
+    Edge(usize),
+}
+
+impl Piece {
+    pub fn name(self) -> &'static str {
+        match self {
+            Piece::Corner(piece) => CORNER_NAMES[piece],
+            Piece::Edge(piece) => EDGE_NAMES[piece],
+        }
+    }
+
+    /// State of the piece among the corner and edge states of a cube
+    pub fn state(self, corners: &[u8; 8], edges: &[u8; 12]) -> usize {
+        match self {
+            Piece::Corner(piece) => corners[piece] as usize,
+            Piece::Edge(piece) => edges[piece] as usize,
+        }
+    }
+
+    /// Where the piece goes under each face turn
+    pub fn transitions(self) -> &'static [[u8; 24]; MOVE_COUNT] {
+        match self {
+            Piece::Corner(_) => corner_transitions(),
+            Piece::Edge(_) => edge_transitions(),
+        }
+    }
+}
+
+/// Exact move counts for up to `GROUP_SIZE` pieces on their own
+pub struct PieceDistances {
+    pub pieces: Vec<Piece>,
+    distances: Vec<u8>,
+}
+
+impl PieceDistances {
+    /// Breadth-first search from where `is_goal` accepts the state of every piece, over
+    /// `move_count` moves that take a piece from state `s` to `transition(piece, m)[s]`
+    pub fn new<'a>(
+        pieces: Vec<Piece>,
+        move_count: usize,
+        transition: impl Fn(Piece, usize) -> &'a [u8; 24],
+        is_goal: impl Fn(Piece, usize) -> bool,
+    ) -> Self {
+        let mut distances = vec![u8::MAX; 24usize.pow(pieces.len() as u32)];
+        let mut queue = VecDeque::new();
+        for (index, distance) in distances.iter_mut().enumerate() {
+            let states = states_of(index, pieces.len());
+            if pieces
+                .iter()
+                .zip(&states)
+                .all(|(&piece, &state)| is_goal(piece, state))
+            {
+                *distance = 0;
+                queue.push_back(index);
+            }
+        }
+        while let Some(index) = queue.pop_front() {
+            let states = states_of(index, pieces.len());
+            for m in 0..move_count {
+                let next = states
+                    .iter()
+                    .zip(&pieces)
+                    .fold(0, |next, (&state, &piece)| {
+                        next * 24 + transition(piece, m)[state] as usize
+                    });
+                if distances[next] == u8::MAX {
+                    distances[next] = distances[index] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        Self { pieces, distances }
+    }
+
+    /// Moves needed at least, or `u8::MAX` when the moves cannot place the pieces
+    pub fn distance(&self, corners: &[u8; 8], edges: &[u8; 12]) -> u8 {
+        let index = self
+            .pieces
+            .iter()
+            .fold(0, |index, piece| index * 24 + piece.state(corners, edges));
+        self.distances[index]
+    }
+
+    pub fn name(&self) -> String {
+        let names: Vec<&str> = self.pieces.iter().map(|piece| piece.name()).collect();
+        names.join(" + ")
+    }
+}
+
+/// States of the pieces packed into `index`, first piece first
+pub fn states_of(mut index: usize, count: usize) -> [usize; GROUP_SIZE] {
+    let mut states = [0; GROUP_SIZE];
+    for state in states[..count].iter_mut().rev() {
+        *state = index % 24;
+        index /= 24;
+    }
+    states
+}
@@ -0,0 +1,74 @@
+//! First-layer cross: an exact distance table for the four D edges.
+
+use super::coordinates::{
+    MOVE_COUNT, edge_group_from_index, edge_group_index, edge_states, edge_transitions, face_turn,
+};
+use crate::cube_model::CubieCube;
+use crate::move_sequence::MoveSequence;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+/// Edge pieces DR, DF, DL, DB
+const CROSS_EDGES: Range<usize> = 4..8;
+/// Placements of four edges in twelve positions: 12 * 11 * 10 * 9
+const CROSS_PLACEMENTS: usize = 11880;
+const CROSS_STATES: usize = CROSS_PLACEMENTS << 4;
+
+fn cross_group(edges: &[u8; 12]) -> [u8; 4] {
+    edges[CROSS_EDGES]
+        .try_into()
+        .expect("the cross has four edges")
+}
+
+/// Face turns from every cross state to the solved cross, found by breadth-first search
+fn distances() -> &'static [u8] {
+    static DISTANCES: OnceLock<Vec<u8>> = OnceLock::new();
+    DISTANCES.get_or_init(|| {
+        let transitions = edge_transitions();
+        let mut distances = vec![u8::MAX; CROSS_STATES];
+        let solved = edge_group_index(&cross_group(&edge_states(&CubieCube::SOLVED)));
+        distances[solved] = 0;
+        let mut queue = VecDeque::from([solved]);
+        while let Some(index) = queue.pop_front() {
+            let group: [u8; 4] = edge_group_from_index(index);
+            for transition in transitions {
+                let next = edge_group_index(&group.map(|state| transition[state as usize]));
+                if distances[next] == u8::MAX {
+                    distances[next] = distances[index] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    })
+}
+
+/// Number of face turns needed to solve the D cross
+pub fn cross_distance(cube: &CubieCube) -> usize {
+    cross_distance_of_edges(&edge_states(cube))
+}
+
+/// [`cross_distance`] from the states of all edges, as given by [`edge_states`]
+pub fn cross_distance_of_edges(edges: &[u8; 12]) -> usize {
+    distances()[edge_group_index(&cross_group(edges))] as usize
+}
+
+/// A shortest sequence of face turns that solves the D cross
+pub fn solve_cross(cube: &CubieCube) -> MoveSequence {
+    let transitions = edge_transitions();
+    let distances = distances();
+    let mut group = cross_group(&edge_states(cube));
+    let mut distance = distances[edge_group_index(&group)];
+    let mut moves = Vec::with_capacity(distance as usize);
+    while distance > 0 {
+        let (m, next) = (0..MOVE_COUNT)
+            .map(|m| (m, group.map(|state| transitions[m][state as usize])))
+            .find(|(_, next)| distances[edge_group_index(next)] < distance)
+            .expect("some face turn brings every unsolved cross closer");
+        moves.push(face_turn(m));
+        group = next;
+        distance -= 1;
+    }
+    MoveSequence::new(moves)
+}
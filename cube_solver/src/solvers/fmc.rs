@@ -0,0 +1,838 @@
+//! Fewest-moves tools: NISS, edge orientation and domino reduction on every axis, and
+//! skeletons finished by inserting a corner or edge 3-cycle.
+//!
+//! NISS lets a solve continue on the inverse scramble: moves found there are undone
+//! at the end of the solution, so an attempt can switch sides whenever the other one
+//! looks easier. Challenges are played on random-state scrambles, the same for
+//! everyone on a given day, and typed solutions are checked against them.
+
+use super::coordinates::{
+    MOVE_COUNT, corner_states, corner_transitions, edge_states, edge_transitions, face_turn,
+    face_turn_cube,
+};
+use super::thistlethwaite::{self, reduced_pieces};
+use crate::cube_model::{
+    Axis, CORNER_NAMES, CubeModel, CubieCube, EDGE_NAMES, Face, Move, MoveKind,
+};
+use crate::move_sequence::{Metric, MoveSequence};
+use crate::notation::{Alg, AlgNode, Span};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Axes in the order fewest-moves solvers name them
+pub const AXES: [Axis; 3] = [Axis::Z, Axis::X, Axis::Y];
+
+/// Algorithms kept for every 3-cycle; equally short ones cancel into a skeleton differently
+const ALGORITHMS_PER_CYCLE: usize = 8;
+
+/// Name of an axis by the faces on it, such as "F/B"
+pub fn axis_name(axis: Axis) -> &'static str {
+    match axis {
+        Axis::X => "R/L",
+        Axis::Y => "U/D",
+        Axis::Z => "F/B",
+    }
+}
+
+/// Which scramble the moves of an attempt are found on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Side {
+    #[default]
+    Normal,
+    Inverse,
+}
+
+impl Side {
+    pub fn other(self) -> Side {
+        match self {
+            Side::Normal => Side::Inverse,
+            Side::Inverse => Side::Normal,
+        }
+    }
+}
+
+/// A fewest-moves attempt that may switch between the normal and the inverse scramble
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NissAttempt {
+    pub scramble: MoveSequence,
+    /// Moves found on the normal scramble
+    pub normal: MoveSequence,
+    /// Moves found on the inverse scramble
+    pub inverse: MoveSequence,
+    pub side: Side,
+}
+
+impl NissAttempt {
+    pub fn new(scramble: MoveSequence) -> Self {
+        Self {
+            scramble,
+            ..Self::default()
+        }
+    }
+
+    /// Continue on the other scramble
+    pub fn switch(&mut self) {
+        self.side = self.side.other();
+    }
+
+    /// Add moves found on the current side
+    pub fn push(&mut self, moves: &MoveSequence) {
+        let found = match self.side {
+            Side::Normal => &mut self.normal,
+            Side::Inverse => &mut self.inverse,
+        };
+        *found = found.then(moves);
+    }
+
+    /// The cube on the current side: its scramble with the moves of the other side as
+    /// premoves, followed by the moves of this side
+    pub fn cube(&self) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        match self.side {
+            Side::Normal => {
+                cube.apply_moves(&self.inverse.inverse().moves);
+                cube.apply_moves(&self.scramble.moves);
+                cube.apply_moves(&self.normal.moves);
+            }
+            Side::Inverse => {
+                cube.apply_moves(&self.normal.inverse().moves);
+                cube.apply_moves(&self.scramble.inverse().moves);
+                cube.apply_moves(&self.inverse.moves);
+            }
+        }
+        cube
+    }
+
+    /// The normal moves followed by the inverse moves undone, which solves the scramble
+    /// once the cube of either side is solved
+    pub fn solution(&self) -> MoveSequence {
+        self.normal.then(&self.inverse.inverse()).simplify()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.cube().is_solved()
+    }
+}
+
+/// The cube after the whole-cube turns that take the faces of each `(from, to)` axis
+/// onto the `to` axis
+fn turned(cube: &CubieCube, axes: &[(Axis, Axis)]) -> CubieCube {
+    let rotations: Vec<Move> = [Axis::X, Axis::Y, Axis::Z]
+        .into_iter()
+        .flat_map(|axis| (0..4).map(move |turns| Move::new(MoveKind::Rotation(axis), turns)))
+        .collect();
+    let lands_on = |turns: &[Move], from: Axis| {
+        let face = Face::ALL
+            .into_iter()
+            .find(|face| face.axis() == from)
+            .expect("every axis has faces");
+        turns
+            .iter()
+            .fold(face, |face, turn| {
+                face.rotated(turn.axis(), turn.axis_quarter_turns())
+            })
+            .axis()
+    };
+    let turns = rotations
+        .iter()
+        .flat_map(|&first| rotations.iter().map(move |&second| [first, second]))
+        .find(|turns| axes.iter().all(|&(from, to)| lands_on(turns, from) == to))
+        .expect("two rotations take any axes anywhere");
+
+    let mut model = CubeModel::from_cubie(cube);
+    model.apply_moves(&turns);
+    model.to_cubie().expect("rotations keep a valid cube valid")
+}
+
+/// Number of edges that are bad for orientation on `axis`: edges that cannot be
+/// solved without quarter turns of the two faces on it
+pub fn bad_edges(cube: &CubieCube, axis: Axis) -> usize {
+    let (_, edges) = reduced_pieces(&turned(cube, &[(axis, Axis::Z)]), 0);
+    edges.iter().filter(|&&good| !good).count()
+}
+
+/// Number of corners twisted on `axis`: corners whose sticker of the two faces on it
+/// is not on those faces
+pub fn twisted_corners(cube: &CubieCube, axis: Axis) -> usize {
+    let (corners, _) = reduced_pieces(&turned(cube, &[(axis, Axis::Y)]), 1);
+    corners.iter().filter(|&&good| !good).count()
+}
+
+/// How far a cube is from domino reduction: the group of half turns of the faces
+/// around `axis` and any turns of the two faces on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DominoStatus {
+    pub axis: Axis,
+    /// Axis the edges are counted as oriented on
+    pub edge_axis: Axis,
+    /// Corners whose sticker of the `axis` faces is not on those faces
+    pub bad_corners: usize,
+    /// Edges that are bad for orientation on `edge_axis`
+    pub bad_edges: usize,
+    /// Edges of the middle slice across `axis` that are outside it
+    pub misplaced_slice_edges: usize,
+}
+
+impl DominoStatus {
+    pub fn is_reduced(&self) -> bool {
+        self.bad_corners == 0 && self.bad_edges == 0 && self.misplaced_slice_edges == 0
+    }
+
+    /// Pieces still to fix, for picking the closer of two reductions
+    pub fn bad_pieces(&self) -> usize {
+        self.bad_corners + self.bad_edges + self.misplaced_slice_edges
+    }
+}
+
+/// Distance of `cube` from domino reduction on `axis`, reached from edges oriented on
+/// `edge_axis`, which must be another axis
+pub fn domino_status(cube: &CubieCube, axis: Axis, edge_axis: Axis) -> DominoStatus {
+    assert_ne!(
+        axis, edge_axis,
+        "edges are oriented on an axis across the domino"
+    );
+    let turned = turned(cube, &[(axis, Axis::Y), (edge_axis, Axis::Z)]);
+    let count_bad = |pieces: &[bool]| pieces.iter().filter(|&&good| !good).count();
+    let (_, edges) = reduced_pieces(&turned, 0);
+    let (corners, slice_edges) = reduced_pieces(&turned, 1);
+    DominoStatus {
+        axis,
+        edge_axis,
+        bad_corners: count_bad(&corners),
+        bad_edges: count_bad(&edges),
+        misplaced_slice_edges: count_bad(&slice_edges) / 2,
+    }
+}
+
+/// Edge orientation and domino reduction of a cube on every axis
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmcReport {
+    /// Bad edges on each axis, in [`AXES`] order
+    pub bad_edges: [(Axis, usize); 3],
+    /// Domino reduction on each axis, from whichever edge axis is closer
+    pub domino: [DominoStatus; 3],
+}
+
+pub fn analyze(cube: &CubieCube) -> FmcReport {
+    FmcReport {
+        bad_edges: AXES.map(|axis| (axis, bad_edges(cube, axis))),
+        domino: AXES.map(|axis| {
+            AXES.into_iter()
+                .filter(|&edge_axis| edge_axis != axis)
+                .map(|edge_axis| domino_status(cube, axis, edge_axis))
+                .min_by_key(DominoStatus::bad_pieces)
+                .expect("every axis has two others")
+        }),
+    }
+}
+
+/// What a skeleton leaves unsolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leftover {
+    Solved,
+    /// Three corners cycled, everything else solved
+    CornerCycle,
+    /// Three edges cycled, everything else solved
+    EdgeCycle,
+    /// More than a single 3-cycle
+    Unsolved,
+}
+
+pub fn leftover(cube: &CubieCube) -> Leftover {
+    leftover_of_states(&corner_states(cube), &edge_states(cube))
+}
+
+fn leftover_of_states(corners: &[u8; 8], edges: &[u8; 12]) -> Leftover {
+    // Pieces out of place, and pieces out of place or turned in place
+    let count = |states: &[u8], orientations: usize| {
+        states
+            .iter()
+            .enumerate()
+            .fold((0, 0), |(moved, wrong), (piece, &state)| {
+                let state = state as usize;
+                (
+                    moved + usize::from(state / orientations != piece),
+                    wrong + usize::from(state != piece * orientations),
+                )
+            })
+    };
+    match (count(corners, 3), count(edges, 2)) {
+        ((_, 0), (_, 0)) => Leftover::Solved,
+        ((3, 3), (_, 0)) => Leftover::CornerCycle,
+        ((_, 0), (3, 3)) => Leftover::EdgeCycle,
+        _ => Leftover::Unsolved,
+    }
+}
+
+/// A 3-cycle algorithm inserted into a skeleton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Insertion {
+    /// Number of skeleton face turns before the algorithm
+    pub position: usize,
+    pub algorithm: MoveSequence,
+    /// The skeleton with the algorithm inserted, after cancellations
+    pub solution: MoveSequence,
+    /// Moves of the skeleton and the algorithm that cancelled
+    pub cancelled: usize,
+}
+
+/// Insertions of a single 3-cycle algorithm that complete `skeleton` on `cube`, with
+/// the shortest results first. The skeleton is taken as face turns, so positions count
+/// wide moves and slices as the face turns they are made of.
+pub fn find_insertions(
+    cube: &CubieCube,
+    skeleton: &MoveSequence,
+    limit: usize,
+) -> Result<Vec<Insertion>, String> {
+    let skeleton = skeleton.to_face_turns();
+    let mut end = *cube;
+    end.apply_moves(&skeleton.moves);
+    match leftover(&end) {
+        Leftover::Solved => return Err("The skeleton already solves the cube".to_string()),
+        Leftover::Unsolved => {
+            return Err("The skeleton leaves more than a 3-cycle unsolved".to_string());
+        }
+        Leftover::CornerCycle | Leftover::EdgeCycle => {}
+    }
+
+    let algorithms = cycle_algorithms();
+    let mut insertions: Vec<Insertion> = Vec::new();
+    let mut before = *cube;
+    for position in 0..=skeleton.len() {
+        if position > 0 {
+            before.apply_move(skeleton.moves[position - 1]);
+        }
+        let (prefix, suffix) = skeleton.moves.split_at(position);
+        let mut after = CubieCube::SOLVED;
+        after.apply_moves(suffix);
+        // before * needed * after is solved
+        let needed = before.inverse().multiply(&after.inverse());
+        for algorithm in algorithms.get(&needed).into_iter().flatten() {
+            let solution = MoveSequence::new(prefix.to_vec())
+                .then(algorithm)
+                .then(&MoveSequence::new(suffix.to_vec()))
+                .simplify();
+            if insertions.iter().any(|other| other.solution == solution) {
+                continue;
+            }
+            insertions.push(Insertion {
+                position,
+                algorithm: algorithm.clone(),
+                cancelled: skeleton.len() + algorithm.len() - solution.len(),
+                solution,
+            });
+        }
+    }
+    if insertions.is_empty() {
+        return Err("No 3-cycle algorithm fits this skeleton".to_string());
+    }
+    insertions.sort_by_key(|insertion| (insertion.solution.len(), insertion.position));
+    insertions.truncate(limit);
+    Ok(insertions)
+}
+
+/// Skeletons of at most `longest` face turns that leave `cube` solved or a single
+/// 3-cycle away from solved, shortest first. The search gives up once `should_stop`
+/// returns true, returning what it found so far.
+pub fn find_skeletons(
+    cube: &CubieCube,
+    longest: usize,
+    limit: usize,
+    should_stop: &dyn Fn() -> bool,
+) -> Vec<MoveSequence> {
+    let mut search = SkeletonSearch {
+        moves: Vec::new(),
+        found: Vec::new(),
+        limit,
+        should_stop,
+    };
+    let (corners, edges) = (corner_states(cube), edge_states(cube));
+    for length in 0..=longest {
+        if search.found.len() >= limit || should_stop() {
+            break;
+        }
+        search.search(&corners, &edges, length);
+    }
+    search.found
+}
+
+/// A skeleton with the insertion that completes it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finish {
+    pub skeleton: MoveSequence,
+    /// None when the skeleton solves the cube by itself
+    pub insertion: Option<Insertion>,
+}
+
+impl Finish {
+    /// The moves that solve the cube
+    pub fn solution(&self) -> &MoveSequence {
+        self.insertion
+            .as_ref()
+            .map_or(&self.skeleton, |insertion| &insertion.solution)
+    }
+}
+
+/// Skeletons searched for by [`find_finish`]
+const FINISH_SKELETONS: usize = 500;
+
+/// The shortest solution of `cube` made of a skeleton of at most `longest` face turns
+/// and at most one inserted 3-cycle
+pub fn find_finish(
+    cube: &CubieCube,
+    longest: usize,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<Finish, String> {
+    let mut best: Option<Finish> = None;
+    for skeleton in find_skeletons(cube, longest, FINISH_SKELETONS, should_stop) {
+        let mut end = *cube;
+        end.apply_moves(&skeleton.moves);
+        let insertion = if end.is_solved() {
+            None
+        } else {
+            match find_insertions(cube, &skeleton, 1) {
+                Ok(insertions) => insertions.into_iter().next(),
+                Err(_) => continue,
+            }
+        };
+        let finish = Finish {
+            skeleton,
+            insertion,
+        };
+        let is_better = best
+            .as_ref()
+            .is_none_or(|best| finish.solution().len() < best.solution().len());
+        if is_better {
+            best = Some(finish);
+        }
+    }
+    best.ok_or_else(|| {
+        format!(
+            "No skeleton of at most {} moves leaves a single 3-cycle",
+            longest
+        )
+    })
+}
+
+/// Moves every scramble starts and ends with, as in official fewest-moves scrambles,
+/// so the first and last moves of a solution cannot cancel into it
+const SCRAMBLE_PADDING: &str = "R' U' F";
+
+/// A calendar day in UTC, which picks the daily scramble
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Date::from_days_since_epoch((seconds / 86_400) as i64)
+    }
+
+    /// The day `days` after 1970-01-01
+    pub fn from_days_since_epoch(days: i64) -> Date {
+        // Civil calendar from day number, counting in 400-year eras that start in March
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        } as u32;
+        let year = (year_of_era + era * 400) as i32 + i32::from(month <= 2);
+        Date { year, month, day }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// The scramble of the day, the same on every device
+pub fn daily_scramble(date: Date) -> MoveSequence {
+    let seed = date.year as u64 * 10_000 + date.month as u64 * 100 + date.day as u64;
+    random_scramble(seed)
+}
+
+/// Scramble to a random state picked by `seed`. The state is solved with the
+/// Thistlethwaite solver and the solution inverted, between the official padding.
+pub fn random_scramble(seed: u64) -> MoveSequence {
+    let mut random = SplitMix64(seed);
+    let cube = random_cube(&mut random);
+    let padding = MoveSequence::parse(SCRAMBLE_PADDING).expect("the padding is valid notation");
+    let solution = thistlethwaite::solve(&cube)
+        .expect("a random cube is a valid cube")
+        .moves;
+    padding.then(&solution.inverse()).then(&padding).simplify()
+}
+
+/// A cube state picked uniformly among all solvable states
+fn random_cube(random: &mut SplitMix64) -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    shuffle(&mut cube.cp, random);
+    shuffle(&mut cube.ep, random);
+    if cube.corner_parity() != cube.edge_parity() {
+        cube.ep.swap(0, 1);
+    }
+    for i in 0..7 {
+        cube.co[i] = random.below(3) as u8;
+    }
+    cube.co[7] = (3 - cube.co[..7].iter().sum::<u8>() % 3) % 3;
+    for i in 0..11 {
+        cube.eo[i] = random.below(2) as u8;
+    }
+    cube.eo[11] = cube.eo[..11].iter().sum::<u8>() % 2;
+    cube
+}
+
+fn shuffle(pieces: &mut [u8], random: &mut SplitMix64) {
+    for i in (1..pieces.len()).rev() {
+        pieces.swap(i, random.below(i as u64 + 1) as usize);
+    }
+}
+
+/// Small seeded generator, so a seed gives the same scramble on every platform
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `bound`; the bias is far too small to matter for scrambles
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+/// Parse a typed solution. Comments (`//` to the end of the line, `/* */`) annotate
+/// skeletons and insertions. Slice moves are rejected, as in official fewest-moves
+/// attempts, so the HTM count of what is left is the official count.
+pub fn parse_solution(text: &str) -> Result<MoveSequence, String> {
+    let alg = Alg::parse(text).map_err(|error| error.to_string())?;
+    if let Some(span) = first_slice(&alg.nodes) {
+        return Err(format!(
+            "slice moves are not allowed in FMC: '{}' at {}..{}",
+            &text[span.clone()],
+            span.start,
+            span.end
+        ));
+    }
+    alg.expand()
+        .map(MoveSequence::new)
+        .map_err(|error| error.to_string())
+}
+
+/// Span of the first slice move, looking inside groups, commutators and conjugates
+fn first_slice(nodes: &[AlgNode]) -> Option<Span> {
+    nodes.iter().find_map(|node| match node {
+        AlgNode::Move { mv, span } => matches!(mv.kind, MoveKind::Slice(_)).then(|| span.clone()),
+        AlgNode::Group { nodes, .. } => first_slice(nodes),
+        AlgNode::Commutator { a, b, .. } | AlgNode::Conjugate { a, b, .. } => {
+            first_slice(a).or_else(|| first_slice(b))
+        }
+    })
+}
+
+/// How a solution left the scrambled cube
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verification {
+    /// Length in the half turn metric, as fewest-moves results are counted; a slice
+    /// move, which [`parse_solution`] does not accept, would count as two
+    pub move_count: usize,
+    /// Positions whose corner is missing or twisted, such as "URF"
+    pub unsolved_corners: Vec<&'static str>,
+    /// Positions whose edge is missing or flipped, such as "UF"
+    pub unsolved_edges: Vec<&'static str>,
+}
+
+impl Verification {
+    pub fn is_solved(&self) -> bool {
+        self.unsolved_corners.is_empty() && self.unsolved_edges.is_empty()
+    }
+}
+
+/// Apply `solution` to the cube scrambled with `scramble` and report what is left.
+/// Rotations are free, so the cube counts as solved however it ends up held.
+pub fn verify(scramble: &MoveSequence, solution: &MoveSequence) -> Verification {
+    let mut cube = CubieCube::SOLVED;
+    cube.apply_moves(&scramble.moves);
+    cube.apply_moves(&solution.moves);
+    Verification {
+        move_count: solution.length(Metric::Htm),
+        unsolved_corners: (0..8)
+            .filter(|&i| cube.cp[i] as usize != i || cube.co[i] != 0)
+            .map(|i| CORNER_NAMES[i])
+            .collect(),
+        unsolved_edges: (0..12)
+            .filter(|&i| cube.ep[i] as usize != i || cube.eo[i] != 0)
+            .map(|i| EDGE_NAMES[i])
+            .collect(),
+    }
+}
+
+/// Depth-first search for skeletons of an exact length
+struct SkeletonSearch<'a> {
+    moves: Vec<usize>,
+    found: Vec<MoveSequence>,
+    limit: usize,
+    should_stop: &'a dyn Fn() -> bool,
+}
+
+impl SkeletonSearch<'_> {
+    fn search(&mut self, corners: &[u8; 8], edges: &[u8; 12], remaining: usize) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+        if remaining == 0 {
+            if leftover_of_states(corners, edges) != Leftover::Unsolved {
+                let moves = self.moves.iter().map(|&m| face_turn(m)).collect();
+                self.found.push(MoveSequence::new(moves));
+            }
+            return;
+        }
+        if self.moves.len() % 4 == 3 && (self.should_stop)() {
+            return;
+        }
+        // A face turn moves four corners and four edges, and a 3-cycle leaves three
+        // pieces of one kind wrong
+        let wrong = |states: &[u8], orientations: usize| {
+            states
+                .iter()
+                .enumerate()
+                .filter(|&(piece, &state)| state as usize != piece * orientations)
+                .count()
+        };
+        let fixable = |wrong: usize, left: usize| wrong <= 4 * remaining + left;
+        let (wrong_corners, wrong_edges) = (wrong(corners, 3), wrong(edges, 2));
+        if !(fixable(wrong_corners, 3) && fixable(wrong_edges, 0)
+            || fixable(wrong_corners, 0) && fixable(wrong_edges, 3))
+        {
+            return;
+        }
+
+        let corner_transitions = corner_transitions();
+        let edge_transitions = edge_transitions();
+        for m in 0..MOVE_COUNT {
+            if let Some(&last) = self.moves.last() {
+                let (face, last_face) = (m / 3, last / 3);
+                // Same face twice, or opposite faces in both orders, repeat other paths
+                if face == last_face || (face % 3 == last_face % 3 && face < last_face) {
+                    continue;
+                }
+            }
+            let next_corners = corners.map(|state| corner_transitions[m][state as usize]);
+            let next_edges = edges.map(|state| edge_transitions[m][state as usize]);
+            self.moves.push(m);
+            self.search(&next_corners, &next_edges, remaining - 1);
+            self.moves.pop();
+        }
+    }
+}
+
+/// A turn of one face or of both faces on an axis, numbered as in `coordinates`
+type AxisTurn = Vec<usize>;
+
+/// Pure commutators and their conjugates for every corner and edge 3-cycle, shortest
+/// first, keyed by the cubie state they leave the solved cube in
+fn cycle_algorithms() -> &'static HashMap<CubieCube, Vec<MoveSequence>> {
+    static ALGORITHMS: OnceLock<HashMap<CubieCube, Vec<MoveSequence>>> = OnceLock::new();
+    ALGORITHMS.get_or_init(|| {
+        let cube_of = |turns: &[usize]| {
+            turns.iter().fold(CubieCube::SOLVED, |cube, &m| {
+                cube.multiply(&face_turn_cube(m))
+            })
+        };
+        let sequence_of =
+            |turns: &[usize]| MoveSequence::new(turns.iter().map(|&m| face_turn(m)).collect());
+        let mut algorithms: HashMap<CubieCube, Vec<MoveSequence>> = HashMap::new();
+
+        // [A, B] with B a face turn and A up to three turns of faces or axes, as in
+        // [R U R', D] or [R' L F2 R L', U2]
+        let axis_turns: Vec<AxisTurn> = (0..MOVE_COUNT)
+            .map(|m| vec![m])
+            .chain((0..MOVE_COUNT).flat_map(|m| {
+                (0..MOVE_COUNT)
+                    .filter(move |&n| m / 3 < 3 && n / 3 == m / 3 + 3)
+                    .map(move |n| vec![m, n])
+            }))
+            .collect();
+        let axis_of = |turn: &AxisTurn| turn[0] / 3 % 3;
+        let mut interchanges: Vec<Vec<usize>> = Vec::new();
+        let mut shorter: Vec<(Vec<usize>, Option<usize>)> = vec![(Vec::new(), None)];
+        for _ in 0..3 {
+            let mut longer = Vec::new();
+            for (prefix, last_axis) in &shorter {
+                for turn in axis_turns
+                    .iter()
+                    .filter(|turn| Some(axis_of(turn)) != *last_axis)
+                {
+                    longer.push(([prefix.as_slice(), turn].concat(), Some(axis_of(turn))));
+                }
+            }
+            interchanges.extend(longer.iter().map(|(turns, _)| turns.clone()));
+            shorter = longer;
+        }
+
+        let mut commutators: Vec<(CubieCube, MoveSequence)> = Vec::new();
+        for interchange in &interchanges {
+            let a = cube_of(interchange);
+            let a_inverse = a.inverse();
+            for b in 0..MOVE_COUNT {
+                let b_cube = face_turn_cube(b);
+                let effect = a
+                    .multiply(&b_cube)
+                    .multiply(&a_inverse)
+                    .multiply(&b_cube.inverse());
+                if !matches!(
+                    leftover(&effect),
+                    Leftover::CornerCycle | Leftover::EdgeCycle
+                ) {
+                    continue;
+                }
+                let a_moves = sequence_of(interchange);
+                let b_moves = sequence_of(&[b]);
+                let algorithm = a_moves
+                    .then(&b_moves)
+                    .then(&a_moves.inverse())
+                    .then(&b_moves.inverse())
+                    .simplify();
+                commutators.push((effect, algorithm));
+            }
+        }
+        // Every 3-cycle of up to six turns, such as R L' F2 R' L U2, which is no commutator
+        short_cycles(CubieCube::SOLVED, &mut Vec::new(), 6, &mut commutators);
+        commutators.sort_by_key(|(_, algorithm)| algorithm.len());
+        for (effect, algorithm) in &commutators {
+            add_algorithm(&mut algorithms, *effect, algorithm.clone());
+        }
+
+        // Setups of up to two turns reach the 3-cycles no commutator above does
+        let shortest: Vec<(CubieCube, MoveSequence)> = algorithms
+            .iter()
+            .map(|(effect, known)| (*effect, known[0].clone()))
+            .collect();
+        let setups: Vec<Vec<usize>> = (0..MOVE_COUNT)
+            .map(|m| vec![m])
+            .chain((0..MOVE_COUNT).flat_map(|m| {
+                (0..MOVE_COUNT)
+                    .filter(move |&n| n / 3 != m / 3)
+                    .map(move |n| vec![m, n])
+            }))
+            .collect();
+        let mut conjugates: Vec<(CubieCube, MoveSequence)> = Vec::new();
+        for setup in &setups {
+            let s = cube_of(setup);
+            let s_inverse = s.inverse();
+            let setup_moves = sequence_of(setup);
+            for (effect, algorithm) in &shortest {
+                let conjugate = setup_moves
+                    .then(algorithm)
+                    .then(&setup_moves.inverse())
+                    .simplify();
+                conjugates.push((s.multiply(effect).multiply(&s_inverse), conjugate));
+            }
+        }
+        conjugates.sort_by_key(|(_, algorithm)| algorithm.len());
+        for (effect, algorithm) in conjugates {
+            add_algorithm(&mut algorithms, effect, algorithm);
+        }
+        algorithms
+    })
+}
+
+/// Keep `algorithm` among the shortest known for `effect`
+fn add_algorithm(
+    algorithms: &mut HashMap<CubieCube, Vec<MoveSequence>>,
+    effect: CubieCube,
+    algorithm: MoveSequence,
+) {
+    let known = algorithms.entry(effect).or_default();
+    if known.contains(&algorithm) {
+        return;
+    }
+    let at = known.partition_point(|other| other.len() <= algorithm.len());
+    if at < ALGORITHMS_PER_CYCLE {
+        known.insert(at, algorithm);
+        known.truncate(ALGORITHMS_PER_CYCLE);
+    }
+}
+
+/// Collect the sequences of at most `remaining` more face turns after `moves` that leave
+/// a single 3-cycle
+fn short_cycles(
+    cube: CubieCube,
+    moves: &mut Vec<usize>,
+    remaining: usize,
+    out: &mut Vec<(CubieCube, MoveSequence)>,
+) {
+    if matches!(leftover(&cube), Leftover::CornerCycle | Leftover::EdgeCycle) {
+        let algorithm = moves.iter().map(|&m| face_turn(m)).collect::<Vec<_>>();
+        out.push((cube, MoveSequence::new(algorithm)));
+    }
+    if remaining == 0 {
+        return;
+    }
+    for m in 0..MOVE_COUNT {
+        if let Some(&last) = moves.last() {
+            let (face, last_face) = (m / 3, last / 3);
+            if face == last_face || (face % 3 == last_face % 3 && face < last_face) {
+                continue;
+            }
+        }
+        moves.push(m);
+        short_cycles(cube.multiply(&face_turn_cube(m)), moves, remaining - 1, out);
+        moves.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solutions_keep_their_comments_out() {
+        let solution = parse_solution("R U // EO\n(F2 D)2 /* skeleton */ Rw x").unwrap();
+        assert_eq!(solution, MoveSequence::parse("R U F2 D F2 D Rw x").unwrap());
+    }
+
+    #[test]
+    fn slice_moves_are_rejected() {
+        assert_eq!(
+            parse_solution("R U [F: M2]"),
+            Err("slice moves are not allowed in FMC: 'M2' at 8..10".to_string())
+        );
+        assert!(parse_solution("R U (").is_err());
+    }
+
+    #[test]
+    fn verification_counts_face_turns() {
+        let scramble = MoveSequence::parse("R U R' U'").unwrap();
+        let solved = verify(&scramble, &parse_solution("U R U' R' y").unwrap());
+        assert!(solved.is_solved());
+        assert_eq!(solved.move_count, 4);
+
+        let unsolved = verify(&scramble, &parse_solution("U R").unwrap());
+        assert!(!unsolved.is_solved());
+        assert!(unsolved.unsolved_corners.contains(&"URF"));
+    }
+}
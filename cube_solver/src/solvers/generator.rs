@@ -0,0 +1,422 @@
+//! Every algorithm up to a given length that solves a case, in the way Cube Explorer
+//! generates them: the case is the cube as it is, the moves come from a move set, and
+//! the results are sorted by how easy they are on the hands.
+//!
+//! Pieces are followed in fixed space, so slices and wide turns work like face turns,
+//! and an algorithm has to bring the centers back where they started. The search is
+//! iterative deepening over the set's moves, pruned with exact distance tables for
+//! groups of four pieces, so the shortest algorithms are listed first.
+
+use super::coordinates::{Piece, PieceDistances, STOP_CHECK_INTERVAL, corner_states, edge_states};
+use super::last_layer::{first_two_layers_solved, recognize_coll, recognize_oll, recognize_pll};
+use super::move_set::MoveSet;
+use crate::algorithm_library::AlgorithmSet;
+use crate::cube_model::{
+    CENTER_FACELETS, CORNER_FACELETS, CubieCube, EDGE_FACELETS, FACELET_COUNT, Face, Move,
+};
+use crate::ergonomics;
+use crate::move_sequence::MoveSequence;
+use crate::permutation;
+use std::fmt;
+
+/// Longest algorithm the generator searches for
+pub const MAX_LENGTH: usize = 14;
+/// Algorithms listed before the search gives up on finding more
+pub const MAX_RESULTS: usize = 500;
+
+/// What counts as solving the case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaseGoal {
+    /// The whole cube, as for a 3-cycle or a PLL with its AUF
+    #[default]
+    Solved,
+    /// The whole cube but for a last turn of U
+    SolvedUpToAuf,
+    /// The first two layers, and every last-layer piece showing its U sticker on U
+    Oriented,
+}
+
+impl CaseGoal {
+    pub const ALL: [CaseGoal; 3] = [
+        CaseGoal::Solved,
+        CaseGoal::SolvedUpToAuf,
+        CaseGoal::Oriented,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CaseGoal::Solved => "Solved",
+            CaseGoal::SolvedUpToAuf => "Solved up to U",
+            CaseGoal::Oriented => "Oriented (OLL)",
+        }
+    }
+}
+
+impl fmt::Display for CaseGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    pub goal: CaseGoal,
+    pub move_set: MoveSet,
+    /// Longest algorithm listed, each move of the set counting one
+    pub max_length: usize,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        Self {
+            goal: CaseGoal::Solved,
+            move_set: MoveSet::all_faces(),
+            max_length: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedAlgorithm {
+    pub moves: MoveSequence,
+    /// Ergonomics score; lower is easier
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Generated {
+    /// Easiest first, shorter first among equally easy ones
+    pub algorithms: Vec<GeneratedAlgorithm>,
+    /// Every algorithm up to the length was found, rather than the search stopping or
+    /// reaching `MAX_RESULTS`
+    pub complete: bool,
+}
+
+/// Where the pieces and centers are in fixed space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct State {
+    /// `position * 3 + twist` of every corner piece
+    corners: [u8; 8],
+    /// `position * 2 + flip` of every edge piece
+    edges: [u8; 12],
+    /// Facelets the U and F centers are on
+    centers: [u8; 2],
+}
+
+/// The U and F centers where they start
+const HOME_CENTERS: [u8; 2] = [CENTER_FACELETS[0] as u8, CENTER_FACELETS[2] as u8];
+
+/// What one move does to a single corner, a single edge and the centers
+struct MoveEffect {
+    mv: Move,
+    corners: [u8; 24],
+    edges: [u8; 24],
+    facelets: [usize; FACELET_COUNT],
+}
+
+impl MoveEffect {
+    fn new(mv: Move) -> Self {
+        let facelets = mv.facelet_permutation();
+        Self {
+            mv,
+            corners: piece_transition(&facelets, &CORNER_FACELETS),
+            edges: piece_transition(&facelets, &EDGE_FACELETS),
+            facelets,
+        }
+    }
+
+    fn apply(&self, state: &State) -> State {
+        State {
+            corners: state.corners.map(|s| self.corners[s as usize]),
+            edges: state.edges.map(|s| self.edges[s as usize]),
+            centers: state.centers.map(|c| self.facelets[c as usize] as u8),
+        }
+    }
+}
+
+/// Where a piece in state `position * N + orientation` goes, following the sticker that
+/// defines its orientation: the U/D sticker of a corner, the first sticker of an edge
+fn piece_transition<const N: usize>(
+    permutation: &[usize; FACELET_COUNT],
+    positions: &[[usize; N]],
+) -> [u8; 24] {
+    std::array::from_fn(|state| {
+        let facelet = permutation[positions[state / N][state % N]];
+        positions
+            .iter()
+            .enumerate()
+            .find_map(|(position, facelets)| {
+                let orientation = facelets.iter().position(|&f| f == facelet)?;
+                Some((position * N + orientation) as u8)
+            })
+            .expect("a move takes a piece's sticker to another piece position")
+    })
+}
+
+/// States each piece may end up in: corners first, then edges. With `auf` the pieces
+/// must moreover all be home after one and the same turn of U.
+struct Goal {
+    allowed: [[bool; 24]; 20],
+    auf: Option<MoveEffect>,
+}
+
+impl Goal {
+    fn new(goal: CaseGoal) -> Self {
+        let home = |piece: usize| {
+            let mut allowed = [false; 24];
+            allowed[if piece < 8 {
+                piece * 3
+            } else {
+                (piece - 8) * 2
+            }] = true;
+            allowed
+        };
+        let turn = MoveEffect::new(Move::face(Face::U, 1));
+        let allowed = match goal {
+            CaseGoal::Solved => std::array::from_fn(home),
+            CaseGoal::SolvedUpToAuf => std::array::from_fn(|piece| {
+                // Home after some turn of U
+                let transitions = if piece < 8 {
+                    &turn.corners
+                } else {
+                    &turn.edges
+                };
+                let mut allowed = home(piece);
+                for _ in 0..3 {
+                    allowed = std::array::from_fn(|state| {
+                        allowed[state] || allowed[transitions[state] as usize]
+                    });
+                }
+                allowed
+            }),
+            CaseGoal::Oriented => std::array::from_fn(|piece| match piece {
+                // Oriented anywhere in the U layer
+                0..4 => std::array::from_fn(|state| state < 12 && state % 3 == 0),
+                8..12 => std::array::from_fn(|state| state < 8 && state % 2 == 0),
+                _ => home(piece),
+            }),
+        };
+        Self {
+            allowed,
+            auf: (goal == CaseGoal::SolvedUpToAuf).then_some(turn),
+        }
+    }
+
+    fn allows(&self, piece: Piece, state: usize) -> bool {
+        match piece {
+            Piece::Corner(corner) => self.allowed[corner][state],
+            Piece::Edge(edge) => self.allowed[8 + edge][state],
+        }
+    }
+
+    fn reached(&self, state: &State) -> bool {
+        if state.centers != HOME_CENTERS {
+            return false;
+        }
+        let Some(turn) = &self.auf else {
+            return state
+                .corners
+                .iter()
+                .chain(&state.edges)
+                .enumerate()
+                .all(|(piece, &s)| self.allowed[piece][s as usize]);
+        };
+        let solved = State {
+            corners: std::array::from_fn(|piece| piece as u8 * 3),
+            edges: std::array::from_fn(|piece| piece as u8 * 2),
+            centers: HOME_CENTERS,
+        };
+        let mut turned = *state;
+        for _ in 0..4 {
+            if turned == solved {
+                return true;
+            }
+            turned = turn.apply(&turned);
+        }
+        false
+    }
+}
+
+/// List every algorithm of up to `options.max_length` moves of `options.move_set` that
+/// solves `case` to `options.goal`. The search gives up once `should_stop` returns
+/// true, keeping what it has found.
+pub fn generate(
+    case: &CubieCube,
+    options: &GeneratorOptions,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<Generated, String> {
+    if options.max_length > MAX_LENGTH {
+        return Err(format!(
+            "Algorithms are searched up to {} moves",
+            MAX_LENGTH
+        ));
+    }
+    let goal = Goal::new(options.goal);
+    let start = State {
+        corners: corner_states(case),
+        edges: edge_states(case),
+        centers: HOME_CENTERS,
+    };
+    if goal.reached(&start) {
+        return Err(match options.goal {
+            CaseGoal::Oriented => "The last layer is already oriented".to_string(),
+            _ => "The case is already solved".to_string(),
+        });
+    }
+
+    let effects: Vec<MoveEffect> = options
+        .move_set
+        .moves()
+        .into_iter()
+        .map(MoveEffect::new)
+        .collect();
+    // The U and D layer corners, and the U layer, D layer and E slice edges. Each
+    // table is a breadth-first search over the set's moves from every state the goal
+    // allows.
+    let corners = [0..4, 4..8].map(|pieces| pieces.map(Piece::Corner).collect());
+    let edges = [0..4, 4..8, 8..12].map(|pieces| pieces.map(Piece::Edge).collect());
+    let groups: Vec<PieceDistances> = corners
+        .into_iter()
+        .chain(edges)
+        .map(|pieces: Vec<Piece>| {
+            PieceDistances::new(
+                pieces,
+                effects.len(),
+                |piece, m| match piece {
+                    Piece::Corner(_) => &effects[m].corners,
+                    Piece::Edge(_) => &effects[m].edges,
+                },
+                |piece, state| goal.allows(piece, state),
+            )
+        })
+        .collect();
+    let mut search = Search {
+        goal: &goal,
+        groups: &groups,
+        effects: &effects,
+        layers: effects
+            .iter()
+            .map(|effect| {
+                effects
+                    .iter()
+                    .position(|other| other.mv.kind == effect.mv.kind)
+                    .unwrap_or(0)
+            })
+            .collect(),
+        moves: Vec::new(),
+        found: Vec::new(),
+        nodes: 0,
+        should_stop,
+    };
+
+    let mut complete = true;
+    for length in search.lower_bound(&start)..=options.max_length {
+        if !search.search(&start, length) {
+            complete = false;
+            break;
+        }
+    }
+    let mut algorithms: Vec<GeneratedAlgorithm> = search
+        .found
+        .into_iter()
+        .map(|moves| {
+            let score = ergonomics::evaluate(&moves).score;
+            GeneratedAlgorithm { moves, score }
+        })
+        .collect();
+    algorithms.sort_by(|a, b| {
+        a.score
+            .total_cmp(&b.score)
+            .then(a.moves.len().cmp(&b.moves.len()))
+    });
+    Ok(Generated {
+        algorithms,
+        complete,
+    })
+}
+
+struct Search<'a> {
+    goal: &'a Goal,
+    groups: &'a [PieceDistances],
+    effects: &'a [MoveEffect],
+    /// Position in the set of the layer each move turns
+    layers: Vec<usize>,
+    moves: Vec<usize>,
+    found: Vec<MoveSequence>,
+    nodes: usize,
+    should_stop: &'a dyn Fn() -> bool,
+}
+
+impl Search<'_> {
+    fn lower_bound(&self, state: &State) -> usize {
+        self.groups
+            .iter()
+            .map(|group| group.distance(&state.corners, &state.edges) as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Whether `next` may follow the last move: turns of one axis are done in the
+    /// order of the set, each layer once, so no algorithm is listed twice
+    fn may_follow(&self, next: usize) -> bool {
+        let Some(&last) = self.moves.last() else {
+            return true;
+        };
+        self.effects[last].mv.axis() != self.effects[next].mv.axis()
+            || self.layers[last] < self.layers[next]
+    }
+
+    /// Record the algorithms of exactly `length` moves that reach the goal from
+    /// `state` after the moves so far. False when stopped, or when enough are found.
+    fn search(&mut self, state: &State, length: usize) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) && (self.should_stop)() {
+            return false;
+        }
+        let bound = self.lower_bound(state);
+        if bound == 0 && self.goal.reached(state) {
+            // Only whole algorithms: a shorter one was listed before
+            if self.moves.len() == length {
+                self.found.push(MoveSequence::new(
+                    self.moves.iter().map(|&m| self.effects[m].mv).collect(),
+                ));
+            }
+            return self.found.len() < MAX_RESULTS;
+        }
+        if self.moves.len() + bound > length {
+            return true;
+        }
+        for m in 0..self.effects.len() {
+            if !self.may_follow(m) {
+                continue;
+            }
+            let next = self.effects[m].apply(state);
+            self.moves.push(m);
+            let going_on = self.search(&next, length);
+            self.moves.pop();
+            if !going_on {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The library set and name an algorithm for `case` is saved under: the last-layer
+/// case it is, or else the cycles of its pieces
+pub fn case_name(case: &CubieCube, goal: CaseGoal) -> (AlgorithmSet, String) {
+    if goal == CaseGoal::Oriented
+        && let Some((_, oll)) = recognize_oll(case)
+    {
+        return (AlgorithmSet::Oll, oll.label());
+    }
+    if first_two_layers_solved(case) {
+        if let Some((_, pll, _)) = recognize_pll(case) {
+            return (AlgorithmSet::Pll, pll.label());
+        }
+        if let Some((_, coll, _)) = recognize_coll(case) {
+            return (AlgorithmSet::Coll, coll.label());
+        }
+    }
+    (AlgorithmSet::Other, permutation::of_cube(case).cycles())
+}
@@ -0,0 +1,168 @@
+//! Hints that reveal a solution one stage at a time: first the goal of the next stage
+//! and the pieces it works on, then its moves.
+
+use super::Solution;
+use crate::cube_model::{CORNER_FACELETS, CubeModel, CubieCube, EDGE_FACELETS, Face};
+use crate::move_sequence::MoveSequence;
+
+/// The next stage of a solution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub label: String,
+    /// One line on what the stage achieves
+    pub explanation: String,
+    pub moves: MoveSequence,
+    /// Corners the stage works on, indexed by piece
+    pub corners: [bool; 8],
+    /// Edges the stage works on, indexed by piece
+    pub edges: [bool; 12],
+}
+
+impl Hint {
+    /// The stage on its own, as a one-stage solution
+    pub fn solution(&self) -> Solution {
+        let mut solution = Solution::default();
+        solution.push_stage(&self.label, &self.explanation, &self.moves);
+        solution
+    }
+}
+
+/// The first stage of `solution` for `cube`; a solution without stages is a single
+/// stage. None when there is nothing left to do.
+pub fn next_stage(cube: &CubieCube, solution: &Solution) -> Option<Hint> {
+    let (label, explanation, moves) = match solution.stages.first() {
+        Some(stage) => (
+            stage.label.clone(),
+            stage.explanation.clone(),
+            MoveSequence::new(solution.moves.moves[..stage.move_count].to_vec()),
+        ),
+        None => (
+            "Solve".to_string(),
+            "Solve the rest of the cube".to_string(),
+            solution.moves.clone(),
+        ),
+    };
+    if moves.is_empty() {
+        return None;
+    }
+    let (corners, edges) = stage_pieces(cube, &moves);
+    Some(Hint {
+        label,
+        explanation,
+        moves,
+        corners,
+        edges,
+    })
+}
+
+/// Pieces that `moves` work on, indexed by piece: the ones they solve. Stages that
+/// solve no piece, such as orienting the last layer, work on the pieces that gain
+/// stickers matching their centers, or failing that on every piece they displace.
+pub fn stage_pieces(cube: &CubieCube, moves: &MoveSequence) -> ([bool; 8], [bool; 12]) {
+    let mut after = *cube;
+    after.apply_moves(&moves.moves);
+
+    let (corners_before, edges_before) = matching_stickers(cube);
+    let (corners_after, edges_after) = matching_stickers(&after);
+    for gain in [
+        |before: usize, after: usize, stickers: usize| before < stickers && after == stickers,
+        |before: usize, after: usize, _| after > before,
+    ] {
+        let corners: [bool; 8] =
+            std::array::from_fn(|piece| gain(corners_before[piece], corners_after[piece], 3));
+        let edges: [bool; 12] =
+            std::array::from_fn(|piece| gain(edges_before[piece], edges_after[piece], 2));
+        if corners.contains(&true) || edges.contains(&true) {
+            return (corners, edges);
+        }
+    }
+
+    // Pieces are displaced when their position or orientation changes
+    let mut corners = [false; 8];
+    let mut edges = [false; 12];
+    for position in 0..8 {
+        let piece = cube.cp[position] as usize;
+        let moved = after.cp.iter().position(|&p| p as usize == piece) != Some(position)
+            || after.co[position] != cube.co[position];
+        corners[piece] = moved;
+    }
+    for position in 0..12 {
+        let piece = cube.ep[position] as usize;
+        let moved = after.ep.iter().position(|&p| p as usize == piece) != Some(position)
+            || after.eo[position] != cube.eo[position];
+        edges[piece] = moved;
+    }
+    (corners, edges)
+}
+
+/// Number of stickers of each piece that match the center of the face they are on
+fn matching_stickers(cube: &CubieCube) -> ([usize; 8], [usize; 12]) {
+    let model = CubeModel::from_cubie(cube);
+    let matching = |facelet: usize| model.facelet(facelet) == Face::from_index(facelet / 9);
+
+    let mut corners = [0; 8];
+    for (position, facelets) in CORNER_FACELETS.iter().enumerate() {
+        corners[cube.cp[position] as usize] += facelets.iter().filter(|&&f| matching(f)).count();
+    }
+    let mut edges = [0; 12];
+    for (position, facelets) in EDGE_FACELETS.iter().enumerate() {
+        edges[cube.ep[position] as usize] += facelets.iter().filter(|&&f| matching(f)).count();
+    }
+    (corners, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers::beginner;
+
+    fn cube_after(algorithm: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(algorithm).unwrap().moves);
+        cube
+    }
+
+    #[test]
+    fn hints_walk_through_every_stage() {
+        let mut cube = cube_after("D2 R' F U2 L B' D R2 U' F2 L' B2");
+        let mut remaining = beginner::solve(&cube).unwrap();
+        while let Some(hint) = next_stage(&cube, &remaining) {
+            let stage = remaining.stages.remove(0);
+            assert_eq!(hint.label, stage.label);
+            assert_eq!(hint.moves.len(), stage.move_count);
+            assert!(hint.corners.contains(&true) || hint.edges.contains(&true));
+            cube.apply_moves(&hint.moves.moves);
+            remaining.moves.moves.drain(..stage.move_count);
+        }
+        assert!(remaining.stages.is_empty());
+        assert!(cube.is_solved());
+    }
+
+    #[test]
+    fn solution_without_stages_is_one_hint() {
+        let cube = cube_after("R U");
+        let solution = Solution {
+            moves: MoveSequence::parse("U' R'").unwrap(),
+            stages: Vec::new(),
+        };
+        let hint = next_stage(&cube, &solution).unwrap();
+        assert_eq!(hint.label, "Solve");
+        assert_eq!(hint.moves, solution.moves);
+        assert_eq!(next_stage(&CubieCube::SOLVED, &Solution::default()), None);
+    }
+
+    #[test]
+    fn stage_pieces_are_the_ones_solved_or_else_displaced() {
+        let r_corners = [true, false, false, true, true, false, false, true];
+        let r_edges = [
+            true, false, false, false, true, false, false, false, true, false, false, true,
+        ];
+        let undo = MoveSequence::parse("R'").unwrap();
+        assert_eq!(stage_pieces(&cube_after("R"), &undo), (r_corners, r_edges));
+        let turn = MoveSequence::parse("R").unwrap();
+        assert_eq!(
+            stage_pieces(&CubieCube::SOLVED, &turn),
+            (r_corners, r_edges)
+        );
+    }
+}
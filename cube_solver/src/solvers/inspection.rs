@@ -0,0 +1,290 @@
+//! Cross, X-cross and XX-cross analysis for inspection practice.
+//!
+//! Each color is analysed on the cube turned so that color's center is on D. The
+//! searches are IDA* over face turns, bounded by the cross table and the F2L pair
+//! tables of the CFOP solver, so every reported length is optimal.
+
+use super::cfop::{SLOT_FACES, pair_distance_of_states};
+use super::coordinates::{
+    MOVE_COUNT, corner_states, corner_transitions, edge_states, edge_transitions, face_turn,
+};
+use super::cross::cross_distance_of_edges;
+use crate::cube_model::{Axis, CubeModel, Face, Move, MoveKind};
+use crate::move_sequence::MoveSequence;
+
+/// Longest X-cross and XX-cross worth reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InspectionBudget {
+    pub x_cross: usize,
+    pub xx_cross: usize,
+}
+
+impl Default for InspectionBudget {
+    fn default() -> Self {
+        Self {
+            x_cross: 9,
+            xx_cross: 11,
+        }
+    }
+}
+
+/// A cross with F2L pairs solved along with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedCross {
+    /// Names of the solved slots as the cube is held, such as "FR"
+    pub slots: Vec<String>,
+    pub moves: MoveSequence,
+}
+
+/// The shortest cross, X-cross and XX-cross on one face
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossAnalysis {
+    /// Face whose center the cross is built around
+    pub face: Face,
+    pub cross: MoveSequence,
+    /// The shortest X-cross, if one fits the budget
+    pub x_cross: Option<ExtendedCross>,
+    /// The shortest XX-cross, if one fits the budget
+    pub xx_cross: Option<ExtendedCross>,
+}
+
+/// Analyse the crosses on all six faces of the cube in `facelets`. Searches give up
+/// once `should_stop` returns true, leaving the extended crosses they were after out.
+pub fn analyze(
+    facelets: &str,
+    budget: InspectionBudget,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<Vec<CrossAnalysis>, String> {
+    let model = CubeModel::from_facelets(facelets).map_err(|error| error.to_string())?;
+    model.to_cubie().map_err(|error| error.to_string())?;
+    Face::ALL
+        .iter()
+        .map(|&face| analyze_face(&model, face, budget, should_stop))
+        .collect()
+}
+
+fn analyze_face(
+    model: &CubeModel,
+    face: Face,
+    budget: InspectionBudget,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<CrossAnalysis, String> {
+    let rotation = rotation_to_bottom(face);
+    let mut turned = model.clone();
+    turned.apply_move(rotation);
+    let cube = turned.to_cubie().map_err(|error| error.to_string())?;
+    let corners = corner_states(&cube);
+    let edges = edge_states(&cube);
+
+    let back = rotation.inverse();
+    let extended = |pairs: usize, longest: usize, should_stop: &dyn Fn() -> bool| {
+        let mut search = CrossSearch {
+            pairs,
+            moves: Vec::new(),
+            should_stop,
+        };
+        search
+            .solve(&corners, &edges, longest)
+            .map(|(slots, moves)| {
+                let slots = slots.iter().map(|&slot| slot_name(slot, back)).collect();
+                ExtendedCross {
+                    slots,
+                    moves: moves.rotate(back),
+                }
+            })
+    };
+
+    // The cross table makes the plain cross instant, so it is never stopped
+    let cross = extended(0, usize::MAX, &|| false)
+        .expect("every cross can be solved")
+        .moves;
+    Ok(CrossAnalysis {
+        face,
+        cross,
+        x_cross: extended(1, budget.x_cross, should_stop),
+        xx_cross: extended(2, budget.xx_cross, should_stop),
+    })
+}
+
+/// Whole-cube turn that brings `face` to D
+fn rotation_to_bottom(face: Face) -> Move {
+    [
+        (Axis::X, 0),
+        (Axis::X, 1),
+        (Axis::X, 2),
+        (Axis::X, 3),
+        (Axis::Z, 1),
+        (Axis::Z, 3),
+    ]
+    .into_iter()
+    .map(|(axis, turns)| Move::new(MoveKind::Rotation(axis), turns))
+    .find(|rotation| face.rotated(rotation.axis(), rotation.axis_quarter_turns()) == Face::D)
+    .expect("one of the rotations brings every face to D")
+}
+
+/// Name of a slot of the turned cube as the cube is held, such as "FR"
+fn slot_name(slot: usize, back: Move) -> String {
+    let (front_or_back, side) = SLOT_FACES[slot];
+    [front_or_back, side]
+        .map(|face| {
+            face.rotated(back.axis(), back.axis_quarter_turns())
+                .to_char()
+        })
+        .iter()
+        .collect()
+}
+
+/// Search for the D cross with `pairs` F2L pairs solved along with it
+struct CrossSearch<'a> {
+    pairs: usize,
+    moves: Vec<usize>,
+    should_stop: &'a dyn Fn() -> bool,
+}
+
+impl CrossSearch<'_> {
+    /// Lower bound on the turns to the goal, which is zero exactly at the goal: the
+    /// cross and any `pairs` of the slots solved
+    fn lower_bound(&self, corners: &[u8; 8], edges: &[u8; 12]) -> usize {
+        let cross = cross_distance_of_edges(edges);
+        if self.pairs == 0 {
+            return cross;
+        }
+        let mut slots: [usize; 4] =
+            std::array::from_fn(|slot| pair_distance_of_states(corners, edges, slot));
+        slots.sort_unstable();
+        cross.max(slots[self.pairs - 1])
+    }
+
+    /// The shortest solution of at most `longest` turns, with the slots it solves
+    fn solve(
+        &mut self,
+        corners: &[u8; 8],
+        edges: &[u8; 12],
+        longest: usize,
+    ) -> Option<(Vec<usize>, MoveSequence)> {
+        let mut budget = self.lower_bound(corners, edges);
+        while budget <= longest {
+            if self.search(corners, edges, budget) {
+                let mut cube = (*corners, *edges);
+                let transitions = (corner_transitions(), edge_transitions());
+                for &m in &self.moves {
+                    cube.0 = cube.0.map(|state| transitions.0[m][state as usize]);
+                    cube.1 = cube.1.map(|state| transitions.1[m][state as usize]);
+                }
+                let slots = (0..4)
+                    .filter(|&slot| pair_distance_of_states(&cube.0, &cube.1, slot) == 0)
+                    .collect();
+                let moves = self.moves.iter().map(|&m| face_turn(m)).collect();
+                return Some((slots, MoveSequence::new(moves)));
+            }
+            if (self.should_stop)() {
+                return None;
+            }
+            budget += 1;
+        }
+        None
+    }
+
+    /// Depth-first search within `budget` turns
+    fn search(&mut self, corners: &[u8; 8], edges: &[u8; 12], budget: usize) -> bool {
+        let bound = self.lower_bound(corners, edges);
+        if bound == 0 {
+            return true;
+        }
+        if bound > budget || (self.moves.len() % 4 == 3 && (self.should_stop)()) {
+            return false;
+        }
+        let corner_transitions = corner_transitions();
+        let edge_transitions = edge_transitions();
+        for m in 0..MOVE_COUNT {
+            if let Some(&last) = self.moves.last() {
+                let (face, last_face) = (m / 3, last / 3);
+                // Same face twice, or opposite faces in both orders, repeat other paths
+                if face == last_face || (face % 3 == last_face % 3 && face < last_face) {
+                    continue;
+                }
+            }
+            let next_corners = corners.map(|state| corner_transitions[m][state as usize]);
+            let next_edges = edges.map(|state| edge_transitions[m][state as usize]);
+            self.moves.push(m);
+            if self.search(&next_corners, &next_edges, budget - 1) {
+                return true;
+            }
+            self.moves.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube_model::CubieCube;
+    use crate::solvers::cross::cross_distance;
+
+    fn scrambled(scramble: &str) -> CubeModel {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(scramble).unwrap().moves);
+        CubeModel::from_cubie(&cube)
+    }
+
+    /// `model` after `moves`, held with `face` on D
+    fn held(model: &CubeModel, moves: &MoveSequence, face: Face) -> CubieCube {
+        let mut model = model.clone();
+        model.apply_moves(&moves.moves);
+        model.apply_move(rotation_to_bottom(face));
+        model.to_cubie().unwrap()
+    }
+
+    fn solved_pairs(cube: &CubieCube) -> usize {
+        let (corners, edges) = (corner_states(cube), edge_states(cube));
+        (0..SLOT_FACES.len())
+            .filter(|&slot| pair_distance_of_states(&corners, &edges, slot) == 0)
+            .count()
+    }
+
+    #[test]
+    fn one_turn_breaks_the_crosses_it_touches() {
+        let model = scrambled("F");
+        let analyses = analyze(
+            &model.to_facelet_string(),
+            InspectionBudget::default(),
+            &|| false,
+        )
+        .unwrap();
+        let lengths: Vec<usize> = analyses
+            .iter()
+            .map(|analysis| analysis.cross.len())
+            .collect();
+        // U, R, F, D, L, B: only the B cross is left alone
+        assert_eq!(lengths, [1, 1, 1, 1, 1, 0]);
+    }
+
+    #[test]
+    fn crosses_solve_on_every_face() {
+        let model = scrambled("R U2 F' L D B2 R' U F2 D' L2 B");
+        let analyses = analyze(
+            &model.to_facelet_string(),
+            InspectionBudget::default(),
+            &|| false,
+        )
+        .unwrap();
+        for analysis in analyses {
+            let face = analysis.face;
+            assert_eq!(cross_distance(&held(&model, &analysis.cross, face)), 0);
+            for (extended, pairs) in [(&analysis.x_cross, 1), (&analysis.xx_cross, 2)] {
+                let Some(extended) = extended else { continue };
+                let cube = held(&model, &extended.moves, face);
+                assert_eq!(cross_distance(&cube), 0, "{:?}", face);
+                assert!(
+                    solved_pairs(&cube) >= pairs,
+                    "{:?}: {}",
+                    face,
+                    extended.moves
+                );
+                assert_eq!(extended.slots.len(), pairs);
+                assert!(extended.moves.len() >= analysis.cross.len());
+            }
+        }
+    }
+}
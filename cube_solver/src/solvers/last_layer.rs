@@ -0,0 +1,815 @@
+//! Last-layer case tables: the 57 OLL and 21 PLL cases with standard algorithms and
+//! the 42 COLL cases with the shortest algorithms in `<R,U,F,L>`.
+//!
+//! Cases are recognised on a cube with the first two layers solved and the last
+//! layer on U. Each case is stored as its algorithm; the case itself is the state
+//! the inverse algorithm makes from the solved cube. The 472 ZBLL cases are
+//! numbered as they are enumerated and solved with their COLL algorithm followed by
+//! the edge permutation. `recognize` finds the last layer in any orientation.
+
+use crate::cube_model::{Axis, CubeModel, CubieCube, Face, Move, MoveKind};
+use crate::move_sequence::MoveSequence;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// A case of orienting the last layer
+#[derive(Debug)]
+pub struct OllCase {
+    pub number: u8,
+    pub name: &'static str,
+    pub algorithm: &'static str,
+}
+
+impl OllCase {
+    /// Stage label such as "OLL 27 (Sune)"
+    pub fn label(&self) -> String {
+        format!("OLL {} ({})", self.number, self.name)
+    }
+}
+
+/// A case of permuting the oriented last layer
+#[derive(Debug)]
+pub struct PllCase {
+    pub name: &'static str,
+    pub algorithm: &'static str,
+}
+
+impl PllCase {
+    /// Stage label such as "PLL T-perm"
+    pub fn label(&self) -> String {
+        format!("PLL {}-perm", self.name)
+    }
+}
+
+/// A case of solving the last-layer corners while keeping the edges oriented
+#[derive(Debug)]
+pub struct CollCase {
+    /// Shape of the corner twists as in OCLL: H, Pi, U, T, L, AS (Anti-Sune) or
+    /// S (Sune), and O for corners that are only permuted
+    pub shape: &'static str,
+    /// Number of the case within its shape
+    pub number: u8,
+    /// Algorithms for the case, all from the same angle and leaving the corners the
+    /// same way; the case is recognised by the first
+    pub algorithms: &'static [&'static str],
+}
+
+impl CollCase {
+    /// Label such as "COLL H-3"
+    pub fn label(&self) -> String {
+        format!("COLL {}-{}", self.shape, self.number)
+    }
+}
+
+/// A case of solving the whole last layer with its edges oriented
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZbllCase {
+    /// Shape of the corner twists, as for COLL
+    pub shape: &'static str,
+    /// Number of the case within its shape
+    pub number: u16,
+}
+
+impl ZbllCase {
+    /// Label such as "ZBLL T 12"
+    pub fn label(&self) -> String {
+        format!("ZBLL {} {}", self.shape, self.number)
+    }
+}
+
+macro_rules! oll {
+    ($number:literal, $name:literal, $algorithm:literal) => {
+        OllCase {
+            number: $number,
+            name: $name,
+            algorithm: $algorithm,
+        }
+    };
+}
+
+pub const OLL_CASES: [OllCase; 57] = [
+    oll!(1, "Dot", "R U2 R2 F R F' U2 R' F R F'"),
+    oll!(2, "Dot", "F R U R' U' F' f R U R' U' f'"),
+    oll!(3, "Dot", "f R U R' U' f' U' F R U R' U' F'"),
+    oll!(4, "Dot", "f R U R' U' f' U F R U R' U' F'"),
+    oll!(5, "Square", "r' U2 R U R' U r"),
+    oll!(6, "Square", "r U2 R' U' R U' r'"),
+    oll!(7, "Lightning", "r U R' U R U2 r'"),
+    oll!(8, "Lightning", "l' U' L U' L' U2 l"),
+    oll!(9, "Fish", "R U R' U' R' F R2 U R' U' F'"),
+    oll!(10, "Fish", "R U R' U R' F R F' R U2 R'"),
+    oll!(11, "Lightning", "r U R' U R' F R F' R U2 r'"),
+    oll!(12, "Lightning", "M' R' U' R U' R' U2 R U' M"),
+    oll!(13, "Knight move", "F U R U' R2 F' R U R U' R'"),
+    oll!(14, "Knight move", "R' F R U R' F' R F U' F'"),
+    oll!(15, "Knight move", "r' U' r R' U' R U r' U r"),
+    oll!(16, "Knight move", "r U r' R U R' U' r U' r'"),
+    oll!(17, "Dot", "R U R' U R' F R F' U2 R' F R F'"),
+    oll!(18, "Dot", "r U R' U R U2 r2 U' R U' R' U2 r"),
+    oll!(19, "Dot", "r' R U R U R' U' M' R' F R F'"),
+    oll!(20, "Dot", "r U R' U' M2 U R U' R' U' M'"),
+    oll!(21, "H", "R U2 R' U' R U R' U' R U' R'"),
+    oll!(22, "Pi", "R U2 R2 U' R2 U' R2 U2 R"),
+    oll!(23, "Headlights", "R2 D' R U2 R' D R U2 R"),
+    oll!(24, "Chameleon", "r U R' U' r' F R F'"),
+    oll!(25, "Bowtie", "F' r U R' U' r' F R"),
+    oll!(26, "Anti-Sune", "R U2 R' U' R U' R'"),
+    oll!(27, "Sune", "R U R' U R U2 R'"),
+    oll!(28, "Corners oriented", "r U R' U' r' R U R U' R'"),
+    oll!(29, "Awkward", "R U R' U' R U' R' F' U' F R U R'"),
+    oll!(30, "Awkward", "F R' F R2 U' R' U' R U R' F2"),
+    oll!(31, "P shape", "R' U' F U R U' R' F' R"),
+    oll!(32, "P shape", "L U F' U' L' U L F L'"),
+    oll!(33, "T shape", "R U R' U' R' F R F'"),
+    oll!(34, "C shape", "R U R2 U' R' F R U R U' F'"),
+    oll!(35, "Fish", "R U2 R2 F R F' R U2 R'"),
+    oll!(36, "W shape", "L' U' L U' L' U L U L F' L' F"),
+    oll!(37, "Fish", "F R' F' R U R U' R'"),
+    oll!(38, "W shape", "R U R' U R U' R' U' R' F R F'"),
+    oll!(39, "Big lightning", "L F' L' U' L U F U' L'"),
+    oll!(40, "Big lightning", "R' F R U R' U' F' U R"),
+    oll!(41, "Awkward", "R U R' U R U2 R' F R U R' U' F'"),
+    oll!(42, "Awkward", "R' U' R U' R' U2 R F R U R' U' F'"),
+    oll!(43, "P shape", "f' L' U' L U f"),
+    oll!(44, "P shape", "f R U R' U' f'"),
+    oll!(45, "T shape", "F R U R' U' F'"),
+    oll!(46, "C shape", "R' U' R' F R F' U R"),
+    oll!(47, "L shape", "R' U' R' F R F' R' F R F' U R"),
+    oll!(48, "L shape", "F R U R' U' R U R' U' F'"),
+    oll!(49, "L shape", "r U' r2 U r2 U r2 U' r"),
+    oll!(50, "L shape", "r' U r2 U' r2 U' r2 U r'"),
+    oll!(51, "Line", "f R U R' U' R U R' U' f'"),
+    oll!(52, "Line", "R U R' U R U' B U' B' R'"),
+    oll!(53, "L shape", "l' U2 L U L' U' L U L' U l"),
+    oll!(54, "L shape", "r U2 R' U' R U R' U' R U' r'"),
+    oll!(55, "Line", "R' F R U R U' R2 F' R2 U' R' U R U R'"),
+    oll!(56, "Line", "r' U' r U' R' U R U' R' U R r' U r"),
+    oll!(57, "Corners oriented", "R U R' U' M' U R U' r'"),
+];
+
+macro_rules! pll {
+    ($name:literal, $algorithm:literal) => {
+        PllCase {
+            name: $name,
+            algorithm: $algorithm,
+        }
+    };
+}
+
+pub const PLL_CASES: [PllCase; 21] = [
+    pll!("Aa", "x R' U R' D2 R U' R' D2 R2 x'"),
+    pll!("Ab", "x R2 D2 R U R' D2 R U' R x'"),
+    pll!("E", "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    pll!("F", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    pll!("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    pll!("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    pll!("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    pll!("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    pll!("H", "M2 U M2 U2 M2 U M2"),
+    pll!("Ja", "R' U L' U2 R U' R' U2 R L"),
+    pll!("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    pll!(
+        "Na",
+        "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'"
+    ),
+    pll!("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    pll!("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    pll!("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    pll!("T", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    pll!("Ua", "M2 U M U2 M' U M2"),
+    pll!("Ub", "M2 U' M U2 M' U' M2"),
+    pll!("V", "R' U R' U' y R' F' R2 U' R' U R' F R F"),
+    pll!("Y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    pll!("Z", "M' U M2 U M2 U M' U2 M2"),
+];
+
+macro_rules! coll {
+    ($shape:literal, $number:literal, [$($algorithm:literal),+]) => {
+        CollCase {
+            shape: $shape,
+            number: $number,
+            algorithms: &[$($algorithm),+],
+        }
+    };
+}
+
+pub const COLL_CASES: [CollCase; 42] = [
+    coll!(
+        "H",
+        1,
+        ["R U R' U R U' R' U R U2 R'", "R' U' R U' R' U R U' R' U2 R"]
+    ),
+    coll!(
+        "H",
+        2,
+        [
+            "R U2 R' L U' F2 U' F2 U F2 L'",
+            "U2 L' U2 R' L U F2 U F2 U' F2 R U2"
+        ]
+    ),
+    coll!(
+        "H",
+        3,
+        ["R U R' U R U L' U R' U' L", "R' U' R U' R' U' L U' R U L'"]
+    ),
+    coll!(
+        "H",
+        4,
+        [
+            "F R2 F2 U2 F R2 F' U2 F2 R2 F'",
+            "F' L2 F2 U2 F' L2 F U2 F2 L2 F"
+        ]
+    ),
+    coll!(
+        "Pi",
+        1,
+        ["R U2 R2 U' R2 U' R2 U2 R", "R' U2 R2 U R2 U R2 U2 R'"]
+    ),
+    coll!(
+        "Pi",
+        2,
+        [
+            "R' F2 U F2 U' F2 U' L' R U2 L",
+            "U2 L F2 U' F2 U F2 U L' R U2 R' U2"
+        ]
+    ),
+    coll!(
+        "Pi",
+        3,
+        [
+            "R' F2 R U2 R U2 R' F2 U' R U' R'",
+            "L' U2 L U2 L F2 L' F2 U' R U' R'"
+        ]
+    ),
+    coll!(
+        "Pi",
+        4,
+        [
+            "R U2 R' U2 R' F2 R F2 U L' U L",
+            "R U2 R' F R' F L F L' F2 R F'"
+        ]
+    ),
+    coll!(
+        "Pi",
+        5,
+        ["L U' R' U L' U R U R' U R", "L' U R U' L U' R' U' R U' R'"]
+    ),
+    coll!(
+        "Pi",
+        6,
+        [
+            "F' R U F2 U R' U' R F2 U' R' F",
+            "F L' U' F2 U' L U L' F2 U L F'"
+        ]
+    ),
+    coll!(
+        "U",
+        1,
+        [
+            "R U2 R' F U2 L' U2 L F2 R' F R",
+            "R' U2 L U' F2 L F2 L' U2 R U' L'"
+        ]
+    ),
+    coll!(
+        "U",
+        2,
+        [
+            "F U2 L' U2 L F2 U2 R U2 R' F",
+            "F U2 R' F2 R F2 U2 L F2 L' F"
+        ]
+    ),
+    coll!(
+        "U",
+        3,
+        ["F2 R2 F L2 F' R2 F L2 F", "U2 F2 L2 F' R2 F L2 F' R2 F'"]
+    ),
+    coll!(
+        "U",
+        4,
+        [
+            "F' U2 R U2 R' F2 U2 L' U2 L F'",
+            "F' U2 L F2 L' F2 U2 R' F2 R F'"
+        ]
+    ),
+    coll!(
+        "U",
+        5,
+        [
+            "R' L U L2 U2 R U' R' U2 L2 R U2 L'",
+            "R' L U' R2 U2 L' U L U2 L' R2 U2 R U2"
+        ]
+    ),
+    coll!(
+        "U",
+        6,
+        ["F' U L U' L' U' F L U2 L'", "F' U2 F L U' F' U' F U L'"]
+    ),
+    coll!(
+        "T",
+        1,
+        [
+            "R' U' L U2 R' F2 R F2 U' R U2 L'",
+            "F' R U2 R' U2 R' F' R F2 L' U2 L"
+        ]
+    ),
+    coll!("T", 2, ["F' L' F R' F' L F R"]),
+    coll!(
+        "T",
+        3,
+        ["F R2 F L2 F' R2 F L2 F2", "F' L2 F' R2 F L2 F' R2 F2 U2"]
+    ),
+    coll!(
+        "T",
+        4,
+        ["R' U R U2 L' R' U R U' L", "L U' R U L' R' U2 R U R'"]
+    ),
+    coll!("T", 5, ["F R F' L F R' F' L'"]),
+    coll!(
+        "T",
+        6,
+        [
+            "F U' R F U2 F' U2 R' U F U' F2",
+            "L2 U L' U' F U2 L U2 L' F' U L' U2"
+        ]
+    ),
+    coll!(
+        "L",
+        1,
+        [
+            "R' U R L U2 L' U' L U' L' R' U2 R",
+            "L U2 R' L' U' R U' L U' L' R' U2 R"
+        ]
+    ),
+    coll!(
+        "L",
+        2,
+        [
+            "R' U L' U R2 U' L U R2 U2 R",
+            "R' U' R U2 L' U R' U' L U' R U2"
+        ]
+    ),
+    coll!("L", 3, ["L F R F' L' F R' F'", "U2 F' L F R' F' L' F R U2"]),
+    coll!(
+        "L",
+        4,
+        [
+            "L' U2 R U R' U2 L U' R U2 R'",
+            "L U L' U2 R U' L U R' U L' U2"
+        ]
+    ),
+    coll!("L", 5, ["R' F' L' F R F' L F", "U2 F R' F' L F R F' L' U2"]),
+    coll!(
+        "L",
+        6,
+        ["R U2 R' L' U R U' L U2 R'", "R' U2 L U' R U L' R' U2 R"]
+    ),
+    coll!("AS", 1, ["R' U' R U' R' U2 R", "U R U2 R' U' R U' R' U'"]),
+    coll!(
+        "AS",
+        2,
+        [
+            "R' F U2 F' R F R' U2 R F'",
+            "U F' L U2 L' F L F' U2 F L' U'"
+        ]
+    ),
+    coll!("AS", 3, ["F U R' U' R F' U' R' U2 R"]),
+    coll!("AS", 4, ["R U2 R' U2 L' U R U' L R'"]),
+    coll!("AS", 5, ["L' U R U' L U R'", "U2 R' U L U' R U L' U2"]),
+    coll!(
+        "AS",
+        6,
+        [
+            "R U2 L' U R' U' L U' R U' R'",
+            "L' U' L U' R U' L' U R' U2 L"
+        ]
+    ),
+    coll!("S", 1, ["R' U2 R U R' U R", "U R U R' U R U2 R' U'"]),
+    coll!("S", 2, ["L U' R' U L' U' R", "U2 R U' L' U R' U' L U2"]),
+    coll!("S", 3, ["L' U2 L U2 R U' L' U L R'"]),
+    coll!("S", 4, ["F' U' L U L' F U L U2 L'"]),
+    coll!(
+        "S",
+        5,
+        [
+            "F R' U2 R F' R' F U2 F' R",
+            "U L F' U2 F L' F' L U2 L' F U'"
+        ]
+    ),
+    coll!(
+        "S",
+        6,
+        [
+            "R U R' U L' U R U' L U2 R'",
+            "U2 R' U2 L U' R U L' U R' U R U2"
+        ]
+    ),
+    coll!(
+        "O",
+        1,
+        ["F2 R2 F L F' R2 F L' F", "F2 L2 F' R' F L2 F' R F' U2"]
+    ),
+    coll!(
+        "O",
+        2,
+        [
+            "R U' R2 F2 U' R F2 R' U F2 R2 U R'",
+            "R2 U' R2 U' R2 U F U F' R2 F U' F' U'"
+        ]
+    ),
+];
+
+/// OCLL shapes by the OLL case with the same corner twists and oriented edges
+const SHAPES: [(u8, &str); 7] = [
+    (21, "H"),
+    (22, "Pi"),
+    (23, "U"),
+    (24, "T"),
+    (25, "L"),
+    (26, "AS"),
+    (27, "S"),
+];
+
+/// Face turns of a built-in algorithm, with slices, wide turns and rotations resolved
+pub fn algorithm_moves(algorithm: &str) -> MoveSequence {
+    MoveSequence::parse(algorithm)
+        .expect("built-in algorithms are valid")
+        .to_face_turns()
+}
+
+/// Effect of an algorithm on the solved cube; its inverse is the case the algorithm solves
+fn algorithm_cube(algorithm: &str) -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    cube.apply_moves(&algorithm_moves(algorithm).moves);
+    cube
+}
+
+fn top_turn(turns: i32) -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    cube.apply_move(Move::face(Face::U, turns));
+    cube
+}
+
+/// Twists of the U corners and flips of the U edges, by position
+fn orientation_pattern(cube: &CubieCube) -> [u8; 8] {
+    let mut pattern = [0; 8];
+    pattern[..4].copy_from_slice(&cube.co[..4]);
+    pattern[4..].copy_from_slice(&cube.eo[..4]);
+    pattern
+}
+
+/// Whether every corner and edge of the last layer is oriented
+pub fn last_layer_oriented(cube: &CubieCube) -> bool {
+    orientation_pattern(cube) == [0; 8]
+}
+
+/// Turns of U (0..4) done before the algorithm, and the OLL case they lead to.
+/// `None` when the last layer is already oriented or the cube is not at the OLL step.
+pub fn recognize_oll(cube: &CubieCube) -> Option<(i32, &'static OllCase)> {
+    static PATTERNS: OnceLock<Vec<[u8; 8]>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        OLL_CASES
+            .iter()
+            .map(|case| orientation_pattern(&algorithm_cube(case.algorithm).inverse()))
+            .collect()
+    });
+    (0..4).find_map(|turns| {
+        let pattern = orientation_pattern(&cube.multiply(&top_turn(turns)));
+        let index = patterns.iter().position(|&case| case == pattern)?;
+        Some((turns, &OLL_CASES[index]))
+    })
+}
+
+/// Turns of U (0..4) that finish a cube whose last layer only needs adjusting
+pub fn adjust_turns(cube: &CubieCube) -> Option<i32> {
+    (0..4).find(|&turns| cube.multiply(&top_turn(turns)).is_solved())
+}
+
+/// Turns of U before the algorithm, the PLL case and turns of U after it.
+/// `None` when no single PLL algorithm finishes the cube.
+pub fn recognize_pll(cube: &CubieCube) -> Option<(i32, &'static PllCase, i32)> {
+    static CASES: OnceLock<Vec<CubieCube>> = OnceLock::new();
+    let cases = CASES.get_or_init(|| {
+        PLL_CASES
+            .iter()
+            .map(|case| algorithm_cube(case.algorithm))
+            .collect()
+    });
+    (0..4).find_map(|before| {
+        let turned = cube.multiply(&top_turn(before));
+        cases.iter().zip(&PLL_CASES).find_map(|(algorithm, case)| {
+            let after = adjust_turns(&turned.multiply(algorithm))?;
+            Some((before, case, after))
+        })
+    })
+}
+
+/// Whether the corners and edges below the last layer are solved
+pub fn first_two_layers_solved(cube: &CubieCube) -> bool {
+    (4..8).all(|i| cube.cp[i] as usize == i && cube.co[i] == 0)
+        && (4..12).all(|i| cube.ep[i] as usize == i && cube.eo[i] == 0)
+}
+
+/// Turns of U (0..4) that solve the last-layer corners, whatever the edges
+fn corner_adjust_turns(cube: &CubieCube) -> Option<i32> {
+    (0..4).find(|&turns| {
+        let turned = cube.multiply(&top_turn(turns));
+        turned.cp[..4] == [0, 1, 2, 3] && turned.co[..4] == [0; 4]
+    })
+}
+
+/// Turns of U before the algorithm, the COLL case and turns of U after it that
+/// solve the corners. `None` when the edges are not oriented or the corners only
+/// need adjusting.
+pub fn recognize_coll(cube: &CubieCube) -> Option<(i32, &'static CollCase, i32)> {
+    static CASES: OnceLock<Vec<CubieCube>> = OnceLock::new();
+    let cases = CASES.get_or_init(|| {
+        COLL_CASES
+            .iter()
+            .map(|case| algorithm_cube(case.algorithms[0]))
+            .collect()
+    });
+    if cube.eo[..4] != [0; 4] || corner_adjust_turns(cube).is_some() {
+        return None;
+    }
+    (0..4).find_map(|before| {
+        let turned = cube.multiply(&top_turn(before));
+        cases.iter().zip(&COLL_CASES).find_map(|(algorithm, case)| {
+            let after = corner_adjust_turns(&turned.multiply(algorithm))?;
+            Some((before, case, after))
+        })
+    })
+}
+
+/// Positions of the last-layer corners, their twists and positions of the edges
+fn last_layer_key(cube: &CubieCube) -> [u8; 12] {
+    let mut key = [0; 12];
+    key[..4].copy_from_slice(&cube.cp[..4]);
+    key[4..8].copy_from_slice(&cube.co[..4]);
+    key[8..].copy_from_slice(&cube.ep[..4]);
+    key
+}
+
+/// ZBLL case of every last layer with oriented edges and twisted corners. Cases are
+/// the states that differ by turns of U before and after; they are numbered within
+/// their shape in the order they are enumerated.
+fn zbll_cases() -> &'static HashMap<[u8; 12], ZbllCase> {
+    static CASES: OnceLock<HashMap<[u8; 12], ZbllCase>> = OnceLock::new();
+    CASES.get_or_init(|| {
+        let mut permutations = Vec::new();
+        for first in 0..4u8 {
+            for second in (0..4).filter(|&piece| piece != first) {
+                for third in (0..4).filter(|&piece| piece != first && piece != second) {
+                    permutations.push([first, second, third, 6 - first - second - third]);
+                }
+            }
+        }
+
+        // Every case as its shape and the states it covers
+        let mut classes: Vec<(usize, Vec<[u8; 12]>)> = Vec::new();
+        let mut covered = HashSet::new();
+        for corners in &permutations {
+            for twists in 0..27u8 {
+                for edges in &permutations {
+                    let mut cube = CubieCube::SOLVED;
+                    cube.cp[..4].copy_from_slice(corners);
+                    cube.co[..3].copy_from_slice(&[twists % 3, twists / 3 % 3, twists / 9]);
+                    cube.co[3] = (6 - cube.co[..3].iter().sum::<u8>()) % 3;
+                    cube.ep[..4].copy_from_slice(edges);
+                    if cube.co[..4] == [0; 4]
+                        || cube.verify().is_err()
+                        || covered.contains(&last_layer_key(&cube))
+                    {
+                        continue;
+                    }
+                    let Some((_, oll)) = recognize_oll(&cube) else {
+                        continue;
+                    };
+                    let shape = SHAPES
+                        .iter()
+                        .position(|&(number, _)| number == oll.number)
+                        .expect("twisted corners with oriented edges make an OCLL shape");
+                    let mut states = Vec::new();
+                    for before in 0..4 {
+                        for after in 0..4 {
+                            let state = top_turn(before).multiply(&cube).multiply(&top_turn(after));
+                            let key = last_layer_key(&state);
+                            if covered.insert(key) {
+                                states.push(key);
+                            }
+                        }
+                    }
+                    classes.push((shape, states));
+                }
+            }
+        }
+
+        classes.sort_by_key(|&(shape, _)| shape);
+        let mut numbers = [0; SHAPES.len()];
+        let mut cases = HashMap::new();
+        for (shape, states) in classes {
+            numbers[shape] += 1;
+            let case = ZbllCase {
+                shape: SHAPES[shape].1,
+                number: numbers[shape],
+            };
+            cases.extend(states.into_iter().map(|key| (key, case)));
+        }
+        cases
+    })
+}
+
+/// The ZBLL case of a last layer with oriented edges and twisted corners
+pub fn recognize_zbll(cube: &CubieCube) -> Option<ZbllCase> {
+    if cube.eo[..4] != [0; 4] {
+        return None;
+    }
+    zbll_cases().get(&last_layer_key(cube)).copied()
+}
+
+/// Turns of U before the moves, the moves and turns of U after them that solve a
+/// ZBLL case: its COLL algorithm followed by the edge permutation
+pub fn zbll_algorithm(cube: &CubieCube) -> Option<(i32, MoveSequence, i32)> {
+    recognize_zbll(cube)?;
+    let (before, case, _) = recognize_coll(cube)?;
+    let coll = MoveSequence::parse(case.algorithms[0]).expect("built-in algorithms are valid");
+    let mut corners_solved = cube.multiply(&top_turn(before));
+    corners_solved.apply_moves(&coll.moves);
+    if let Some(after) = adjust_turns(&corners_solved) {
+        return Some((before, coll, after));
+    }
+    let (pll_before, pll, after) = recognize_pll(&corners_solved)?;
+    let moves = coll
+        .then(&MoveSequence::new(vec![Move::face(Face::U, pll_before)]))
+        .then(&MoveSequence::parse(pll.algorithm).expect("built-in algorithms are valid"))
+        .simplify();
+    Some((before, moves, after))
+}
+
+/// A recognised last-layer case with the turns of U around its algorithms
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecognizedCase {
+    /// Label such as "OLL 45 (T shape)", "COLL H-3" or "PLL Ua-perm"
+    pub label: String,
+    /// Turns of U (0..4) before the algorithm
+    pub before: i32,
+    /// Algorithms for the case, as written in the tables
+    pub algorithms: Vec<MoveSequence>,
+    /// Turns of U (0..4) after the algorithm
+    pub after: i32,
+}
+
+/// The last-layer cases of a cube with its first two layers solved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastLayerReport {
+    /// Whole-cube rotation that brings the last layer to U, if it is on another face
+    pub rotation: Option<Move>,
+    /// The OLL, COLL, ZBLL and PLL cases that apply
+    pub cases: Vec<RecognizedCase>,
+}
+
+impl LastLayerReport {
+    /// Moves that solve `case` with its algorithm `algorithm`, from the cube as it is held
+    pub fn moves(&self, case: &RecognizedCase, algorithm: usize) -> MoveSequence {
+        let mut moves: Vec<Move> = self.rotation.into_iter().collect();
+        moves.push(Move::face(Face::U, case.before));
+        moves.extend_from_slice(&case.algorithms[algorithm].moves);
+        moves.push(Move::face(Face::U, case.after));
+        MoveSequence::new(moves.into_iter().filter(|mv| !mv.is_identity()).collect())
+    }
+}
+
+/// Recognise the last-layer cases of the cube in `facelets`, with the last layer on
+/// any face. `None` when no face has the two layers below it solved, or the cube
+/// only needs adjusting.
+pub fn recognize(facelets: &str) -> Result<Option<LastLayerReport>, String> {
+    let model = CubeModel::from_facelets(facelets).map_err(|error| error.to_string())?;
+    model.to_cubie().map_err(|error| error.to_string())?;
+    for (axis, turns) in [
+        (Axis::X, 0),
+        (Axis::X, 1),
+        (Axis::X, 2),
+        (Axis::X, 3),
+        (Axis::Z, 1),
+        (Axis::Z, 3),
+    ] {
+        let rotation = Move::new(MoveKind::Rotation(axis), turns);
+        let mut turned = model.clone();
+        turned.apply_move(rotation);
+        let cube = turned.to_cubie().map_err(|error| error.to_string())?;
+        if !first_two_layers_solved(&cube) {
+            continue;
+        }
+        let cases = recognize_cases(&cube);
+        if !cases.is_empty() {
+            return Ok(Some(LastLayerReport {
+                rotation: (!rotation.is_identity()).then_some(rotation),
+                cases,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Every case that applies to a cube with its last layer on U
+fn recognize_cases(cube: &CubieCube) -> Vec<RecognizedCase> {
+    let parse =
+        |algorithm: &str| MoveSequence::parse(algorithm).expect("built-in algorithms are valid");
+    let mut cases = Vec::new();
+    if let Some((before, case)) = recognize_oll(cube) {
+        cases.push(RecognizedCase {
+            label: case.label(),
+            before,
+            algorithms: vec![parse(case.algorithm)],
+            after: 0,
+        });
+    }
+    if let Some((before, case, after)) = recognize_coll(cube) {
+        cases.push(RecognizedCase {
+            label: case.label(),
+            before,
+            algorithms: case
+                .algorithms
+                .iter()
+                .map(|algorithm| parse(algorithm))
+                .collect(),
+            after,
+        });
+    }
+    if let Some(case) = recognize_zbll(cube)
+        && let Some((before, moves, after)) = zbll_algorithm(cube)
+    {
+        cases.push(RecognizedCase {
+            label: case.label(),
+            before,
+            algorithms: vec![moves],
+            after,
+        });
+    }
+    if let Some((before, case, after)) = recognize_pll(cube) {
+        cases.push(RecognizedCase {
+            label: case.label(),
+            before,
+            algorithms: vec![parse(case.algorithm)],
+            after,
+        });
+    }
+    cases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUNE: &str = "R U R' U R U2 R'";
+    const UA_PERM: &str = "M2 U M U2 M' U M2";
+
+    /// The case `algorithm` solves
+    fn case_cube(algorithm: &str) -> CubieCube {
+        algorithm_cube(algorithm).inverse()
+    }
+
+    fn solve_case(model: &mut CubeModel, report: &LastLayerReport, label: &str) {
+        let case = report
+            .cases
+            .iter()
+            .find(|case| case.label.starts_with(label))
+            .unwrap_or_else(|| panic!("{label} not in {:?}", report.cases));
+        model.apply_moves(&report.moves(case, 0).moves);
+    }
+
+    #[test]
+    fn sune_is_oll_27() {
+        let cube = case_cube(SUNE);
+        let (before, case) = recognize_oll(&cube).unwrap();
+        assert_eq!(case.number, 27);
+
+        let mut oriented = cube.multiply(&top_turn(before));
+        oriented.apply_moves(&algorithm_moves(case.algorithm).moves);
+        assert!(last_layer_oriented(&oriented));
+    }
+
+    #[test]
+    fn ua_perm_is_recognized_and_solved() {
+        let mut model = CubeModel::from_cubie(&case_cube(UA_PERM));
+        let report = recognize(&model.to_facelet_string()).unwrap().unwrap();
+        assert_eq!(report.rotation, None);
+        solve_case(&mut model, &report, "PLL Ua-perm");
+        assert!(model.to_cubie().unwrap().is_solved());
+    }
+
+    #[test]
+    fn last_layer_on_f_is_recognized_through_a_rotation() {
+        let mut model = CubeModel::from_cubie(&case_cube(SUNE));
+        model.apply_moves(&MoveSequence::parse("x'").unwrap().moves);
+        let report = recognize(&model.to_facelet_string()).unwrap().unwrap();
+        assert!(report.rotation.is_some());
+        solve_case(&mut model, &report, "OLL 27");
+        assert!(last_layer_oriented(&model.to_cubie().unwrap()));
+    }
+
+    #[test]
+    fn solved_cube_has_no_cases() {
+        let solved = CubeModel::from_cubie(&CubieCube::SOLVED);
+        assert_eq!(recognize(&solved.to_facelet_string()), Ok(None));
+    }
+}
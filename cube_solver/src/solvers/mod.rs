@@ -0,0 +1,93 @@
+//! Solvers implemented in this crate, next to the min2phase two-phase solver.
+
+pub mod analysis;
+pub mod beginner;
+pub mod cfop;
+pub mod coordinates;
+pub mod cross;
+pub mod fmc;
+pub mod generator;
+pub mod hint;
+pub mod inspection;
+pub mod last_layer;
+pub mod move_set;
+pub mod optimal;
+pub mod personal;
+pub mod restricted;
+pub mod roux;
+pub mod target;
+pub mod thistlethwaite;
+
+use crate::cube_model::{Axis, CubeModel, CubieCube, Move, MoveKind};
+use crate::move_sequence::MoveSequence;
+
+/// Whole-cube turn between the app's orientation (first layer on U) and the one the
+/// layer-based solvers work in (first layer on D)
+fn upside_down() -> Move {
+    Move::new(MoveKind::Rotation(Axis::X), 2)
+}
+
+/// Cubie state of `cube` turned upside down
+pub fn upside_down_cube(cube: &CubieCube) -> CubieCube {
+    let mut model = CubeModel::from_cubie(cube);
+    model.apply_move(upside_down());
+    model
+        .to_cubie()
+        .expect("a turned cube is still a valid cube")
+}
+
+/// Moves found on the upside-down cube, expressed for the cube as the app holds it
+pub fn from_upside_down(moves: &MoveSequence) -> MoveSequence {
+    moves.rotate(upside_down().inverse())
+}
+
+/// A labelled part of a staged solution, such as the first-layer cross
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolutionStage {
+    pub label: String,
+    /// One line on what the stage achieves
+    pub explanation: String,
+    /// Number of moves of the solution that belong to this stage
+    pub move_count: usize,
+}
+
+/// A solution, optionally split into stages
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Solution {
+    pub moves: MoveSequence,
+    /// Consecutive stages covering the moves; empty for solvers without stages
+    pub stages: Vec<SolutionStage>,
+}
+
+impl Solution {
+    pub fn move_count(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Append a stage; stages without moves are left out
+    pub fn push_stage(
+        &mut self,
+        label: impl Into<String>,
+        explanation: impl Into<String>,
+        moves: &MoveSequence,
+    ) {
+        if moves.is_empty() {
+            return;
+        }
+        self.moves.moves.extend_from_slice(&moves.moves);
+        self.stages.push(SolutionStage {
+            label: label.into(),
+            explanation: explanation.into(),
+            move_count: moves.len(),
+        });
+    }
+}
+
+impl From<MoveSequence> for Solution {
+    fn from(moves: MoveSequence) -> Self {
+        Self {
+            moves,
+            stages: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Sets of allowed moves, such as `<R,U>` for two-generator practice or
+//! `<R,U,F,L,D>` for a robot without a B gripper.
+
+use crate::cube_model::{Face, Move, MoveKind};
+use crate::notation;
+use std::fmt;
+
+/// Layers that may be turned, each by the turns listed with it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MoveSet {
+    layers: Vec<(MoveKind, HalfTurnsOnly)>,
+}
+
+/// Whether a layer was given as `R2`: only its half turn is allowed
+type HalfTurnsOnly = bool;
+
+impl MoveSet {
+    /// Every face turn
+    pub fn all_faces() -> Self {
+        Self {
+            layers: Face::ALL
+                .iter()
+                .map(|&face| (MoveKind::Face(face), false))
+                .collect(),
+        }
+    }
+
+    /// Parse a list of layers like `<R,U,M>` or `R U F2`. A layer allows all its turns,
+    /// or only the half turn when written with a 2.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let text = text
+            .strip_prefix('<')
+            .and_then(|inner| inner.strip_suffix('>'))
+            .unwrap_or(text);
+        let mut layers: Vec<(MoveKind, HalfTurnsOnly)> = Vec::new();
+        for token in text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+        {
+            let mv =
+                notation::parse_move(token).map_err(|error| format!("{}: {}", token, error))?;
+            if matches!(mv.kind, MoveKind::Rotation(_)) {
+                return Err(format!("{} turns the whole cube, not a layer", token));
+            }
+            let half_turns_only = mv.turns == 2;
+            match layers.iter_mut().find(|(kind, _)| *kind == mv.kind) {
+                // R2 and R together allow every turn of R
+                Some((_, existing)) => *existing &= half_turns_only,
+                None => layers.push((mv.kind, half_turns_only)),
+            }
+        }
+        if layers.is_empty() {
+            return Err("The move set is empty".to_string());
+        }
+        Ok(Self { layers })
+    }
+
+    /// Every allowed move, grouped by layer: quarter, half and inverse turn, or the
+    /// half turn alone
+    pub fn moves(&self) -> Vec<Move> {
+        self.layers
+            .iter()
+            .flat_map(|&(kind, half_turns_only)| {
+                let turns: &[i32] = if half_turns_only { &[2] } else { &[1, 2, 3] };
+                turns.iter().map(move |&turns| Move::new(kind, turns))
+            })
+            .collect()
+    }
+
+    /// Sets offered in the solver settings: every face turn, then common restrictions
+    pub fn presets() -> Vec<Self> {
+        let restricted = [
+            "<R,U,F,L,D>",
+            "<R,U,F>",
+            "<R,U>",
+            "<R,U,M>",
+            "<U,D,R2,L2,F2,B2>",
+        ]
+        .into_iter()
+        .map(|text| Self::parse(text).expect("preset move sets parse"));
+        std::iter::once(Self::all_faces())
+            .chain(restricted)
+            .collect()
+    }
+
+    /// Whether the set allows every face turn, so any solver can be used
+    pub fn has_all_faces(&self) -> bool {
+        Face::ALL
+            .iter()
+            .all(|&face| self.layers.contains(&(MoveKind::Face(face), false)))
+    }
+
+    pub fn allows(&self, mv: &Move) -> bool {
+        mv.is_identity()
+            || self.layers.iter().any(|&(kind, half_turns_only)| {
+                kind == mv.kind && (!half_turns_only || mv.turns == 2)
+            })
+    }
+}
+
+impl Default for MoveSet {
+    fn default() -> Self {
+        Self::all_faces()
+    }
+}
+
+impl fmt::Display for MoveSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layers: Vec<String> = self
+            .layers
+            .iter()
+            .map(|&(kind, half_turns_only)| {
+                Move::new(kind, if half_turns_only { 2 } else { 1 }).to_string()
+            })
+            .collect();
+        write!(f, "<{}>", layers.join(","))
+    }
+}
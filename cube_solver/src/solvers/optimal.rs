@@ -0,0 +1,594 @@
+//! Optimal solver: Korf-style IDA* over pattern databases.
+//!
+//! Three pattern databases bound the distance to solved from below: the corners
+//! (88 million states) and two groups of six edges (42 million states each).
+//! Depths are stored in 4 bits, about 86 MB together. Building them takes
+//! seconds to minutes depending on the build profile, so they are generated once
+//! on a background thread and cached on disk.
+//!
+//! The databases hold half turn metric distances. A half turn costs two quarter
+//! turns, so they are lower bounds in the quarter turn metric as well and the
+//! same tables drive both searches.
+
+use super::coordinates::{
+    MOVE_COUNT, edge_group_from_index, edge_group_index, edge_states, edge_transitions, face_turn,
+    face_turn_cube, rank_placement, unrank_placement,
+};
+use crate::cube_model::{CubeModelError, CubieCube};
+use crate::move_sequence::Metric;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Once, OnceLock};
+use std::time::Instant;
+
+/// 3^7: the twist of the last corner follows from the others
+const CORNER_TWISTS: usize = 2187;
+/// 8!
+const CORNER_PERMUTATIONS: usize = 40320;
+const CORNER_STATES: usize = CORNER_TWISTS * CORNER_PERMUTATIONS;
+
+/// Edges tracked by each edge database
+const EDGE_GROUP_SIZE: usize = 6;
+/// Placements of six edges in twelve positions: 12 * 11 * 10 * 9 * 8 * 7
+const EDGE_PLACEMENTS: usize = 665_280;
+const EDGE_STATES: usize = EDGE_PLACEMENTS << EDGE_GROUP_SIZE;
+
+/// Marks a database entry that has not been reached yet
+const UNVISITED: u8 = 0xF;
+
+/// Cache file header; bump it when the table layout changes
+const CACHE_MAGIC: &[u8; 8] = b"CUBEPDB1";
+
+/// Nodes expanded between checks of the stop condition
+const STOP_CHECK_INTERVAL: u64 = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptimalSolveError {
+    /// The pattern databases are still being loaded or generated
+    TablesNotReady,
+    /// The stop condition fired before a solution was proven optimal
+    Stopped,
+    InvalidCube(CubeModelError),
+}
+
+impl fmt::Display for OptimalSolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptimalSolveError::TablesNotReady => write!(f, "pattern databases are not ready yet"),
+            OptimalSolveError::Stopped => write!(f, "search stopped"),
+            OptimalSolveError::InvalidCube(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for OptimalSolveError {}
+
+static TABLES: OnceLock<PatternTables> = OnceLock::new();
+
+/// Start loading (or generating) the pattern databases on a background thread.
+/// Only the first call does anything.
+pub fn prepare_tables() {
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        let spawned = std::thread::Builder::new()
+            .name("optimal-solver-tables".to_string())
+            .spawn(|| {
+                let _ = TABLES.set(load_or_generate_tables());
+            });
+        if let Err(error) = spawned {
+            log::error!("Failed to start pattern database thread: {}", error);
+        }
+    });
+}
+
+pub fn tables_ready() -> bool {
+    TABLES.get().is_some()
+}
+
+/// Lower bound on the face turns that solve `cube`, from the pattern databases;
+/// `None` until they are ready
+pub fn distance_bound(cube: &CubieCube) -> Option<usize> {
+    let tables = TABLES.get()?;
+    Some(tables.distance_bound(&Node::from_cubie(cube)) as usize)
+}
+
+/// Find a solution of a facelet string that is optimal in `metric`.
+///
+/// Only the quarter turn metric changes the cost of moves; any other metric is
+/// searched in the half turn metric. Gives up with [`OptimalSolveError::Stopped`]
+/// as soon as `should_stop` returns true.
+pub fn solve(
+    facelets: &str,
+    metric: Metric,
+    should_stop: impl Fn() -> bool,
+) -> Result<String, OptimalSolveError> {
+    let cube = CubieCube::from_facelets(facelets).map_err(OptimalSolveError::InvalidCube)?;
+    let tables = TABLES.get().ok_or(OptimalSolveError::TablesNotReady)?;
+
+    let mut search = Search {
+        tables,
+        moves: move_tables(),
+        quarter_turns: metric == Metric::Qtm,
+        should_stop: &should_stop,
+        nodes: 0,
+        stopped: false,
+        path: Vec::new(),
+    };
+    let start = Node::from_cubie(&cube);
+    let mut bound = tables.distance_bound(&start) as usize;
+    loop {
+        log::debug!(
+            "Optimal search: bound {} ({} nodes so far)",
+            bound,
+            search.nodes
+        );
+        match search.search(&start, 0, bound, None) {
+            Ok(()) => break,
+            Err(_) if search.stopped => return Err(OptimalSolveError::Stopped),
+            Err(next_bound) => bound = next_bound,
+        }
+    }
+
+    log::info!(
+        "Optimal search finished after {} nodes: {} moves",
+        search.nodes,
+        search.path.len()
+    );
+    let moves: Vec<String> = search
+        .path
+        .iter()
+        .map(|&m| face_turn(m as usize).to_string())
+        .collect();
+    Ok(moves.join(" "))
+}
+
+/// Depth-first search state of one IDA* run
+struct Search<'a> {
+    tables: &'a PatternTables,
+    moves: &'a MoveTables,
+    quarter_turns: bool,
+    should_stop: &'a dyn Fn() -> bool,
+    nodes: u64,
+    stopped: bool,
+    path: Vec<u8>,
+}
+
+impl Search<'_> {
+    /// Search below `node` for a solution costing at most `bound`.
+    /// On failure returns the smallest cost estimate that exceeded the bound.
+    fn search(
+        &mut self,
+        node: &Node,
+        cost: usize,
+        bound: usize,
+        last_face: Option<usize>,
+    ) -> Result<(), usize> {
+        let distance = self.tables.distance_bound(node) as usize;
+        if cost + distance > bound {
+            return Err(cost + distance);
+        }
+        if distance == 0 {
+            // Every piece is covered by a database, so zero means solved
+            return Ok(());
+        }
+
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) && (self.should_stop)() {
+            self.stopped = true;
+        }
+        if self.stopped {
+            return Err(usize::MAX);
+        }
+
+        let mut next_bound = usize::MAX;
+        for face in 0..6 {
+            // Never turn the same face twice in a row, and turn opposite faces in one order only
+            if let Some(last) = last_face
+                && (face == last || face + 3 == last)
+            {
+                continue;
+            }
+            for turn in 0..3 {
+                let m = face * 3 + turn;
+                let move_cost = if self.quarter_turns && turn == 1 {
+                    2
+                } else {
+                    1
+                };
+                self.path.push(m as u8);
+                match self.search(
+                    &node.apply(m, self.moves),
+                    cost + move_cost,
+                    bound,
+                    Some(face),
+                ) {
+                    Ok(()) => return Ok(()),
+                    Err(estimate) => next_bound = next_bound.min(estimate),
+                }
+                self.path.pop();
+                if self.stopped {
+                    return Err(usize::MAX);
+                }
+            }
+        }
+        Err(next_bound)
+    }
+}
+
+/// Search node: corner coordinates plus the position and flip of every edge
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    corner_twist: u16,
+    corner_permutation: u16,
+    /// `position * 2 + flip` of each edge piece
+    edges: [u8; 12],
+}
+
+impl Node {
+    fn from_cubie(cube: &CubieCube) -> Node {
+        Node {
+            corner_twist: corner_twist_coordinate(&cube.co) as u16,
+            corner_permutation: rank_placement(&cube.cp, 8) as u16,
+            edges: edge_states(cube),
+        }
+    }
+
+    fn apply(&self, m: usize, moves: &MoveTables) -> Node {
+        Node {
+            corner_twist: moves.corner_twist[self.corner_twist as usize][m],
+            corner_permutation: moves.corner_permutation[self.corner_permutation as usize][m],
+            edges: self.edges.map(|state| moves.edge[m][state as usize]),
+        }
+    }
+
+    fn corner_index(&self) -> usize {
+        self.corner_permutation as usize * CORNER_TWISTS + self.corner_twist as usize
+    }
+}
+
+/// Transition tables for the coordinates of a node
+struct MoveTables {
+    corner_twist: Vec<[u16; MOVE_COUNT]>,
+    corner_permutation: Vec<[u16; MOVE_COUNT]>,
+    /// `edge[m][position * 2 + flip]`: where a single edge goes under move `m`
+    edge: [[u8; 24]; MOVE_COUNT],
+}
+
+fn move_tables() -> &'static MoveTables {
+    static MOVE_TABLES: OnceLock<MoveTables> = OnceLock::new();
+    MOVE_TABLES.get_or_init(|| {
+        let cubes: [CubieCube; MOVE_COUNT] = std::array::from_fn(face_turn_cube);
+
+        // The piece at position cp[i] of a move cube goes to position i, gaining twist co[i]
+        let corner_twist = (0..CORNER_TWISTS)
+            .map(|twist| {
+                let co = corner_twist_from_coordinate(twist);
+                std::array::from_fn(|m| {
+                    let cube = &cubes[m];
+                    let moved: [u8; 8] =
+                        std::array::from_fn(|i| (co[cube.cp[i] as usize] + cube.co[i]) % 3);
+                    corner_twist_coordinate(&moved) as u16
+                })
+            })
+            .collect();
+        let corner_permutation = (0..CORNER_PERMUTATIONS)
+            .map(|permutation| {
+                let mut cp = [0; 8];
+                unrank_placement(permutation, 8, &mut cp);
+                std::array::from_fn(|m| {
+                    let moved: [u8; 8] = std::array::from_fn(|i| cp[cubes[m].cp[i] as usize]);
+                    rank_placement(&moved, 8) as u16
+                })
+            })
+            .collect();
+        MoveTables {
+            corner_twist,
+            corner_permutation,
+            edge: *edge_transitions(),
+        }
+    })
+}
+
+fn corner_twist_coordinate(co: &[u8; 8]) -> usize {
+    co[..7]
+        .iter()
+        .fold(0, |coordinate, &twist| coordinate * 3 + twist as usize)
+}
+
+fn corner_twist_from_coordinate(mut coordinate: usize) -> [u8; 8] {
+    let mut co = [0; 8];
+    for twist in co[..7].iter_mut().rev() {
+        *twist = (coordinate % 3) as u8;
+        coordinate /= 3;
+    }
+    co[7] = (3 - co[..7].iter().sum::<u8>() % 3) % 3;
+    co
+}
+
+/// Distances to solved packed two per byte
+struct PatternTable {
+    data: Vec<u8>,
+}
+
+impl PatternTable {
+    fn new(size: usize) -> Self {
+        Self {
+            data: vec![0xFF; size.div_ceil(2)],
+        }
+    }
+
+    fn get(&self, index: usize) -> u8 {
+        (self.data[index / 2] >> (index % 2 * 4)) & 0xF
+    }
+
+    fn set(&mut self, index: usize, depth: u8) {
+        let shift = index % 2 * 4;
+        let byte = &mut self.data[index / 2];
+        *byte = (*byte & !(0xF << shift)) | (depth << shift);
+    }
+}
+
+struct PatternTables {
+    corners: PatternTable,
+    /// Edges UR, UF, UL, UB, DR, DF
+    edges_low: PatternTable,
+    /// Edges DL, DB, FR, FL, BL, BR
+    edges_high: PatternTable,
+}
+
+impl PatternTables {
+    /// Lower bound on the number of face turns needed to solve `node`
+    fn distance_bound(&self, node: &Node) -> u8 {
+        let corners = self.corners.get(node.corner_index());
+        let edges_low = self
+            .edges_low
+            .get(edge_group_index(&node.edges[..EDGE_GROUP_SIZE]));
+        let edges_high = self
+            .edges_high
+            .get(edge_group_index(&node.edges[EDGE_GROUP_SIZE..]));
+        corners.max(edges_low).max(edges_high)
+    }
+}
+
+/// Breadth-first search over a coordinate space from the solved state.
+///
+/// Levels are expanded forwards while the frontier is small; once fewer states
+/// are left than the frontier holds, unvisited states look for a neighbour on
+/// the current level instead.
+fn generate_table(
+    size: usize,
+    solved: usize,
+    neighbors: impl Fn(usize, &mut [usize; MOVE_COUNT]),
+) -> PatternTable {
+    let mut table = PatternTable::new(size);
+    table.set(solved, 0);
+    let mut visited = 1;
+    let mut frontier = 1;
+    let mut depth = 0;
+    let mut next = [0; MOVE_COUNT];
+
+    while visited < size && frontier > 0 {
+        let backwards = size - visited < frontier;
+        frontier = 0;
+        for index in 0..size {
+            if backwards {
+                if table.get(index) != UNVISITED {
+                    continue;
+                }
+                neighbors(index, &mut next);
+                if next.iter().any(|&neighbor| table.get(neighbor) == depth) {
+                    table.set(index, depth + 1);
+                    frontier += 1;
+                }
+            } else {
+                if table.get(index) != depth {
+                    continue;
+                }
+                neighbors(index, &mut next);
+                for &neighbor in &next {
+                    if table.get(neighbor) == UNVISITED {
+                        table.set(neighbor, depth + 1);
+                        frontier += 1;
+                    }
+                }
+            }
+        }
+        visited += frontier;
+        depth += 1;
+        log::debug!("Pattern database depth {}: {} states", depth, frontier);
+    }
+    table
+}
+
+fn generate_tables() -> PatternTables {
+    let moves = move_tables();
+    let solved = Node::from_cubie(&CubieCube::SOLVED);
+
+    let corners = generate_table(CORNER_STATES, solved.corner_index(), |index, next| {
+        let twist = index % CORNER_TWISTS;
+        let permutation = index / CORNER_TWISTS;
+        for (m, neighbor) in next.iter_mut().enumerate() {
+            *neighbor = moves.corner_permutation[permutation][m] as usize * CORNER_TWISTS
+                + moves.corner_twist[twist][m] as usize;
+        }
+    });
+
+    let edge_neighbors = |index: usize, next: &mut [usize; MOVE_COUNT]| {
+        let group: [u8; EDGE_GROUP_SIZE] = edge_group_from_index(index);
+        for (m, neighbor) in next.iter_mut().enumerate() {
+            *neighbor = edge_group_index(&group.map(|state| moves.edge[m][state as usize]));
+        }
+    };
+    let edges_low = generate_table(
+        EDGE_STATES,
+        edge_group_index(&solved.edges[..EDGE_GROUP_SIZE]),
+        edge_neighbors,
+    );
+    let edges_high = generate_table(
+        EDGE_STATES,
+        edge_group_index(&solved.edges[EDGE_GROUP_SIZE..]),
+        edge_neighbors,
+    );
+
+    PatternTables {
+        corners,
+        edges_low,
+        edges_high,
+    }
+}
+
+/// Where the generated databases are cached: `$CUBE_SOLVER_CACHE_DIR`, the
+/// user's cache directory, or the temporary directory as a last resort
+fn cache_path() -> PathBuf {
+    let directory = std::env::var_os("CUBE_SOLVER_CACHE_DIR")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("XDG_CACHE_HOME").map(|dir| PathBuf::from(dir).join("cube_solver"))
+        })
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|dir| PathBuf::from(dir).join(".cache").join("cube_solver"))
+        })
+        .unwrap_or_else(|| std::env::temp_dir().join("cube_solver"));
+    directory.join("optimal_tables.bin")
+}
+
+fn load_tables(path: &Path) -> io::Result<PatternTables> {
+    let mut file = File::open(path)?;
+    let mut magic = [0; CACHE_MAGIC.len()];
+    file.read_exact(&mut magic)?;
+    if &magic != CACHE_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unknown table format",
+        ));
+    }
+
+    let mut read_table = |size| -> io::Result<PatternTable> {
+        let mut table = PatternTable::new(size);
+        file.read_exact(&mut table.data)?;
+        Ok(table)
+    };
+    Ok(PatternTables {
+        corners: read_table(CORNER_STATES)?,
+        edges_low: read_table(EDGE_STATES)?,
+        edges_high: read_table(EDGE_STATES)?,
+    })
+}
+
+fn save_tables(path: &Path, tables: &PatternTables) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    // Write to a temporary file first so an interrupted write never leaves a truncated cache
+    let temporary = path.with_extension("tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(CACHE_MAGIC)?;
+    for table in [&tables.corners, &tables.edges_low, &tables.edges_high] {
+        file.write_all(&table.data)?;
+    }
+    file.sync_all()?;
+    fs::rename(temporary, path)
+}
+
+fn load_or_generate_tables() -> PatternTables {
+    let path = cache_path();
+    match load_tables(&path) {
+        Ok(tables) => {
+            log::info!("Loaded optimal solver tables from {}", path.display());
+            return tables;
+        }
+        Err(error) => log::info!(
+            "No usable optimal solver tables at {} ({}), generating them",
+            path.display(),
+            error
+        ),
+    }
+
+    let started = Instant::now();
+    let tables = generate_tables();
+    log::info!(
+        "Generated optimal solver tables in {:.1}s",
+        started.elapsed().as_secs_f32()
+    );
+    match save_tables(&path, &tables) {
+        Ok(()) => log::info!("Cached optimal solver tables at {}", path.display()),
+        Err(error) => log::warn!(
+            "Failed to cache optimal solver tables at {}: {}",
+            path.display(),
+            error
+        ),
+    }
+    tables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_sequence::MoveSequence;
+    use std::time::Duration;
+
+    fn scrambled(scramble: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(scramble).unwrap().moves);
+        cube
+    }
+
+    #[test]
+    fn distance_bound_is_zero_for_solved() {
+        // Only the solved entries are filled in, which is all the solved cube looks up
+        let mut tables = PatternTables {
+            corners: PatternTable::new(CORNER_STATES),
+            edges_low: PatternTable::new(EDGE_STATES),
+            edges_high: PatternTable::new(EDGE_STATES),
+        };
+        let solved = Node::from_cubie(&CubieCube::SOLVED);
+        tables.corners.set(solved.corner_index(), 0);
+        tables
+            .edges_low
+            .set(edge_group_index(&solved.edges[..EDGE_GROUP_SIZE]), 0);
+        tables
+            .edges_high
+            .set(edge_group_index(&solved.edges[EDGE_GROUP_SIZE..]), 0);
+
+        assert_eq!(tables.distance_bound(&solved), 0);
+        assert_ne!(tables.distance_bound(&Node::from_cubie(&scrambled("R"))), 0);
+    }
+
+    /// Builds the pattern databases, which takes minutes without optimizations:
+    /// `cargo test --release -p cube_solver optimal -- --ignored`
+    #[test]
+    #[ignore]
+    fn proven_lengths_of_known_cases() {
+        let cache = std::env::temp_dir().join(format!("cube_solver_test_{}", std::process::id()));
+        // SAFETY: this is the only ignored test, so no other test thread is running
+        unsafe { std::env::set_var("CUBE_SOLVER_CACHE_DIR", &cache) };
+        prepare_tables();
+        while !tables_ready() {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        assert!(cache.join("optimal_tables.bin").exists());
+        assert_eq!(distance_bound(&CubieCube::SOLVED), Some(0));
+
+        let cases = [
+            ("R U R' U'", 4, 4),
+            ("R2 U2", 2, 4),
+            // Ua perm
+            ("F2 U' L R' F2 L' R U' F2", 9, 12),
+        ];
+        for (scramble, htm, qtm) in cases {
+            let cube = scrambled(scramble);
+            for (metric, length) in [(Metric::Htm, htm), (Metric::Qtm, qtm)] {
+                let solution = solve(&cube.to_facelets(), metric, || false).unwrap();
+                let moves = MoveSequence::parse(&solution).unwrap();
+                assert_eq!(moves.length(metric), length, "{}: {}", scramble, solution);
+                let mut solved = cube;
+                solved.apply_moves(&moves.moves);
+                assert!(solved.is_solved(), "{}: {}", scramble, solution);
+            }
+        }
+        let _ = fs::remove_dir_all(&cache);
+    }
+}
@@ -0,0 +1,269 @@
+//! CFOP with the user's own algorithms: each F2L pair and last-layer step uses an
+//! algorithm marked as known in the library when one fits, so the solution is one
+//! the user can do from memory.
+//!
+//! Algorithms are tried after every turn of U, and F2L algorithms, written for the
+//! front-right slot, from every slot. Steps no known algorithm fits are searched
+//! instead: the cross always, F2L pairs as in the CFOP solver, and the last layer
+//! with the search the caller passes in. Every stage names the algorithm it used.
+
+use super::cfop::{pair_distance, slot_name, solve_next_pair, top_turns};
+use super::cross::{cross_distance, solve_cross};
+use super::last_layer::{adjust_turns, first_two_layers_solved, last_layer_oriented};
+use super::target::relative_to;
+use super::{Solution, from_upside_down, upside_down_cube};
+use crate::algorithm_library::{AlgorithmLibrary, AlgorithmSet, LibraryEntry};
+use crate::cube_model::{Axis, CubieCube, Move, MoveKind};
+use crate::move_sequence::MoveSequence;
+
+/// Sets whose algorithms are tried on the last layer
+const LAST_LAYER_SETS: [AlgorithmSet; 4] = [
+    AlgorithmSet::Oll,
+    AlgorithmSet::Coll,
+    AlgorithmSet::Pll,
+    AlgorithmSet::Other,
+];
+
+/// Solve `cube` with the known algorithms of `library`. `search` solves a
+/// cube, held with the cross on D, where the last layer has no known algorithm.
+pub fn solve(
+    cube: &CubieCube,
+    library: &AlgorithmLibrary,
+    search: &mut dyn FnMut(&CubieCube) -> Result<MoveSequence, String>,
+) -> Result<Solution, String> {
+    let mut cube = upside_down_cube(cube);
+    let mut solution = Solution::default();
+    let mut push_stage =
+        |cube: &mut CubieCube, label: &str, explanation: &str, moves: MoveSequence| {
+            cube.apply_moves(&moves.moves);
+            solution.push_stage(label, explanation, &from_upside_down(&moves));
+        };
+
+    let cross = solve_cross(&cube);
+    push_stage(
+        &mut cube,
+        "Cross",
+        "Solve the four edges around the white center, found by search",
+        cross,
+    );
+
+    let mut placed = Vec::new();
+    let mut remaining = vec![0, 1, 2, 3];
+    for number in 1..=4 {
+        let (slot, moves, explanation) = match known_pair(&cube, library, &placed, &remaining) {
+            Some((slot, moves, entry)) => {
+                let explanation = known_explanation(entry);
+                (slot, moves, explanation)
+            }
+            None => {
+                let (slot, moves) = solve_next_pair(&cube, &placed, &remaining);
+                let explanation = format!(
+                    "No known algorithm fits: pair up the {} corner and edge with moves found by search",
+                    slot_name(slot)
+                );
+                (slot, moves, explanation)
+            }
+        };
+        remaining.retain(|&other| other != slot);
+        placed.push(slot);
+        push_stage(
+            &mut cube,
+            &format!("F2L {} ({})", number, slot_name(slot)),
+            &explanation,
+            moves,
+        );
+    }
+
+    let finished = |cube: &CubieCube| cube.is_solved();
+    let oriented = |cube: &CubieCube| first_two_layers_solved(cube) && last_layer_oriented(cube);
+    if !last_layer_oriented(&cube) && known_step(&cube, library, finished).is_none() {
+        match known_step(&cube, library, oriented) {
+            Some((moves, entry)) => {
+                push_stage(&mut cube, &entry.name, &known_explanation(entry), moves)
+            }
+            None => {
+                // Orient the pieces where they are
+                let mut goal = cube;
+                goal.co[..4].fill(0);
+                goal.eo[..4].fill(0);
+                let moves = search(&relative_to(&cube, &goal))?;
+                push_stage(
+                    &mut cube,
+                    "OLL (search)",
+                    "No known algorithm fits: orient the last layer with moves found by search",
+                    moves,
+                );
+            }
+        }
+    }
+
+    if let Some(turns) = adjust_turns(&cube) {
+        push_stage(
+            &mut cube,
+            "AUF",
+            "Turn the last layer to line it up with the centers",
+            top_turns(turns),
+        );
+    } else {
+        match known_step(&cube, library, finished) {
+            Some((moves, entry)) => {
+                push_stage(&mut cube, &entry.name, &known_explanation(entry), moves)
+            }
+            None => {
+                let moves = search(&cube)?;
+                push_stage(
+                    &mut cube,
+                    "Last layer (search)",
+                    "No known algorithm fits: finish the cube with moves found by search",
+                    moves,
+                );
+            }
+        }
+    }
+
+    if !cube.is_solved() {
+        return Err("Solve with known algorithms did not finish the cube".to_string());
+    }
+    Ok(solution)
+}
+
+/// How a stage cites the algorithm it used
+fn known_explanation(entry: &LibraryEntry) -> String {
+    format!("Known algorithm for {}: {}", entry.name, entry.algorithm)
+}
+
+/// The shortest use of a known F2L algorithm, from any slot after any turn of U, that
+/// solves one of the `remaining` pairs while keeping the cross and the `placed` pairs
+fn known_pair<'a>(
+    cube: &CubieCube,
+    library: &'a AlgorithmLibrary,
+    placed: &[usize],
+    remaining: &[usize],
+) -> Option<(usize, MoveSequence, &'a LibraryEntry)> {
+    let mut best: Option<(usize, MoveSequence, &LibraryEntry)> = None;
+    for entry in library.known(AlgorithmSet::F2l) {
+        let Ok(algorithm) = entry.moves() else {
+            continue;
+        };
+        for turns in 0..4 {
+            // The slot the algorithm works on is turned to the front right
+            let rotated = algorithm.rotate(Move::new(MoveKind::Rotation(Axis::Y), turns));
+            for before in 0..4 {
+                let moves = top_turns(before).then(&rotated).simplify();
+                if best
+                    .as_ref()
+                    .is_some_and(|(_, shortest, _)| shortest.len() <= moves.len())
+                {
+                    continue;
+                }
+                let mut after = *cube;
+                after.apply_moves(&moves.moves);
+                let kept = cross_distance(&after) == 0
+                    && placed.iter().all(|&slot| pair_distance(&after, slot) == 0);
+                let solved = remaining
+                    .iter()
+                    .copied()
+                    .find(|&slot| pair_distance(&after, slot) == 0);
+                if kept && let Some(slot) = solved {
+                    best = Some((slot, moves, entry));
+                }
+            }
+        }
+    }
+    best
+}
+
+/// The shortest use of a known last-layer algorithm, with turns of U before and after
+/// it, that reaches `goal`
+fn known_step<'a>(
+    cube: &CubieCube,
+    library: &'a AlgorithmLibrary,
+    goal: impl Fn(&CubieCube) -> bool,
+) -> Option<(MoveSequence, &'a LibraryEntry)> {
+    let mut best: Option<(MoveSequence, &LibraryEntry)> = None;
+    let entries = LAST_LAYER_SETS.iter().flat_map(|&set| library.known(set));
+    for entry in entries {
+        let Ok(algorithm) = entry.moves() else {
+            continue;
+        };
+        for before in 0..4 {
+            for after in 0..4 {
+                let moves = top_turns(before)
+                    .then(&algorithm)
+                    .then(&top_turns(after))
+                    .simplify();
+                if best
+                    .as_ref()
+                    .is_some_and(|(shortest, _)| shortest.len() <= moves.len())
+                {
+                    continue;
+                }
+                let mut result = *cube;
+                result.apply_moves(&moves.moves);
+                if goal(&result) {
+                    best = Some((moves, entry));
+                }
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers::last_layer::algorithm_moves;
+    use crate::solvers::thistlethwaite;
+
+    fn search(cube: &CubieCube) -> Result<MoveSequence, String> {
+        thistlethwaite::solve(cube).map(|solution| solution.moves)
+    }
+
+    fn solves(cube: &CubieCube, solution: &Solution) -> bool {
+        let mut cube = *cube;
+        cube.apply_moves(&solution.moves.moves);
+        let staged: usize = solution.stages.iter().map(|stage| stage.move_count).sum();
+        cube.is_solved() && staged == solution.move_count()
+    }
+
+    #[test]
+    fn known_last_layer_algorithm_is_used() {
+        let mut library = AlgorithmLibrary::built_in();
+        let sune = "R U R' U R U2 R'";
+        library.add(AlgorithmSet::Oll, "Sune", sune).unwrap();
+
+        // The Sune case, with the last layer on D as the app holds the cube
+        let mut case = CubieCube::SOLVED;
+        case.apply_moves(&algorithm_moves(sune).inverse().moves);
+        let cube = upside_down_cube(&case);
+
+        let solution = solve(&cube, &library, &mut search).unwrap();
+        assert!(solves(&cube, &solution));
+        let labels: Vec<&str> = solution
+            .stages
+            .iter()
+            .map(|stage| stage.label.as_str())
+            .collect();
+        assert_eq!(labels, ["OLL 27 (Sune)"]);
+    }
+
+    #[test]
+    fn unknown_steps_are_searched() {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(
+            &MoveSequence::parse("L2 D' B R2 U F' D2 R B2 U' L F")
+                .unwrap()
+                .moves,
+        );
+        let solution = solve(&cube, &AlgorithmLibrary::built_in(), &mut search).unwrap();
+        assert!(solves(&cube, &solution));
+        assert_eq!(solution.stages[0].label, "Cross");
+        // Nothing is known, so no stage can cite an algorithm
+        assert!(
+            solution
+                .stages
+                .iter()
+                .all(|stage| !stage.explanation.starts_with("Known algorithm"))
+        );
+    }
+}
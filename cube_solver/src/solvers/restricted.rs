@@ -0,0 +1,1210 @@
+//! Solving with a restricted set of moves, such as `<R,U>` or `<R,U,F,L,D>`.
+//!
+//! Whether the cube can be solved with the set at all is decided exactly with the
+//! Schreier-Sims algorithm on the 54 stickers. A first solution places the pieces one
+//! at a time and leaves the last few to commutators found for the set, the way a
+//! blindfolded solver would; a set of face turns that generates every state, such as
+//! `<R,U,F,L,D>`, gets it phase by phase instead, with Thistlethwaite's groups cut down
+//! to the set. Stretches of it are then swapped for shorter sequences, and the rest of
+//! the time goes into an IDA* search for shorter solutions. Slice and wide turns carry
+//! the centers along, so the cube is followed in fixed space and counts as solved in
+//! whichever orientation the set can bring it to.
+
+use super::coordinates::{
+    GROUP_SIZE, MOVE_COUNT, Piece, PieceDistances, STOP_CHECK_INTERVAL, face_turn,
+};
+use super::move_set::MoveSet;
+use super::thistlethwaite;
+use crate::cube_model::{
+    Axis, CENTER_FACELETS, CORNER_FACELETS, CubieCube, EDGE_FACELETS, FACELET_COUNT, Move, MoveKind,
+};
+use crate::move_sequence::MoveSequence;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Stretches of the first solution searched for shorter replacements
+const SHORTEN_FROM: usize = 4;
+const SHORTEN_TO: usize = 10;
+/// Longest step tried while building the first solution
+const MAX_STEP_DEPTH: usize = 24;
+/// Pieces left to the commutators at the end of the first solution
+const FINISH_PIECES: usize = 8;
+/// Pieces left to them instead when placing the next one would take a long step
+const EARLY_FINISH_PIECES: usize = 12;
+const LONG_STEP: usize = 8;
+/// Words enumerated for the first half of the commutators, and their longest length
+const TOOL_WORDS: usize = 20000;
+const TOOL_LENGTH: usize = 8;
+/// Longest setup moved around a commutator
+const SETUP_LENGTH: usize = 2;
+
+/// Where every sticker of the solved cube is now: `stickers[home] = facelet`
+type Stickers = [u8; FACELET_COUNT];
+
+fn identity() -> Stickers {
+    std::array::from_fn(|facelet| facelet as u8)
+}
+
+/// `first` followed by `second`
+fn then(first: &Stickers, second: &Stickers) -> Stickers {
+    first.map(|facelet| second[facelet as usize])
+}
+
+fn inverse(stickers: &Stickers) -> Stickers {
+    let mut result = [0; FACELET_COUNT];
+    for (home, &facelet) in stickers.iter().enumerate() {
+        result[facelet as usize] = home as u8;
+    }
+    result
+}
+
+fn move_stickers(mv: &Move) -> Stickers {
+    mv.facelet_permutation().map(|facelet| facelet as u8)
+}
+
+/// Stickers of a cubie state, whose centers are in place
+fn cube_stickers(cube: &CubieCube) -> Stickers {
+    let mut stickers = identity();
+    for position in 0..8 {
+        let piece = cube.cp[position] as usize;
+        for n in 0..3 {
+            stickers[CORNER_FACELETS[piece][n]] =
+                CORNER_FACELETS[position][(n + cube.co[position] as usize) % 3] as u8;
+        }
+    }
+    for position in 0..12 {
+        let piece = cube.ep[position] as usize;
+        for n in 0..2 {
+            stickers[EDGE_FACELETS[piece][n]] =
+                EDGE_FACELETS[position][(n + cube.eo[position] as usize) % 2] as u8;
+        }
+    }
+    stickers
+}
+
+/// The 24 orientations of the whole cube
+fn rotations() -> &'static [Stickers] {
+    static ROTATIONS: OnceLock<Vec<Stickers>> = OnceLock::new();
+    ROTATIONS.get_or_init(|| {
+        let turns =
+            [Axis::X, Axis::Y].map(|axis| move_stickers(&Move::new(MoveKind::Rotation(axis), 1)));
+        let mut rotations = vec![identity()];
+        let mut next = 0;
+        while next < rotations.len() {
+            for turn in &turns {
+                let rotated = then(&rotations[next], turn);
+                if !rotations.contains(&rotated) {
+                    rotations.push(rotated);
+                }
+            }
+            next += 1;
+        }
+        rotations
+    })
+}
+
+/// One level of a stabilizer chain: the permutations that fix the bases of the levels
+/// before it, and where they can take this level's base
+struct Level {
+    base: usize,
+    generators: Vec<Stickers>,
+    /// `transversal[point]` takes the base to `point`, for every point of its orbit
+    transversal: Vec<Option<Stickers>>,
+}
+
+impl Level {
+    fn new(base: usize) -> Self {
+        let mut transversal = vec![None; FACELET_COUNT];
+        transversal[base] = Some(identity());
+        Self {
+            base,
+            generators: Vec::new(),
+            transversal,
+        }
+    }
+
+    /// Extend the orbit by what the generators reach from it
+    fn extend_orbit(&mut self) {
+        let mut queue: VecDeque<usize> = (0..FACELET_COUNT)
+            .filter(|&point| self.transversal[point].is_some())
+            .collect();
+        while let Some(point) = queue.pop_front() {
+            let reached = self.transversal[point].expect("orbit points have a transversal");
+            for generator in &self.generators {
+                let image = generator[point] as usize;
+                if self.transversal[image].is_none() {
+                    self.transversal[image] = Some(then(&reached, generator));
+                    queue.push_back(image);
+                }
+            }
+        }
+    }
+}
+
+/// The group a move set generates, as a stabilizer chain (Schreier-Sims)
+struct StabilizerChain {
+    levels: Vec<Level>,
+}
+
+impl StabilizerChain {
+    fn new(generators: &[Stickers]) -> Self {
+        let mut chain = Self { levels: Vec::new() };
+        for generator in generators {
+            let residue = chain.sift(0, *generator);
+            if residue != identity() {
+                chain.add(0, residue);
+            }
+        }
+        chain
+    }
+
+    /// Strip `permutation` level by level from `depth` on; what is left is the identity
+    /// exactly when it belongs to the group of that level
+    fn sift(&self, depth: usize, mut permutation: Stickers) -> Stickers {
+        for level in &self.levels[depth..] {
+            let image = permutation[level.base] as usize;
+            match &level.transversal[image] {
+                Some(reached) => permutation = then(&permutation, &inverse(reached)),
+                None => break,
+            }
+        }
+        permutation
+    }
+
+    fn add(&mut self, depth: usize, generator: Stickers) {
+        if depth == self.levels.len() {
+            let base = (0..FACELET_COUNT)
+                .find(|&point| generator[point] as usize != point)
+                .expect("only non-identity permutations are added");
+            self.levels.push(Level::new(base));
+        }
+        let level = &mut self.levels[depth];
+        level.generators.push(generator);
+        level.extend_orbit();
+
+        // Schreier generators fix this level's base, so they belong one level down
+        let generators = level.generators.clone();
+        let transversal = level.transversal.clone();
+        for (point, reached) in transversal.iter().enumerate() {
+            let Some(reached) = reached else {
+                continue;
+            };
+            for generator in &generators {
+                let image = generator[point] as usize;
+                let back = transversal[image].expect("the orbit is closed");
+                let schreier = then(&then(reached, generator), &inverse(&back));
+                let residue = self.sift(depth + 1, schreier);
+                if residue != identity() {
+                    self.add(depth + 1, residue);
+                }
+            }
+        }
+    }
+
+    fn contains(&self, permutation: &Stickers) -> bool {
+        self.sift(0, *permutation) == identity()
+    }
+
+    /// Number of states of the group
+    fn order(&self) -> u128 {
+        self.levels
+            .iter()
+            .map(|level| level.transversal.iter().flatten().count() as u128)
+            .product()
+    }
+}
+
+/// Corner or edge `index`, edges first as in the distance tables
+fn piece(index: usize) -> Piece {
+    if index < 12 {
+        Piece::Edge(index)
+    } else {
+        Piece::Corner(index - 12)
+    }
+}
+
+/// Cube state in fixed space: every corner and edge, and the arrangement of the centers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct State {
+    corners: [u8; 8],
+    edges: [u8; 12],
+    centers: u8,
+}
+
+impl State {
+    fn distance(&self, table: &PieceDistances) -> usize {
+        table.distance(&self.corners, &self.edges) as usize
+    }
+
+    fn apply(&self, effect: &Effect) -> State {
+        State {
+            corners: self.corners.map(|s| effect.corners[s as usize]),
+            edges: self.edges.map(|s| effect.edges[s as usize]),
+            centers: self.centers,
+        }
+    }
+
+    /// Whether the corners and the edges are each an even permutation away from `goal`
+    fn even_to(&self, goal: &State) -> bool {
+        fn even(states: &[u8], goal: &[u8], twists: u8) -> bool {
+            let mut to = [0; 12];
+            for (&state, &home) in states.iter().zip(goal) {
+                to[(home / twists) as usize] = (state / twists) as usize;
+            }
+            let mut seen = [false; 12];
+            // A cycle of n positions takes n - 1 swaps
+            let mut cycles = 0;
+            for start in 0..states.len() {
+                if seen[start] {
+                    continue;
+                }
+                cycles += 1;
+                let mut position = start;
+                while !seen[position] {
+                    seen[position] = true;
+                    position = to[position];
+                }
+            }
+            (states.len() - cycles).is_multiple_of(2)
+        }
+        even(&self.corners, &goal.corners, 3) && even(&self.edges, &goal.edges, 2)
+    }
+
+    /// Positions that do not hold the piece `goal` has there, as in [`Effect::support`]
+    fn wrong_positions(&self, goal: &State) -> u32 {
+        let corners = self
+            .corners
+            .iter()
+            .zip(&goal.corners)
+            .filter(|(state, home)| state != home)
+            .map(|(&state, _)| state as u32 / 3);
+        let edges = self
+            .edges
+            .iter()
+            .zip(&goal.edges)
+            .filter(|(state, home)| state != home)
+            .map(|(&state, _)| 8 + state as u32 / 2);
+        corners.chain(edges).fold(0, |mask, bit| mask | 1 << bit)
+    }
+
+    /// Pieces not where `goal` has them
+    fn misplaced(&self, goal: &State) -> usize {
+        let corners = self.corners.iter().zip(&goal.corners);
+        let edges = self.edges.iter().zip(&goal.edges);
+        corners
+            .chain(edges)
+            .filter(|(state, home)| state != home)
+            .count()
+    }
+}
+
+/// What a move sequence does to every corner and edge state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Effect {
+    corners: [u8; 24],
+    edges: [u8; 24],
+}
+
+impl Effect {
+    fn identity() -> Self {
+        let states = std::array::from_fn(|state| state as u8);
+        Self {
+            corners: states,
+            edges: states,
+        }
+    }
+
+    /// `self` followed by `other`
+    fn then(&self, other: &Effect) -> Self {
+        Self {
+            corners: self.corners.map(|s| other.corners[s as usize]),
+            edges: self.edges.map(|s| other.edges[s as usize]),
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        let mut result = *self;
+        for state in 0..24 {
+            result.corners[self.corners[state] as usize] = state as u8;
+            result.edges[self.edges[state] as usize] = state as u8;
+        }
+        result
+    }
+
+    /// Positions whose pieces it takes elsewhere, as in [`Effect::support`]
+    fn moved(&self) -> u32 {
+        let corners =
+            (0..8).filter(|&position| self.corners[position * 3] as usize / 3 != position);
+        let edges = (0..12).filter(|&position| self.edges[position * 2] as usize / 2 != position);
+        corners
+            .chain(edges.map(|position| position + 8))
+            .fold(0, |mask, bit| mask | 1 << bit)
+    }
+
+    /// Positions whose pieces it moves or turns: corners in the low bits, then edges
+    fn support(&self) -> u32 {
+        let corners = (0..8).filter(|&position| {
+            (0..3).any(|twist| self.corners[position * 3 + twist] as usize != position * 3 + twist)
+        });
+        let edges = (0..12).filter(|&position| {
+            (0..2).any(|flip| self.edges[position * 2 + flip] as usize != position * 2 + flip)
+        });
+        corners
+            .chain(edges.map(|position| position + 8))
+            .fold(0, |mask, bit| mask | 1 << bit)
+    }
+}
+
+/// A short sequence that moves only a few pieces and leaves the centers: a commutator,
+/// possibly with setup moves around it
+struct Macro {
+    moves: Vec<usize>,
+    effect: Effect,
+    support: u32,
+}
+
+/// The macros of a move set, and the positions few of them move a piece away from,
+/// whose pieces are better placed before the macros take over
+struct Tools {
+    macros: Vec<Macro>,
+    scarce: u32,
+}
+
+/// A move set with everything that does not depend on the goal orientation
+struct Subgroup {
+    moves: Vec<Move>,
+    chain: StabilizerChain,
+    /// Arrangements of the centers the set reaches, as the facelets of the six centers
+    center_arrangements: Vec<[u8; 6]>,
+    /// Per move, the new state of a corner, an edge and the centers
+    corner_transitions: Vec<[u8; 24]>,
+    edge_transitions: Vec<[u8; 24]>,
+    center_transitions: Vec<Vec<u8>>,
+    /// Distance tables for each goal orientation used so far
+    goals: Mutex<HashMap<usize, Arc<Distances>>>,
+    tools: OnceLock<Tools>,
+    /// The moves as face turns numbered as in `coordinates`, when they are all face
+    /// turns and keep enough of every Thistlethwaite group to solve phase by phase
+    phase_moves: Option<Vec<usize>>,
+}
+
+impl Subgroup {
+    fn new(set: &MoveSet) -> Self {
+        let moves = set.moves();
+        let move_stickers: Vec<Stickers> = moves.iter().map(move_stickers).collect();
+        let chain = StabilizerChain::new(&move_stickers);
+
+        // Facelet of every corner and edge state
+        let corner_facelets: Vec<u8> = (0..24)
+            .map(|state| CORNER_FACELETS[state / 3][state % 3] as u8)
+            .collect();
+        let edge_facelets: Vec<u8> = (0..24)
+            .map(|state| EDGE_FACELETS[state / 2][state % 2] as u8)
+            .collect();
+        let state_of = |facelets: &[u8], facelet: u8| {
+            facelets
+                .iter()
+                .position(|&other| other == facelet)
+                .expect("pieces stay pieces") as u8
+        };
+        let corner_transitions = move_stickers
+            .iter()
+            .map(|stickers| {
+                std::array::from_fn(|state| {
+                    state_of(&corner_facelets, stickers[corner_facelets[state] as usize])
+                })
+            })
+            .collect();
+        let edge_transitions = move_stickers
+            .iter()
+            .map(|stickers| {
+                std::array::from_fn(|state| {
+                    state_of(&edge_facelets, stickers[edge_facelets[state] as usize])
+                })
+            })
+            .collect();
+
+        let mut center_arrangements = vec![CENTER_FACELETS.map(|facelet| facelet as u8)];
+        let mut center_transitions = vec![Vec::new(); moves.len()];
+        let mut next = 0;
+        while next < center_arrangements.len() {
+            let arrangement = center_arrangements[next];
+            for (stickers, transitions) in move_stickers.iter().zip(&mut center_transitions) {
+                let moved = arrangement.map(|facelet| stickers[facelet as usize]);
+                let index = match center_arrangements.iter().position(|&a| a == moved) {
+                    Some(index) => index,
+                    None => {
+                        center_arrangements.push(moved);
+                        center_arrangements.len() - 1
+                    }
+                };
+                transitions.push(index as u8);
+            }
+            next += 1;
+        }
+
+        let phase_moves = phase_moves(&moves);
+        Self {
+            moves,
+            chain,
+            phase_moves,
+            center_arrangements,
+            corner_transitions,
+            edge_transitions,
+            center_transitions,
+            goals: Mutex::new(HashMap::new()),
+            tools: OnceLock::new(),
+        }
+    }
+
+    fn state(&self, stickers: &Stickers) -> State {
+        let corner = |piece: usize| {
+            let facelet = stickers[CORNER_FACELETS[piece][0]] as usize;
+            let position = (0..8)
+                .find(|&position| CORNER_FACELETS[position].contains(&facelet))
+                .expect("corners stay corners");
+            let twist = CORNER_FACELETS[position]
+                .iter()
+                .position(|&other| other == facelet)
+                .unwrap_or_default();
+            (position * 3 + twist) as u8
+        };
+        let edge = |piece: usize| {
+            let facelet = stickers[EDGE_FACELETS[piece][0]] as usize;
+            let position = (0..12)
+                .find(|&position| EDGE_FACELETS[position].contains(&facelet))
+                .expect("edges stay edges");
+            let flip = EDGE_FACELETS[position]
+                .iter()
+                .position(|&other| other == facelet)
+                .unwrap_or_default();
+            (position * 2 + flip) as u8
+        };
+        let centers = CENTER_FACELETS.map(|facelet| stickers[facelet]);
+        State {
+            corners: std::array::from_fn(corner),
+            edges: std::array::from_fn(edge),
+            centers: self
+                .center_arrangements
+                .iter()
+                .position(|&arrangement| arrangement == centers)
+                .unwrap_or(u8::MAX as usize) as u8,
+        }
+    }
+
+    fn apply(&self, state: &State, m: usize) -> State {
+        State {
+            corners: state
+                .corners
+                .map(|s| self.corner_transitions[m][s as usize]),
+            edges: state.edges.map(|s| self.edge_transitions[m][s as usize]),
+            centers: self.center_transitions[m][state.centers as usize],
+        }
+    }
+
+    /// An orientation of the solved cube the set can take `cube` to
+    fn reachable_goal(&self, cube: &CubieCube) -> Option<usize> {
+        let undo = inverse(&cube_stickers(cube));
+        rotations()
+            .iter()
+            .position(|rotation| self.chain.contains(&then(&undo, rotation)))
+    }
+
+    fn effect(&self, moves: &[usize]) -> Effect {
+        let mut effect = Effect::identity();
+        for &m in moves {
+            effect.corners = effect
+                .corners
+                .map(|s| self.corner_transitions[m][s as usize]);
+            effect.edges = effect.edges.map(|s| self.edge_transitions[m][s as usize]);
+        }
+        effect
+    }
+
+    /// `moves` with neighbouring turns of one layer merged
+    fn simplify(&self, moves: impl IntoIterator<Item = usize>) -> Vec<usize> {
+        let sequence = MoveSequence::new(moves.into_iter().map(|m| self.moves[m]).collect());
+        sequence
+            .simplify()
+            .moves
+            .iter()
+            .map(|mv| {
+                self.moves
+                    .iter()
+                    .position(|other| other == mv)
+                    .expect("merged turns of a layer stay in the set")
+            })
+            .collect()
+    }
+
+    fn invert(&self, moves: &[usize]) -> Vec<usize> {
+        self.simplify(moves.iter().rev().map(|&m| {
+            let inverse = self.moves[m].inverse();
+            self.moves
+                .iter()
+                .position(|other| *other == inverse)
+                .expect("the set has the inverse of each of its moves")
+        }))
+    }
+
+    /// Sequences of one to `limit` words' worth of moves, in canonical order, shortest first
+    fn words(&self, limit: usize) -> Vec<Vec<usize>> {
+        let mut words = vec![Vec::new()];
+        let mut next = 0;
+        while next < words.len() && words.len() < limit && words[next].len() < TOOL_LENGTH {
+            let word: Vec<usize> = words[next].clone();
+            for m in 0..self.moves.len() {
+                if word
+                    .last()
+                    .is_none_or(|&last| may_follow(&self.moves[last], &self.moves[m]))
+                {
+                    let mut longer = word.clone();
+                    longer.push(m);
+                    words.push(longer);
+                }
+            }
+            next += 1;
+        }
+        words
+    }
+
+    /// Commutators that move at most three pieces, each with the setups around it that
+    /// give new effects, shortest first
+    fn tools(&self) -> &Tools {
+        self.tools.get_or_init(|| {
+            let words = self.words(TOOL_WORDS);
+            let effects: Vec<(Effect, Effect)> = words
+                .iter()
+                .map(|word| {
+                    let effect = self.effect(word);
+                    (effect, effect.inverse())
+                })
+                .collect();
+            let fixes_centers = |moves: &[usize]| {
+                moves.iter().fold(0, |centers, &m| {
+                    self.center_transitions[m][centers] as usize
+                }) == 0
+            };
+            let mut tools: HashMap<Effect, Vec<usize>> = HashMap::new();
+            let seconds = words
+                .iter()
+                .take_while(|word| word.len() <= SETUP_LENGTH)
+                .count();
+            for (first, (effect, undo)) in words.iter().zip(&effects).skip(1) {
+                for (second, (second_effect, second_undo)) in
+                    words[1..seconds].iter().zip(&effects[1..seconds])
+                {
+                    let commutator = effect.then(second_effect).then(undo).then(second_undo);
+                    if !(1..=3).contains(&commutator.support().count_ones()) {
+                        continue;
+                    }
+                    let length = 2 * (first.len() + second.len());
+                    if tools
+                        .get(&commutator)
+                        .is_some_and(|known| known.len() <= length)
+                    {
+                        continue;
+                    }
+                    let moves = self.simplify(
+                        first
+                            .iter()
+                            .chain(second)
+                            .chain(&self.invert(first))
+                            .chain(&self.invert(second))
+                            .copied(),
+                    );
+                    if fixes_centers(&moves) {
+                        tools.insert(commutator, moves);
+                    }
+                }
+            }
+
+            let setups: Vec<(&Vec<usize>, Effect)> = words
+                .iter()
+                .take_while(|word| word.len() <= SETUP_LENGTH)
+                .map(|word| (word, self.effect(word)))
+                .collect();
+            let mut macros: HashMap<Effect, Vec<usize>> = HashMap::new();
+            for (effect, tool) in &tools {
+                for (setup, setup_effect) in &setups {
+                    let conjugate = setup_effect.inverse().then(effect).then(setup_effect);
+                    let length = tool.len() + 2 * setup.len();
+                    if macros
+                        .get(&conjugate)
+                        .is_some_and(|known| known.len() <= length)
+                    {
+                        continue;
+                    }
+                    let moves =
+                        self.simplify(self.invert(setup).iter().chain(tool).chain(*setup).copied());
+                    macros.insert(conjugate, moves);
+                }
+            }
+            let mut macros: Vec<Macro> = macros
+                .into_iter()
+                .map(|(effect, moves)| Macro {
+                    moves,
+                    support: effect.support(),
+                    effect,
+                })
+                .collect();
+            macros.sort_by_key(|tool| tool.moves.len());
+
+            let movers: Vec<usize> = (0..20)
+                .map(|bit| {
+                    macros
+                        .iter()
+                        .filter(|tool| tool.effect.moved() & 1 << bit != 0)
+                        .count()
+                })
+                .collect();
+            let most = movers.iter().copied().max().unwrap_or(0);
+            let scarce = (0..20)
+                .filter(|&bit| movers[bit] * 4 < most || most == 0)
+                .fold(0, |mask, bit| mask | 1 << bit);
+            Tools { macros, scarce }
+        })
+    }
+
+    /// Exact move counts within the set for `pieces` on their own, towards where
+    /// `goal` has them
+    fn table(&self, pieces: Vec<Piece>, goal: &State) -> PieceDistances {
+        PieceDistances::new(
+            pieces,
+            self.moves.len(),
+            |piece, m| match piece {
+                Piece::Corner(_) => &self.corner_transitions[m],
+                Piece::Edge(_) => &self.edge_transitions[m],
+            },
+            |piece, state| piece.state(&goal.corners, &goal.edges) == state,
+        )
+    }
+
+    fn distances(&self, goal: usize) -> Arc<Distances> {
+        let mut goals = self
+            .goals
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        goals
+            .entry(goal)
+            .or_insert_with(|| Arc::new(Distances::new(self, &self.state(&rotations()[goal]))))
+            .clone()
+    }
+}
+
+/// Distance tables towards one goal orientation: every corner and edge in groups and
+/// on its own, and the centers
+struct Distances {
+    goal: State,
+    groups: Vec<PieceDistances>,
+    /// The pieces of `groups` one by one, in the same order
+    pieces: Vec<PieceDistances>,
+    centers: Vec<u8>,
+}
+
+impl Distances {
+    fn new(subgroup: &Subgroup, goal: &State) -> Self {
+        let corners = (0..8).map(Piece::Corner).collect::<Vec<_>>();
+        let edges = (0..12).map(Piece::Edge).collect::<Vec<_>>();
+        let groups = edges
+            .chunks(GROUP_SIZE)
+            .chain(corners.chunks(GROUP_SIZE))
+            .map(|pieces| subgroup.table(pieces.to_vec(), goal))
+            .collect();
+        let pieces = edges
+            .iter()
+            .chain(&corners)
+            .map(|&piece| subgroup.table(vec![piece], goal))
+            .collect();
+
+        let mut centers = vec![u8::MAX; subgroup.center_arrangements.len()];
+        centers[goal.centers as usize] = 0;
+        let mut queue = VecDeque::from([goal.centers as usize]);
+        // Center moves are reversible, so distances from the goal are distances to it
+        while let Some(index) = queue.pop_front() {
+            for transitions in &subgroup.center_transitions {
+                let next = transitions[index] as usize;
+                if centers[next] == u8::MAX {
+                    centers[next] = centers[index] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        Self {
+            goal: *goal,
+            groups,
+            pieces,
+            centers,
+        }
+    }
+
+    fn centers_distance(&self, state: &State) -> usize {
+        self.centers[state.centers as usize] as usize
+    }
+
+    fn lower_bound(&self, state: &State) -> usize {
+        self.groups
+            .iter()
+            .map(|group| state.distance(group))
+            .fold(self.centers_distance(state), usize::max)
+    }
+}
+
+/// `moves` as face turns, if each Thistlethwaite group is generated by the ones it holds
+fn phase_moves(moves: &[Move]) -> Option<Vec<usize>> {
+    let turns: Vec<usize> = moves
+        .iter()
+        .map(|mv| (0..MOVE_COUNT).find(|&m| face_turn(m) == *mv))
+        .collect::<Option<_>>()?;
+    let order = |group: &[usize]| {
+        let generators: Vec<Stickers> = group
+            .iter()
+            .map(|&m| move_stickers(&face_turn(m)))
+            .collect();
+        StabilizerChain::new(&generators).order()
+    };
+    thistlethwaite::groups()
+        .iter()
+        .all(|group| {
+            let kept: Vec<usize> = group
+                .iter()
+                .copied()
+                .filter(|m| turns.contains(m))
+                .collect();
+            order(&kept) == order(group)
+        })
+        .then_some(turns)
+}
+
+fn subgroup(set: &MoveSet) -> Arc<Subgroup> {
+    static SUBGROUPS: OnceLock<Mutex<HashMap<MoveSet, Arc<Subgroup>>>> = OnceLock::new();
+    let mut subgroups = SUBGROUPS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    subgroups
+        .entry(set.clone())
+        .or_insert_with(|| Arc::new(Subgroup::new(set)))
+        .clone()
+}
+
+/// Number of states the moves of `set` reach from the solved cube, centers included
+pub fn group_order(set: &MoveSet) -> u128 {
+    subgroup(set).chain.order()
+}
+
+/// Whether the moves of `set` can solve `cube`
+pub fn is_reachable(cube: &CubieCube, set: &MoveSet) -> bool {
+    subgroup(set).reachable_goal(cube).is_some()
+}
+
+/// Solve `cube` with the moves of `set` only. A first solution is built phase by phase
+/// when the set allows it, or else a group of pieces at a time; after that shorter ones
+/// are searched for until `should_stop` returns true or the shortest is proven, and
+/// `on_improved` hears the length of each.
+pub fn solve(
+    cube: &CubieCube,
+    set: &MoveSet,
+    should_stop: &dyn Fn() -> bool,
+    on_improved: &mut dyn FnMut(usize),
+) -> Result<MoveSequence, String> {
+    let subgroup = subgroup(set);
+    let goal = subgroup
+        .reachable_goal(cube)
+        .ok_or_else(|| format!("This state cannot be solved with {}", set))?;
+    let distances = subgroup.distances(goal);
+    let start = subgroup.state(&cube_stickers(cube));
+    let mut search = Search {
+        subgroup: &subgroup,
+        distances: &distances,
+        moves: Vec::new(),
+        nodes: 0,
+        should_stop,
+    };
+
+    let mut best = match &subgroup.phase_moves {
+        Some(turns) => {
+            let solution = thistlethwaite::solve_within(cube, turns)?;
+            subgroup.simplify(solution.moves.moves.iter().map(|mv| {
+                subgroup
+                    .moves
+                    .iter()
+                    .position(|other| other == mv)
+                    .expect("every phase move is a move of the set")
+            }))
+        }
+        None => search.place_pieces(&start)?,
+    };
+    on_improved(best.len());
+    if !search.shorten(&mut best, on_improved) {
+        return Ok(MoveSequence::new(
+            best.into_iter().map(|m| subgroup.moves[m]).collect(),
+        ));
+    }
+    // IDA* over the whole cube, each depth a move shorter than the best so far
+    let mut depth = distances.lower_bound(&start);
+    while depth < best.len() {
+        match search.search(&start, depth, &|state| distances.lower_bound(state)) {
+            Some(true) => {
+                best = std::mem::take(&mut search.moves);
+                on_improved(best.len());
+            }
+            Some(false) => depth += 1,
+            None => break,
+        }
+    }
+    Ok(MoveSequence::new(
+        best.into_iter().map(|m| subgroup.moves[m]).collect(),
+    ))
+}
+
+struct Search<'a> {
+    subgroup: &'a Subgroup,
+    distances: &'a Distances,
+    moves: Vec<usize>,
+    nodes: usize,
+    should_stop: &'a dyn Fn() -> bool,
+}
+
+impl<'a> Search<'a> {
+    /// A first solution: the nearest piece is placed while keeping the pieces placed
+    /// before it, then the next. Placed pieces are kept with a table per group of them.
+    /// The last few pieces are left to commutators once the centers are home and no
+    /// odd permutation is left; should that fail, placing goes on one piece at a time.
+    fn place_pieces(&mut self, start: &State) -> Result<Vec<usize>, String> {
+        let Distances { goal, pieces, .. } = self.distances;
+        let mut state = *start;
+        let mut solution = Vec::new();
+        let mut placed: Vec<usize> = Vec::new();
+        let mut kept: Vec<PieceDistances> = Vec::new();
+        let mut finishing = true;
+        let mut finish_now = false;
+        let scarce_positions = self.subgroup.tools().scarce;
+        // A face the set turns by quarter turns is left for last: a turn of it makes
+        // the permutations even for the commutators without moving placed pieces
+        let last_layer = (0..self.subgroup.moves.len())
+            .find(|&m| {
+                let mv = self.subgroup.moves[m];
+                matches!(mv.kind, MoveKind::Face(_)) && mv.turns == 1
+            })
+            .map_or(0, |m| self.subgroup.effect(&[m]).moved());
+        let home = |index: usize| match piece(index) {
+            Piece::Corner(corner) => 1 << (goal.corners[corner] / 3),
+            Piece::Edge(edge) => 1 << (8 + goal.edges[edge] / 2),
+        };
+        loop {
+            // Pieces of the last layer that happen to be in place are not kept while the
+            // layer may still be turned, unless the macros hardly move them
+            let keeps = |index: usize| {
+                !finishing || scarce_positions & home(index) != 0 || last_layer & home(index) == 0
+            };
+            for (piece, table) in pieces.iter().enumerate() {
+                if state.distance(table) == 0 && !placed.contains(&piece) && keeps(piece) {
+                    placed.push(piece);
+                }
+            }
+            while placed.len() >= (kept.len() + 1) * GROUP_SIZE {
+                let group = placed[kept.len() * GROUP_SIZE..][..GROUP_SIZE]
+                    .iter()
+                    .map(|&index| piece(index))
+                    .collect();
+                kept.push(self.subgroup.table(group, goal));
+            }
+            let loose = &placed[kept.len() * GROUP_SIZE..];
+            let unplaced: Vec<usize> = (0..pieces.len())
+                .filter(|index| !placed.contains(index))
+                .collect();
+            let scarce: Vec<usize> = unplaced
+                .iter()
+                .copied()
+                .filter(|&index| scarce_positions & home(index) != 0)
+                .collect();
+            let keep = |state: &State| {
+                kept.iter()
+                    .map(|group| state.distance(group))
+                    .chain(loose.iter().map(|&index| state.distance(&pieces[index])))
+                    .max()
+                    .unwrap_or(0)
+            };
+
+            if finishing && scarce.is_empty() && (unplaced.len() <= FINISH_PIECES || finish_now) {
+                finishing = false;
+                let prepare = |state: &State| {
+                    keep(state)
+                        .max(self.distances.centers_distance(state))
+                        .max(usize::from(!state.even_to(goal)))
+                };
+                let mut prepared = state;
+                let mut moves = self
+                    .step(&state, &prepare, MAX_STEP_DEPTH)?
+                    .ok_or_else(|| "No solution found within the move set".to_string())?;
+                for &m in &moves {
+                    prepared = self.subgroup.apply(&prepared, m);
+                }
+                if let Some(rest) = self.finish(&prepared) {
+                    moves.extend(rest);
+                    solution.extend(moves);
+                    return Ok(self.subgroup.simplify(solution));
+                }
+                if (self.should_stop)() {
+                    return Err("Stopped before a solution was found".to_string());
+                }
+                continue;
+            }
+
+            // The last few pieces are placed together, with a table of their own
+            let last = (unplaced.len() <= GROUP_SIZE && !unplaced.is_empty()).then(|| {
+                let group = unplaced.iter().map(|&index| piece(index)).collect();
+                self.subgroup.table(group, goal)
+            });
+            let targets: Vec<usize> = if scarce.is_empty() {
+                let rest: Vec<usize> = unplaced
+                    .iter()
+                    .copied()
+                    .filter(|&index| last_layer & home(index) == 0)
+                    .collect();
+                if rest.is_empty() {
+                    unplaced.clone()
+                } else {
+                    rest
+                }
+            } else {
+                scarce.clone()
+            };
+            let bound = |state: &State| {
+                let next = match &last {
+                    Some(last) => state.distance(last),
+                    None => targets
+                        .iter()
+                        .map(|&index| state.distance(&pieces[index]))
+                        .min()
+                        .unwrap_or_else(|| self.distances.centers_distance(state)),
+                };
+                keep(state).max(next)
+            };
+            if unplaced.is_empty() && bound(&state) == 0 {
+                return Ok(solution);
+            }
+            // Once few pieces are left, a long step is better left to the macros
+            let early = finishing && scarce.is_empty() && unplaced.len() <= EARLY_FINISH_PIECES;
+            let longest = if early { LONG_STEP } else { MAX_STEP_DEPTH };
+            let Some(found) = self.step(&state, &bound, longest)? else {
+                if early {
+                    finish_now = true;
+                    continue;
+                }
+                return Err("No solution found within the move set".to_string());
+            };
+            for &m in &found {
+                state = self.subgroup.apply(&state, m);
+            }
+            solution.extend(found);
+        }
+    }
+
+    /// Replace stretches of `moves` with shorter sequences that do the same, from short
+    /// stretches to long ones. False when stopped.
+    fn shorten(&mut self, moves: &mut Vec<usize>, on_improved: &mut dyn FnMut(usize)) -> bool {
+        {
+            for width in SHORTEN_FROM..=SHORTEN_TO {
+                let mut start = 0;
+                while start + width <= moves.len() {
+                    // Solving the goal with the stretch undone gives sequences that do
+                    // what the stretch does
+                    let mut state = self.distances.goal;
+                    for m in self.subgroup.invert(&moves[start..start + width]) {
+                        state = self.subgroup.apply(&state, m);
+                    }
+                    let bound = |state: &State| self.distances.lower_bound(state);
+                    match self.step(&state, &bound, width - 1) {
+                        Ok(Some(shorter)) => {
+                            let rest = moves.split_off(start + width);
+                            moves.truncate(start);
+                            *moves = self
+                                .subgroup
+                                .simplify(moves.iter().chain(&shorter).chain(&rest).copied());
+                            on_improved(moves.len());
+                        }
+                        Ok(None) => start += 1,
+                        Err(_) => return false,
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Shortest moves to a state where `bound` is zero
+    fn step(
+        &mut self,
+        state: &State,
+        bound: &dyn Fn(&State) -> usize,
+        longest: usize,
+    ) -> Result<Option<Vec<usize>>, String> {
+        let mut depth = bound(state);
+        loop {
+            if depth > longest {
+                return Ok(None);
+            }
+            match self.search(state, depth, bound) {
+                Some(true) => return Ok(Some(std::mem::take(&mut self.moves))),
+                Some(false) => depth += 1,
+                None => return Err("Stopped before a solution was found".to_string()),
+            }
+        }
+    }
+
+    /// The rest of the way with macros: each time the one or two in a row that leave
+    /// the fewest pieces misplaced, shortest first
+    fn finish(&self, start: &State) -> Option<Vec<usize>> {
+        let goal = &self.distances.goal;
+        let macros: &'a [Macro] = &self.subgroup.tools().macros;
+        let mut state = *start;
+        let mut moves = Vec::new();
+        while state != *goal {
+            if (self.should_stop)() {
+                return None;
+            }
+            let touching = move |state: &State| {
+                let wrong = state.wrong_positions(goal);
+                macros.iter().filter(move |tool| tool.support & wrong != 0)
+            };
+            let misplaced = state.misplaced(goal);
+            // Fewest pieces left misplaced, then fewest moves
+            let pick = |best: Option<(usize, usize, Vec<&'a Macro>)>,
+                        (next, tools): (State, Vec<&'a Macro>)| {
+                let left = next.misplaced(goal);
+                let length = tools.iter().map(|tool| tool.moves.len()).sum();
+                match best {
+                    Some((best_left, best_length, _))
+                        if (best_left, best_length) <= (left, length) =>
+                    {
+                        best
+                    }
+                    _ if left < misplaced => Some((left, length, tools)),
+                    _ => best,
+                }
+            };
+            let mut best = touching(&state)
+                .map(|first| (state.apply(&first.effect), vec![first]))
+                .fold(None, pick);
+            if best.is_none() {
+                best = touching(&state)
+                    .flat_map(|first| {
+                        let between = state.apply(&first.effect);
+                        touching(&between)
+                            .map(move |second| (between.apply(&second.effect), vec![first, second]))
+                    })
+                    .fold(None, pick);
+            }
+            let (_, _, tools) = best?;
+            for tool in tools {
+                state = state.apply(&tool.effect);
+                moves.extend(&tool.moves);
+            }
+        }
+        Some(moves)
+    }
+
+    /// Depth-first search within `depth` moves for a state where `bound`, a lower bound
+    /// on the moves left, is zero: whether one was found, or `None` when stopped
+    fn search(
+        &mut self,
+        state: &State,
+        depth: usize,
+        bound: &dyn Fn(&State) -> usize,
+    ) -> Option<bool> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) && (self.should_stop)() {
+            return None;
+        }
+        let distance = bound(state);
+        if distance == 0 {
+            return Some(true);
+        }
+        if distance > depth {
+            return Some(false);
+        }
+        let moves = &self.subgroup.moves;
+        for m in 0..moves.len() {
+            if let Some(&last) = self.moves.last()
+                && !may_follow(&moves[last], &moves[m])
+            {
+                continue;
+            }
+            let next = self.subgroup.apply(state, m);
+            self.moves.push(m);
+            if self.search(&next, depth - 1, bound)? {
+                return Some(true);
+            }
+            self.moves.pop();
+        }
+        Some(false)
+    }
+}
+
+/// Whether `next` may follow `last`: never the same layers twice, and layers on one
+/// axis commute, so only one order of them is tried
+fn may_follow(last: &Move, next: &Move) -> bool {
+    last.kind != next.kind && (last.axis() != next.axis() || layer_order(last) < layer_order(next))
+}
+
+fn layer_order(mv: &Move) -> usize {
+    match mv.kind {
+        MoveKind::Face(face) => face.index() * 4,
+        MoveKind::Wide(face) => face.index() * 4 + 1,
+        MoveKind::Slice(slice) => slice.axis().index() * 4 + 2,
+        MoveKind::Rotation(axis) => axis.index() * 4 + 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrambled(scramble: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&MoveSequence::parse(scramble).unwrap().moves);
+        cube
+    }
+
+    fn set(text: &str) -> MoveSet {
+        MoveSet::parse(text).unwrap()
+    }
+
+    #[test]
+    fn two_generator_group_order() {
+        assert_eq!(group_order(&set("<R,U>")), 73_483_200);
+    }
+
+    #[test]
+    fn other_faces_are_unreachable_with_two_generators() {
+        assert!(!is_reachable(&scrambled("F"), &set("<R,U>")));
+        assert!(is_reachable(&scrambled("R U R' U'"), &set("<R,U>")));
+    }
+
+    #[test]
+    fn two_generator_scramble_solves_within_the_set() {
+        let set = set("<R,U>");
+        let cube = scrambled("R U2 R' U' R U' R' U2 R U R2");
+        let moves = solve(&cube, &set, &|| false, &mut |_| {}).unwrap();
+        assert!(moves.moves.iter().all(|mv| set.allows(mv)), "{}", moves);
+        let mut solved = cube;
+        solved.apply_moves(&moves.moves);
+        assert!(solved.is_solved(), "{}", moves);
+    }
+
+    #[test]
+    fn five_faces_solve_phase_by_phase() {
+        let set = set("<R,U,F,L,D>");
+        assert!(subgroup(&set).phase_moves.is_some());
+        let cube = scrambled("R U F2 L' D B R2 U' F D2 L B' U2 R F' D L2 B2 U R'");
+        // Stop right after the first solution, which ignores the stop condition
+        let moves = solve(&cube, &set, &|| true, &mut |_| {}).unwrap();
+        assert!(moves.moves.iter().all(|mv| set.allows(mv)), "{}", moves);
+        assert!(moves.len() <= 45, "{}", moves);
+        let mut solved = cube;
+        solved.apply_moves(&moves.moves);
+        assert!(solved.is_solved(), "{}", moves);
+    }
+}
@@ -0,0 +1,541 @@
+//! Roux solver: first block, second block, CMLL and the last six edges in three steps.
+//!
+//! The blocks are built on the left and right of the D layer, so the last six edges
+//! are solved with M and U alone. M turns carry the centers along, so the cube is
+//! followed in fixed space: pieces are numbered by where they belong once the
+//! centers are back, and the quarter turns of the M-slice centers are kept aside.
+//!
+//! CMLL is done in two looks: the shortest combination of a corner-orienting OLL
+//! algorithm and a PLL algorithm that swaps the corners into place.
+
+use super::Solution;
+use super::coordinates::{
+    corner_states, corner_transition, edge_group_index, edge_states, edge_transition,
+    rank_placement,
+};
+use super::last_layer::{OLL_CASES, PLL_CASES, algorithm_moves};
+use crate::cube_model::{CubieCube, Face, Move, MoveKind, Slice, face_move_cube};
+use crate::move_sequence::MoveSequence;
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+
+/// Solve `cube` with Roux, one solution stage per block, CMLL and LSE step
+pub fn solve(cube: &CubieCube) -> Result<Solution, String> {
+    let mut cube = SpatialCube {
+        cube: *cube,
+        centers: 0,
+    };
+    let mut solution = Solution::default();
+    let mut push_stage = |cube: &mut SpatialCube, label: &str, explanation: &str, moves| {
+        *cube = cube.apply_moves(&moves);
+        solution.push_stage(label, explanation, &moves);
+    };
+
+    let (moves, tables) = first_block_tables();
+    let first_block = search_block(&cube.cube, moves, tables);
+    push_stage(
+        &mut cube,
+        "First block",
+        "Build a 1x2x3 block on the left of the bottom layer",
+        first_block,
+    );
+    let (moves, tables) = second_block_tables();
+    let second_block = search_block(&cube.cube, moves, tables);
+    push_stage(
+        &mut cube,
+        "Second block",
+        "Build the matching block on the right with R, U and M only",
+        second_block,
+    );
+
+    let (label, explanation, moves) = solve_cmll(&cube.cube)?;
+    push_stage(&mut cube, &label, &explanation, moves);
+
+    for step in &LSE_STEPS {
+        let moves = search_last_six_edges(&cube, step.goal)
+            .ok_or_else(|| format!("{} could not be solved", step.label))?;
+        push_stage(&mut cube, step.label, step.explanation, moves);
+    }
+
+    if !cube.is_solved() {
+        return Err("Roux solve did not finish the cube".to_string());
+    }
+    Ok(solution)
+}
+
+/// Cubie state of an M turn in fixed space: the slice edges move, the centers with them
+fn m_slice_cube(turns: u8) -> CubieCube {
+    // UB -> UF -> DF -> DB -> UB, each edge flipped on the way
+    let quarter_turn = CubieCube {
+        ep: [0, 3, 2, 7, 4, 1, 6, 5, 8, 9, 10, 11],
+        eo: [0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0],
+        ..CubieCube::SOLVED
+    };
+    (0..turns).fold(CubieCube::SOLVED, |cube, _| cube.multiply(&quarter_turn))
+}
+
+fn move_cube(mv: Move) -> CubieCube {
+    match mv.kind {
+        MoveKind::Face(face) => face_move_cube(face, mv.turns),
+        MoveKind::Slice(Slice::M) => m_slice_cube(mv.turns),
+        _ => unreachable!("Roux uses face turns and M only"),
+    }
+}
+
+/// The cube in fixed space and the quarter turns of the M-slice centers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct SpatialCube {
+    cube: CubieCube,
+    centers: u8,
+}
+
+impl SpatialCube {
+    const SOLVED: SpatialCube = SpatialCube {
+        cube: CubieCube::SOLVED,
+        centers: 0,
+    };
+
+    fn apply_move(&self, mv: Move) -> SpatialCube {
+        let centers = match mv.kind {
+            MoveKind::Slice(Slice::M) => (self.centers + mv.turns) % 4,
+            _ => self.centers,
+        };
+        SpatialCube {
+            cube: self.cube.multiply(&move_cube(mv)),
+            centers,
+        }
+    }
+
+    fn apply_moves(&self, moves: &MoveSequence) -> SpatialCube {
+        moves
+            .moves
+            .iter()
+            .fold(*self, |cube, &mv| cube.apply_move(mv))
+    }
+
+    fn is_solved(&self) -> bool {
+        *self == Self::SOLVED
+    }
+
+    /// Every edge oriented with the centers on their axis; M2 and U keep this
+    fn edges_oriented(&self) -> bool {
+        self.centers.is_multiple_of(2) && self.cube.eo.iter().all(|&flip| flip == 0)
+    }
+
+    /// Oriented, and the UL and UR edges next to their corners
+    fn side_edges_placed(&self) -> bool {
+        self.edges_oriented()
+            && (0..4).any(|turns| {
+                let turned = self.cube.multiply(&face_move_cube(Face::U, turns));
+                turned.cp[..4] == [0, 1, 2, 3] && turned.ep[0] == 0 && turned.ep[2] == 2
+            })
+    }
+}
+
+/// Moves a block is built with, and where each of them takes single pieces
+struct MoveSet {
+    moves: Vec<Move>,
+    corners: Vec<[u8; 24]>,
+    edges: Vec<[u8; 24]>,
+}
+
+impl MoveSet {
+    /// Quarter, half and inverse turn of every listed layer
+    fn new(layers: &[MoveKind]) -> Self {
+        let moves: Vec<Move> = layers
+            .iter()
+            .flat_map(|&kind| [1, 2, 3].map(|turns| Move::new(kind, turns)))
+            .collect();
+        let cubes: Vec<CubieCube> = moves.iter().map(|&mv| move_cube(mv)).collect();
+        Self {
+            corners: cubes.iter().map(corner_transition).collect(),
+            edges: cubes.iter().map(edge_transition).collect(),
+            moves,
+        }
+    }
+
+    /// Whether move `next` may follow move `last`: never the same layer twice, and
+    /// layers on one axis commute, so only one order of them is tried
+    fn may_follow(&self, last: usize, next: usize) -> bool {
+        let (last_layer, next_layer) = (last / 3, next / 3);
+        last_layer != next_layer
+            && (self.moves[last].axis() != self.moves[next].axis() || last_layer < next_layer)
+    }
+}
+
+/// Exact move counts for a few pieces of a block on their own
+struct PieceTable {
+    corners: &'static [usize],
+    edges: &'static [usize],
+    distances: Vec<u8>,
+}
+
+impl PieceTable {
+    /// Breadth-first search from the solved pieces
+    fn new(corners: &'static [usize], edges: &'static [usize], moves: &MoveSet) -> Self {
+        let size = placements(8, corners.len())
+            * 3usize.pow(corners.len() as u32)
+            * (placements(12, edges.len()) << edges.len());
+        let mut table = Self {
+            corners,
+            edges,
+            distances: vec![u8::MAX; size],
+        };
+        let solved = (
+            corner_states(&CubieCube::SOLVED),
+            edge_states(&CubieCube::SOLVED),
+        );
+        let solved_index = table.index(&solved.0, &solved.1);
+        table.distances[solved_index] = 0;
+        let mut queue = VecDeque::from([solved]);
+        while let Some((corner_states, edge_states)) = queue.pop_front() {
+            let distance = table.distances[table.index(&corner_states, &edge_states)];
+            for (corner_moves, edge_moves) in moves.corners.iter().zip(&moves.edges) {
+                let next_corners = corner_states.map(|state| corner_moves[state as usize]);
+                let next_edges = edge_states.map(|state| edge_moves[state as usize]);
+                let index = table.index(&next_corners, &next_edges);
+                if table.distances[index] == u8::MAX {
+                    table.distances[index] = distance + 1;
+                    queue.push_back((next_corners, next_edges));
+                }
+            }
+        }
+        table
+    }
+
+    fn index(&self, corner_states: &[u8; 8], edge_states: &[u8; 12]) -> usize {
+        let mut positions = [0; 8];
+        let mut twists = 0;
+        for (position, &piece) in positions.iter_mut().zip(self.corners) {
+            *position = corner_states[piece] / 3;
+            twists = twists * 3 + (corner_states[piece] % 3) as usize;
+        }
+        let corner_index = rank_placement(&positions[..self.corners.len()], 8)
+            * 3usize.pow(self.corners.len() as u32)
+            + twists;
+        let mut group = [0; 12];
+        for (state, &piece) in group.iter_mut().zip(self.edges) {
+            *state = edge_states[piece];
+        }
+        let edge_count = placements(12, self.edges.len()) << self.edges.len();
+        corner_index * edge_count + edge_group_index(&group[..self.edges.len()])
+    }
+
+    fn distance(&self, corner_states: &[u8; 8], edge_states: &[u8; 12]) -> usize {
+        self.distances[self.index(corner_states, edge_states)] as usize
+    }
+}
+
+/// Ordered choices of `k` out of `n`
+fn placements(n: usize, k: usize) -> usize {
+    (n - k + 1..=n).product()
+}
+
+/// Corners DLF, DBL and edges DL, FL, BL, built with any face turn
+fn first_block_tables() -> &'static (MoveSet, [PieceTable; 2]) {
+    static TABLES: OnceLock<(MoveSet, [PieceTable; 2])> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let moves = MoveSet::new(&Face::ALL.map(MoveKind::Face));
+        let tables = [
+            PieceTable::new(&[5, 6], &[6], &moves),
+            PieceTable::new(&[], &[6, 9, 10], &moves),
+        ];
+        (moves, tables)
+    })
+}
+
+/// Corners DFR, DRB and edges DR, FR, BR, built with R, U and M so the first block stays
+fn second_block_tables() -> &'static (MoveSet, [PieceTable; 2]) {
+    static TABLES: OnceLock<(MoveSet, [PieceTable; 2])> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let moves = MoveSet::new(&[
+            MoveKind::Face(Face::U),
+            MoveKind::Face(Face::R),
+            MoveKind::Slice(Slice::M),
+        ]);
+        let tables = [
+            PieceTable::new(&[4, 7], &[4], &moves),
+            PieceTable::new(&[], &[4, 8, 11], &moves),
+        ];
+        (moves, tables)
+    })
+}
+
+/// Shortest sequence that solves the pieces of `tables`, found by IDA*
+fn search_block(cube: &CubieCube, moves: &MoveSet, tables: &[PieceTable]) -> MoveSequence {
+    fn search(
+        corners: &[u8; 8],
+        edges: &[u8; 12],
+        budget: usize,
+        moves: &MoveSet,
+        tables: &[PieceTable],
+        path: &mut Vec<usize>,
+    ) -> bool {
+        let bound = tables
+            .iter()
+            .map(|table| table.distance(corners, edges))
+            .max()
+            .unwrap_or(0);
+        if bound == 0 {
+            return true;
+        }
+        if bound > budget {
+            return false;
+        }
+        for m in 0..moves.moves.len() {
+            if path.last().is_some_and(|&last| !moves.may_follow(last, m)) {
+                continue;
+            }
+            path.push(m);
+            let next_corners = corners.map(|state| moves.corners[m][state as usize]);
+            let next_edges = edges.map(|state| moves.edges[m][state as usize]);
+            if search(&next_corners, &next_edges, budget - 1, moves, tables, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let corners = corner_states(cube);
+    let edges = edge_states(cube);
+    let mut path = Vec::new();
+    let mut budget = 0;
+    while !search(&corners, &edges, budget, moves, tables, &mut path) {
+        budget += 1;
+    }
+    MoveSequence::new(path.iter().map(|&m| moves.moves[m]).collect())
+}
+
+fn top_turn(turns: i32) -> MoveSequence {
+    MoveSequence::new(vec![Move::face(Face::U, turns)]).simplify()
+}
+
+fn corners_oriented(cube: &CubieCube) -> bool {
+    cube.co[..4].iter().all(|&twist| twist == 0)
+}
+
+fn corners_solved(cube: &CubieCube) -> bool {
+    corners_oriented(cube) && cube.cp[..4] == [0, 1, 2, 3]
+}
+
+/// A named algorithm that moves the top corners
+struct CornerAlgorithm {
+    name: &'static str,
+    moves: MoveSequence,
+    cube: CubieCube,
+}
+
+impl CornerAlgorithm {
+    fn new(name: &'static str, algorithm: &str) -> Self {
+        let moves = algorithm_moves(algorithm);
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&moves.moves);
+        Self { name, moves, cube }
+    }
+}
+
+/// CMLL set of each corner-orienting OLL, by OLL name
+const CMLL_SETS: [(&str, &str); 7] = [
+    ("H", "H"),
+    ("Pi", "Pi"),
+    ("Headlights", "U"),
+    ("Chameleon", "T"),
+    ("Bowtie", "L"),
+    ("Anti-Sune", "Anti-Sune"),
+    ("Sune", "Sune"),
+];
+
+/// The corner-orienting OLL algorithms and the PLL algorithms, to swap the corners with
+fn corner_algorithms() -> &'static (Vec<CornerAlgorithm>, Vec<CornerAlgorithm>) {
+    static ALGORITHMS: OnceLock<(Vec<CornerAlgorithm>, Vec<CornerAlgorithm>)> = OnceLock::new();
+    ALGORITHMS.get_or_init(|| {
+        let orient = CMLL_SETS
+            .iter()
+            .map(|&(name, _)| {
+                let case = OLL_CASES
+                    .iter()
+                    .find(|case| case.name == name)
+                    .expect("the table holds every OLL");
+                CornerAlgorithm::new(case.name, case.algorithm)
+            })
+            .collect();
+        let permute = PLL_CASES
+            .iter()
+            .map(|case| CornerAlgorithm::new(case.name, case.algorithm))
+            .collect();
+        (orient, permute)
+    })
+}
+
+/// Solve the top corners, keeping both blocks, with up to one orienting and one
+/// swapping algorithm; returns the stage label, its explanation and the moves
+fn solve_cmll(cube: &CubieCube) -> Result<(String, String, MoveSequence), String> {
+    let (orient, permute) = corner_algorithms();
+    let mut best: Option<(
+        MoveSequence,
+        Option<&CornerAlgorithm>,
+        Option<&CornerAlgorithm>,
+    )> = None;
+    let orient_choices = std::iter::once(None).chain(orient.iter().map(Some));
+    for orientation in orient_choices {
+        for before in 0..4 {
+            let mut oriented = cube.multiply(&move_cube(Move::face(Face::U, before)));
+            if let Some(algorithm) = orientation {
+                oriented = oriented.multiply(&algorithm.cube);
+            }
+            if !corners_oriented(&oriented) {
+                continue;
+            }
+            let permute_choices = std::iter::once(None).chain(permute.iter().map(Some));
+            for permutation in permute_choices {
+                for between in 0..4 {
+                    let mut placed = oriented.multiply(&move_cube(Move::face(Face::U, between)));
+                    if let Some(algorithm) = permutation {
+                        placed = placed.multiply(&algorithm.cube);
+                    }
+                    let Some(after) = (0..4).find(|&turns| {
+                        corners_solved(&placed.multiply(&move_cube(Move::face(Face::U, turns))))
+                    }) else {
+                        continue;
+                    };
+                    let mut moves = top_turn(before);
+                    if let Some(algorithm) = orientation {
+                        moves = moves.then(&algorithm.moves);
+                    }
+                    moves = moves.then(&top_turn(between));
+                    if let Some(algorithm) = permutation {
+                        moves = moves.then(&algorithm.moves);
+                    }
+                    let moves = moves.then(&top_turn(after)).simplify();
+                    if best
+                        .as_ref()
+                        .is_none_or(|(best, _, _)| moves.len() < best.len())
+                    {
+                        best = Some((moves, orientation, permutation));
+                    }
+                }
+            }
+        }
+    }
+    let (moves, orientation, permutation) =
+        best.ok_or_else(|| "CMLL case not recognised".to_string())?;
+
+    let set = orientation.map_or("O", |algorithm| {
+        CMLL_SETS
+            .iter()
+            .find(|(name, _)| *name == algorithm.name)
+            .map_or(algorithm.name, |(_, set)| set)
+    });
+    let explanation = match (orientation, permutation) {
+        (Some(orientation), Some(permutation)) => format!(
+            "Orient the top corners with the {} algorithm, then swap them with the {}-perm",
+            orientation.name, permutation.name
+        ),
+        (Some(orientation), None) => format!(
+            "Orient the top corners with the {} algorithm",
+            orientation.name
+        ),
+        (None, Some(permutation)) => format!(
+            "Swap the top corners into place with the {}-perm",
+            permutation.name
+        ),
+        (None, None) => "Turn the top layer to line the corners up with the blocks".to_string(),
+    };
+    Ok((format!("CMLL {}", set), explanation, moves))
+}
+
+/// A step of the last six edges, finished when `goal` holds
+struct LseStep {
+    label: &'static str,
+    explanation: &'static str,
+    goal: fn(&SpatialCube) -> bool,
+}
+
+const LSE_STEPS: [LseStep; 3] = [
+    LseStep {
+        label: "LSE: EO",
+        explanation: "Orient the last six edges and bring the U and D centers to U or D",
+        goal: SpatialCube::edges_oriented,
+    },
+    LseStep {
+        label: "LSE: UL/UR",
+        explanation: "Bring the UL and UR edges next to their corners",
+        goal: SpatialCube::side_edges_placed,
+    },
+    LseStep {
+        label: "LSE: EP",
+        explanation: "Solve the M-slice edges and centers",
+        goal: SpatialCube::is_solved,
+    },
+];
+
+/// Shortest sequence of M and U turns after which `goal` holds
+fn search_last_six_edges(
+    cube: &SpatialCube,
+    goal: fn(&SpatialCube) -> bool,
+) -> Option<MoveSequence> {
+    const MAX_DEPTH: usize = 16;
+
+    fn search(
+        cube: &SpatialCube,
+        remaining: usize,
+        goal: fn(&SpatialCube) -> bool,
+        path: &mut Vec<Move>,
+    ) -> bool {
+        if remaining == 0 {
+            return goal(cube);
+        }
+        for kind in [MoveKind::Slice(Slice::M), MoveKind::Face(Face::U)] {
+            if path.last().is_some_and(|last| last.kind == kind) {
+                continue;
+            }
+            for turns in 1..=3 {
+                let mv = Move::new(kind, turns);
+                path.push(mv);
+                if search(&cube.apply_move(mv), remaining - 1, goal, path) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+
+    let mut path = Vec::new();
+    (0..=MAX_DEPTH)
+        .find(|&depth| search(cube, depth, goal, &mut path))
+        .map(|_| MoveSequence::new(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube_model::CubeModel;
+
+    #[test]
+    fn scrambles_solve_stage_by_stage() {
+        for scramble in [
+            "R U2 F' L D B2 R' U F2 D' L2 B",
+            "D2 F' U R2 B L' U2 F D' R B2 L U' F2 R'",
+        ] {
+            let mut cube = CubieCube::SOLVED;
+            cube.apply_moves(&MoveSequence::parse(scramble).unwrap().moves);
+            let solution = solve(&cube).unwrap();
+            // M turns move the centers, so the solution is applied to the facelets
+            let mut model = CubeModel::from_cubie(&cube);
+            model.apply_moves(&solution.moves.moves);
+            assert_eq!(
+                model,
+                CubeModel::solved(),
+                "{}: {}",
+                scramble,
+                solution.moves
+            );
+
+            let staged: usize = solution.stages.iter().map(|stage| stage.move_count).sum();
+            assert_eq!(staged, solution.move_count(), "{}", scramble);
+            assert_eq!(solution.stages[0].label, "First block");
+        }
+    }
+}
@@ -0,0 +1,418 @@
+//! Solving to a chosen target state instead of the solved cube.
+//!
+//! A complete target is reached by solving the cube relative to it, so every solver
+//! can be used. A target with blank stickers, which may end up any color, is reached
+//! a group of pieces at a time: each step is the shortest sequence that brings one
+//! more group into place while keeping the groups before it, found by IDA* over
+//! small exact distance tables.
+
+use super::Solution;
+use super::coordinates::{
+    GROUP_SIZE, MOVE_COUNT, Piece, PieceDistances, STOP_CHECK_INTERVAL, corner_states,
+    corner_transitions, edge_states, edge_transitions, face_turn,
+};
+use crate::cube_model::{
+    CENTER_FACELETS, CORNER_FACELETS, CubeModel, CubieCube, EDGE_FACELETS, Face,
+};
+use crate::move_sequence::MoveSequence;
+use std::sync::OnceLock;
+
+/// Longest step tried before a partial target is given up as unreachable
+const MAX_STEP_DEPTH: usize = 20;
+
+/// A cube state to solve to; blank stickers may end up any color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub name: String,
+    pub model: CubeModel,
+}
+
+impl Target {
+    pub fn solved() -> Self {
+        Self {
+            name: "Solved".to_string(),
+            model: CubeModel::solved(),
+        }
+    }
+
+    /// A target from a facelet string, with `.` or a space for stickers that may be any color
+    pub fn parse(name: impl Into<String>, facelets: &str) -> Result<Self, String> {
+        let model = CubeModel::from_facelets(facelets).map_err(|error| error.to_string())?;
+        Self::from_model(name, model)
+    }
+
+    /// The cube as painted, relabelled by its centers like the cube handed to the solvers
+    pub fn from_model(name: impl Into<String>, model: CubeModel) -> Result<Self, String> {
+        let model = CubeModel::from_facelets(&model.normalized_facelet_string())
+            .map_err(|error| error.to_string())?;
+        let centers_kept = Face::ALL
+            .iter()
+            .zip(CENTER_FACELETS)
+            .all(|(&face, center)| model.facelet(center).is_none_or(|color| color == face));
+        if !centers_kept {
+            return Err("Target centers must match the cube's centers".to_string());
+        }
+        Ok(Self {
+            name: name.into(),
+            model,
+        })
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.model == CubeModel::solved()
+    }
+
+    /// Built-in targets: the solved cube, classic patterns and the first stages of a solve
+    pub fn presets() -> &'static [Target] {
+        static PRESETS: OnceLock<Vec<Target>> = OnceLock::new();
+        PRESETS.get_or_init(|| {
+            let pattern = |name: &str, algorithm: &str| {
+                let mut cube = CubieCube::SOLVED;
+                cube.apply_moves(
+                    &MoveSequence::parse(algorithm)
+                        .expect("built-in patterns are valid")
+                        .moves,
+                );
+                Target {
+                    name: name.to_string(),
+                    model: CubeModel::from_cubie(&cube),
+                }
+            };
+            vec![
+                Target::solved(),
+                pattern("Checkerboard", "U2 D2 F2 B2 L2 R2"),
+                pattern(
+                    "Superflip",
+                    "U R2 F B R B2 R U2 L B2 R U' D' R2 F R' L B2 U2 F2",
+                ),
+                pattern("Cube in cube", "F L F U' R U F2 L2 U' L' B D' B' L2 U"),
+                // The app builds the first layer on U
+                partial("Cross", &[], &[0, 1, 2, 3]),
+                partial("First layer", &[0, 1, 2, 3], &[0, 1, 2, 3]),
+                partial("F2L", &[0, 1, 2, 3], &[0, 1, 2, 3, 8, 9, 10, 11]),
+            ]
+        })
+    }
+}
+
+/// The solved cube with only the centers and the given corner and edge positions colored
+fn partial(name: &str, corners: &[usize], edges: &[usize]) -> Target {
+    let solved = CubeModel::solved();
+    let mut model = CubeModel::empty();
+    let kept = CENTER_FACELETS
+        .iter()
+        .chain(corners.iter().flat_map(|&corner| &CORNER_FACELETS[corner]))
+        .chain(edges.iter().flat_map(|&edge| &EDGE_FACELETS[edge]));
+    for &facelet in kept {
+        model.set_facelet(facelet, solved.facelet(facelet));
+    }
+    Target {
+        name: name.to_string(),
+        model,
+    }
+}
+
+/// The state that is solved by exactly the moves that take `cube` to `target`
+pub fn relative_to(cube: &CubieCube, target: &CubieCube) -> CubieCube {
+    target.inverse().multiply(cube)
+}
+
+/// States (`position * 3 + twist` or `position * 2 + flip`) in which `piece` sits at a
+/// position with painted stickers and shows the colors painted there
+fn fitting_states(piece: Piece, target: &CubeModel) -> [bool; 24] {
+    std::array::from_fn(|state| {
+        let mut cube = CubieCube::SOLVED;
+        let facelets: &[usize] = match piece {
+            Piece::Corner(corner) => {
+                let position = state / 3;
+                cube.cp[position] = corner as u8;
+                cube.co[position] = (state % 3) as u8;
+                &CORNER_FACELETS[position]
+            }
+            Piece::Edge(edge) => {
+                let position = state / 2;
+                cube.ep[position] = edge as u8;
+                cube.eo[position] = (state % 2) as u8;
+                &EDGE_FACELETS[position]
+            }
+        };
+        let shown = CubeModel::from_cubie(&cube);
+        is_painted(target, facelets)
+            && facelets.iter().all(|&facelet| {
+                target
+                    .facelet(facelet)
+                    .is_none_or(|color| shown.facelet(facelet) == Some(color))
+            })
+    })
+}
+
+fn is_painted(target: &CubeModel, facelets: &[usize]) -> bool {
+    facelets
+        .iter()
+        .any(|&facelet| target.facelet(facelet).is_some())
+}
+
+/// The pieces that fit the painted positions of one kind, with the states they fit in.
+/// Every such piece has to end up in one only when there are as many of them as
+/// painted positions; with more, which piece goes where is left open.
+fn fitting_pieces(
+    target: &CubeModel,
+    pieces: impl Iterator<Item = Piece>,
+    positions: &[&[usize]],
+) -> Result<Vec<(Piece, [bool; 24])>, String> {
+    let fitting: Vec<(Piece, [bool; 24])> = pieces
+        .map(|piece| (piece, fitting_states(piece, target)))
+        .filter(|(_, states)| states.contains(&true))
+        .collect();
+    let painted: Vec<usize> = (0..positions.len())
+        .filter(|&position| is_painted(target, positions[position]))
+        .collect();
+    let states_per_position = 24 / positions.len();
+    let unfilled = painted.iter().any(|&position| {
+        let states = position * states_per_position..(position + 1) * states_per_position;
+        !fitting
+            .iter()
+            .any(|(_, fits)| fits[states.clone()].contains(&true))
+    });
+    if unfilled || fitting.len() < painted.len() {
+        return Err("No cube matches the target".to_string());
+    }
+    if fitting.len() > painted.len() {
+        return Err("The target must show which piece goes where".to_string());
+    }
+    Ok(fitting)
+}
+
+/// Pieces the target places, in groups of up to `GROUP_SIZE`: the edges beyond the
+/// number of corners on their own, then each edge with a corner, then the corners left
+/// over. A target like F2L thus places its cross first and then two pairs at a time.
+fn groups(target: &CubeModel) -> Result<Vec<PieceDistances>, String> {
+    let corner_positions = CORNER_FACELETS
+        .each_ref()
+        .map(|facelets| facelets.as_slice());
+    let edge_positions = EDGE_FACELETS.each_ref().map(|facelets| facelets.as_slice());
+    let corners = fitting_pieces(target, (0..8).map(Piece::Corner), &corner_positions)?;
+    let edges = fitting_pieces(target, (0..12).map(Piece::Edge), &edge_positions)?;
+    let paired = edges.len().min(corners.len());
+    let (lone_edges, paired_edges) = edges.split_at(edges.len() - paired);
+    let units = lone_edges
+        .iter()
+        .map(|&edge| vec![edge])
+        .chain(
+            paired_edges
+                .iter()
+                .zip(&corners)
+                .map(|(&edge, &corner)| vec![edge, corner]),
+        )
+        .chain(corners[paired..].iter().map(|&corner| vec![corner]));
+
+    let mut groups: Vec<Vec<(Piece, [bool; 24])>> = Vec::new();
+    for unit in units {
+        match groups.last_mut() {
+            Some(group) if group.len() + unit.len() <= GROUP_SIZE => group.extend(unit),
+            _ => groups.push(unit),
+        }
+    }
+    Ok(groups
+        .into_iter()
+        .map(|fitting| {
+            // Breadth-first search from every state the target allows
+            PieceDistances::new(
+                fitting.iter().map(|&(piece, _)| piece).collect(),
+                MOVE_COUNT,
+                |piece, m| &piece.transitions()[m],
+                |piece, state| {
+                    fitting
+                        .iter()
+                        .any(|&(fitting, allowed)| fitting == piece && allowed[state])
+                },
+            )
+        })
+        .collect())
+}
+
+/// Solve to a target with blank stickers, one solution stage per group of pieces
+pub fn solve_partial(
+    cube: &CubieCube,
+    target: &Target,
+    should_stop: &dyn Fn() -> bool,
+) -> Result<Solution, String> {
+    let groups = groups(&target.model)?;
+
+    let mut corners = corner_states(cube);
+    let mut edges = edge_states(cube);
+    let mut solution = Solution::default();
+    let mut placed: Vec<usize> = Vec::new();
+    let mut remaining: Vec<usize> = (0..groups.len()).collect();
+    while !remaining.is_empty() {
+        let mut search = StepSearch {
+            groups: &groups,
+            placed: &placed,
+            remaining: &remaining,
+            moves: Vec::new(),
+            nodes: 0,
+            should_stop,
+        };
+        let (group, moves) = search.run(&corners, &edges)?;
+        for &m in &moves {
+            corners = corners.map(|state| corner_transitions()[m][state as usize]);
+            edges = edges.map(|state| edge_transitions()[m][state as usize]);
+        }
+        remaining.retain(|&other| other != group);
+        placed.push(group);
+        let name = groups[group].name();
+        solution.push_stage(
+            format!("Place {}", name),
+            format!(
+                "Bring {} to where the target has them, keeping the pieces placed before",
+                name
+            ),
+            &MoveSequence::new(moves.into_iter().map(face_turn).collect()),
+        );
+    }
+    Ok(solution)
+}
+
+struct StepSearch<'a> {
+    groups: &'a [PieceDistances],
+    placed: &'a [usize],
+    remaining: &'a [usize],
+    moves: Vec<usize>,
+    nodes: usize,
+    should_stop: &'a dyn Fn() -> bool,
+}
+
+impl StepSearch<'_> {
+    /// The shortest turns that place one more group, with the group they place
+    fn run(&mut self, corners: &[u8; 8], edges: &[u8; 12]) -> Result<(usize, Vec<usize>), String> {
+        let mut budget = self.lower_bound(corners, edges);
+        while budget <= MAX_STEP_DEPTH {
+            match self.search(corners, edges, budget) {
+                Some(Some(group)) => return Ok((group, std::mem::take(&mut self.moves))),
+                Some(None) => budget += 1,
+                None => return Err("Stopped before the target was reached".to_string()),
+            }
+        }
+        Err("The target cannot be reached".to_string())
+    }
+
+    /// Zero exactly when the placed groups are kept and one more group is placed
+    fn lower_bound(&self, corners: &[u8; 8], edges: &[u8; 12]) -> usize {
+        let kept = self
+            .placed
+            .iter()
+            .map(|&group| self.groups[group].distance(corners, edges) as usize)
+            .max()
+            .unwrap_or(0);
+        let next = self
+            .remaining
+            .iter()
+            .map(|&group| self.groups[group].distance(corners, edges) as usize)
+            .min()
+            .unwrap_or(0);
+        kept.max(next)
+    }
+
+    /// Depth-first search within `budget` turns: the group placed, `Some(None)` when
+    /// there is none within the budget, `None` when stopped
+    fn search(
+        &mut self,
+        corners: &[u8; 8],
+        edges: &[u8; 12],
+        budget: usize,
+    ) -> Option<Option<usize>> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(STOP_CHECK_INTERVAL) && (self.should_stop)() {
+            return None;
+        }
+        let bound = self.lower_bound(corners, edges);
+        if bound == 0 {
+            let placed = self
+                .remaining
+                .iter()
+                .copied()
+                .find(|&group| self.groups[group].distance(corners, edges) == 0);
+            return Some(placed);
+        }
+        if bound > budget {
+            return Some(None);
+        }
+        for m in 0..MOVE_COUNT {
+            if let Some(&last) = self.moves.last() {
+                let (face, last_face) = (m / 3, last / 3);
+                // Same face twice, or opposite faces in both orders, repeat other paths
+                if face == last_face || (face % 3 == last_face % 3 && face < last_face) {
+                    continue;
+                }
+            }
+            let next_corners = corners.map(|state| corner_transitions()[m][state as usize]);
+            let next_edges = edges.map(|state| edge_transitions()[m][state as usize]);
+            self.moves.push(m);
+            match self.search(&next_corners, &next_edges, budget - 1)? {
+                Some(group) => return Some(Some(group)),
+                None => {
+                    self.moves.pop();
+                }
+            }
+        }
+        Some(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cube_model::FACELET_COUNT;
+    use crate::solvers::thistlethwaite;
+
+    fn preset(name: &str) -> &'static Target {
+        Target::presets()
+            .iter()
+            .find(|target| target.name == name)
+            .unwrap()
+    }
+
+    /// Every painted sticker of the target shows its color on the cube
+    fn reaches(cube: &CubieCube, target: &Target) -> bool {
+        let model = CubeModel::from_cubie(cube);
+        (0..FACELET_COUNT).all(|facelet| {
+            target
+                .model
+                .facelet(facelet)
+                .is_none_or(|color| model.facelet(facelet) == Some(color))
+        })
+    }
+
+    #[test]
+    fn partial_targets_are_reached_group_by_group() {
+        let scramble = MoveSequence::parse("R U F2 L' D B R2 U' F D2 L").unwrap();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&scramble.moves);
+        for name in ["Cross", "First layer"] {
+            let target = preset(name);
+            assert!(!reaches(&cube, target));
+            let solution = solve_partial(&cube, target, &|| false).unwrap();
+            let mut reached = cube;
+            reached.apply_moves(&solution.moves.moves);
+            assert!(reaches(&reached, target), "{}: {}", name, solution.moves);
+
+            let staged: usize = solution.stages.iter().map(|stage| stage.move_count).sum();
+            assert_eq!(staged, solution.move_count(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn complete_target_is_solved_relative_to_it() {
+        let checkerboard = preset("Checkerboard").model.to_cubie().unwrap();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(
+            &MoveSequence::parse("R U F2 L' D B R2 U' F D2 L")
+                .unwrap()
+                .moves,
+        );
+        let relative = relative_to(&cube, &checkerboard);
+        let solution = thistlethwaite::solve(&relative).unwrap();
+        cube.apply_moves(&solution.moves.moves);
+        assert_eq!(cube, checkerboard, "{}", solution.moves);
+    }
+}
@@ -0,0 +1,368 @@
+//! Thistlethwaite's four-phase solver, for showing how the cube is reduced step by step.
+//!
+//! Each phase takes the cube into a smaller group, from which the next phase can
+//! finish using fewer kinds of turns:
+//! G0 = <U, D, R, L, F, B>, G1 = <U, D, R, L, F2, B2>, G2 = <U, D, R2, L2, F2, B2>,
+//! G3 = <U2, D2, R2, L2, F2, B2> and finally the solved cube. Every phase is solved
+//! optimally with a distance table over the two coordinates that tell its groups apart.
+
+use super::Solution;
+use super::coordinates::{face_turn, face_turn_cube, rank_placement};
+use crate::cube_model::CubieCube;
+use crate::move_sequence::MoveSequence;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Face turns of G0: every turn, numbered as in `coordinates`
+const G0_MOVES: [usize; 18] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17];
+/// Face turns of G1: F and B only by half turns
+const G1_MOVES: [usize; 14] = [0, 1, 2, 3, 4, 5, 7, 9, 10, 11, 12, 13, 14, 16];
+/// Face turns of G2: only U and D by quarter turns
+const G2_MOVES: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+/// Face turns of G3: half turns only
+const G3_MOVES: [usize; 6] = [1, 4, 7, 10, 13, 16];
+
+/// Edges of the E slice (FR, FL, BL, BR) as a mask of positions
+const E_SLICE: u32 = 0b1111_0000_0000;
+/// Edges of the M slice (UF, UB, DF, DB) as a mask of positions
+const M_SLICE: u32 = 0b0000_1010_1010;
+/// Corners of the tetrad with URF; the other tetrad holds UFL
+const URF_TETRAD: [usize; 4] = [0, 2, 5, 7];
+
+struct Phase {
+    label: &'static str,
+    explanation: &'static str,
+    moves: &'static [usize],
+    /// Moves of the group the phase ends in; empty when it ends at the solved cube
+    goal_moves: &'static [usize],
+    /// What the phase changes, as two numbers that are the same for every cube of
+    /// the goal group
+    coordinates: [fn(&CubieCube) -> u32; 2],
+}
+
+// The UI font has no arrow glyph, so the labels use "->"
+const PHASES: [Phase; 4] = [
+    Phase {
+        label: "G0 -> G1",
+        explanation: "Orient every edge, so F and B never need a quarter turn again",
+        moves: &G0_MOVES,
+        goal_moves: &G1_MOVES,
+        coordinates: [edge_flips, |_| 0],
+    },
+    Phase {
+        label: "G1 -> G2",
+        explanation: "Orient every corner and gather the E-slice edges in the E slice, so R and L only need half turns",
+        moves: &G1_MOVES,
+        goal_moves: &G2_MOVES,
+        coordinates: [corner_twists, |cube| edge_mask(cube, E_SLICE)],
+    },
+    Phase {
+        label: "G2 -> G3",
+        explanation: "Put each corner in its tetrad and the M- and S-slice edges in their slices, so every face only needs half turns",
+        moves: &G2_MOVES,
+        goal_moves: &G3_MOVES,
+        coordinates: [corner_permutation, |cube| edge_mask(cube, M_SLICE)],
+    },
+    Phase {
+        label: "G3 -> solved",
+        explanation: "Solve the cube with half turns only",
+        moves: &G3_MOVES,
+        goal_moves: &[],
+        coordinates: [corner_permutation, edge_permutation],
+    },
+];
+
+/// Solve `cube` phase by phase, one solution stage per group reduction
+pub fn solve(cube: &CubieCube) -> Result<Solution, String> {
+    solve_with_tables(cube, tables())
+}
+
+/// Solve `cube` phase by phase with only the face turns `allowed`, numbered as in
+/// `coordinates`. Each phase keeps the allowed turns of its group, so the allowed turns
+/// of every group in [`groups`] have to generate all of it for the solve to succeed.
+pub fn solve_within(cube: &CubieCube, allowed: &[usize]) -> Result<Solution, String> {
+    static TABLES: OnceLock<Mutex<HashMap<Vec<usize>, Arc<Tables>>>> = OnceLock::new();
+    let tables = TABLES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .entry(allowed.to_vec())
+        .or_insert_with(|| {
+            Arc::new(
+                PHASES
+                    .each_ref()
+                    .map(|phase| PhaseTable::new(phase, allowed)),
+            )
+        })
+        .clone();
+    solve_with_tables(cube, &tables)
+}
+
+/// Face turns of G0, G1, G2 and G3, numbered as in `coordinates`
+pub fn groups() -> [&'static [usize]; 4] {
+    PHASES.each_ref().map(|phase| phase.moves)
+}
+
+fn solve_with_tables(cube: &CubieCube, tables: &Tables) -> Result<Solution, String> {
+    let mut cube = *cube;
+    let mut solution = Solution::default();
+    for (phase, table) in PHASES.iter().zip(tables) {
+        let moves = table
+            .solve(&cube)
+            .ok_or_else(|| format!("Phase {} could not be solved", phase.label))?;
+        cube.apply_moves(&moves.moves);
+        solution.push_stage(phase.label, phase.explanation, &moves);
+    }
+
+    if !cube.is_solved() {
+        return Err("Thistlethwaite solve did not finish the cube".to_string());
+    }
+    Ok(solution)
+}
+
+/// Number of the phase a stage label belongs to
+pub fn phase_of_label(label: &str) -> Option<usize> {
+    PHASES.iter().position(|phase| phase.label == label)
+}
+
+/// Corner and edge positions whose pieces already meet the goal of phase `phase`:
+/// they would not have to move for the cube to be in the next group
+pub fn reduced_pieces(cube: &CubieCube, phase: usize) -> ([bool; 8], [bool; 12]) {
+    let in_mask = |mask: u32, index: usize| mask & (1 << index) != 0;
+    let tetrad = |corner: usize| URF_TETRAD.contains(&corner);
+    let corners = std::array::from_fn(|position| {
+        let corner = cube.cp[position] as usize;
+        match phase {
+            0 => true,
+            1 => cube.co[position] == 0,
+            2 => tetrad(corner) == tetrad(position),
+            _ => corner == position && cube.co[position] == 0,
+        }
+    });
+    let edges = std::array::from_fn(|position| {
+        let edge = cube.ep[position] as usize;
+        match phase {
+            0 => cube.eo[position] == 0,
+            1 => in_mask(E_SLICE, edge) == in_mask(E_SLICE, position),
+            2 => in_mask(M_SLICE, edge) == in_mask(M_SLICE, position),
+            _ => edge == position && cube.eo[position] == 0,
+        }
+    });
+    (corners, edges)
+}
+
+fn edge_flips(cube: &CubieCube) -> u32 {
+    cube.eo
+        .iter()
+        .enumerate()
+        .map(|(position, &flip)| (flip as u32) << position)
+        .sum()
+}
+
+fn corner_twists(cube: &CubieCube) -> u32 {
+    cube.co
+        .iter()
+        .fold(0, |twists, &twist| twists * 3 + twist as u32)
+}
+
+/// Positions holding an edge of `mask`
+fn edge_mask(cube: &CubieCube, mask: u32) -> u32 {
+    cube.ep
+        .iter()
+        .enumerate()
+        .filter(|&(_, &edge)| mask & (1 << edge) != 0)
+        .map(|(position, _)| 1 << position)
+        .sum()
+}
+
+fn corner_permutation(cube: &CubieCube) -> u32 {
+    rank_placement(&cube.cp, 8) as u32
+}
+
+fn edge_permutation(cube: &CubieCube) -> u32 {
+    rank_placement(&cube.ep, 12) as u32
+}
+
+/// One coordinate over the values the phase moves reach from the solved cube,
+/// numbered in the order they are found; the solved value is 0
+struct Coordinate {
+    indices: HashMap<u32, u32>,
+    /// `transitions[index * moves + i]`: the value after the i-th phase move
+    transitions: Vec<u32>,
+}
+
+impl Coordinate {
+    fn new(raw: fn(&CubieCube) -> u32, moves: &[usize]) -> Self {
+        let move_cubes: Vec<CubieCube> = moves.iter().map(|&m| face_turn_cube(m)).collect();
+        let mut indices = HashMap::from([(raw(&CubieCube::SOLVED), 0)]);
+        let mut representatives = vec![CubieCube::SOLVED];
+        let mut transitions = Vec::new();
+        let mut next = 0;
+        while next < representatives.len() {
+            let cube = representatives[next];
+            for move_cube in &move_cubes {
+                let moved = cube.multiply(move_cube);
+                let count = indices.len() as u32;
+                let index = *indices.entry(raw(&moved)).or_insert(count);
+                if index == count {
+                    representatives.push(moved);
+                }
+                transitions.push(index);
+            }
+            next += 1;
+        }
+        Self {
+            indices,
+            transitions,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+/// Move tables of the two coordinates of a phase and the number of phase moves
+/// from every pair of values to the goal group
+struct PhaseTable {
+    raw: [fn(&CubieCube) -> u32; 2],
+    coordinates: [Coordinate; 2],
+    /// The phase moves among the allowed face turns
+    moves: Vec<usize>,
+    distances: Vec<u8>,
+}
+
+impl PhaseTable {
+    /// Tables for `phase` using only the face turns `allowed`
+    fn new(phase: &Phase, allowed: &[usize]) -> Self {
+        let moves: Vec<usize> = phase
+            .moves
+            .iter()
+            .copied()
+            .filter(|m| allowed.contains(m))
+            .collect();
+        let coordinates = phase.coordinates.map(|raw| Coordinate::new(raw, &moves));
+        let move_count = moves.len();
+        let mut table = Self {
+            raw: phase.coordinates,
+            coordinates,
+            moves,
+            distances: Vec::new(),
+        };
+
+        // The goal states are those the goal group's moves reach from the solved cube
+        let goal_moves: Vec<usize> = phase
+            .goal_moves
+            .iter()
+            .filter(|m| allowed.contains(m))
+            .map(|m| {
+                table
+                    .moves
+                    .iter()
+                    .position(|other| other == m)
+                    .expect("goal moves are among the phase's moves")
+            })
+            .collect();
+        let size = table.coordinates[0].len() * table.coordinates[1].len();
+        let mut is_goal = vec![false; size];
+        is_goal[0] = true;
+        let mut queue = VecDeque::from([0]);
+        while let Some(state) = queue.pop_front() {
+            for &i in &goal_moves {
+                let next = table.next_state(state, i);
+                if !is_goal[next] {
+                    is_goal[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut distances = vec![u8::MAX; size];
+        let mut queue = VecDeque::new();
+        for (state, _) in is_goal.iter().enumerate().filter(|&(_, &goal)| goal) {
+            distances[state] = 0;
+            queue.push_back(state);
+        }
+        while let Some(state) = queue.pop_front() {
+            for i in 0..move_count {
+                let next = table.next_state(state, i);
+                if distances[next] == u8::MAX {
+                    distances[next] = distances[state] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        table.distances = distances;
+        table
+    }
+
+    fn next_state(&self, state: usize, i: usize) -> usize {
+        let [first, second] = &self.coordinates;
+        let (a, b) = (state / second.len(), state % second.len());
+        let a = first.transitions[a * self.moves.len() + i] as usize;
+        let b = second.transitions[b * self.moves.len() + i] as usize;
+        a * second.len() + b
+    }
+
+    /// The shortest phase moves into the goal group; `None` when the cube is not in
+    /// the group the phase starts from
+    fn solve(&self, cube: &CubieCube) -> Option<MoveSequence> {
+        let [first, second] = &self.coordinates;
+        let [first_raw, second_raw] = self.raw;
+        let a = *first.indices.get(&first_raw(cube))? as usize;
+        let b = *second.indices.get(&second_raw(cube))? as usize;
+        let mut state = a * second.len() + b;
+        let mut moves = Vec::new();
+        while self.distances[state] > 0 {
+            let distance = self.distances[state];
+            let i = (0..self.moves.len())
+                .find(|&i| self.distances[self.next_state(state, i)] < distance)?;
+            moves.push(face_turn(self.moves[i]));
+            state = self.next_state(state, i);
+        }
+        Some(MoveSequence::new(moves))
+    }
+}
+
+/// The table of every phase
+type Tables = [PhaseTable; 4];
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        PHASES
+            .each_ref()
+            .map(|phase| PhaseTable::new(phase, &G0_MOVES))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_phase_stays_in_its_group() {
+        let scramble = MoveSequence::parse("R U2 F' L D B2 R' U F2 D' L2 B").unwrap();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_moves(&scramble.moves);
+        let solution = solve(&cube).unwrap();
+        let mut solved = cube;
+        solved.apply_moves(&solution.moves.moves);
+        assert!(solved.is_solved(), "{}", solution.moves);
+
+        let mut rest = solution.moves.moves.as_slice();
+        for stage in &solution.stages {
+            let phase = phase_of_label(&stage.label).unwrap();
+            let (moves, after) = rest.split_at(stage.move_count);
+            let allowed: Vec<_> = groups()[phase].iter().map(|&m| face_turn(m)).collect();
+            assert!(
+                moves.iter().all(|mv| allowed.contains(mv)),
+                "{}: {:?}",
+                stage.label,
+                moves
+            );
+            rest = after;
+        }
+        assert!(rest.is_empty());
+    }
+}
@@ -0,0 +1,618 @@
+use crate::cube::CubeModelResource;
+use crate::cube_model::CubeModel;
+use crate::move_sequence::{Metric, MoveSequence};
+use crate::solver_integration::{SolverConfig, solve_with_config};
+use crate::solvers::Solution;
+use crate::solvers::fmc::{self, Date, Verification};
+use crate::solvers::optimal;
+use crate::ui::rotations_panel::MoveQueue;
+use bevy::color::palettes::css;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Time allowed for an attempt, as in official fewest-moves competition
+const CHALLENGE_TIME: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Component)]
+pub struct ChallengeToggleButton;
+
+#[derive(Component)]
+pub struct ChallengePanel;
+
+/// Starts an attempt on a scramble
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeStartButton {
+    /// The scramble of the day, the same for everyone
+    Daily,
+    Random,
+}
+
+/// Hands in the typed solution before the time is up
+#[derive(Component)]
+pub struct ChallengeSubmitButton;
+
+/// A key of the on-screen move pad, for typing without a hardware keyboard
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeKey {
+    /// A face turn or rotation, started as a new move
+    Move(&'static str),
+    /// Text added to the end as it is, such as a suffix or a comment
+    Text(&'static str),
+    Backspace,
+}
+
+impl ChallengeKey {
+    /// Rows of the move pad. Slices are left out, as FMC doesn't allow them.
+    const ROWS: [[(&'static str, ChallengeKey); 6]; 3] = [
+        [
+            ("R", ChallengeKey::Move("R")),
+            ("U", ChallengeKey::Move("U")),
+            ("F", ChallengeKey::Move("F")),
+            ("L", ChallengeKey::Move("L")),
+            ("D", ChallengeKey::Move("D")),
+            ("B", ChallengeKey::Move("B")),
+        ],
+        [
+            ("'", ChallengeKey::Text("'")),
+            ("2", ChallengeKey::Text("2")),
+            ("w", ChallengeKey::Text("w")),
+            ("x", ChallengeKey::Move("x")),
+            ("y", ChallengeKey::Move("y")),
+            ("z", ChallengeKey::Move("z")),
+        ],
+        [
+            ("(", ChallengeKey::Move("(")),
+            (")", ChallengeKey::Text(")")),
+            ("//", ChallengeKey::Text(" // ")),
+            ("Line", ChallengeKey::Text("\n")),
+            ("Space", ChallengeKey::Text(" ")),
+            ("Del", ChallengeKey::Backspace),
+        ],
+    ];
+
+    /// Type the key into `text`
+    fn type_into(self, text: &mut String) {
+        match self {
+            ChallengeKey::Move(token) => {
+                if !text.is_empty() && !text.ends_with([' ', '\n', '(']) {
+                    text.push(' ');
+                }
+                text.push_str(token);
+            }
+            ChallengeKey::Text(token) => text.push_str(token),
+            ChallengeKey::Backspace => {
+                text.pop();
+            }
+        }
+    }
+}
+
+/// Which line of the challenge panel a text shows
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeText {
+    Scramble,
+    Clock,
+    /// The solution as typed so far
+    Editor,
+    Result,
+}
+
+/// The attempt in progress or last finished, with the reference solutions searched
+/// for in the background while the user works
+#[derive(Resource, Default)]
+pub struct ChallengeState {
+    /// Name of the scramble, such as "Daily 2026-10-17"
+    title: String,
+    scramble: Option<MoveSequence>,
+    started: Option<Instant>,
+    /// The solution being typed, comments included
+    text: String,
+    /// The checked solution once the attempt is over, or why it could not be read
+    result: Option<Result<Verification, String>>,
+    two_phase: ReferenceSolve,
+    optimal: ReferenceSolve,
+    /// Time the optimal search gets once its tables are ready
+    optimal_budget: Duration,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Length of a reference solution, searched for in the background
+#[derive(Default)]
+enum ReferenceSolve {
+    #[default]
+    Idle,
+    /// The optimal search of these facelets starts once the pattern databases are built
+    WaitingForTables(String),
+    Running(Task<Option<usize>>),
+    Done(Option<usize>),
+    /// The attempt ended before the search did
+    Stopped,
+}
+
+impl ReferenceSolve {
+    fn poll(&mut self) {
+        if let ReferenceSolve::Running(task) = self
+            && let Some(length) = block_on(poll_once(task))
+        {
+            *self = ReferenceSolve::Done(length);
+        }
+    }
+
+    /// Give up on a search that has not finished
+    fn stop(&mut self) {
+        if matches!(
+            self,
+            ReferenceSolve::WaitingForTables(_) | ReferenceSolve::Running(_)
+        ) {
+            *self = ReferenceSolve::Stopped;
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            ReferenceSolve::Idle => "-".to_string(),
+            ReferenceSolve::WaitingForTables(_) => "building tables".to_string(),
+            ReferenceSolve::Running(_) => "searching…".to_string(),
+            ReferenceSolve::Stopped => "stopped".to_string(),
+            ReferenceSolve::Done(Some(length)) => length.to_string(),
+            ReferenceSolve::Done(None) => "not found".to_string(),
+        }
+    }
+}
+
+impl ChallengeState {
+    fn is_running(&self) -> bool {
+        self.started.is_some() && self.result.is_none()
+    }
+
+    /// Start an attempt on `scramble`, with fresh reference solves within the limits
+    /// of `config`
+    fn start(&mut self, title: String, scramble: MoveSequence, config: &SolverConfig) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.cancelled = Arc::new(AtomicBool::new(false));
+
+        let mut model = CubeModel::solved();
+        model.apply_moves(&scramble.moves);
+        let facelets = model.to_facelet_string();
+        let (cancelled, task_facelets) = (self.cancelled.clone(), facelets.clone());
+        let task_config = config.clone();
+        self.two_phase = ReferenceSolve::Running(AsyncComputeTaskPool::get().spawn(async move {
+            let stop = || cancelled.load(Ordering::Relaxed);
+            solve_with_config(&task_facelets, &task_config, stop, |_| {})
+                .ok()
+                .and_then(|output| output.solutions.first().map(Solution::move_count))
+        }));
+        optimal::prepare_tables();
+        self.optimal = ReferenceSolve::WaitingForTables(facelets);
+        self.optimal_budget = config.optimal_budget;
+        self.start_optimal_search();
+
+        self.title = title;
+        self.scramble = Some(scramble);
+        self.started = Some(Instant::now());
+        self.text.clear();
+        self.result = None;
+    }
+
+    /// Start the optimal search once the pattern databases are ready, so no pool
+    /// thread waits for them
+    fn start_optimal_search(&mut self) {
+        let ReferenceSolve::WaitingForTables(facelets) = &self.optimal else {
+            return;
+        };
+        if !optimal::tables_ready() {
+            return;
+        }
+        let facelets = facelets.clone();
+        let cancelled = self.cancelled.clone();
+        let deadline = Instant::now() + self.optimal_budget;
+        self.optimal = ReferenceSolve::Running(AsyncComputeTaskPool::get().spawn(async move {
+            let stop = || cancelled.load(Ordering::Relaxed) || Instant::now() >= deadline;
+            optimal::solve(&facelets, Metric::Htm, stop)
+                .ok()
+                .map(|solution| solution.split_whitespace().count())
+        }));
+    }
+
+    /// End the attempt, stop the reference searches and check the typed solution
+    fn submit(&mut self) {
+        let Some(scramble) = &self.scramble else {
+            return;
+        };
+        self.result =
+            Some(fmc::parse_solution(&self.text).map(|solution| fmc::verify(scramble, &solution)));
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.two_phase.poll();
+        self.optimal.poll();
+        self.two_phase.stop();
+        self.optimal.stop();
+    }
+
+    fn time_left(&self) -> Duration {
+        self.started.map_or(CHALLENGE_TIME, |started| {
+            CHALLENGE_TIME.saturating_sub(started.elapsed())
+        })
+    }
+}
+
+/// Creates the challenge toggle button and the (hidden) challenge panel
+pub fn create_challenge_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(170.0), // Left of the FMC toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            ChallengeToggleButton,
+            Name::new("Challenge Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("1h"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0),
+                right: Val::Px(20.0),
+                width: Val::Px(300.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            ChallengePanel,
+            Name::new("Challenge Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("FMC challenge"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            panel_parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|row_parent| {
+                    spawn_panel_button(row_parent, "Daily", ChallengeStartButton::Daily);
+                    spawn_panel_button(row_parent, "Random", ChallengeStartButton::Random);
+                    spawn_panel_button(row_parent, "Submit", ChallengeSubmitButton);
+                });
+            spawn_panel_text(
+                panel_parent,
+                "Pick a scramble to start the clock",
+                ChallengeText::Scramble,
+            );
+            spawn_panel_text(panel_parent, "", ChallengeText::Clock);
+            panel_parent
+                .spawn((
+                    Node {
+                        min_height: Val::Px(80.0),
+                        padding: UiRect::all(Val::Px(4.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::from(css::BLACK).with_alpha(0.6)),
+                    BorderColor(css::WHITE.into()),
+                    Name::new("Challenge Editor"),
+                ))
+                .with_children(|editor_parent| {
+                    spawn_panel_text(editor_parent, "", ChallengeText::Editor);
+                });
+            for row in ChallengeKey::ROWS {
+                panel_parent
+                    .spawn(Node {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    })
+                    .with_children(|row_parent| {
+                        for (label, key) in row {
+                            spawn_key(row_parent, label, key);
+                        }
+                    });
+            }
+            spawn_panel_text(panel_parent, "", ChallengeText::Result);
+        });
+}
+
+fn spawn_key(parent: &mut ChildBuilder, label: &str, key: ChallengeKey) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(43.0),
+                height: Val::Px(32.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.6)),
+            BorderColor(css::WHITE.into()),
+            key,
+            Name::new(format!("Challenge Key {}", label)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+fn spawn_panel_button(parent: &mut ChildBuilder, label: &str, marker: impl Component) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(70.0),
+                height: Val::Px(28.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(css::DIM_GRAY.into()),
+            BorderColor(css::WHITE.into()),
+            marker,
+            Name::new(format!("{} Challenge Button", label)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+fn spawn_panel_text(parent: &mut ChildBuilder, text: &str, marker: impl Component) {
+    parent.spawn((
+        Text::new(text),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(css::WHITE.into()),
+        marker,
+    ));
+}
+
+/// System to show and hide the challenge panel
+pub fn handle_challenge_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ChallengeToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<ChallengePanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to scramble the cube and start the clock
+pub fn handle_challenge_start(
+    interaction_query: Query<(&Interaction, &ChallengeStartButton), Changed<Interaction>>,
+    mut state: ResMut<ChallengeState>,
+    mut cube_model: ResMut<CubeModelResource>,
+    mut move_queue: ResMut<MoveQueue>,
+    solver_config: Res<SolverConfig>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (title, scramble) = match button {
+            ChallengeStartButton::Daily => {
+                let today = Date::today();
+                (format!("Daily {}", today), fmc::daily_scramble(today))
+            }
+            ChallengeStartButton::Random => {
+                let seed = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+                ("Random".to_string(), fmc::random_scramble(seed))
+            }
+        };
+        info!("Starting FMC challenge {}: {}", title, scramble);
+
+        // Faces are re-rendered from the scrambled model
+        let mut model = CubeModel::solved();
+        model.apply_moves(&scramble.moves);
+        cube_model.model = model;
+        move_queue.load_solution(&Solution::default());
+        move_queue.current = None;
+        move_queue.highlight_index = None;
+
+        state.start(title, scramble, &solver_config);
+    }
+}
+
+/// System to type the solution while an attempt runs
+pub fn handle_challenge_typing(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    panel_query: Query<&Visibility, With<ChallengePanel>>,
+    mut state: ResMut<ChallengeState>,
+) {
+    let is_open = panel_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden);
+    if !is_open || !state.is_running() {
+        keyboard_events.clear();
+        return;
+    }
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(text) => state.text.push_str(text),
+            Key::Space => state.text.push(' '),
+            Key::Enter => state.text.push('\n'),
+            Key::Backspace => {
+                state.text.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// System to type with the on-screen move pad while an attempt runs
+pub fn handle_challenge_keys(
+    interaction_query: Query<(&Interaction, &ChallengeKey), Changed<Interaction>>,
+    mut state: ResMut<ChallengeState>,
+) {
+    for (interaction, key) in &interaction_query {
+        if *interaction == Interaction::Pressed && state.is_running() {
+            key.type_into(&mut state.text);
+        }
+    }
+}
+
+/// System to hand in the solution early
+pub fn handle_challenge_submit(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ChallengeSubmitButton>)>,
+    mut state: ResMut<ChallengeState>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed && state.is_running() {
+            state.submit();
+        }
+    }
+}
+
+/// System to run the clock, collect the reference solves and refresh the panel
+pub fn update_challenge_panel(
+    mut state: ResMut<ChallengeState>,
+    panel_query: Query<&Visibility, With<ChallengePanel>>,
+    mut text_query: Query<(&mut Text, &ChallengeText)>,
+) {
+    if state.is_running() && state.time_left().is_zero() {
+        info!("FMC challenge time is up");
+        state.submit();
+    }
+    let state = state.bypass_change_detection();
+    state.start_optimal_search();
+    state.two_phase.poll();
+    state.optimal.poll();
+
+    let is_open = panel_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden);
+    let Some(scramble) = state.scramble.as_ref().filter(|_| is_open) else {
+        return;
+    };
+    for (mut text, line) in &mut text_query {
+        text.0 = match line {
+            ChallengeText::Scramble => format!("{}: {}", state.title, scramble),
+            ChallengeText::Clock if state.is_running() => {
+                let seconds = state.time_left().as_secs();
+                format!("{:02}:{:02} left", seconds / 60, seconds % 60)
+            }
+            ChallengeText::Clock => "Attempt over".to_string(),
+            ChallengeText::Editor if state.is_running() => format!("{}|", state.text),
+            ChallengeText::Editor => state.text.clone(),
+            ChallengeText::Result => describe_result(state),
+        };
+    }
+}
+
+/// The verdict on the submitted solution next to the reference solutions
+fn describe_result(state: &ChallengeState) -> String {
+    let verdict = match &state.result {
+        None => return String::new(),
+        Some(Err(error)) => format!("Cannot read the solution: {}", error),
+        Some(Ok(verification)) if verification.is_solved() => {
+            format!("Solved in {} moves", verification.move_count)
+        }
+        Some(Ok(verification)) => {
+            let mut unsolved = Vec::new();
+            if !verification.unsolved_corners.is_empty() {
+                unsolved.push(format!(
+                    "corners {}",
+                    verification.unsolved_corners.join(", ")
+                ));
+            }
+            if !verification.unsolved_edges.is_empty() {
+                unsolved.push(format!("edges {}", verification.unsolved_edges.join(", ")));
+            }
+            format!(
+                "Not solved after {} moves; unsolved {}",
+                verification.move_count,
+                unsolved.join("; ")
+            )
+        }
+    };
+    format!(
+        "{}\nOptimal: {}, min2phase: {}",
+        verdict,
+        state.optimal.describe(),
+        state.two_phase.describe()
+    )
+}
+
+/// Plugin for the fewest-moves challenge
+pub struct ChallengePanelPlugin;
+
+impl Plugin for ChallengePanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChallengeState>()
+            .add_systems(Startup, create_challenge_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_challenge_toggle,
+                    handle_challenge_start,
+                    handle_challenge_typing,
+                    handle_challenge_keys,
+                    handle_challenge_submit,
+                    update_challenge_panel,
+                ),
+            );
+    }
+}
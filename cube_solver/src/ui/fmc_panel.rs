@@ -0,0 +1,414 @@
+use crate::cube::CubeModelResource;
+use crate::cube_model::{CubeModel, CubieCube};
+use crate::solver_integration::CubeSolverResource;
+use crate::solvers::Solution;
+use crate::solvers::fmc::{self, Finish, Side};
+use crate::ui::rotations_panel::MoveQueue;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Longest skeleton the Finish button searches for
+const FINISH_SKELETON_LENGTH: usize = 6;
+
+#[derive(Component)]
+pub struct FmcToggleButton;
+
+#[derive(Component)]
+pub struct FmcPanel;
+
+/// Switches the cube between the normal and the inverse scramble
+#[derive(Component)]
+pub struct NissButton;
+
+/// Searches for a skeleton and an insertion that finish the cube
+#[derive(Component)]
+pub struct FinishButton;
+
+/// Side of the NISS attempt
+#[derive(Component)]
+pub struct NissSideText;
+
+/// Edge orientation and domino reduction of the current cube
+#[derive(Component)]
+pub struct FmcReportText;
+
+/// Outcome of the last Finish search
+#[derive(Component)]
+pub struct FinishResultText;
+
+/// Fewest-moves state of the app: the NISS side and the Finish search running in the background
+#[derive(Resource, Default)]
+pub struct FmcState {
+    pub side: Side,
+    /// Facelets of the cube the report is for
+    facelets: Option<String>,
+    /// Facelets of the cube the Finish search is for
+    finish_facelets: Option<String>,
+    task: Option<Task<Result<Finish, String>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl FmcState {
+    /// Start a Finish search on `facelets`, superseding any search still running
+    fn spawn_finish(&mut self, facelets: String, cube: CubieCube) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled = self.cancelled.clone();
+        self.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            fmc::find_finish(&cube, FINISH_SKELETON_LENGTH, &|| {
+                cancelled.load(Ordering::Relaxed)
+            })
+        }));
+        self.finish_facelets = Some(facelets);
+    }
+}
+
+/// Creates the FMC toggle button and the (hidden) FMC panel
+pub fn create_fmc_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(120.0), // Left of the inspection toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            FmcToggleButton,
+            Name::new("FMC Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("FMC"),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0),
+                left: Val::Px(290.0), // Right of the inspection panel
+                width: Val::Px(250.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            FmcPanel,
+            Name::new("FMC Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("Fewest moves"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            panel_parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                })
+                .with_children(|row_parent| {
+                    spawn_panel_button(row_parent, "NISS", NissButton);
+                    spawn_panel_button(row_parent, "Finish", FinishButton);
+                    row_parent.spawn((
+                        Text::new(side_label(Side::Normal)),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                        NissSideText,
+                    ));
+                });
+            panel_parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                FmcReportText,
+            ));
+            panel_parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::LIGHT_GREEN.into()),
+                FinishResultText,
+            ));
+        });
+}
+
+fn spawn_panel_button(parent: &mut ChildBuilder, label: &str, marker: impl Component) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(60.0),
+                height: Val::Px(28.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(css::DIM_GRAY.into()),
+            BorderColor(css::WHITE.into()),
+            marker,
+            Name::new(format!("{} Button", label)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+fn side_label(side: Side) -> &'static str {
+    match side {
+        Side::Normal => "Normal scramble",
+        Side::Inverse => "Inverse scramble",
+    }
+}
+
+/// System to show and hide the FMC panel
+pub fn handle_fmc_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<FmcToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<FmcPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to replace the cube with its inverse, the other side of a NISS attempt
+pub fn handle_niss_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<NissButton>)>,
+    solver: Res<CubeSolverResource>,
+    mut cube_model: ResMut<CubeModelResource>,
+    mut move_queue: ResMut<MoveQueue>,
+    mut state: ResMut<FmcState>,
+    mut side_query: Query<&mut Text, With<NissSideText>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(cube) = solver
+            .facelets()
+            .filter(|_| solver.is_valid())
+            .and_then(|facelets| CubieCube::from_facelets(facelets).ok())
+        else {
+            info!("NISS needs a valid cube");
+            continue;
+        };
+
+        // Faces are re-rendered from the inverse; moves loaded for the other side no longer apply
+        cube_model.model = CubeModel::from_cubie(&cube.inverse());
+        move_queue.load_solution(&Solution::default());
+        move_queue.current = None;
+        move_queue.highlight_index = None;
+
+        state.side = state.side.other();
+        if let Ok(mut text) = side_query.get_single_mut() {
+            text.0 = side_label(state.side).to_string();
+        }
+        info!("Switched to the {}", side_label(state.side).to_lowercase());
+    }
+}
+
+/// System to describe edge orientation and domino reduction while the panel is open,
+/// once per cube state
+pub fn update_fmc_report(
+    panel_query: Query<&Visibility, With<FmcPanel>>,
+    solver: Res<CubeSolverResource>,
+    mut state: ResMut<FmcState>,
+    mut report_query: Query<&mut Text, With<FmcReportText>>,
+) {
+    let is_open = panel_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden);
+    if !is_open {
+        return;
+    }
+    let facelets = solver.facelets().filter(|_| solver.is_valid());
+    if state.facelets.as_deref() == facelets {
+        return;
+    }
+    state.facelets = facelets.map(str::to_string);
+    let Ok(mut text) = report_query.get_single_mut() else {
+        return;
+    };
+    let Some(cube) = facelets.and_then(|facelets| CubieCube::from_facelets(facelets).ok()) else {
+        text.0 = "Paint a valid cube to analyse it".to_string();
+        return;
+    };
+
+    let report = fmc::analyze(&cube);
+    let mut lines = vec![format!(
+        "EO bad edges: {}",
+        report
+            .bad_edges
+            .iter()
+            .map(|(axis, count)| format!("{} {}", fmc::axis_name(*axis), count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )];
+    for domino in &report.domino {
+        let axis = fmc::axis_name(domino.axis);
+        lines.push(if domino.is_reduced() {
+            format!("DR {}: reached", axis)
+        } else {
+            format!(
+                "DR {} from EO {}: {} corners, {} edges, {} slice edges",
+                axis,
+                fmc::axis_name(domino.edge_axis),
+                domino.bad_corners,
+                domino.bad_edges,
+                domino.misplaced_slice_edges
+            )
+        });
+    }
+    text.0 = lines.join("\n");
+}
+
+/// System to start a Finish search on the current cube
+pub fn handle_finish_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<FinishButton>)>,
+    solver: Res<CubeSolverResource>,
+    mut state: ResMut<FmcState>,
+    mut result_query: Query<&mut Text, With<FinishResultText>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Ok(mut text) = result_query.get_single_mut() else {
+            continue;
+        };
+        let Some((facelets, cube)) = solver
+            .facelets()
+            .filter(|_| solver.is_valid())
+            .and_then(|facelets| Some((facelets, CubieCube::from_facelets(facelets).ok()?)))
+        else {
+            text.0 = "Paint a valid cube first".to_string();
+            continue;
+        };
+        state.spawn_finish(facelets.to_string(), cube);
+        text.0 = format!(
+            "Searching skeletons of up to {} moves…",
+            FINISH_SKELETON_LENGTH
+        );
+    }
+}
+
+/// System to collect a finished search and load its solution into the move queue
+pub fn update_finish_result(
+    mut state: ResMut<FmcState>,
+    solver: Res<CubeSolverResource>,
+    mut move_queue: ResMut<MoveQueue>,
+    mut result_query: Query<&mut Text, With<FinishResultText>>,
+) {
+    let Some(task) = state.bypass_change_detection().task.as_mut() else {
+        return;
+    };
+    let Some(result) = block_on(poll_once(task)) else {
+        return;
+    };
+    state.task = None;
+    let Ok(mut text) = result_query.get_single_mut() else {
+        return;
+    };
+    // The cube was turned while the search ran, so its moves no longer apply
+    if state.finish_facelets.as_deref() != solver.facelets() {
+        text.0 = String::new();
+        return;
+    }
+
+    let finish = match result {
+        Ok(finish) => finish,
+        Err(error) => {
+            text.0 = error;
+            return;
+        }
+    };
+    let (label, explanation) = match &finish.insertion {
+        Some(insertion) => (
+            "Skeleton with insertion",
+            format!(
+                "Skeleton {} with {} inserted after move {}, cancelling {} moves",
+                finish.skeleton, insertion.algorithm, insertion.position, insertion.cancelled
+            ),
+        ),
+        None => (
+            "Skeleton",
+            format!("Skeleton {} solves the cube by itself", finish.skeleton),
+        ),
+    };
+    text.0 = format!("{} moves: {}", finish.solution().len(), explanation);
+    let mut solution = Solution::default();
+    solution.push_stage(label, explanation, finish.solution());
+    move_queue.load_solution(&solution);
+    move_queue.current = None;
+    move_queue.highlight_index = Some(0);
+    info!("Loaded fewest-moves finish: {}", finish.solution());
+}
+
+/// Plugin for the fewest-moves panel
+pub struct FmcPanelPlugin;
+
+impl Plugin for FmcPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FmcState>()
+            .add_systems(Startup, create_fmc_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_fmc_toggle,
+                    handle_niss_button,
+                    update_fmc_report,
+                    handle_finish_button,
+                    update_finish_result,
+                ),
+            );
+    }
+}
@@ -0,0 +1,562 @@
+use crate::cube_model::CubieCube;
+use crate::solver_integration::{CubeSolverResource, SolverConfig};
+use crate::solvers::generator::{
+    self, CaseGoal, Generated, GeneratorOptions, MAX_LENGTH, MAX_RESULTS,
+};
+use crate::solvers::move_set::MoveSet;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Algorithms listed per page of the panel
+const PAGE_SIZE: usize = 6;
+
+#[derive(Component)]
+pub struct GeneratorToggleButton;
+
+#[derive(Component)]
+pub struct GeneratorPanel;
+
+/// Option of the search, or the page of results, edited by a row of the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratorSetting {
+    Goal,
+    Moves,
+    Length,
+    Page,
+}
+
+impl GeneratorSetting {
+    pub const ALL: [GeneratorSetting; 4] = [
+        GeneratorSetting::Goal,
+        GeneratorSetting::Moves,
+        GeneratorSetting::Length,
+        GeneratorSetting::Page,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GeneratorSetting::Goal => "Goal",
+            GeneratorSetting::Moves => "Moves",
+            GeneratorSetting::Length => "Length",
+            GeneratorSetting::Page => "Page",
+        }
+    }
+
+    pub fn format_value(self, state: &GeneratorPanelState) -> String {
+        let options = &state.options;
+        match self {
+            GeneratorSetting::Goal => options.goal.to_string(),
+            GeneratorSetting::Moves if options.move_set.has_all_faces() => "All".to_string(),
+            GeneratorSetting::Moves => options.move_set.to_string(),
+            GeneratorSetting::Length => format!("up to {}", options.max_length),
+            GeneratorSetting::Page => format!("{} of {}", state.page + 1, state.pages()),
+        }
+    }
+
+    /// Step the option or the page, wrapping around; lengths stop at 1 and `MAX_LENGTH`
+    pub fn adjust(self, state: &mut GeneratorPanelState, step: i32) {
+        let wrap = |index: usize, count: usize| (index as i32 + step).rem_euclid(count as i32);
+        match self {
+            GeneratorSetting::Goal => {
+                let goals = CaseGoal::ALL;
+                let index = goals
+                    .iter()
+                    .position(|&goal| goal == state.options.goal)
+                    .unwrap_or(0);
+                state.options.goal = goals[wrap(index, goals.len()) as usize];
+            }
+            GeneratorSetting::Moves => {
+                let presets = MoveSet::presets();
+                let index = presets
+                    .iter()
+                    .position(|set| *set == state.options.move_set)
+                    .unwrap_or(0);
+                state.options.move_set = presets[wrap(index, presets.len()) as usize].clone();
+            }
+            GeneratorSetting::Length => {
+                state.options.max_length =
+                    (state.options.max_length as i32 + step).clamp(1, MAX_LENGTH as i32) as usize;
+            }
+            GeneratorSetting::Page => {
+                state.page = wrap(state.page, state.pages()) as usize;
+            }
+        }
+    }
+}
+
+/// Button that steps a row of the panel by `step`
+#[derive(Component)]
+pub struct GeneratorAdjustButton {
+    pub setting: GeneratorSetting,
+    pub step: i32,
+}
+
+#[derive(Component)]
+pub struct GeneratorValueLabel {
+    pub setting: GeneratorSetting,
+}
+
+/// Starts listing the algorithms for the cube on screen
+#[derive(Component)]
+pub struct GenerateButton;
+
+#[derive(Component)]
+pub struct GeneratorStatusText;
+
+/// Algorithms of the current page, rebuilt when the page or the results change
+#[derive(Component)]
+pub struct GeneratorEntries;
+
+/// Saves an algorithm of the results into the user's library
+#[derive(Component)]
+pub struct GeneratorEntryButton {
+    pub index: usize,
+}
+
+/// Options of the search, the case it ran for and what it found
+#[derive(Resource, Default)]
+pub struct GeneratorPanelState {
+    pub options: GeneratorOptions,
+    pub page: usize,
+    /// The case and goal the results are for
+    case: Option<(CubieCube, CaseGoal)>,
+    generated: Generated,
+    status: String,
+    task: Option<Task<Result<Generated, String>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl GeneratorPanelState {
+    fn pages(&self) -> usize {
+        self.generated.algorithms.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    /// Start listing algorithms for `case`, superseding any search still running
+    fn spawn(&mut self, case: CubieCube) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled = self.cancelled.clone();
+        let options = self.options.clone();
+        self.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            generator::generate(&case, &options, &|| cancelled.load(Ordering::Relaxed))
+        }));
+        self.case = Some((case, self.options.goal));
+        self.generated = Generated::default();
+        self.page = 0;
+        self.status = format!(
+            "Searching {} up to {} moves…",
+            self.options.move_set, self.options.max_length
+        );
+    }
+}
+
+/// Creates the generator toggle button and the (hidden) generator panel
+pub fn create_generator_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(370.0), // Left of the state toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            GeneratorToggleButton,
+            Name::new("Generator Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("Gen"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0),
+                right: Val::Px(20.0),
+                width: Val::Px(320.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            GeneratorPanel,
+            Name::new("Generator Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("Algorithms for this case"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            for setting in GeneratorSetting::ALL {
+                if setting == GeneratorSetting::Page {
+                    spawn_generate_button(panel_parent);
+                    panel_parent.spawn((
+                        Text::new("Set up the case on the cube, then press Generate"),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                        GeneratorStatusText,
+                    ));
+                }
+                spawn_setting_row(panel_parent, setting);
+            }
+            panel_parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                GeneratorEntries,
+                Name::new("Generator Entries"),
+            ));
+        });
+}
+
+/// A row with a label, and a value between a "<" and a ">" button
+fn spawn_setting_row(parent: &mut ChildBuilder, setting: GeneratorSetting) {
+    parent
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            Name::new(format!("Generator {} Row", setting.label())),
+        ))
+        .with_children(|row_parent| {
+            row_parent.spawn((
+                Text::new(setting.label()),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                Node {
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+            ));
+            spawn_adjust_button(row_parent, setting, -1, "<");
+            row_parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    width: Val::Px(140.0),
+                    ..default()
+                },
+                GeneratorValueLabel { setting },
+            ));
+            spawn_adjust_button(row_parent, setting, 1, ">");
+        });
+}
+
+fn spawn_adjust_button(
+    parent: &mut ChildBuilder,
+    setting: GeneratorSetting,
+    step: i32,
+    text: &str,
+) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(32.0),
+                height: Val::Px(28.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(css::DIM_GRAY.into()),
+            BorderColor(css::WHITE.into()),
+            GeneratorAdjustButton { setting, step },
+            Name::new(format!("Generator {} {} Button", setting.label(), text)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+fn spawn_generate_button(parent: &mut ChildBuilder) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                height: Val::Px(32.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(css::DARK_GREEN.into()),
+            BorderColor(css::WHITE.into()),
+            GenerateButton,
+            Name::new("Generate Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("Generate"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+/// System to show and hide the generator panel
+pub fn handle_generator_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<GeneratorToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<GeneratorPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to apply presses of the < and > buttons
+pub fn handle_generator_adjust(
+    interaction_query: Query<(&Interaction, &GeneratorAdjustButton), Changed<Interaction>>,
+    mut state: ResMut<GeneratorPanelState>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            button.setting.adjust(&mut state, button.step);
+        }
+    }
+}
+
+/// System to start a search for the cube on screen when Generate is pressed
+pub fn handle_generate_press(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<GenerateButton>)>,
+    solver: Res<CubeSolverResource>,
+    mut state: ResMut<GeneratorPanelState>,
+) {
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let case = solver
+            .facelets()
+            .filter(|_| solver.is_valid())
+            .map(CubieCube::from_facelets);
+        match case {
+            Some(Ok(case)) => {
+                info!("Generating algorithms: {:?}", state.options);
+                state.spawn(case);
+            }
+            Some(Err(error)) => state.status = error.to_string(),
+            None => state.status = solver.get_validation_message(),
+        }
+    }
+}
+
+/// System to collect a finished search
+pub fn poll_generator_task(mut state: ResMut<GeneratorPanelState>) {
+    let Some(task) = state.bypass_change_detection().task.as_mut() else {
+        return;
+    };
+    let Some(result) = block_on(poll_once(task)) else {
+        return;
+    };
+    state.task = None;
+    match result {
+        Ok(generated) => {
+            let count = generated.algorithms.len();
+            let length = state.options.max_length;
+            state.status = if count == 0 {
+                format!("No algorithms up to {} moves", length)
+            } else if generated.complete {
+                format!(
+                    "{} up to {} moves, easiest first; tap one to save it",
+                    count, length
+                )
+            } else if count >= MAX_RESULTS {
+                format!(
+                    "The first {} found, easiest first; tap one to save it",
+                    count
+                )
+            } else {
+                format!("Stopped after {}, easiest first; tap one to save it", count)
+            };
+            state.generated = generated;
+        }
+        Err(error) => state.status = error,
+    }
+}
+
+/// System to save the pressed algorithm into the library, named after the case
+pub fn handle_generator_entry_press(
+    interaction_query: Query<(&Interaction, &GeneratorEntryButton), Changed<Interaction>>,
+    mut state: ResMut<GeneratorPanelState>,
+    mut config: ResMut<SolverConfig>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (Some((case, goal)), Some(algorithm)) =
+            (state.case, state.generated.algorithms.get(button.index))
+        else {
+            continue;
+        };
+        let (set, name) = generator::case_name(&case, goal);
+        let algorithm = algorithm.moves.to_string();
+        let saved = config
+            .library
+            .add(set, &name, &algorithm)
+            .and_then(|_| config.library.save().map_err(|error| error.to_string()));
+        state.status = match saved {
+            Ok(()) => {
+                info!("Saved to {} as {}: {}", set, name, algorithm);
+                format!("Saved to {} as {}", set, name)
+            }
+            Err(error) => {
+                warn!("Could not save the algorithm: {}", error);
+                error
+            }
+        };
+    }
+}
+
+/// System to refresh the labels, status and listed page when anything changes
+pub fn update_generator_panel(
+    state: Res<GeneratorPanelState>,
+    mut label_query: Query<(&mut Text, &GeneratorValueLabel), Without<GeneratorStatusText>>,
+    mut status_query: Query<&mut Text, (With<GeneratorStatusText>, Without<GeneratorValueLabel>)>,
+    entries_query: Query<Entity, With<GeneratorEntries>>,
+    mut commands: Commands,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for (mut text, label) in &mut label_query {
+        text.0 = label.setting.format_value(&state);
+    }
+    if let Ok(mut text) = status_query.get_single_mut() {
+        text.0 = state.status.clone();
+    }
+
+    let Ok(entries) = entries_query.get_single() else {
+        return;
+    };
+    let page = state.page.min(state.pages() - 1);
+    commands.entity(entries).despawn_descendants();
+    commands.entity(entries).with_children(|parent| {
+        let listed = state
+            .generated
+            .algorithms
+            .iter()
+            .enumerate()
+            .skip(page * PAGE_SIZE)
+            .take(PAGE_SIZE);
+        for (index, algorithm) in listed {
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::axes(Val::Px(6.0), Val::Px(4.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    BackgroundColor(css::DIM_GRAY.into()),
+                    BorderColor(css::WHITE.into()),
+                    GeneratorEntryButton { index },
+                    Name::new(format!("Generated Algorithm {}", index)),
+                ))
+                .with_children(|button_parent| {
+                    button_parent.spawn((
+                        Text::new(algorithm.moves.to_string()),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                    ));
+                    button_parent.spawn((
+                        Text::new(format!(
+                            "{} moves, ergonomics score {:.1}",
+                            algorithm.moves.len(),
+                            algorithm.score
+                        )),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(css::LIGHT_GRAY.into()),
+                    ));
+                });
+        }
+    });
+}
+
+/// Plugin for the algorithm generator panel
+pub struct GeneratorPanelPlugin;
+
+impl Plugin for GeneratorPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GeneratorPanelState>()
+            .add_systems(Startup, create_generator_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_generator_toggle,
+                    handle_generator_adjust,
+                    handle_generate_press,
+                    poll_generator_task,
+                    handle_generator_entry_press,
+                    update_generator_panel,
+                ),
+            );
+    }
+}
@@ -0,0 +1,336 @@
+use crate::cube::{CubeModelResource, HighlightedPieces};
+use crate::cube_model::{CENTER_FACELETS, CORNER_FACELETS, CubeModel, CubieCube, EDGE_FACELETS};
+use crate::solver_integration::{CubeSolverResource, FaceColor};
+use crate::solvers::Solution;
+use crate::solvers::hint::{self, Hint};
+use crate::ui::color_panel::COLOR_NAMES;
+use crate::ui::rotations_panel::MoveQueue;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+
+/// Pieces are named in the goal only up to this many; the highlight shows the rest
+const NAMED_PIECES: usize = 4;
+
+/// Button next to Solve: the first press reveals the goal of the next stage, the
+/// second its moves
+#[derive(Component)]
+pub struct HintButton;
+
+#[derive(Component)]
+pub struct HintButtonText;
+
+#[derive(Component)]
+pub struct HintCaption;
+
+#[derive(Component)]
+pub struct HintCaptionText;
+
+/// The hint revealed for the current cube
+#[derive(Resource, Default)]
+pub struct HintState {
+    /// Facelets of the cube the hint was given for, as the solver sees them
+    facelets: Option<String>,
+    hint: Option<Hint>,
+    /// Goal of the hint, spelled out with the pieces it works on
+    goal: String,
+    /// Cube once the stage is done; the hint is cleared when it is reached
+    done: Option<CubieCube>,
+    /// The moves of the hint are in the queue
+    moves_shown: bool,
+    /// A press is waiting for the background solve
+    waiting: bool,
+}
+
+impl HintState {
+    /// Whether the next press reveals the moves of the hint for `facelets`
+    fn reveals_moves(&self, facelets: Option<&str>) -> bool {
+        self.hint.is_some()
+            && !self.moves_shown
+            && facelets.is_some()
+            && self.facelets.as_deref() == facelets
+    }
+
+    fn clear(&mut self, highlighted: &mut HighlightedPieces) {
+        *self = Self::default();
+        highlighted.pieces = None;
+    }
+}
+
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HintState>()
+            .add_systems(Startup, create_hint_caption)
+            .add_systems(
+                Update,
+                (
+                    handle_hint_button_clicks,
+                    show_hint_when_solved,
+                    clear_finished_hint,
+                    update_hint_button,
+                    update_hint_caption,
+                ),
+            );
+    }
+}
+
+/// Caption with the goal of the next stage, in the place of the stage caption
+fn create_hint_caption(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(188.0), // Above the solution list
+                left: Val::Px(20.0),
+                right: Val::Px(20.0),
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::DARK_SLATE_BLUE).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(6.0)),
+            HintCaption,
+            Name::new("Hint Caption"),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                HintCaptionText,
+            ));
+        });
+}
+
+/// System to reveal the goal of the next stage, or its moves when the goal is already shown
+fn handle_hint_button_clicks(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<HintButton>)>,
+    mut solver: ResMut<CubeSolverResource>,
+    mut state: ResMut<HintState>,
+    mut highlighted: ResMut<HighlightedPieces>,
+    mut move_queue: ResMut<MoveQueue>,
+    cube_model: Res<CubeModelResource>,
+) {
+    for interaction in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if !solver.is_valid() {
+            log::info!("Hint button pressed but not active");
+            log::info!("Issue: {}", solver.get_validation_message());
+        } else if state.reveals_moves(solver.facelets()) {
+            let Some(hint) = &state.hint else {
+                continue;
+            };
+            log::info!("Hint: moves of {}: {}", hint.label, hint.moves);
+            move_queue.load_solution(&hint.solution());
+            move_queue.current = None;
+            move_queue.highlight_index = Some(0);
+            solver.set_solving(true);
+            state.moves_shown = true;
+        } else if solver.is_solvable() {
+            show_goal(
+                &solver,
+                &cube_model.model,
+                &mut state,
+                &mut highlighted,
+                &mut move_queue,
+            );
+        } else {
+            state.clear(&mut highlighted);
+            state.waiting = true;
+            log::info!("Hint button pressed - waiting for the background solver");
+        }
+    }
+}
+
+/// System to reveal the goal a press was waiting for once the background solve is done
+fn show_hint_when_solved(
+    solver: Res<CubeSolverResource>,
+    mut state: ResMut<HintState>,
+    mut highlighted: ResMut<HighlightedPieces>,
+    mut move_queue: ResMut<MoveQueue>,
+    cube_model: Res<CubeModelResource>,
+) {
+    if !state.waiting || !solver.is_changed() {
+        return;
+    }
+    if solver.is_solvable() {
+        show_goal(
+            &solver,
+            &cube_model.model,
+            &mut state,
+            &mut highlighted,
+            &mut move_queue,
+        );
+    } else if !solver.is_valid() {
+        log::info!("No hint: {}", solver.get_validation_message());
+        state.waiting = false;
+    }
+}
+
+/// Take the next stage of the selected solution as the hint and highlight its pieces.
+/// The queue is emptied so no earlier solution gives the moves away.
+fn show_goal(
+    solver: &CubeSolverResource,
+    model: &CubeModel,
+    state: &mut HintState,
+    highlighted: &mut HighlightedPieces,
+    move_queue: &mut MoveQueue,
+) {
+    state.clear(highlighted);
+    let Some(facelets) = solver.facelets() else {
+        return;
+    };
+    let Ok(cube) = CubieCube::from_facelets(facelets) else {
+        return;
+    };
+    let solution = solver
+        .solutions()
+        .get(solver.selected_solution())
+        .cloned()
+        .unwrap_or_default();
+    let Some(hint) = hint::next_stage(&cube, &solution) else {
+        log::info!("Hint: the cube is already solved");
+        return;
+    };
+
+    let mut done = cube;
+    done.apply_moves(&hint.moves.moves);
+    let pieces = piece_names(model, &hint.corners, &hint.edges);
+    state.goal = if pieces.is_empty() || pieces.len() > NAMED_PIECES {
+        format!("{}: {}", hint.label, hint.explanation)
+    } else {
+        format!(
+            "{}: {} ({})",
+            hint.label,
+            hint.explanation,
+            pieces.join(", ")
+        )
+    };
+    log::info!("Hint: {}", state.goal);
+
+    move_queue.load_solution(&Solution::default());
+    move_queue.current = None;
+    move_queue.highlight_index = None;
+
+    highlighted.pieces = Some((hint.corners, hint.edges));
+    state.facelets = Some(facelets.to_string());
+    state.hint = Some(hint);
+    state.done = Some(done);
+}
+
+/// Names of the chosen pieces by their colors, such as "green-red edge"
+fn piece_names(model: &CubeModel, corners: &[bool; 8], edges: &[bool; 12]) -> Vec<String> {
+    // A piece belongs where the centers of its colors are
+    let name = |facelets: &[usize], kind: &str| {
+        let colors: Vec<&str> = facelets
+            .iter()
+            .map(|&facelet| {
+                model
+                    .facelet(CENTER_FACELETS[facelet / 9])
+                    .map_or("?", |color| {
+                        COLOR_NAMES[FaceColor::from_model_face(color).index()]
+                    })
+            })
+            .collect();
+        format!("{} {}", colors.join("-").to_lowercase(), kind)
+    };
+
+    let corners = CORNER_FACELETS
+        .iter()
+        .zip(corners)
+        .filter(|(_, chosen)| **chosen)
+        .map(|(facelets, _)| name(facelets, "corner"));
+    let edges = EDGE_FACELETS
+        .iter()
+        .zip(edges)
+        .filter(|(_, chosen)| **chosen)
+        .map(|(facelets, _)| name(facelets, "edge"));
+    corners.chain(edges).collect()
+}
+
+/// System to drop the hint and its highlight once the stage is done or the cube is no
+/// longer complete
+fn clear_finished_hint(
+    cube_model: Res<CubeModelResource>,
+    mut state: ResMut<HintState>,
+    mut highlighted: ResMut<HighlightedPieces>,
+) {
+    if !cube_model.is_changed() || state.hint.is_none() {
+        return;
+    }
+    match cube_model.model.to_cubie() {
+        Ok(cube) if state.done != Some(cube) => {}
+        Ok(_) => {
+            log::info!("Hint: stage done");
+            state.clear(&mut highlighted);
+        }
+        Err(_) => state.clear(&mut highlighted),
+    }
+}
+
+/// System to label the hint button with what the next press reveals
+fn update_hint_button(
+    solver: Res<CubeSolverResource>,
+    state: Res<HintState>,
+    mut text_query: Query<(&mut Text, &mut TextColor), With<HintButtonText>>,
+) {
+    if !solver.is_changed() && !state.is_changed() {
+        return;
+    }
+
+    let label = if state.reveals_moves(solver.facelets()) {
+        "Moves"
+    } else {
+        "Hint"
+    };
+    let color = if solver.is_valid() {
+        css::WHITE
+    } else {
+        css::DIM_GRAY
+    };
+    for (mut text, mut text_color) in &mut text_query {
+        if text.0 != label {
+            text.0 = label.to_string();
+        }
+        *text_color = TextColor(color.into());
+    }
+}
+
+/// System to show the goal of the hint until its moves are in the queue
+fn update_hint_caption(
+    state: Res<HintState>,
+    mut caption_query: Query<&mut Visibility, With<HintCaption>>,
+    mut text_query: Query<&mut Text, With<HintCaptionText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    let caption = if state.waiting {
+        Some("Hint: working out the next stage…".to_string())
+    } else if state.hint.is_some() && !state.moves_shown {
+        Some(format!("Hint: {}", state.goal))
+    } else {
+        None
+    };
+
+    if let Ok(mut visibility) = caption_query.get_single_mut() {
+        *visibility = if caption.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    if let Some(caption) = caption
+        && let Ok(mut text) = text_query.get_single_mut()
+        && text.0 != caption
+    {
+        text.0 = caption;
+    }
+}
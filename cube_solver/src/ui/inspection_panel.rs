@@ -0,0 +1,416 @@
+use crate::cube::CubeModelResource;
+use crate::cube_model::CENTER_FACELETS;
+use crate::move_sequence::MoveSequence;
+use crate::solver_integration::{CubeSolverResource, FaceColor};
+use crate::solvers::Solution;
+use crate::solvers::inspection::{self, CrossAnalysis, InspectionBudget};
+use crate::ui::color_panel::COLOR_NAMES;
+use crate::ui::rotations_panel::MoveQueue;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Component)]
+pub struct InspectionToggleButton;
+
+#[derive(Component)]
+pub struct InspectionPanel;
+
+/// Rows of the inspection panel, rebuilt when an analysis finishes
+#[derive(Component)]
+pub struct InspectionRows;
+
+/// Which of the crosses of a face a button loads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossKind {
+    Cross,
+    XCross,
+    XxCross,
+}
+
+impl CrossKind {
+    pub const ALL: [CrossKind; 3] = [CrossKind::Cross, CrossKind::XCross, CrossKind::XxCross];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CrossKind::Cross => "Cross",
+            CrossKind::XCross => "X-cross",
+            CrossKind::XxCross => "XX-cross",
+        }
+    }
+
+    /// The moves of this kind of cross in `analysis` with the slots they solve, if found
+    fn moves(self, analysis: &CrossAnalysis) -> Option<(&MoveSequence, &[String])> {
+        match self {
+            CrossKind::Cross => Some((&analysis.cross, &[])),
+            CrossKind::XCross => analysis
+                .x_cross
+                .as_ref()
+                .map(|x_cross| (&x_cross.moves, x_cross.slots.as_slice())),
+            CrossKind::XxCross => analysis
+                .xx_cross
+                .as_ref()
+                .map(|xx_cross| (&xx_cross.moves, xx_cross.slots.as_slice())),
+        }
+    }
+}
+
+/// Loads one cross of the analysis into the move queue
+#[derive(Component)]
+pub struct InspectionChoiceButton {
+    pub row: usize,
+    pub kind: CrossKind,
+}
+
+/// Analysis of the crosses of the current cube, run in the background while the panel is open
+#[derive(Resource, Default)]
+pub struct InspectionState {
+    /// Facelets of the cube the analysis is for, or is being run for
+    facelets: Option<String>,
+    /// Each analysed face with the name of its center color
+    analyses: Vec<(String, CrossAnalysis)>,
+    error: Option<String>,
+    task: Option<Task<Result<Vec<CrossAnalysis>, String>>>,
+    cancelled: Arc<AtomicBool>,
+    /// Names of the center colors of the cube being analysed
+    colors: Vec<String>,
+}
+
+impl InspectionState {
+    /// Start analysing `facelets`, superseding any analysis still running
+    fn spawn(&mut self, facelets: String, colors: Vec<String>) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.cancelled = Arc::new(AtomicBool::new(false));
+        let cancelled = self.cancelled.clone();
+        let task_facelets = facelets.clone();
+        self.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+            inspection::analyze(&task_facelets, InspectionBudget::default(), &|| {
+                cancelled.load(Ordering::Relaxed)
+            })
+        }));
+        self.facelets = Some(facelets);
+        self.colors = colors;
+        self.analyses.clear();
+        self.error = None;
+    }
+}
+
+/// Creates the inspection toggle button and the (hidden) inspection panel
+pub fn create_inspection_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(70.0), // Left of the settings toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            InspectionToggleButton,
+            Name::new("Inspection Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("+"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0), // Below the color panel
+                left: Val::Px(20.0),
+                width: Val::Px(250.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            InspectionPanel,
+            Name::new("Inspection Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("Inspection"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            panel_parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                InspectionRows,
+                Name::new("Inspection Rows"),
+            ));
+        });
+}
+
+/// System to show and hide the inspection panel
+pub fn handle_inspection_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<InspectionToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<InspectionPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to analyse the cube while the panel is open, once per cube state
+pub fn start_inspection_analysis(
+    panel_query: Query<&Visibility, With<InspectionPanel>>,
+    solver: Res<CubeSolverResource>,
+    cube_model: Res<CubeModelResource>,
+    mut state: ResMut<InspectionState>,
+    rows_query: Query<Entity, With<InspectionRows>>,
+    mut commands: Commands,
+) {
+    let is_open = panel_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden);
+    if !is_open || !solver.is_valid() {
+        return;
+    }
+    let Some(facelets) = solver.facelets() else {
+        return;
+    };
+    if state.facelets.as_deref() == Some(facelets) {
+        return;
+    }
+
+    // Rows are named by the color of their center, wherever the user has turned it
+    let colors = CENTER_FACELETS
+        .iter()
+        .map(|&index| {
+            cube_model.model.facelet(index).map_or("?", |color| {
+                COLOR_NAMES[FaceColor::from_model_face(color).index()]
+            })
+        })
+        .map(str::to_string)
+        .collect();
+    state.spawn(facelets.to_string(), colors);
+
+    if let Ok(rows) = rows_query.get_single() {
+        commands.entity(rows).despawn_descendants();
+        commands.entity(rows).with_children(|parent| {
+            parent.spawn((
+                Text::new("Analysing…"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+    }
+}
+
+/// System to collect a finished analysis and rebuild the rows of the panel
+pub fn update_inspection_rows(
+    mut state: ResMut<InspectionState>,
+    rows_query: Query<Entity, With<InspectionRows>>,
+    mut commands: Commands,
+) {
+    let Some(task) = state.bypass_change_detection().task.as_mut() else {
+        return;
+    };
+    let Some(result) = block_on(poll_once(task)) else {
+        return;
+    };
+    state.task = None;
+    match result {
+        Ok(analyses) => {
+            let colors = std::mem::take(&mut state.colors);
+            state.analyses = colors.into_iter().zip(analyses).collect();
+        }
+        Err(error) => state.error = Some(error),
+    }
+
+    let Ok(rows) = rows_query.get_single() else {
+        return;
+    };
+    commands.entity(rows).despawn_descendants();
+    commands.entity(rows).with_children(|parent| {
+        if let Some(error) = &state.error {
+            parent.spawn((
+                Text::new(error.clone()),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        }
+        for (row, (color, analysis)) in state.analyses.iter().enumerate() {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    },
+                    Name::new(format!("{} Inspection Row", color)),
+                ))
+                .with_children(|row_parent| {
+                    row_parent.spawn((
+                        Text::new(color.clone()),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                        Node {
+                            width: Val::Px(60.0),
+                            ..default()
+                        },
+                    ));
+                    for kind in CrossKind::ALL {
+                        spawn_choice_button(row_parent, row, kind, analysis);
+                    }
+                });
+        }
+    });
+}
+
+/// Button showing the length of one cross, or a dash if none fit the budget
+fn spawn_choice_button(
+    parent: &mut ChildBuilder,
+    row: usize,
+    kind: CrossKind,
+    analysis: &CrossAnalysis,
+) {
+    let prefix = match kind {
+        CrossKind::Cross => "+",
+        CrossKind::XCross => "X",
+        CrossKind::XxCross => "XX",
+    };
+    let length = kind.moves(analysis).map(|(moves, _)| moves.len());
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(50.0),
+                height: Val::Px(28.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(if length.is_some() {
+                css::DIM_GRAY.into()
+            } else {
+                css::DARK_GRAY.into()
+            }),
+            BorderColor(css::WHITE.into()),
+            InspectionChoiceButton { row, kind },
+            Name::new(format!("{} {} Button", kind.label(), row + 1)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(match length {
+                    Some(length) => format!("{} {}", prefix, length),
+                    None => format!("{} -", prefix),
+                }),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+/// System to load the picked cross into the move queue, ready to step through
+pub fn handle_inspection_choice(
+    interaction_query: Query<(&Interaction, &InspectionChoiceButton), Changed<Interaction>>,
+    state: Res<InspectionState>,
+    mut move_queue: ResMut<MoveQueue>,
+) {
+    for (interaction, choice) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some((color, analysis)) = state.analyses.get(choice.row) else {
+            continue;
+        };
+        let Some((moves, slots)) = choice.kind.moves(analysis) else {
+            info!("No {} within the budget", choice.kind.label());
+            continue;
+        };
+
+        let explanation = if slots.is_empty() {
+            format!(
+                "Solve the {} cross in the fewest moves",
+                color.to_lowercase()
+            )
+        } else {
+            let pairs = if slots.len() == 1 { "pair" } else { "pairs" };
+            format!(
+                "Solve the {} cross together with the {} {} in the fewest moves",
+                color.to_lowercase(),
+                slots.join(" and "),
+                pairs
+            )
+        };
+        let mut solution = Solution::default();
+        solution.push_stage(
+            format!("{} {}", color, choice.kind.label()),
+            explanation,
+            moves,
+        );
+        move_queue.load_solution(&solution);
+        move_queue.current = None;
+        move_queue.highlight_index = Some(0);
+        info!("Loaded {} {}: {}", color, choice.kind.label(), moves);
+    }
+}
+
+/// Plugin for the cross analysis panel
+pub struct InspectionPanelPlugin;
+
+impl Plugin for InspectionPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InspectionState>()
+            .add_systems(Startup, create_inspection_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_inspection_toggle,
+                    start_inspection_analysis,
+                    update_inspection_rows,
+                    handle_inspection_choice,
+                ),
+            );
+    }
+}
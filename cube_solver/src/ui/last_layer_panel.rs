@@ -0,0 +1,278 @@
+use crate::cube_model::{Face, Move};
+use crate::solver_integration::CubeSolverResource;
+use crate::solvers::Solution;
+use crate::solvers::last_layer::{self, LastLayerReport};
+use crate::ui::rotations_panel::MoveQueue;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct LastLayerToggleButton;
+
+#[derive(Component)]
+pub struct LastLayerPanel;
+
+/// Cases of the panel, rebuilt when the cube changes
+#[derive(Component)]
+pub struct LastLayerCases;
+
+/// Loads one algorithm of a recognised case into the move queue
+#[derive(Component)]
+pub struct LastLayerAlgorithmButton {
+    pub case: usize,
+    pub algorithm: usize,
+}
+
+/// Last-layer cases of the current cube, recognised while the panel is open
+#[derive(Resource, Default)]
+pub struct LastLayerState {
+    /// Facelets of the cube the cases are for
+    facelets: Option<String>,
+    report: Option<LastLayerReport>,
+}
+
+/// Creates the last-layer toggle button and the (hidden) last-layer panel
+pub fn create_last_layer_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(220.0), // Left of the challenge toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            LastLayerToggleButton,
+            Name::new("Last Layer Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("LL"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0),
+                right: Val::Px(340.0), // Left of the challenge panel
+                width: Val::Px(280.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            LastLayerPanel,
+            Name::new("Last Layer Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("Last layer"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            panel_parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                LastLayerCases,
+                Name::new("Last Layer Cases"),
+            ));
+        });
+}
+
+/// System to show and hide the last-layer panel
+pub fn handle_last_layer_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LastLayerToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<LastLayerPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// Turn of U as written, or "none" when there is nothing to turn
+fn auf_name(turns: i32) -> String {
+    match turns.rem_euclid(4) {
+        0 => "none".to_string(),
+        turns => Move::face(Face::U, turns).to_string(),
+    }
+}
+
+/// System to recognise the last layer while the panel is open, once per cube state,
+/// and rebuild the cases of the panel
+pub fn update_last_layer_cases(
+    panel_query: Query<&Visibility, With<LastLayerPanel>>,
+    solver: Res<CubeSolverResource>,
+    mut state: ResMut<LastLayerState>,
+    cases_query: Query<Entity, With<LastLayerCases>>,
+    mut commands: Commands,
+) {
+    let is_open = panel_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden);
+    if !is_open || !solver.is_valid() {
+        return;
+    }
+    let Some(facelets) = solver.facelets() else {
+        return;
+    };
+    if state.facelets.as_deref() == Some(facelets) {
+        return;
+    }
+    state.facelets = Some(facelets.to_string());
+    let (report, message) = match last_layer::recognize(facelets) {
+        Ok(Some(report)) => {
+            let message = match report.rotation {
+                Some(rotation) => format!("Turn the cube with {} first", rotation),
+                None => "Last layer on U".to_string(),
+            };
+            (Some(report), message)
+        }
+        Ok(None) => (
+            None,
+            "No case: the first two layers are not solved, or only an AUF is left".to_string(),
+        ),
+        Err(error) => (None, error),
+    };
+    state.report = report;
+
+    let Ok(cases) = cases_query.get_single() else {
+        return;
+    };
+    commands.entity(cases).despawn_descendants();
+    commands.entity(cases).with_children(|parent| {
+        parent.spawn((
+            Text::new(message),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(css::WHITE.into()),
+        ));
+        let Some(report) = &state.report else {
+            return;
+        };
+        for (case_index, case) in report.cases.iter().enumerate() {
+            parent.spawn((
+                Text::new(format!(
+                    "{}, AUF: {} before, {} after",
+                    case.label,
+                    auf_name(case.before),
+                    auf_name(case.after)
+                )),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            for (algorithm_index, algorithm) in case.algorithms.iter().enumerate() {
+                parent
+                    .spawn((
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(4.0)),
+                            border: UiRect::all(Val::Px(1.0)),
+                            ..default()
+                        },
+                        BackgroundColor(css::DIM_GRAY.into()),
+                        BorderColor(css::WHITE.into()),
+                        LastLayerAlgorithmButton {
+                            case: case_index,
+                            algorithm: algorithm_index,
+                        },
+                        Name::new(format!("{} Algorithm {}", case.label, algorithm_index + 1)),
+                    ))
+                    .with_children(|button_parent| {
+                        button_parent.spawn((
+                            Text::new(algorithm.to_string()),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(css::WHITE.into()),
+                        ));
+                    });
+            }
+        }
+    });
+}
+
+/// System to load the picked algorithm, with the rotation and turns of U around it,
+/// into the move queue, ready to step through
+pub fn handle_last_layer_choice(
+    interaction_query: Query<(&Interaction, &LastLayerAlgorithmButton), Changed<Interaction>>,
+    state: Res<LastLayerState>,
+    mut move_queue: ResMut<MoveQueue>,
+) {
+    for (interaction, choice) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(report) = &state.report else {
+            continue;
+        };
+        let Some(case) = report.cases.get(choice.case) else {
+            continue;
+        };
+        let moves = report.moves(case, choice.algorithm);
+        let mut solution = Solution::default();
+        solution.push_stage(
+            case.label.clone(),
+            "Algorithm for the case from the built-in database",
+            &moves,
+        );
+        move_queue.load_solution(&solution);
+        move_queue.current = None;
+        move_queue.highlight_index = Some(0);
+        info!("Loaded {}: {}", case.label, moves);
+    }
+}
+
+/// Plugin for the last-layer recognition panel
+pub struct LastLayerPanelPlugin;
+
+impl Plugin for LastLayerPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LastLayerState>()
+            .add_systems(Startup, create_last_layer_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_last_layer_toggle,
+                    update_last_layer_cases,
+                    handle_last_layer_choice,
+                ),
+            );
+    }
+}
@@ -0,0 +1,401 @@
+use crate::algorithm_library::{AlgorithmLibrary, AlgorithmSet};
+use crate::solver_integration::SolverConfig;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+
+/// Algorithms listed per page of the panel
+const PAGE_SIZE: usize = 8;
+
+#[derive(Component)]
+pub struct LibraryToggleButton;
+
+#[derive(Component)]
+pub struct LibraryPanel;
+
+/// Steps the listed set by `step`
+#[derive(Component)]
+pub struct LibrarySetButton {
+    pub step: i32,
+}
+
+/// Steps the listed page by `step`
+#[derive(Component)]
+pub struct LibraryPageButton {
+    pub step: i32,
+}
+
+#[derive(Component)]
+pub struct LibrarySetLabel;
+
+#[derive(Component)]
+pub struct LibraryPageLabel;
+
+/// Algorithms of the current page, rebuilt when the page or the library changes
+#[derive(Component)]
+pub struct LibraryEntries;
+
+/// Marks an algorithm of the library as known, or no longer known
+#[derive(Component)]
+pub struct LibraryEntryButton {
+    pub index: usize,
+}
+
+/// Set and page of the library the panel lists
+#[derive(Resource)]
+pub struct LibraryPanelState {
+    pub set: AlgorithmSet,
+    pub page: usize,
+}
+
+impl Default for LibraryPanelState {
+    fn default() -> Self {
+        Self {
+            set: AlgorithmSet::F2l,
+            page: 0,
+        }
+    }
+}
+
+/// Indices of the library's algorithms in `set`
+fn set_entries(library: &AlgorithmLibrary, set: AlgorithmSet) -> Vec<usize> {
+    (0..library.entries.len())
+        .filter(|&index| library.entries[index].set == set)
+        .collect()
+}
+
+/// System to read the user's library when the app starts
+pub fn load_algorithm_library(mut config: ResMut<SolverConfig>) {
+    config.library = AlgorithmLibrary::load();
+    info!("Algorithm library loaded: {:?}", config.library);
+}
+
+/// Creates the library toggle button and the (hidden) library panel
+pub fn create_library_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(270.0), // Left of the last-layer toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            LibraryToggleButton,
+            Name::new("Library Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("Alg"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0),
+                right: Val::Px(20.0),
+                width: Val::Px(320.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            LibraryPanel,
+            Name::new("Library Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("My algorithms: tap the ones you know"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            spawn_stepper_row(panel_parent, "Set", LibrarySetLabel, |step| {
+                LibrarySetButton { step }
+            });
+            spawn_stepper_row(panel_parent, "Page", LibraryPageLabel, |step| {
+                LibraryPageButton { step }
+            });
+            panel_parent.spawn((
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                LibraryEntries,
+                Name::new("Library Entries"),
+            ));
+        });
+}
+
+/// A row with a label, and a value between a "<" and a ">" button
+fn spawn_stepper_row<B: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    value_label: impl Component,
+    button: impl Fn(i32) -> B,
+) {
+    parent
+        .spawn((
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(6.0),
+                ..default()
+            },
+            Name::new(format!("Library {} Row", label)),
+        ))
+        .with_children(|row_parent| {
+            row_parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                Node {
+                    width: Val::Px(50.0),
+                    ..default()
+                },
+            ));
+            spawn_step_button(row_parent, label, "<", button(-1));
+            row_parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    width: Val::Px(120.0),
+                    ..default()
+                },
+                value_label,
+            ));
+            spawn_step_button(row_parent, label, ">", button(1));
+        });
+}
+
+fn spawn_step_button(parent: &mut ChildBuilder, row: &str, text: &str, button: impl Component) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(32.0),
+                height: Val::Px(28.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(css::DIM_GRAY.into()),
+            BorderColor(css::WHITE.into()),
+            button,
+            Name::new(format!("Library {} {} Button", row, text)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+/// System to show and hide the library panel
+pub fn handle_library_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LibraryToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<LibraryPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to step through the sets and their pages
+pub fn handle_library_navigation(
+    set_query: Query<(&Interaction, &LibrarySetButton), Changed<Interaction>>,
+    page_query: Query<(&Interaction, &LibraryPageButton), Changed<Interaction>>,
+    config: Res<SolverConfig>,
+    mut state: ResMut<LibraryPanelState>,
+) {
+    for (interaction, button) in &set_query {
+        if *interaction == Interaction::Pressed {
+            let sets = AlgorithmSet::ALL;
+            let index = sets.iter().position(|&set| set == state.set).unwrap_or(0);
+            let next = (index as i32 + button.step).rem_euclid(sets.len() as i32) as usize;
+            state.set = sets[next];
+            state.page = 0;
+        }
+    }
+    for (interaction, button) in &page_query {
+        if *interaction == Interaction::Pressed {
+            let count = set_entries(&config.library, state.set).len();
+            let pages = count.div_ceil(PAGE_SIZE).max(1);
+            state.page = (state.page as i32 + button.step).rem_euclid(pages as i32) as usize;
+        }
+    }
+}
+
+/// System to mark the pressed algorithm as known, or no longer known, and save the library
+pub fn handle_library_entry_press(
+    interaction_query: Query<(&Interaction, &LibraryEntryButton), Changed<Interaction>>,
+    mut config: ResMut<SolverConfig>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(entry) = config.library.entries.get_mut(button.index) else {
+            continue;
+        };
+        entry.known = !entry.known;
+        info!(
+            "{} {}: {}",
+            if entry.known {
+                "Known"
+            } else {
+                "No longer known"
+            },
+            entry.name,
+            entry.algorithm
+        );
+        if let Err(error) = config.library.save() {
+            warn!("Could not save the algorithm library: {}", error);
+        }
+    }
+}
+
+/// System to relist the page when it or the library changes
+pub fn update_library_entries(
+    config: Res<SolverConfig>,
+    state: Res<LibraryPanelState>,
+    entries_query: Query<Entity, With<LibraryEntries>>,
+    mut set_label_query: Query<&mut Text, (With<LibrarySetLabel>, Without<LibraryPageLabel>)>,
+    mut page_label_query: Query<&mut Text, (With<LibraryPageLabel>, Without<LibrarySetLabel>)>,
+    mut commands: Commands,
+) {
+    if !config.is_changed() && !state.is_changed() {
+        return;
+    }
+    let library = &config.library;
+    let indices = set_entries(library, state.set);
+    let known = indices
+        .iter()
+        .filter(|&&index| library.entries[index].known)
+        .count();
+    let pages = indices.len().div_ceil(PAGE_SIZE).max(1);
+    let page = state.page.min(pages - 1);
+    if let Ok(mut text) = set_label_query.get_single_mut() {
+        text.0 = format!("{} ({}/{} known)", state.set, known, indices.len());
+    }
+    if let Ok(mut text) = page_label_query.get_single_mut() {
+        text.0 = format!("{} of {}", page + 1, pages);
+    }
+
+    let Ok(entries) = entries_query.get_single() else {
+        return;
+    };
+    commands.entity(entries).despawn_descendants();
+    commands.entity(entries).with_children(|parent| {
+        if indices.is_empty() {
+            parent.spawn((
+                Text::new("No algorithms in this set yet"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        }
+        for &index in indices.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE) {
+            let entry = &library.entries[index];
+            let background = if entry.known {
+                css::DARK_GREEN
+            } else {
+                css::DIM_GRAY
+            };
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::axes(Val::Px(6.0), Val::Px(4.0)),
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    BackgroundColor(background.into()),
+                    BorderColor(css::WHITE.into()),
+                    LibraryEntryButton { index },
+                    Name::new(format!("Library Entry {}", index)),
+                ))
+                .with_children(|button_parent| {
+                    button_parent.spawn((
+                        Text::new(&entry.name),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                    ));
+                    button_parent.spawn((
+                        Text::new(&entry.algorithm),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(css::LIGHT_GRAY.into()),
+                    ));
+                });
+        }
+    });
+}
+
+/// Plugin for the algorithm library panel
+pub struct LibraryPanelPlugin;
+
+impl Plugin for LibraryPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LibraryPanelState>()
+            .add_systems(Startup, (load_algorithm_library, create_library_panel))
+            .add_systems(
+                Update,
+                (
+                    handle_library_toggle,
+                    handle_library_navigation,
+                    handle_library_entry_press,
+                    update_library_entries,
+                ),
+            );
+    }
+}
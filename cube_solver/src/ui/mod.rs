@@ -1,11 +1,29 @@
+pub mod challenge_panel;
 pub mod color_panel;
+pub mod fmc_panel;
+pub mod generator_panel;
+pub mod hint;
+pub mod inspection_panel;
+pub mod last_layer_panel;
+pub mod library_panel;
 pub mod move_test;
 pub mod navigation;
 pub mod rotations_panel;
+pub mod settings_panel;
 pub mod solve;
+pub mod state_panel;
 
+pub use challenge_panel::*;
 pub use color_panel::*;
+pub use fmc_panel::*;
+pub use generator_panel::*;
+pub use hint::*;
+pub use inspection_panel::*;
+pub use last_layer_panel::*;
+pub use library_panel::*;
 pub use move_test::*;
 pub use navigation::*;
 pub use rotations_panel::*;
+pub use settings_panel::*;
 pub use solve::*;
+pub use state_panel::*;
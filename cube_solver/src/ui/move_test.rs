@@ -1,5 +1,6 @@
 use super::rotations_panel::MoveQueue;
-use crate::layer_rotation::parse_extended_move_notation;
+use crate::move_sequence::MoveSequence;
+use crate::permutation;
 use bevy::color::palettes::css;
 use bevy::prelude::*;
 
@@ -32,6 +33,10 @@ pub struct MoveSelectionButton {
 #[derive(Component)]
 pub struct BackspaceButton;
 
+/// Order and cycle structure of the moves picked so far
+#[derive(Component)]
+pub struct SequenceStructureText;
+
 #[derive(Resource, Default)]
 pub struct MoveSelectionState {
     pub is_open: bool,
@@ -111,12 +116,31 @@ pub fn create_move_test_panel(mut commands: Commands) {
                         },
                     ));
 
+                    // The picked sequence, with its order and cycles
+                    panel_parent.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(60.0),
+                            margin: UiRect::bottom(Val::Px(6.0)),
+                            overflow: Overflow::clip(),
+                            ..default()
+                        },
+                        SequenceStructureText,
+                        Name::new("Sequence Structure Text"),
+                    ));
+
                     // Scrollable grid of face move buttons
                     panel_parent
                         .spawn((
                             Node {
                                 width: Val::Percent(100.0),
-                                height: Val::Px(380.0), // Taller to show more rows
+                                height: Val::Px(314.0), // Below the sequence structure
                                 flex_direction: FlexDirection::Row,
                                 flex_wrap: FlexWrap::Wrap,
                                 justify_content: JustifyContent::Center,
@@ -363,6 +387,7 @@ impl Plugin for MoveTestPlugin {
                     handle_move_selection,
                     handle_backspace_button,
                     update_move_selection_state,
+                    update_sequence_structure,
                     handle_move_completion,
                     close_move_selection_on_button_press,
                 ),
@@ -394,33 +419,24 @@ pub fn handle_rst_button(
 /// System to handle Clr button clicks (clears all colors)
 pub fn handle_clr_button(
     mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<ClrButton>)>,
-    mut commands: Commands,
-    mut color_manager: ResMut<crate::components::ColorManager>,
+    mut cube_model: ResMut<crate::cube::CubeModelResource>,
     mut solver: ResMut<crate::solver_integration::CubeSolverResource>,
     mut move_queue: ResMut<MoveQueue>,
-    colored_faces_query: Query<(Entity, &crate::components::RecoloredFace)>,
-    placeholder_material: Res<crate::colors::PlaceholderMaterial>,
 ) {
     for interaction in &mut interaction_query {
         if *interaction == Interaction::Pressed {
             info!("Clr button clicked - clearing all face colors and solver state");
 
-            // Clear all existing colors from the color manager
-            color_manager.usage_counts = [0; 6];
-
-            // Remove all existing RecoloredFace components and reset materials to placeholder
-            for (entity, _recolored_face) in colored_faces_query.iter() {
-                commands
-                    .entity(entity)
-                    .remove::<crate::components::RecoloredFace>()
-                    .insert(MeshMaterial3d(placeholder_material.0.clone()));
-            }
+            // Clear the model; faces and color counts are re-rendered from it
+            cube_model.model.clear();
 
             // Reset solver state
             solver.clear_solution();
 
             // Clear move queue
             move_queue.pending.clear();
+            move_queue.stages.clear();
+            move_queue.split_moves.clear();
             move_queue.current = None;
             move_queue.highlight_index = None;
 
@@ -429,211 +445,17 @@ pub fn handle_clr_button(
     }
 }
 
-/// Helper function to calculate facelet index for reset (simplified version)
-fn calculate_facelet_index_for_reset(
-    face_entity: Entity,
-    face_query: &Query<(Entity, &crate::components::Face)>,
-    small_cube_transforms: &Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-    main_cube_transforms: &Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-    face_transforms: &Query<&GlobalTransform, With<crate::components::Face>>,
-) -> Option<usize> {
-    // Get the parent small cube entity from the Face component
-    if let Ok((_, face)) = face_query.get(face_entity) {
-        let small_cube_entity = face.parent_cube;
-
-        // Get the small cube's transform
-        if let Ok(small_cube_transform) = small_cube_transforms.get(small_cube_entity) {
-            // Get the main cube's transform
-            if let Ok(main_cube_transform) = main_cube_transforms.get_single() {
-                // Get the face's transform
-                if let Ok(face_transform) = face_transforms.get(face_entity) {
-                    // Calculate the face's position relative to the main cube
-                    let face_relative_to_main =
-                        main_cube_transform.affine().inverse() * face_transform.affine();
-                    let (_, _, face_main_pos) =
-                        face_relative_to_main.to_scale_rotation_translation();
-
-                    // Determine face orientation from face's position in main cube coordinates
-                    let face_orientation =
-                        determine_face_orientation_from_main_position(&face_main_pos);
-
-                    // Calculate the small cube's position relative to the main cube in LOCAL SPACE
-                    let relative_transform =
-                        main_cube_transform.affine().inverse() * small_cube_transform.affine();
-                    let (_, _, relative_position) =
-                        relative_transform.to_scale_rotation_translation();
-
-                    // Convert the relative position to local indices (-1, 0, 1)
-                    let local_indices = world_to_local_indices(&relative_position);
-
-                    // Calculate position within the face using local indices
-                    let position_in_face =
-                        calculate_position_in_face_from_indices(&local_indices, face_orientation);
-
-                    // Calculate facelet index: group_offset + position_within_face
-                    let group_offset = face_orientation.facelet_offset();
-                    let facelet_index = group_offset + position_in_face;
-
-                    return Some(facelet_index);
-                }
-            }
-        }
-    }
-
-    None
-}
-
-/// Helper function to determine face orientation from main position
-fn determine_face_orientation_from_main_position(
-    position: &Vec3,
-) -> crate::components::Orientation {
-    // Find the axis with the largest absolute value
-    let abs_x = position.x.abs();
-    let abs_y = position.y.abs();
-    let abs_z = position.z.abs();
-
-    if abs_x >= abs_y && abs_x >= abs_z {
-        if position.x > 0.0 {
-            crate::components::Orientation::Right
-        } else {
-            crate::components::Orientation::Left
-        }
-    } else if abs_y >= abs_z {
-        if position.y > 0.0 {
-            crate::components::Orientation::Up
-        } else {
-            crate::components::Orientation::Down
-        }
-    } else if position.z > 0.0 {
-        crate::components::Orientation::Front
-    } else {
-        crate::components::Orientation::Back
-    }
-}
-
-/// Helper function to convert world position to local indices
-fn world_to_local_indices(position: &Vec3) -> Vec3 {
-    const GRID_STEP: f32 = 2.0 / 3.0;
-
-    let x = (position.x / GRID_STEP).round() as i32;
-    let y = (position.y / GRID_STEP).round() as i32;
-    let z = (position.z / GRID_STEP).round() as i32;
-
-    let x = x.clamp(-1, 1);
-    let y = y.clamp(-1, 1);
-    let z = z.clamp(-1, 1);
-
-    Vec3::new(x as f32, y as f32, z as f32)
-}
-
-/// Helper function to calculate position in face from indices
-fn calculate_position_in_face_from_indices(
-    local_indices: &Vec3,
-    face_orientation: crate::components::Orientation,
-) -> usize {
-    // Convert local indices to grid coordinates based on face orientation
-    let (grid_x, grid_y) = match face_orientation {
-        crate::components::Orientation::Up => (local_indices.x + 1.0, local_indices.z + 1.0),
-        crate::components::Orientation::Down => (local_indices.x + 1.0, -(local_indices.z) + 1.0),
-        crate::components::Orientation::Front => (local_indices.x + 1.0, local_indices.y + 1.0),
-        crate::components::Orientation::Back => (-(local_indices.x) + 1.0, local_indices.y + 1.0),
-        crate::components::Orientation::Right => (local_indices.z + 1.0, local_indices.y + 1.0),
-        crate::components::Orientation::Left => (-(local_indices.z) + 1.0, local_indices.y + 1.0),
-    };
-
-    let grid_x = grid_x as usize;
-    let grid_y = grid_y as usize;
-
-    // Convert to 0-8 position within face
-
-    grid_y * 3 + grid_x
-}
-
-/// Helper function to create face material
-fn create_face_material(
-    base_color: Color,
-    materials: &mut Assets<StandardMaterial>,
-) -> Handle<StandardMaterial> {
-    materials.add(StandardMaterial {
-        base_color,
-        metallic: 0.0,
-        perceptual_roughness: 0.3,
-        ..default()
-    })
-}
-
 /// System to handle fix button clicks (copy of original Reset functionality)
 pub fn handle_fix_button(
     mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<FixButton>)>,
-    mut commands: Commands,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut color_manager: ResMut<crate::components::ColorManager>,
-    cube_colors: Res<crate::colors::CubeColors>,
-    colored_faces_query: Query<(Entity, &crate::components::RecoloredFace)>,
-    all_faces_query: Query<(Entity, &crate::components::Face)>,
-    small_cube_transforms: Query<&GlobalTransform, With<crate::cube_moves::CubeMoveTarget>>,
-    main_cube_transforms: Query<&GlobalTransform, With<crate::components::RotatingModel>>,
-    face_transforms: Query<&GlobalTransform, With<crate::components::Face>>,
+    mut cube_model: ResMut<crate::cube::CubeModelResource>,
 ) {
     for interaction in &mut interaction_query {
         if *interaction == Interaction::Pressed {
             info!("Fix button clicked - resetting cube to solved state");
 
-            // Clear all existing colors from the color manager
-            color_manager.usage_counts = [0; 6];
-
-            // Remove all existing RecoloredFace components
-            for (entity, _recolored_face) in colored_faces_query.iter() {
-                commands
-                    .entity(entity)
-                    .remove::<crate::components::RecoloredFace>();
-            }
-
-            // Create solved facelet string: UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB
-            let solved_facelets = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
-
-            // Map solved facelets to cube faces
-            for (entity, _face) in all_faces_query.iter() {
-                // Calculate facelet index based on parent small cube position
-                if let Some(facelet_index) = calculate_facelet_index_for_reset(
-                    entity,
-                    &all_faces_query,
-                    &small_cube_transforms,
-                    &main_cube_transforms,
-                    &face_transforms,
-                ) && facelet_index < solved_facelets.len()
-                {
-                    let facelet_char = solved_facelets.chars().nth(facelet_index).unwrap_or(' ');
-                    if facelet_char != ' ' {
-                        // Convert facelet character to color index
-                        let color_index = match facelet_char {
-                            'U' => 0, // White
-                            'D' => 1, // Yellow
-                            'R' => 2, // Red
-                            'L' => 3, // Orange
-                            'B' => 4, // Blue
-                            'F' => 5, // Green
-                            _ => continue,
-                        };
-
-                        // Create material with the solved color
-                        let color = cube_colors.get(color_index);
-                        let material = create_face_material(color, &mut materials);
-
-                        // Apply color to face
-                        commands
-                            .entity(entity)
-                            .insert(MeshMaterial3d(material))
-                            .insert(crate::components::RecoloredFace::new(
-                                color_index,
-                                bevy::utils::Instant::now().elapsed().as_secs_f64(),
-                            ));
-
-                        // Update color manager
-                        color_manager.usage_counts[color_index] += 1;
-                    }
-                }
-            }
+            // Faces and color counts are re-rendered from the solved model
+            cube_model.model = crate::cube_model::CubeModel::solved();
 
             info!("Cube reset to solved state - all faces colored");
         }
@@ -672,6 +494,8 @@ pub fn handle_select_button(
             } else {
                 // Panel is closed, open it and clear existing rotations
                 move_queue.pending.clear();
+                move_queue.stages.clear();
+                move_queue.split_moves.clear();
                 move_queue.current = None;
                 move_queue.highlight_index = None;
 
@@ -701,11 +525,12 @@ pub fn handle_move_selection(
     for (interaction, move_button) in &mut interaction_query {
         if *interaction == Interaction::Pressed {
             // Add the selected move directly to the rotation pane
-            if parse_extended_move_notation(&move_button.move_notation).is_some() {
-                move_queue.pending.push(move_button.move_notation.clone());
-                info!("Added move to rotation pane: {}", move_button.move_notation);
-            } else {
-                warn!("Invalid move notation: {}", move_button.move_notation);
+            match move_queue.push_algorithm(&move_button.move_notation) {
+                Ok(_) => info!("Added move to rotation pane: {}", move_button.move_notation),
+                Err(error) => warn!(
+                    "Invalid move notation:\n{}",
+                    error.display_with_source(&move_button.move_notation)
+                ),
             }
             // Panel stays open so user can select more moves
         }
@@ -737,6 +562,31 @@ pub fn handle_backspace_button(
     }
 }
 
+/// System to describe the picked moves while the move selection panel is open
+pub fn update_sequence_structure(
+    move_queue: Res<MoveQueue>,
+    move_selection_state: Res<MoveSelectionState>,
+    mut text_query: Query<&mut Text, With<SequenceStructureText>>,
+) {
+    if !move_selection_state.is_open
+        || !(move_queue.is_changed() || move_selection_state.is_changed())
+    {
+        return;
+    }
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+    let algorithm = move_queue.pending.join(" ");
+    text.0 = if algorithm.is_empty() {
+        "Pick moves to see their order and cycles".to_string()
+    } else {
+        match MoveSequence::parse(&algorithm) {
+            Ok(sequence) => format!("{}\n{}", algorithm, permutation::analyze(&sequence)),
+            Err(error) => error.to_string(),
+        }
+    };
+}
+
 /// System to handle move completion by clearing current move when animation finishes
 pub fn handle_move_completion(
     mut move_queue: ResMut<MoveQueue>,
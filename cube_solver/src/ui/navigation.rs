@@ -1,4 +1,5 @@
 use crate::cube_moves::CubeMoveEvent;
+use crate::move_sequence::MoveSequence;
 use crate::ui::rotations_panel::MoveQueue;
 use bevy::color::palettes::css;
 use bevy::prelude::*;
@@ -10,23 +11,10 @@ pub struct NavigationPrevButton;
 pub struct NavigationNextButton;
 
 /// Generate the inverse notation for a move
-fn get_inverse_notation(notation: &str) -> String {
-    if notation.is_empty() {
-        return notation.to_string();
-    }
-
-    // For double moves (2), the inverse is the same
-    if notation.ends_with('2') {
-        return notation.to_string();
-    }
-
-    // For moves with prime ('), remove the prime
-    if notation.ends_with('\'') {
-        return notation[..notation.len() - 1].to_string();
-    }
-
-    // For moves without prime, add prime
-    format!("{}'", notation)
+fn get_inverse_notation(notation: &str) -> Option<String> {
+    MoveSequence::parse(notation)
+        .ok()
+        .map(|sequence| sequence.inverse().to_string())
 }
 
 /// System to handle navigation prev button clicks
@@ -52,7 +40,10 @@ pub fn handle_navigation_prev_button_clicks(
                     // Execute the inverse of the move at the new position
                     if new_index < move_queue.pending.len() {
                         let original_move = move_queue.pending[new_index].clone();
-                        let inverse_move = get_inverse_notation(&original_move);
+                        let Some(inverse_move) = get_inverse_notation(&original_move) else {
+                            warn!("Cannot invert move notation: {}", original_move);
+                            return;
+                        };
                         move_queue.current = Some(inverse_move.clone());
                         info!(
                             "Executing inverse of move at position {}: {} -> {}",
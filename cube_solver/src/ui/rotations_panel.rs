@@ -1,7 +1,10 @@
 use bevy::color::palettes::css;
 use bevy::prelude::*;
 
+use crate::cube_model::Move;
 use crate::cube_moves::CubeMoveEvent;
+use crate::notation::NotationError;
+use crate::solvers::{Solution, SolutionStage};
 
 /// Event sent when a layer rotation animation completes
 #[derive(Event)]
@@ -25,11 +28,107 @@ pub struct RightSideContainer;
 #[derive(Component)]
 pub struct CenterHighlight;
 
+/// Row of alternative solutions above the rotations panel
+#[derive(Component)]
+pub struct SolutionList;
+
+/// Picks one of the solver's alternative solutions
+#[derive(Component)]
+pub struct SolutionChoiceButton {
+    pub index: usize,
+}
+
+/// Caption above the rotations panel naming the stage being stepped through
+#[derive(Component)]
+pub struct StageCaption;
+
+#[derive(Component)]
+pub struct StageCaptionText;
+
 #[derive(Resource, Default, Clone)]
 pub struct MoveQueue {
     pub pending: Vec<String>,
     pub current: Option<String>,
     pub highlight_index: Option<usize>, // Track which position the border is at (can be 0 to len())
+    /// Stages of the loaded solution, covering `pending` from the start
+    pub stages: Vec<SolutionStage>,
+    /// Wide moves and rotations split into several entries of `pending`: the index of
+    /// the first entry and the move as written
+    pub split_moves: Vec<(usize, String)>,
+}
+
+impl MoveQueue {
+    /// Number of the stage that begins with move `index`, if one does
+    pub fn stage_starting_at(&self, index: usize) -> Option<usize> {
+        let mut start = 0;
+        for (number, stage) in self.stages.iter().enumerate() {
+            if start == index {
+                return Some(number);
+            }
+            start += stage.move_count;
+        }
+        None
+    }
+
+    /// The stage that move `index` belongs to, with its number
+    pub fn stage_at(&self, index: usize) -> Option<(usize, &SolutionStage)> {
+        let mut end = 0;
+        for (number, stage) in self.stages.iter().enumerate() {
+            end += stage.move_count;
+            if index < end {
+                return Some((number, stage));
+            }
+        }
+        None
+    }
+
+    /// The move as written whose layer turns start at entry `index`, if it was split
+    pub fn split_move_at(&self, index: usize) -> Option<&str> {
+        self.split_moves
+            .iter()
+            .find(|(start, _)| *start == index)
+            .map(|(_, written)| written.as_str())
+    }
+
+    /// Parse an algorithm and append it to the pending moves.
+    /// Wide moves and rotations are split into layer turns so every entry can be animated.
+    pub fn push_algorithm(&mut self, algorithm: &str) -> Result<usize, NotationError> {
+        let moves = crate::notation::parse_algorithm(algorithm)?;
+        Ok(self.push_moves(&moves))
+    }
+
+    /// Replace the pending moves with `solution`, split into layer turns like
+    /// `push_algorithm`, with its stages counted in those turns
+    pub fn load_solution(&mut self, solution: &Solution) {
+        self.pending.clear();
+        self.split_moves.clear();
+        self.stages.clear();
+        let mut start = 0;
+        for stage in &solution.stages {
+            let end = (start + stage.move_count).min(solution.moves.len());
+            let move_count = self.push_moves(&solution.moves.moves[start..end]);
+            self.stages.push(SolutionStage {
+                move_count,
+                ..stage.clone()
+            });
+            start = end;
+        }
+        self.push_moves(&solution.moves.moves[start..]);
+    }
+
+    /// Append `moves` as layer turns, returning how many entries were added
+    fn push_moves(&mut self, moves: &[Move]) -> usize {
+        let before = self.pending.len();
+        for mv in moves {
+            let layer_moves = mv.layer_moves();
+            if layer_moves.len() > 1 {
+                self.split_moves.push((self.pending.len(), mv.to_string()));
+            }
+            self.pending
+                .extend(layer_moves.iter().map(|layer_move| layer_move.to_string()));
+        }
+        self.pending.len() - before
+    }
 }
 
 /// Create a small horizontal panel above the solve button to display rotation steps
@@ -117,6 +216,121 @@ pub fn create_rotations_panel(mut commands: Commands) {
         });
 }
 
+/// Spawns the (initially empty) list of alternative solutions
+pub fn create_solution_list(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(144.0), // Just above the rotations panel
+            left: Val::Px(20.0),
+            right: Val::Px(130.0), // Leave room for the metric label
+            height: Val::Px(40.0),
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(6.0),
+            overflow: Overflow::clip(),
+            ..default()
+        },
+        SolutionList,
+        Name::new("Solution List"),
+    ));
+}
+
+/// Rebuilds the solution list when the solver's solutions or the selection change.
+/// Each entry shows the move count and the ergonomics score (lower is easier).
+pub fn update_solution_list(
+    solver: Res<crate::solver_integration::CubeSolverResource>,
+    list_query: Query<Entity, With<SolutionList>>,
+    mut shown: Local<(Vec<crate::solvers::Solution>, usize)>,
+    mut commands: Commands,
+) {
+    if !solver.is_changed() {
+        return;
+    }
+    let solutions = solver.solutions();
+    let selected = solver.selected_solution();
+    if shown.0 == solutions && shown.1 == selected {
+        return;
+    }
+    *shown = (solutions.to_vec(), selected);
+
+    let Ok(list) = list_query.get_single() else {
+        return;
+    };
+    commands.entity(list).despawn_descendants();
+
+    // A single solution needs no choice
+    if solutions.len() < 2 {
+        return;
+    }
+
+    commands.entity(list).with_children(|parent| {
+        for (index, solution) in solutions.iter().enumerate() {
+            let report = crate::ergonomics::evaluate(&solution.moves);
+            let is_selected = index == selected;
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(56.0),
+                        height: Val::Px(38.0),
+                        border: UiRect::all(Val::Px(if is_selected { 2.0 } else { 1.0 })),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(if is_selected {
+                        Color::from(css::STEEL_BLUE).with_alpha(0.95)
+                    } else {
+                        Color::from(css::DARK_SLATE_GRAY).with_alpha(0.95)
+                    }),
+                    BorderColor(if is_selected {
+                        css::LIGHT_BLUE.into()
+                    } else {
+                        css::GRAY.into()
+                    }),
+                    BorderRadius::all(Val::Px(6.0)),
+                    SolutionChoiceButton { index },
+                    Name::new(format!("Solution {} Button", index + 1)),
+                ))
+                .with_children(|button_parent| {
+                    button_parent.spawn((
+                        Text::new(format!("{} mv", report.length)),
+                        TextFont {
+                            font_size: 13.0,
+                            ..default()
+                        },
+                        TextColor(css::WHITE.into()),
+                    ));
+                    button_parent.spawn((
+                        Text::new(format!("e {:.0}", report.score)),
+                        TextFont {
+                            font_size: 11.0,
+                            ..default()
+                        },
+                        TextColor(css::LIGHT_GRAY.into()),
+                    ));
+                });
+        }
+    });
+}
+
+/// Loads the picked alternative solution into the move queue
+pub fn handle_solution_choice(
+    interaction_query: Query<(&Interaction, &SolutionChoiceButton), Changed<Interaction>>,
+    mut solver: ResMut<crate::solver_integration::CubeSolverResource>,
+    mut move_queue: ResMut<MoveQueue>,
+) {
+    for (interaction, choice) in &interaction_query {
+        if *interaction == Interaction::Pressed && solver.select_solution(choice.index) {
+            info!("Solution {} selected", choice.index + 1);
+            crate::ui::solve::load_solution_into_queue(&mut solver, &mut move_queue);
+        }
+    }
+}
+
 /// Updates the rotations panel UI to show current and pending moves
 pub fn update_rotations_panel_ui(
     move_queue: Res<MoveQueue>,
@@ -145,6 +359,12 @@ pub fn update_rotations_panel_ui(
             for i in 0..highlight_index {
                 if i < move_queue.pending.len() {
                     commands.entity(left_container).with_children(|parent| {
+                        if let Some(stage) = move_queue.stage_starting_at(i) {
+                            spawn_stage_marker(parent, stage);
+                        }
+                        if let Some(written) = move_queue.split_move_at(i) {
+                            spawn_split_move_marker(parent, written);
+                        }
                         parent.spawn((
                             Text::new(move_queue.pending[i].clone()),
                             TextFont {
@@ -178,6 +398,12 @@ pub fn update_rotations_panel_ui(
         if let Some(highlight_index) = move_queue.highlight_index {
             for i in highlight_index..move_queue.pending.len() {
                 commands.entity(right_container).with_children(|parent| {
+                    if let Some(stage) = move_queue.stage_starting_at(i) {
+                        spawn_stage_marker(parent, stage);
+                    }
+                    if let Some(written) = move_queue.split_move_at(i) {
+                        spawn_split_move_marker(parent, written);
+                    }
                     parent.spawn((
                         Text::new(move_queue.pending[i].clone()),
                         TextFont {
@@ -195,8 +421,14 @@ pub fn update_rotations_panel_ui(
             }
         } else {
             // No highlight, show all moves on right
-            for mv in &move_queue.pending {
+            for (i, mv) in move_queue.pending.iter().enumerate() {
                 commands.entity(right_container).with_children(|parent| {
+                    if let Some(stage) = move_queue.stage_starting_at(i) {
+                        spawn_stage_marker(parent, stage);
+                    }
+                    if let Some(written) = move_queue.split_move_at(i) {
+                        spawn_split_move_marker(parent, written);
+                    }
                     parent.spawn((
                         Text::new(mv.clone()),
                         TextFont {
@@ -216,6 +448,108 @@ pub fn update_rotations_panel_ui(
     }
 }
 
+/// Numbered marker in front of the first move of a stage
+fn spawn_stage_marker(parent: &mut ChildBuilder, stage: usize) {
+    parent.spawn((
+        Text::new(format!("{}.", stage + 1)),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(css::LIGHT_BLUE.into()),
+        Node {
+            margin: UiRect::right(Val::Px(4.0)),
+            ..default()
+        },
+        RotationItem,
+    ));
+}
+
+/// Marker naming the wide move or rotation whose layer turns follow, such as `[x]`
+fn spawn_split_move_marker(parent: &mut ChildBuilder, written: &str) {
+    parent.spawn((
+        Text::new(format!("[{}]", written)),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(css::LIGHT_GREEN.into()),
+        Node {
+            margin: UiRect::right(Val::Px(4.0)),
+            ..default()
+        },
+        RotationItem,
+    ));
+}
+
+/// Spawns the (initially hidden) caption naming the current solution stage
+pub fn create_stage_caption(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(188.0), // Above the solution list
+                left: Val::Px(20.0),
+                right: Val::Px(20.0),
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(6.0)),
+            StageCaption,
+            Name::new("Stage Caption"),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                StageCaptionText,
+            ));
+        });
+}
+
+/// Shows the label and explanation of the stage the next move belongs to
+pub fn update_stage_caption(
+    move_queue: Res<MoveQueue>,
+    mut caption_query: Query<&mut Visibility, With<StageCaption>>,
+    mut text_query: Query<&mut Text, With<StageCaptionText>>,
+) {
+    if !move_queue.is_changed() {
+        return;
+    }
+
+    // At the end of the solution keep showing the last stage
+    let index = move_queue
+        .highlight_index
+        .unwrap_or(0)
+        .min(move_queue.pending.len().saturating_sub(1));
+    let stage = move_queue.stage_at(index);
+
+    if let Ok(mut visibility) = caption_query.get_single_mut() {
+        *visibility = if stage.is_some() {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+    if let Some((number, stage)) = stage
+        && let Ok(mut text) = text_query.get_single_mut()
+    {
+        text.0 = format!(
+            "{}/{} {}: {}",
+            number + 1,
+            move_queue.stages.len(),
+            stage.label,
+            stage.explanation
+        );
+    }
+}
+
 /// Drives the move queue: starts next move when idle and advances after completion
 pub fn drive_move_queue(
     mut move_events: EventWriter<CubeMoveEvent>,
@@ -244,8 +578,23 @@ pub struct RotationsPanelPlugin;
 impl Plugin for RotationsPanelPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<MoveQueue>()
-            .add_systems(Startup, create_rotations_panel)
-            .add_systems(Update, update_rotations_panel_ui);
+            .add_systems(
+                Startup,
+                (
+                    create_rotations_panel,
+                    create_solution_list,
+                    create_stage_caption,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_rotations_panel_ui,
+                    update_stage_caption,
+                    handle_solution_choice,
+                    update_solution_list,
+                ),
+            );
         // .add_systems(Update, drive_move_queue.before(LayerRotationSet::Parse)); // Disabled for manual control
     }
 }
@@ -0,0 +1,410 @@
+use crate::cube::CubeModelResource;
+use crate::cube_model::CubeModel;
+use crate::ergonomics::CostModel;
+use crate::move_sequence::Metric;
+use crate::solver_backend::SolverRegistry;
+use crate::solver_integration::SolverConfig;
+use crate::solvers::move_set::MoveSet;
+use crate::solvers::target::Target;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+use std::time::Duration;
+
+#[derive(Component)]
+pub struct SettingsToggleButton;
+
+#[derive(Component)]
+pub struct SettingsPanel;
+
+/// Solver limit edited by a settings row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolverSetting {
+    Kind,
+    Goal,
+    Moves,
+    MovesBudget,
+    MaxDepth,
+    TargetDepth,
+    SearchLimit,
+    Timeout,
+    SolutionCount,
+    OptimalMetric,
+    OptimalBudget,
+    FingerTricks,
+}
+
+impl SolverSetting {
+    pub const ALL: [SolverSetting; 12] = [
+        SolverSetting::Kind,
+        SolverSetting::Goal,
+        SolverSetting::Moves,
+        SolverSetting::MovesBudget,
+        SolverSetting::MaxDepth,
+        SolverSetting::TargetDepth,
+        SolverSetting::SearchLimit,
+        SolverSetting::Timeout,
+        SolverSetting::SolutionCount,
+        SolverSetting::OptimalMetric,
+        SolverSetting::OptimalBudget,
+        SolverSetting::FingerTricks,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SolverSetting::Kind => "Solver",
+            SolverSetting::Goal => "Solve to",
+            SolverSetting::Moves => "Moves",
+            SolverSetting::MovesBudget => "Moves time",
+            SolverSetting::MaxDepth => "Max depth",
+            SolverSetting::TargetDepth => "Target",
+            SolverSetting::SearchLimit => "Searches",
+            SolverSetting::Timeout => "~Timeout",
+            SolverSetting::SolutionCount => "Solutions",
+            SolverSetting::OptimalMetric => "Optimal in",
+            SolverSetting::OptimalBudget => "Budget",
+            SolverSetting::FingerTricks => "Ergonomic",
+        }
+    }
+
+    pub fn format_value(self, config: &SolverConfig) -> String {
+        match self {
+            SolverSetting::Kind => config.backend.to_string(),
+            SolverSetting::Goal => config.target.name.clone(),
+            SolverSetting::Moves if config.move_set.has_all_faces() => "All".to_string(),
+            SolverSetting::Moves => config.move_set.to_string(),
+            SolverSetting::MovesBudget => format!("{}s", config.move_set_budget.as_secs()),
+            SolverSetting::MaxDepth => config.max_depth.to_string(),
+            SolverSetting::TargetDepth => config.target_depth.to_string(),
+            SolverSetting::SearchLimit => config.max_searches.to_string(),
+            SolverSetting::Timeout => format!("{:.1}s", config.timeout.as_secs_f32()),
+            SolverSetting::SolutionCount => config.solution_count.to_string(),
+            SolverSetting::OptimalMetric => config.optimal_metric.to_string(),
+            SolverSetting::OptimalBudget => format!("{}s", config.optimal_budget.as_secs()),
+            SolverSetting::FingerTricks if config.finger_tricks.is_some() => "On".to_string(),
+            SolverSetting::FingerTricks => "Off".to_string(),
+        }
+    }
+
+    /// Step the setting up or down, keeping the target no longer than the max depth.
+    /// The solver steps through the backends of `registry`, the goal through the
+    /// preset targets and the cube as painted in `model`, and the moves through the
+    /// preset move sets.
+    pub fn adjust(
+        self,
+        config: &mut SolverConfig,
+        registry: &SolverRegistry,
+        model: &CubeModel,
+        step: i32,
+    ) {
+        let depth_range = SolverConfig::DEPTH_RANGE;
+        match self {
+            SolverSetting::Kind => {
+                config.backend = registry.step_name(config.backend, step);
+            }
+            SolverSetting::Goal => {
+                let presets = Target::presets();
+                // One past the presets stands for the painted cube
+                let index = presets
+                    .iter()
+                    .position(|target| target.name == config.target.name)
+                    .unwrap_or(presets.len());
+                let next = (index as i32 + step).rem_euclid(presets.len() as i32 + 1) as usize;
+                match presets.get(next) {
+                    Some(target) => config.target = target.clone(),
+                    None => match Target::from_model(PAINTED_TARGET, model.clone()) {
+                        Ok(target) => config.target = target,
+                        Err(error) => warn!("The painted cube cannot be a target: {}", error),
+                    },
+                }
+            }
+            SolverSetting::Moves => {
+                let presets = MoveSet::presets();
+                let index = presets
+                    .iter()
+                    .position(|set| *set == config.move_set)
+                    .unwrap_or(0);
+                let next = (index as i32 + step).rem_euclid(presets.len() as i32) as usize;
+                config.move_set = presets[next].clone();
+            }
+            SolverSetting::MovesBudget => {
+                let range = SolverConfig::MOVE_SET_BUDGET_RANGE;
+                let seconds = config.move_set_budget.as_secs() as i64 + step as i64 * 5;
+                config.move_set_budget =
+                    Duration::from_secs(seconds.max(0) as u64).clamp(*range.start(), *range.end());
+            }
+            SolverSetting::MaxDepth => {
+                config.max_depth = config
+                    .max_depth
+                    .saturating_add_signed(step as isize)
+                    .clamp(*depth_range.start(), *depth_range.end());
+                config.target_depth = config.target_depth.min(config.max_depth);
+            }
+            SolverSetting::TargetDepth => {
+                config.target_depth = config
+                    .target_depth
+                    .saturating_add_signed(step as isize)
+                    .clamp(*depth_range.start(), config.max_depth);
+            }
+            SolverSetting::SearchLimit => {
+                let range = SolverConfig::SEARCH_LIMIT_RANGE;
+                config.max_searches = config
+                    .max_searches
+                    .saturating_add_signed(step)
+                    .clamp(*range.start(), *range.end());
+            }
+            SolverSetting::Timeout => {
+                let range = SolverConfig::TIMEOUT_RANGE;
+                let millis = config.timeout.as_millis() as i64 + step as i64 * 500;
+                config.timeout =
+                    Duration::from_millis(millis.max(0) as u64).clamp(*range.start(), *range.end());
+            }
+            SolverSetting::SolutionCount => {
+                let range = SolverConfig::SOLUTION_COUNT_RANGE;
+                config.solution_count = config
+                    .solution_count
+                    .saturating_add_signed(step as isize)
+                    .clamp(*range.start(), *range.end());
+            }
+            SolverSetting::OptimalMetric => {
+                config.optimal_metric = match config.optimal_metric {
+                    Metric::Qtm => Metric::Htm,
+                    _ => Metric::Qtm,
+                };
+            }
+            SolverSetting::OptimalBudget => {
+                let range = SolverConfig::OPTIMAL_BUDGET_RANGE;
+                let seconds = config.optimal_budget.as_secs() as i64 + step as i64 * 5;
+                config.optimal_budget =
+                    Duration::from_secs(seconds.max(0) as u64).clamp(*range.start(), *range.end());
+            }
+            SolverSetting::FingerTricks => {
+                config.finger_tricks = match config.finger_tricks {
+                    Some(_) => None,
+                    None => Some(CostModel::default()),
+                };
+            }
+        }
+    }
+}
+
+/// Name of the target captured from the cube as painted
+const PAINTED_TARGET: &str = "Painted";
+
+/// Button that steps a solver setting by `step`
+#[derive(Component)]
+pub struct SettingsAdjustButton {
+    pub setting: SolverSetting,
+    pub step: i32,
+}
+
+#[derive(Component)]
+pub struct SettingsValueLabel {
+    pub setting: SolverSetting,
+}
+
+/// Creates the settings toggle button and the (hidden) solver settings panel
+pub fn create_settings_panel(mut commands: Commands, config: Res<SolverConfig>) {
+    info!("Creating settings panel");
+
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(20.0),
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            SettingsToggleButton,
+            Name::new("Settings Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("§"),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(70.0),
+                right: Val::Px(20.0),
+                width: Val::Px(240.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            SettingsPanel,
+            Name::new("Settings Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("Solver"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+
+            for setting in SolverSetting::ALL {
+                panel_parent
+                    .spawn((
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            column_gap: Val::Px(6.0),
+                            ..default()
+                        },
+                        Name::new(format!("{} Setting Row", setting.label())),
+                    ))
+                    .with_children(|row_parent| {
+                        row_parent.spawn((
+                            Text::new(setting.label()),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(css::WHITE.into()),
+                            Node {
+                                width: Val::Px(80.0),
+                                ..default()
+                            },
+                        ));
+
+                        spawn_adjust_button(row_parent, setting, -1, "-");
+
+                        row_parent.spawn((
+                            Text::new(setting.format_value(&config)),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(css::WHITE.into()),
+                            TextLayout::new_with_justify(JustifyText::Center),
+                            Node {
+                                width: Val::Px(50.0),
+                                ..default()
+                            },
+                            SettingsValueLabel { setting },
+                        ));
+
+                        spawn_adjust_button(row_parent, setting, 1, "+");
+                    });
+            }
+        });
+
+    info!("Settings panel created");
+}
+
+fn spawn_adjust_button(parent: &mut ChildBuilder, setting: SolverSetting, step: i32, label: &str) {
+    parent
+        .spawn((
+            Button,
+            Node {
+                width: Val::Px(32.0),
+                height: Val::Px(28.0),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(css::DIM_GRAY.into()),
+            BorderColor(css::WHITE.into()),
+            SettingsAdjustButton { setting, step },
+            Name::new(format!("{} {} Button", setting.label(), label)),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+}
+
+/// System to show and hide the settings panel
+pub fn handle_settings_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<SettingsToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<SettingsPanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to apply presses of the +/- buttons to the solver configuration
+pub fn handle_settings_adjust(
+    interaction_query: Query<(&Interaction, &SettingsAdjustButton), Changed<Interaction>>,
+    mut config: ResMut<SolverConfig>,
+    registry: Res<SolverRegistry>,
+    cube_model: Res<CubeModelResource>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            button
+                .setting
+                .adjust(&mut config, &registry, &cube_model.model, button.step);
+            info!("Solver settings changed: {:?}", *config);
+        }
+    }
+}
+
+/// System to refresh the value labels when the configuration changes
+pub fn update_settings_labels(
+    config: Res<SolverConfig>,
+    mut label_query: Query<(&mut Text, &SettingsValueLabel)>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    for (mut text, label) in &mut label_query {
+        text.0 = label.setting.format_value(&config);
+    }
+}
+
+/// Plugin for the solver settings panel
+pub struct SettingsPanelPlugin;
+
+impl Plugin for SettingsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SolverConfig>()
+            .add_systems(Startup, create_settings_panel)
+            .add_systems(
+                Update,
+                (
+                    handle_settings_toggle,
+                    handle_settings_adjust,
+                    update_settings_labels,
+                ),
+            );
+    }
+}
@@ -1,3 +1,5 @@
+use crate::move_sequence::{Metric, MoveSequence};
+use crate::solve_task::{SolveFinishedEvent, SolveTasks};
 use crate::ui::rotations_panel::MoveQueue;
 use bevy::color::palettes::css;
 use bevy::prelude::*;
@@ -8,6 +10,19 @@ pub struct SolveButton;
 #[derive(Component)]
 pub struct SolveButtonContainer;
 
+/// Button showing the solution length; pressing it switches the metric
+#[derive(Component)]
+pub struct MetricButton;
+
+#[derive(Component)]
+pub struct MetricLabel;
+
+/// Metric used to display the solution length
+#[derive(Resource, Default)]
+pub struct MetricSelection {
+    pub metric: Metric,
+}
+
 /// Creates a solve button container at the bottom of the screen
 pub fn create_solve_button(
     mut commands: Commands,
@@ -123,6 +138,68 @@ pub fn create_solve_button(
                         TextColor(css::DIM_GRAY.into()),
                     ));
                 });
+
+            // Create Hint button (right of Next)
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(80.0),
+                        height: Val::Px(40.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    BackgroundColor(css::DARK_SLATE_BLUE.into()),
+                    BorderColor(css::WHITE.into()),
+                    crate::ui::hint::HintButton,
+                    Name::new("Hint Button"),
+                ))
+                .with_children(|button_parent| {
+                    button_parent.spawn((
+                        Text::new("Hint"),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(css::DIM_GRAY.into()),
+                        crate::ui::hint::HintButtonText,
+                    ));
+                });
+        });
+
+    // Solution length label above the rotations panel
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(144.0),
+                right: Val::Px(20.0),
+                height: Val::Px(28.0),
+                padding: UiRect::horizontal(Val::Px(10.0)),
+                border: UiRect::all(Val::Px(1.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::DARK_SLATE_GRAY).with_alpha(0.95)),
+            BorderColor(css::GRAY.into()),
+            BorderRadius::all(Val::Px(6.0)),
+            MetricButton,
+            Name::new("Metric Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new(format!("- {}", Metric::default())),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                MetricLabel,
+            ));
         });
 
     info!(
@@ -131,9 +208,59 @@ pub fn create_solve_button(
     );
 }
 
+/// System to switch the solution length metric when the metric button is pressed
+pub fn handle_metric_button_clicks(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<MetricButton>)>,
+    mut metric_selection: ResMut<MetricSelection>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            metric_selection.metric = metric_selection.metric.next();
+            info!("Solution length metric: {}", metric_selection.metric);
+        }
+    }
+}
+
+/// System to show the solution length in the selected metric, or the progress of a running solve
+pub fn update_metric_label(
+    solver: Res<crate::solver_integration::CubeSolverResource>,
+    solve_tasks: Res<SolveTasks>,
+    metric_selection: Res<MetricSelection>,
+    time: Res<Time>,
+    mut label_query: Query<&mut Text, With<MetricLabel>>,
+) {
+    if !solver.is_changed()
+        && !metric_selection.is_changed()
+        && !solve_tasks.is_changed()
+        && !solve_tasks.is_running()
+    {
+        return;
+    }
+
+    let metric = metric_selection.metric;
+    let solution = MoveSequence::parse(&solver.solve_moves().join(" ")).unwrap_or_default();
+    let label = if let Some(elapsed) = solve_tasks.elapsed(time.elapsed_secs()) {
+        match solve_tasks.best_length() {
+            Some(length) => format!("Solving… {:.1}s, best {}", elapsed, length),
+            None => format!("Solving… {:.1}s", elapsed),
+        }
+    } else if solution.is_empty() {
+        format!("- {}", metric)
+    } else {
+        format!("{} {}", solution.length(metric), metric)
+    };
+
+    for mut text in &mut label_query {
+        if text.0 != label {
+            text.0 = label.clone();
+        }
+    }
+}
+
 /// System to update solve button state based on solver validation and move queue
 pub fn update_solve_button(
     solver: Res<crate::solver_integration::CubeSolverResource>,
+    solve_tasks: Res<SolveTasks>,
     move_queue: Res<MoveQueue>,
     mut button_query: Query<(&mut BackgroundColor, &mut BorderColor), With<SolveButton>>,
     mut text_query: Query<(&mut Text, &mut TextColor), (With<Text>, Without<SolveButton>)>,
@@ -148,7 +275,9 @@ pub fn update_solve_button(
         solver.is_valid() && (!solver.is_solving() || move_queue.pending.is_empty());
 
     let (bg_color, text_color) = if should_be_active {
-        if solver.is_solvable() {
+        if solve_tasks.is_running() {
+            (css::STEEL_BLUE.into(), css::WHITE.into())
+        } else if solver.is_solvable() {
             (css::LIGHT_GREEN.into(), css::WHITE.into())
         } else {
             (css::ORANGE.into(), css::WHITE.into())
@@ -156,30 +285,73 @@ pub fn update_solve_button(
     } else {
         (css::DARK_GRAY.into(), css::DIM_GRAY.into())
     };
+    let label = if solve_tasks.is_running() {
+        "Solving…"
+    } else {
+        "Solve"
+    };
+    // Highlight the border while a press is waiting for the background solve
+    let border_color = if solve_tasks.load_when_ready {
+        css::YELLOW
+    } else {
+        css::WHITE
+    };
 
     // Update button appearance
-    if let Ok((mut bg_color_component, mut border_color)) = button_query.get_single_mut() {
+    if let Ok((mut bg_color_component, mut border_color_component)) = button_query.get_single_mut()
+    {
         *bg_color_component = BackgroundColor(bg_color);
-        *border_color = BorderColor(css::WHITE.into());
+        *border_color_component = BorderColor(border_color.into());
     }
 
-    // Update text color
+    // Update text and its color
     if let Ok(solve_button_entity) = solve_button_query.get_single()
         && let Ok(children) = children_query.get(solve_button_entity)
     {
         for &child in children.iter() {
-            if let Ok((_, mut text_color_component)) = text_query.get_mut(child) {
+            if let Ok((mut text, mut text_color_component)) = text_query.get_mut(child) {
                 *text_color_component = TextColor(text_color);
+                if text.0 != label {
+                    text.0 = label.to_string();
+                }
             }
         }
     }
 }
 
+/// Insert the solver's selected solution into the rotation panel and start solution execution mode
+pub fn load_solution_into_queue(
+    solver: &mut crate::solver_integration::CubeSolverResource,
+    move_queue: &mut MoveQueue,
+) {
+    log::info!("Solution moves: {:?}", solver.solve_moves());
+
+    let solution = solver
+        .solutions()
+        .get(solver.selected_solution())
+        .cloned()
+        .unwrap_or_default();
+    move_queue.load_solution(&solution);
+    move_queue.current = None;
+    move_queue.highlight_index = Some(0); // Start at the first move
+
+    solver.set_solving(true);
+
+    log::info!(
+        "Solution execution started with {} moves",
+        move_queue.pending.len()
+    );
+}
+
 /// System to handle solve button clicks
 pub fn handle_solve_button_clicks(
     mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<SolveButton>)>,
     mut solver: ResMut<crate::solver_integration::CubeSolverResource>,
+    mut solve_tasks: ResMut<SolveTasks>,
     mut move_queue: ResMut<MoveQueue>,
+    solver_config: Res<crate::solver_integration::SolverConfig>,
+    registry: Res<crate::solver_backend::SolverRegistry>,
+    time: Res<Time>,
 ) {
     for interaction in &mut interaction_query {
         if *interaction == Interaction::Pressed {
@@ -187,32 +359,53 @@ pub fn handle_solve_button_clicks(
             let should_be_active =
                 solver.is_valid() && (!solver.is_solving() || move_queue.pending.is_empty());
 
-            if should_be_active {
-                // Start solution execution - perform full solve
-                log::info!("Solve button pressed - performing full solve!");
-
-                if solver.perform_full_solve() {
-                    log::info!("Solution found: {} moves", solver.solve_moves().len());
-                    log::info!("Solution moves: {:?}", solver.solve_moves());
-
-                    // Insert solution moves into the rotation panel
-                    move_queue.pending = solver.solve_moves().clone();
-                    move_queue.current = None;
-                    move_queue.highlight_index = Some(0); // Start at the first move
-
-                    // Start solution execution mode
-                    solver.set_solving(true);
-
-                    log::info!(
-                        "Solution execution started with {} moves",
-                        solver.solve_moves().len()
+            if !should_be_active {
+                log::info!("Solve button pressed but not active");
+                log::info!("Issue: {}", solver.get_validation_message());
+            } else if solver.is_solvable() {
+                log::info!("Solve button pressed - loading solution");
+                load_solution_into_queue(&mut solver, &mut move_queue);
+            } else {
+                // The solution is still being computed: load it once the background solve is done
+                solve_tasks.load_when_ready = true;
+                if !solve_tasks.is_running()
+                    && let Some(facelets) = solver.facelets()
+                {
+                    solve_tasks.spawn(
+                        facelets.to_string(),
+                        &solver_config,
+                        &registry,
+                        time.elapsed_secs(),
                     );
-                } else {
-                    log::info!("Solve failed: {}", solver.get_validation_message());
                 }
+                log::info!("Solve button pressed - waiting for the background solver");
+            }
+        }
+    }
+}
+
+/// System to store finished background solves and load them if the user asked for it
+pub fn handle_solve_finished(
+    mut finished_events: EventReader<SolveFinishedEvent>,
+    mut solver: ResMut<crate::solver_integration::CubeSolverResource>,
+    mut solve_tasks: ResMut<SolveTasks>,
+    mut move_queue: ResMut<MoveQueue>,
+) {
+    for event in finished_events.read() {
+        if event.generation != solve_tasks.generation() {
+            log::debug!("Ignoring superseded solve #{}", event.generation);
+            continue;
+        }
+        if !solver.apply_solve_result(&event.facelets, event.result.clone()) {
+            continue;
+        }
+
+        if solve_tasks.load_when_ready {
+            solve_tasks.load_when_ready = false;
+            if solver.is_solvable() {
+                load_solution_into_queue(&mut solver, &mut move_queue);
             } else {
-                log::info!("Solve button pressed but not active");
-                log::info!("Issue: {}", solver.get_validation_message());
+                log::info!("Solve failed: {}", solver.get_validation_message());
             }
         }
     }
@@ -0,0 +1,151 @@
+use crate::cube_model::CubieCube;
+use crate::solver_integration::CubeSolverResource;
+use crate::solvers::analysis;
+use bevy::color::palettes::css;
+use bevy::prelude::*;
+
+#[derive(Component)]
+pub struct StateToggleButton;
+
+#[derive(Component)]
+pub struct StatePanel;
+
+#[derive(Component)]
+pub struct StateText;
+
+/// Facelets of the cube the panel last described
+#[derive(Resource, Default)]
+pub struct StatePanelState {
+    facelets: Option<String>,
+}
+
+/// Creates the state toggle button and the (hidden) state analysis panel
+pub fn create_state_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(20.0),
+                right: Val::Px(320.0), // Left of the library toggle
+                width: Val::Px(40.0),
+                height: Val::Px(40.0),
+                border: UiRect::all(Val::Px(2.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(Color::from(css::BLACK).with_alpha(0.8)),
+            BorderColor(css::WHITE.into()),
+            StateToggleButton,
+            Name::new("State Toggle Button"),
+        ))
+        .with_children(|button_parent| {
+            button_parent.spawn((
+                Text::new("Info"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+        });
+
+    commands
+        .spawn((
+            Button, // Marks the panel as active UI so touches on it don't reach the cube
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(170.0),
+                right: Val::Px(20.0),
+                width: Val::Px(320.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::from(css::SLATE_GRAY).with_alpha(0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            StatePanel,
+            Name::new("State Panel"),
+            Visibility::Hidden, // Hidden by default
+        ))
+        .with_children(|panel_parent| {
+            panel_parent.spawn((
+                Text::new("Cube state"),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+            ));
+            panel_parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(css::WHITE.into()),
+                StateText,
+            ));
+        });
+}
+
+/// System to show and hide the state panel
+pub fn handle_state_toggle(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<StateToggleButton>)>,
+    mut panel_query: Query<&mut Visibility, With<StatePanel>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed
+            && let Ok(mut visibility) = panel_query.get_single_mut()
+        {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+/// System to describe the cube while the panel is open, once per cube state
+pub fn update_state_analysis(
+    panel_query: Query<&Visibility, With<StatePanel>>,
+    solver: Res<CubeSolverResource>,
+    mut state: ResMut<StatePanelState>,
+    mut text_query: Query<&mut Text, With<StateText>>,
+) {
+    let is_open = panel_query
+        .get_single()
+        .is_ok_and(|visibility| *visibility != Visibility::Hidden);
+    if !is_open {
+        return;
+    }
+    let facelets = solver.facelets().filter(|_| solver.is_valid());
+    if state.facelets.is_some() && state.facelets.as_deref() == facelets {
+        return;
+    }
+    state.facelets = facelets.map(str::to_string);
+
+    let description = match facelets.map(CubieCube::from_facelets) {
+        Some(Ok(cube)) => analysis::analyze(&cube).to_string(),
+        Some(Err(error)) => error.to_string(),
+        None => solver.get_validation_message(),
+    };
+    if let Ok(mut text) = text_query.get_single_mut()
+        && text.0 != description
+    {
+        text.0 = description;
+    }
+}
+
+/// Plugin for the state analysis panel
+pub struct StatePanelPlugin;
+
+impl Plugin for StatePanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StatePanelState>()
+            .add_systems(Startup, create_state_panel)
+            .add_systems(Update, (handle_state_toggle, update_state_analysis));
+    }
+}
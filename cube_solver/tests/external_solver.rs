@@ -0,0 +1,98 @@
+//! The external solver backend, run against `scripts/fake_solver.sh`
+
+#![cfg(unix)]
+
+use cube_solver::cube_model::CubieCube;
+use cube_solver::external_solver::ExternalBackend;
+use cube_solver::move_sequence::MoveSequence;
+use cube_solver::solver_backend::{SolveOutput, SolverBackend, SolverError};
+use cube_solver::solver_integration::SolverConfig;
+use std::time::{Duration, Instant};
+
+const FAKE_SOLVER: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../scripts/fake_solver.sh");
+
+/// The fake solver run with `env`, the given variables set
+fn fake_solver(variables: &[&str]) -> ExternalBackend {
+    let mut args: Vec<String> = variables.iter().map(|v| v.to_string()).collect();
+    args.push(FAKE_SOLVER.to_string());
+    ExternalBackend::new("env", args)
+}
+
+/// The cube after R U R' U', one of the two states the fake solver knows
+fn sexy_move() -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    cube.apply_moves(&MoveSequence::parse("R U R' U'").unwrap().moves);
+    cube
+}
+
+fn solve(backend: &ExternalBackend, cube: &CubieCube) -> Result<SolveOutput, SolverError> {
+    backend.solve(cube, &SolverConfig::default(), &|| false, &mut |_| {})
+}
+
+#[test]
+fn solution_is_read_from_stdout() {
+    let cube = sexy_move();
+    let output = solve(&fake_solver(&[]), &cube).unwrap();
+    assert_eq!(output.solutions.len(), 1);
+    assert_eq!(output.solutions[0].moves.to_string(), "U R U' R'");
+}
+
+#[test]
+fn solved_cube_takes_an_empty_answer() {
+    let output = solve(&fake_solver(&[]), &CubieCube::SOLVED).unwrap();
+    assert!(output.solutions[0].moves.is_empty());
+}
+
+#[test]
+fn answers_that_do_not_solve_are_rejected() {
+    let wrong = fake_solver(&["FAKE_SOLVER_ANSWER=R U R' U'"]);
+    match solve(&wrong, &sexy_move()) {
+        Err(SolverError::Failed(reason)) => {
+            assert!(reason.contains("does not solve the cube"), "{reason}")
+        }
+        other => panic!("expected a rejected answer, got {other:?}"),
+    }
+
+    let garbage = fake_solver(&["FAKE_SOLVER_ANSWER=no idea"]);
+    match solve(&garbage, &sexy_move()) {
+        Err(SolverError::Failed(reason)) => {
+            assert!(reason.contains("printed no solution"), "{reason}")
+        }
+        other => panic!("expected no solution, got {other:?}"),
+    }
+}
+
+#[test]
+fn failing_solver_reports_its_error() {
+    let mut cube = CubieCube::SOLVED;
+    cube.apply_moves(&MoveSequence::parse("F").unwrap().moves);
+    match solve(&fake_solver(&[]), &cube) {
+        Err(SolverError::Failed(reason)) => {
+            assert!(reason.contains("fake solver: unknown state"), "{reason}")
+        }
+        other => panic!("expected a failure, got {other:?}"),
+    }
+}
+
+#[test]
+fn slow_solver_is_stopped_with_its_children() {
+    let mut backend = fake_solver(&["FAKE_SOLVER_DELAY=30"]);
+    backend.timeout = Duration::from_millis(300);
+
+    let started = Instant::now();
+    let result = solve(&backend, &sexy_move());
+    assert!(
+        matches!(result, Err(SolverError::NoSolution(_))),
+        "{result:?}"
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn missing_program_fails_to_start() {
+    let backend = ExternalBackend::new("/nonexistent/solver", Vec::new());
+    match solve(&backend, &sexy_move()) {
+        Err(SolverError::Failed(reason)) => assert!(reason.contains("Could not start")),
+        other => panic!("expected a start failure, got {other:?}"),
+    }
+}
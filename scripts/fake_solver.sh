@@ -0,0 +1,36 @@
+#!/usr/bin/env bash
+set -euo pipefail
+
+# Stand-in for a command-line solver, for trying the external solver backend
+# without installing a real one:
+#   CUBE_SOLVER_EXTERNAL=scripts/fake_solver.sh cargo run
+#
+# Reads one facelet string from stdin and prints a solution for the two states it
+# knows: the solved cube and the cube after R U R' U'. Anything else is an error.
+#
+# Configuration via environment variables:
+#   FAKE_SOLVER_DELAY   Seconds to wait before answering, to try the timeout (default: 0)
+#   FAKE_SOLVER_ANSWER  Printed instead of the solution, to try a malformed answer
+
+FAKE_SOLVER_DELAY="${FAKE_SOLVER_DELAY:-0}"
+
+read -r facelets
+sleep "$FAKE_SOLVER_DELAY"
+
+if [ -n "${FAKE_SOLVER_ANSWER:-}" ]; then
+  echo "$FAKE_SOLVER_ANSWER"
+  exit 0
+fi
+
+case "$facelets" in
+  UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB)
+    echo ""
+    ;;
+  UULUUFUUFRRUBRRURRFFDFFUFFFDDRDDDDDDBLLLLLLLLBRRBBBBBB)
+    echo "U R U' R' (4)"
+    ;;
+  *)
+    echo "fake solver: unknown state $facelets" >&2
+    exit 1
+    ;;
+esac